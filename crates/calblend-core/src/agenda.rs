@@ -0,0 +1,755 @@
+//! Day-by-day agenda summaries of events
+//!
+//! [`build_agenda`] groups events by day in a chosen time zone, with all-day
+//! events first, and marks declined events and overlapping commitments. The
+//! result is laid out by an [`AgendaTemplate`]: [`TextTemplate`],
+//! [`MarkdownTemplate`] and [`HtmlTemplate`] are built in, and callers can
+//! implement their own. Recurring series should be expanded first, e.g.
+//! with [`expand_events`](crate::recurrence::expand_events).
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+use crate::error::Result;
+use crate::models::{EventMoment, EventStatus, ParticipantStatus, ShowAs, UnifiedCalendarEvent};
+use crate::timezone::{parse_time_zone, resolve_local};
+use crate::validation::check_date_time_formats;
+
+/// Output format of the built-in templates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgendaFormat {
+    Text,
+    Markdown,
+    /// HTML fragment with every value escaped
+    Html,
+}
+
+/// How events are grouped and labelled
+#[derive(Debug, Clone)]
+pub struct AgendaOptions {
+    /// IANA zone days and times are shown in
+    pub time_zone: String,
+    /// Heading above the days
+    pub title: Option<String>,
+    /// chrono format of day headings
+    pub date_format: String,
+    /// chrono format of times
+    pub time_format: String,
+    /// First and last day to show; days without events are shown empty.
+    /// When unset, only days with events are shown.
+    pub days: Option<(NaiveDate, NaiveDate)>,
+    /// Address identifying the reader among attendees, for events without
+    /// a `self` attendee
+    pub self_email: Option<String>,
+    /// Whether declined events are listed, marked as declined
+    pub include_declined: bool,
+}
+
+impl Default for AgendaOptions {
+    fn default() -> Self {
+        Self {
+            time_zone: "UTC".to_string(),
+            title: None,
+            date_format: "%A, %B %-d, %Y".to_string(),
+            time_format: "%H:%M".to_string(),
+            days: None,
+            self_email: None,
+            include_declined: true,
+        }
+    }
+}
+
+impl AgendaOptions {
+    pub fn new(time_zone: impl Into<String>) -> Self {
+        Self {
+            time_zone: time_zone.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_date_format(mut self, format: impl Into<String>) -> Self {
+        self.date_format = format.into();
+        self
+    }
+
+    pub fn with_time_format(mut self, format: impl Into<String>) -> Self {
+        self.time_format = format.into();
+        self
+    }
+
+    pub fn with_days(mut self, first: NaiveDate, last: NaiveDate) -> Self {
+        self.days = Some((first, last));
+        self
+    }
+
+    pub fn with_self_email(mut self, email: impl Into<String>) -> Self {
+        self.self_email = Some(email.into());
+        self
+    }
+
+    pub fn with_declined(mut self, include: bool) -> Self {
+        self.include_declined = include;
+        self
+    }
+}
+
+/// Events grouped by day, ready to be rendered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Agenda {
+    pub title: Option<String>,
+    pub days: Vec<AgendaDay>,
+}
+
+/// One day of an agenda
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgendaDay {
+    pub date: NaiveDate,
+    /// Day heading in the options' date format
+    pub label: String,
+    /// All-day items first, then by start time
+    pub items: Vec<AgendaItem>,
+}
+
+/// An event as shown on one day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgendaItem {
+    pub event_id: String,
+    /// Title, or `(No title)`
+    pub title: String,
+    /// `All day`, `09:00–09:30`, or `from 23:00`/`until 01:00` for events
+    /// crossing midnight
+    pub time: String,
+    pub all_day: bool,
+    pub location: Option<String>,
+    pub conference_url: Option<String>,
+    pub tentative: bool,
+    /// The reader declined the event
+    pub declined: bool,
+    /// The event overlaps another event the reader is busy for
+    pub conflict: bool,
+}
+
+/// Group events by day in the options' time zone
+///
+/// Cancelled events are left out. Timed events are shown on every day they
+/// cover; two timed events conflict when they overlap and neither is
+/// declined or shown as free.
+pub fn build_agenda(events: &[UnifiedCalendarEvent], options: &AgendaOptions) -> Result<Agenda> {
+    let tz = parse_time_zone(&options.time_zone)?;
+    check_date_time_formats(&options.date_format, &options.time_format)?;
+    let entries: Vec<Entry> = events
+        .iter()
+        .filter(|e| !matches!(e.status, Some(EventStatus::Cancelled)))
+        .map(|event| Entry::new(event, tz, options))
+        .filter(|entry| options.include_declined || !entry.declined)
+        .collect();
+
+    let dates: Vec<NaiveDate> = match options.days {
+        Some((first, last)) => first.iter_days().take_while(|d| *d <= last).collect(),
+        None => {
+            let mut dates: Vec<NaiveDate> = entries.iter().flat_map(Entry::dates).collect();
+            dates.sort();
+            dates.dedup();
+            dates
+        }
+    };
+
+    let days = dates
+        .into_iter()
+        .map(|date| {
+            let mut day: Vec<(&Entry, AgendaItem)> = entries
+                .iter()
+                .filter_map(|entry| Some((entry, entry.item_on(date, &entries, options)?)))
+                .collect();
+            day.sort_by_key(|(entry, item)| (!item.all_day, entry.local_start));
+            AgendaDay {
+                date,
+                label: date.format(&options.date_format).to_string(),
+                items: day.into_iter().map(|(_, item)| item).collect(),
+            }
+        })
+        .collect();
+    Ok(Agenda {
+        title: options.title.clone(),
+        days,
+    })
+}
+
+/// Build an agenda and render it with the built-in template for `format`
+pub fn render_agenda(
+    events: &[UnifiedCalendarEvent],
+    options: &AgendaOptions,
+    format: AgendaFormat,
+) -> Result<String> {
+    let agenda = build_agenda(events, options)?;
+    Ok(match format {
+        AgendaFormat::Text => agenda.render(&TextTemplate),
+        AgendaFormat::Markdown => agenda.render(&MarkdownTemplate),
+        AgendaFormat::Html => agenda.render(&HtmlTemplate),
+    })
+}
+
+/// An event placed in the agenda's zone
+struct Entry<'a> {
+    event: &'a UnifiedCalendarEvent,
+    all_day: bool,
+    /// Wall-clock bounds in the agenda zone, midnight for all-day events
+    local_start: NaiveDateTime,
+    local_end: NaiveDateTime,
+    /// Instants of timed events, for conflicts
+    instants: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    declined: bool,
+    busy: bool,
+}
+
+impl<'a> Entry<'a> {
+    fn new(event: &'a UnifiedCalendarEvent, tz: Tz, options: &AgendaOptions) -> Self {
+        let instant = |moment: &EventMoment| {
+            moment
+                .date_time()
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|| resolve_local(tz, moment.naive_local()).with_timezone(&Utc))
+        };
+        let all_day = event.is_all_day();
+        let (start, end) = (instant(&event.start), instant(&event.end));
+        let (local_start, local_end) = if all_day {
+            (event.start.naive_local(), event.end.naive_local())
+        } else {
+            (
+                start.with_timezone(&tz).naive_local(),
+                end.with_timezone(&tz).naive_local(),
+            )
+        };
+
+        let me = event.attendees.iter().flatten().find(|a| {
+            a.is_self == Some(true)
+                || matches!((&a.email, &options.self_email), (Some(a), Some(b)) if a.eq_ignore_ascii_case(b))
+        });
+        let declined = me.is_some_and(|a| a.response_status == Some(ParticipantStatus::Declined));
+        Self {
+            event,
+            all_day,
+            local_start,
+            local_end,
+            instants: (!all_day).then_some((start, end)),
+            declined,
+            busy: !declined && !matches!(event.show_as, Some(ShowAs::Free)),
+        }
+    }
+
+    /// Days the entry is shown on
+    fn dates(&self) -> Vec<NaiveDate> {
+        let first = self.local_start.date();
+        // An end at midnight does not reach into the next day
+        let last = if self.local_end > self.local_start {
+            (self.local_end - Duration::nanoseconds(1)).date()
+        } else {
+            first
+        };
+        first.iter_days().take_while(|d| *d <= last).collect()
+    }
+
+    fn conflicts(&self, entries: &[Entry]) -> bool {
+        let Some((start, end)) = self.instants.filter(|_| self.busy) else {
+            return false;
+        };
+        entries.iter().any(|other| {
+            !std::ptr::eq(self, other)
+                && other.busy
+                && other.instants.is_some_and(|(s, e)| s < end && start < e)
+        })
+    }
+
+    fn item_on(
+        &self,
+        date: NaiveDate,
+        entries: &[Entry],
+        options: &AgendaOptions,
+    ) -> Option<AgendaItem> {
+        if !self.dates().contains(&date) {
+            return None;
+        }
+        let time = |t: NaiveTime| t.format(&options.time_format).to_string();
+        let starts_today = self.local_start.date() == date;
+        let ends_today = self.local_end <= date.succ_opt()?.and_time(NaiveTime::MIN);
+        let all_day = self.all_day || (!starts_today && !ends_today);
+        let label = if all_day {
+            "All day".to_string()
+        } else if !starts_today {
+            format!("until {}", time(self.local_end.time()))
+        } else if !ends_today {
+            format!("from {}", time(self.local_start.time()))
+        } else {
+            format!(
+                "{}\u{2013}{}",
+                time(self.local_start.time()),
+                time(self.local_end.time())
+            )
+        };
+        let event = self.event;
+        Some(AgendaItem {
+            event_id: event.id.clone(),
+            title: event
+                .title
+                .clone()
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or_else(|| "(No title)".to_string()),
+            time: label,
+            all_day,
+            location: event.location.clone().filter(|l| !l.trim().is_empty()),
+            conference_url: event.conference.as_ref().and_then(|c| c.url.clone()),
+            tentative: matches!(event.status, Some(EventStatus::Tentative)),
+            declined: self.declined,
+            conflict: self.conflicts(entries),
+        })
+    }
+}
+
+/// Lays out an [`Agenda`]
+///
+/// Each method appends to `out`; the defaults of the optional parts write
+/// nothing.
+pub trait AgendaTemplate {
+    /// Before the first day, with the agenda title
+    fn header(&self, _out: &mut String, _title: Option<&str>) {}
+
+    /// A day heading
+    fn day_start(&self, out: &mut String, day: &AgendaDay);
+
+    /// One event of the day
+    fn item(&self, out: &mut String, item: &AgendaItem);
+
+    /// In place of the items of a day without events
+    fn empty_day(&self, out: &mut String, day: &AgendaDay);
+
+    /// After the items of a day
+    fn day_end(&self, _out: &mut String, _day: &AgendaDay) {}
+
+    /// After the last day
+    fn footer(&self, _out: &mut String) {}
+}
+
+impl Agenda {
+    /// Lay out the agenda with `template`
+    pub fn render(&self, template: &impl AgendaTemplate) -> String {
+        let mut out = String::new();
+        template.header(&mut out, self.title.as_deref());
+        for day in &self.days {
+            template.day_start(&mut out, day);
+            if day.items.is_empty() {
+                template.empty_day(&mut out, day);
+            }
+            for item in &day.items {
+                template.item(&mut out, item);
+            }
+            template.day_end(&mut out, day);
+        }
+        template.footer(&mut out);
+        out
+    }
+}
+
+/// Marks shown after an item's title
+fn marks(item: &AgendaItem) -> Vec<&'static str> {
+    [
+        (item.tentative, "tentative"),
+        (item.declined, "declined"),
+        (item.conflict, "conflict"),
+    ]
+    .into_iter()
+    .filter_map(|(on, mark)| on.then_some(mark))
+    .collect()
+}
+
+/// Plain text, e.g. for chat messages and text email parts
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextTemplate;
+
+impl AgendaTemplate for TextTemplate {
+    fn header(&self, out: &mut String, title: Option<&str>) {
+        if let Some(title) = title {
+            out.push_str(title);
+            out.push_str("\n\n");
+        }
+    }
+
+    fn day_start(&self, out: &mut String, day: &AgendaDay) {
+        out.push_str(&day.label);
+        out.push('\n');
+    }
+
+    fn item(&self, out: &mut String, item: &AgendaItem) {
+        out.push_str(&format!("- {}  {}", item.time, item.title));
+        if let Some(location) = &item.location {
+            out.push_str(&format!(" @ {}", location));
+        }
+        for mark in marks(item) {
+            out.push_str(&format!(" [{}]", mark));
+        }
+        out.push('\n');
+        if let Some(url) = &item.conference_url {
+            out.push_str(&format!("  Join: {}\n", url));
+        }
+    }
+
+    fn empty_day(&self, out: &mut String, _day: &AgendaDay) {
+        out.push_str("- No events\n");
+    }
+
+    fn day_end(&self, out: &mut String, _day: &AgendaDay) {
+        out.push('\n');
+    }
+}
+
+/// Markdown, with declined events struck through
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownTemplate;
+
+impl AgendaTemplate for MarkdownTemplate {
+    fn header(&self, out: &mut String, title: Option<&str>) {
+        if let Some(title) = title {
+            out.push_str(&format!("# {}\n\n", escape_markdown(title)));
+        }
+    }
+
+    fn day_start(&self, out: &mut String, day: &AgendaDay) {
+        out.push_str(&format!("## {}\n\n", escape_markdown(&day.label)));
+    }
+
+    fn item(&self, out: &mut String, item: &AgendaItem) {
+        let title = escape_markdown(&item.title);
+        let title = if item.declined {
+            format!("~~{}~~", title)
+        } else {
+            format!("**{}**", title)
+        };
+        out.push_str(&format!("- {} {}", escape_markdown(&item.time), title));
+        if let Some(location) = &item.location {
+            out.push_str(&format!(" @ {}", escape_markdown(location)));
+        }
+        for mark in marks(item) {
+            out.push_str(&format!(" _({})_", mark));
+        }
+        if let Some(url) = item.conference_url.as_deref().filter(|u| is_web_url(u)) {
+            out.push_str(&format!(" [Join](<{}>)", url.replace(['<', '>'], "")));
+        }
+        out.push('\n');
+    }
+
+    fn empty_day(&self, out: &mut String, _day: &AgendaDay) {
+        out.push_str("_No events_\n");
+    }
+
+    fn day_end(&self, out: &mut String, _day: &AgendaDay) {
+        out.push('\n');
+    }
+}
+
+/// HTML fragment for email bodies
+///
+/// All text is escaped and only `http`/`https` conference links become
+/// anchors, so event data cannot inject markup. Items carry the classes
+/// `all-day`, `tentative`, `declined` and `conflict` for styling.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlTemplate;
+
+impl AgendaTemplate for HtmlTemplate {
+    fn header(&self, out: &mut String, title: Option<&str>) {
+        out.push_str("<div class=\"agenda\">\n");
+        if let Some(title) = title {
+            out.push_str(&format!("<h1>{}</h1>\n", escape_html(title)));
+        }
+    }
+
+    fn day_start(&self, out: &mut String, day: &AgendaDay) {
+        out.push_str(&format!("<h2>{}</h2>\n<ul>\n", escape_html(&day.label)));
+    }
+
+    fn item(&self, out: &mut String, item: &AgendaItem) {
+        let mut classes = vec!["agenda-item"];
+        if item.all_day {
+            classes.push("all-day");
+        }
+        classes.extend(marks(item));
+        let title = escape_html(&item.title);
+        let title = if item.declined {
+            format!("<del>{}</del>", title)
+        } else {
+            format!("<strong>{}</strong>", title)
+        };
+        out.push_str(&format!(
+            "<li class=\"{}\"><span class=\"time\">{}</span> {}",
+            classes.join(" "),
+            escape_html(&item.time),
+            title
+        ));
+        if let Some(location) = &item.location {
+            out.push_str(&format!(" @ {}", escape_html(location)));
+        }
+        for mark in marks(item) {
+            out.push_str(&format!(" <em>({})</em>", mark));
+        }
+        if let Some(url) = item.conference_url.as_deref().filter(|u| is_web_url(u)) {
+            out.push_str(&format!(" <a href=\"{}\">Join</a>", escape_html(url)));
+        }
+        out.push_str("</li>\n");
+    }
+
+    fn empty_day(&self, out: &mut String, _day: &AgendaDay) {
+        out.push_str("<li class=\"agenda-empty\">No events</li>\n");
+    }
+
+    fn day_end(&self, out: &mut String, _day: &AgendaDay) {
+        out.push_str("</ul>\n");
+    }
+
+    fn footer(&self, out: &mut String) {
+        out.push_str("</div>\n");
+    }
+}
+
+/// Whether a link is safe to render: `http` or `https` only
+fn is_web_url(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c if c.is_control() => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Backslash-escape characters with meaning in Markdown, flattening lines
+fn escape_markdown(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '~' | '[' | ']' | '(' | ')' | '#' | '<' | '>' | '|' | '!' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CalendarSource, ConferenceLink, Participant};
+
+    fn event(id: &str, title: &str, start: &str, end: &str) -> UnifiedCalendarEvent {
+        let moment = |s: &str| {
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+                Some("Europe/Berlin".to_string()),
+            )
+        };
+        let mut event = UnifiedCalendarEvent::new(
+            id.to_string(),
+            CalendarSource::Google,
+            moment(start),
+            moment(end),
+        );
+        event.title = Some(title.to_string());
+        event
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn events() -> Vec<UnifiedCalendarEvent> {
+        let mut standup = event(
+            "1",
+            "Stand-up",
+            "2024-03-11T09:00:00+01:00",
+            "2024-03-11T09:30:00+01:00",
+        );
+        standup.location = Some("Room 1".to_string());
+        standup.conference = Some(ConferenceLink {
+            url: Some("https://meet.example.com/abc".to_string()),
+            provider: None,
+        });
+        let review = event(
+            "2",
+            "Review",
+            "2024-03-11T09:15:00+01:00",
+            "2024-03-11T10:00:00+01:00",
+        );
+        let mut lunch = event(
+            "3",
+            "Lunch",
+            "2024-03-11T09:00:00+01:00",
+            "2024-03-11T10:00:00+01:00",
+        );
+        lunch.attendees = Some(vec![Participant {
+            is_self: Some(true),
+            response_status: Some(ParticipantStatus::Declined),
+            ..Default::default()
+        }]);
+        let mut offsite = UnifiedCalendarEvent::new(
+            "4".to_string(),
+            CalendarSource::Google,
+            EventMoment::all_day(day(2024, 3, 11)),
+            EventMoment::all_day(day(2024, 3, 13)),
+        );
+        offsite.title = Some("Offsite".to_string());
+        let mut cancelled = event(
+            "5",
+            "Cancelled",
+            "2024-03-11T11:00:00+01:00",
+            "2024-03-11T12:00:00+01:00",
+        );
+        cancelled.status = Some(EventStatus::Cancelled);
+        let flight = event(
+            "6",
+            "Flight",
+            "2024-03-11T22:00:00+01:00",
+            "2024-03-12T01:30:00+01:00",
+        );
+        vec![standup, review, lunch, offsite, cancelled, flight]
+    }
+
+    #[test]
+    fn test_build_agenda() {
+        let agenda = build_agenda(&events(), &AgendaOptions::new("Europe/Berlin")).unwrap();
+        assert_eq!(agenda.days.len(), 2);
+        let monday = &agenda.days[0];
+        assert_eq!(monday.label, "Monday, March 11, 2024");
+        let summary: Vec<(&str, &str, bool, bool)> = monday
+            .items
+            .iter()
+            .map(|i| (i.title.as_str(), i.time.as_str(), i.declined, i.conflict))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Offsite", "All day", false, false),
+                ("Stand-up", "09:00\u{2013}09:30", false, true),
+                ("Lunch", "09:00\u{2013}10:00", true, false),
+                ("Review", "09:15\u{2013}10:00", false, true),
+                ("Flight", "from 22:00", false, false),
+            ]
+        );
+        let tuesday: Vec<&str> = agenda.days[1]
+            .items
+            .iter()
+            .map(|i| i.time.as_str())
+            .collect();
+        assert_eq!(tuesday, vec!["All day", "until 01:30"]);
+
+        // Days are taken in the agenda's zone
+        let options = AgendaOptions::new("America/New_York").with_declined(false);
+        let agenda = build_agenda(&events(), &options).unwrap();
+        let monday: Vec<&str> = agenda.days[0]
+            .items
+            .iter()
+            .map(|i| i.time.as_str())
+            .collect();
+        assert_eq!(
+            monday,
+            vec![
+                "All day",
+                "04:00\u{2013}04:30",
+                "04:15\u{2013}05:00",
+                "17:00\u{2013}20:30"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_days() {
+        let options = AgendaOptions::new("UTC")
+            .with_title("Your day")
+            .with_days(day(2024, 3, 14), day(2024, 3, 14));
+        let text = render_agenda(&events(), &options, AgendaFormat::Text).unwrap();
+        assert_eq!(
+            text,
+            "Your day\n\nThursday, March 14, 2024\n- No events\n\n"
+        );
+
+        for options in [
+            AgendaOptions::new("UTC").with_time_format("%H:%Q"),
+            AgendaOptions::new("UTC").with_date_format("%A %H:%M"),
+            AgendaOptions::new("UTC").with_time_format("%H:%M %Y"),
+            AgendaOptions::new("UTC").with_time_format("%H:%M %z"),
+        ] {
+            assert!(build_agenda(&events(), &options).is_err());
+        }
+    }
+
+    #[test]
+    fn test_render_text_and_markdown() {
+        let options =
+            AgendaOptions::new("Europe/Berlin").with_days(day(2024, 3, 11), day(2024, 3, 11));
+        let text = render_agenda(&events(), &options, AgendaFormat::Text).unwrap();
+        assert!(text.contains(
+            "- 09:00\u{2013}09:30  Stand-up @ Room 1 [conflict]\n  Join: https://meet.example.com/abc\n"
+        ));
+        assert!(text.contains("- 09:00\u{2013}10:00  Lunch [declined]\n"));
+        assert!(!text.contains("Cancelled"));
+
+        let markdown = render_agenda(&events(), &options, AgendaFormat::Markdown).unwrap();
+        assert!(markdown.starts_with("## Monday, March 11, 2024\n\n- All day **Offsite**\n"));
+        assert!(markdown.contains(
+            "- 09:00\u{2013}09:30 **Stand-up** @ Room 1 _(conflict)_ [Join](<https://meet.example.com/abc>)\n"
+        ));
+        assert!(markdown.contains("~~Lunch~~ _(declined)_"));
+    }
+
+    #[test]
+    fn test_render_html_is_sanitized() {
+        let mut evil = event(
+            "7",
+            "<script>alert(1)</script>",
+            "2024-03-11T09:00:00Z",
+            "2024-03-11T10:00:00Z",
+        );
+        evil.location = Some("\"><img src=x onerror=alert(1)>".to_string());
+        evil.conference = Some(ConferenceLink {
+            url: Some("javascript:alert(1)".to_string()),
+            provider: None,
+        });
+        let mut meeting = event(
+            "8",
+            "Sync & plan",
+            "2024-03-11T11:00:00Z",
+            "2024-03-11T12:00:00Z",
+        );
+        meeting.conference = Some(ConferenceLink {
+            url: Some("https://meet.example.com/a?b=1&c=\"2\"".to_string()),
+            provider: None,
+        });
+        let html = render_agenda(
+            &[evil, meeting],
+            &AgendaOptions::new("UTC"),
+            AgendaFormat::Html,
+        )
+        .unwrap();
+        assert!(!html.contains("<script>") && !html.contains("<img"));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(html.contains("<strong>Sync &amp; plan</strong>"));
+        assert!(html.contains("href=\"https://meet.example.com/a?b=1&amp;c=&quot;2&quot;\""));
+        assert!(html.starts_with("<div class=\"agenda\">\n<h2>Monday, March 11, 2024</h2>\n<ul>\n"));
+        assert!(html.ends_with("</ul>\n</div>\n"));
+    }
+}
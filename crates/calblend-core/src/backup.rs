@@ -0,0 +1,840 @@
+//! Whole-account snapshots and restore across providers
+//!
+//! [`backup_account`] captures every calendar a provider lists and all of
+//! their events, raw provider payloads included. The snapshot is stored as a
+//! directory (or any file store, via [`AccountBackup::to_files`]) holding:
+//!
+//! - `manifest.json`: format version, provider, capture time and the
+//!   calendars with the names of their files
+//! - `calendars/<n>.json`: the calendar's events as versioned
+//!   [envelopes](crate::envelope), with every field kept
+//! - `calendars/<n>.ics`: the same events as iCalendar, for other tools
+//!
+//! [`restore_account`] replays a snapshot into any [`CalendarProvider`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::envelope;
+use crate::error::{CalblendError, Result};
+use crate::ical::{export_calendar, read_calendar, ImportOptions};
+use crate::models::{EventMoment, UnifiedCalendarEvent};
+use crate::{Calendar, CalendarProvider};
+
+/// Value of the manifest's `format`
+pub const BACKUP_FORMAT: &str = "calblend-backup";
+/// Manifest version written by this build
+pub const BACKUP_VERSION: u32 = 1;
+/// Path of the manifest within an archive
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// What to capture
+#[derive(Debug, Clone, Default)]
+pub struct BackupOptions {
+    /// Only events overlapping this window; everything when unset
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// Only these calendars; all listed calendars when unset
+    pub calendar_ids: Option<Vec<String>>,
+}
+
+impl BackupOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_window(mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self.end = Some(end);
+        self
+    }
+
+    pub fn with_calendar(mut self, calendar_id: impl Into<String>) -> Self {
+        self.calendar_ids
+            .get_or_insert_with(Vec::new)
+            .push(calendar_id.into());
+        self
+    }
+}
+
+/// A snapshot of an account
+#[derive(Debug, Clone)]
+pub struct AccountBackup {
+    /// Name of the provider the snapshot was taken from
+    pub provider: String,
+    pub created: DateTime<Utc>,
+    pub calendars: Vec<CalendarBackup>,
+}
+
+/// A calendar and its events
+#[derive(Debug, Clone)]
+pub struct CalendarBackup {
+    pub calendar: Calendar,
+    /// Events as listed by the provider, with `raw` payloads
+    pub events: Vec<UnifiedCalendarEvent>,
+}
+
+/// Contents of `manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    /// Always [`BACKUP_FORMAT`]
+    pub format: String,
+    pub version: u32,
+    pub provider: String,
+    pub created: DateTime<Utc>,
+    pub calendars: Vec<ManifestCalendar>,
+}
+
+/// A calendar entry of the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestCalendar {
+    /// Calendar metadata as a versioned envelope
+    pub calendar: Value,
+    /// Path of the events file, relative to the archive root
+    pub events: Option<String>,
+    /// Path of the iCalendar file, relative to the archive root
+    pub ics: Option<String>,
+    pub event_count: usize,
+}
+
+/// Capture every calendar of `provider` and all of its events
+///
+/// Calendars are listed with `list_calendars` and their events with
+/// `list_events_unexpanded`, so recurring series are kept as series with
+/// their exceptions wherever the provider lists them that way.
+pub async fn backup_account(
+    provider: &dyn CalendarProvider,
+    options: &BackupOptions,
+) -> Result<AccountBackup> {
+    let mut calendars = Vec::new();
+    for calendar in provider.list_calendars().await? {
+        if let Some(ids) = &options.calendar_ids {
+            if !ids.contains(&calendar.id) {
+                continue;
+            }
+        }
+        debug!("Backing up calendar: {}", calendar.id);
+        let events = provider
+            .list_events_unexpanded(&calendar.id, options.start, options.end)
+            .await?;
+        calendars.push(CalendarBackup { calendar, events });
+    }
+    Ok(AccountBackup {
+        provider: provider.name().to_string(),
+        created: Utc::now(),
+        calendars,
+    })
+}
+
+impl AccountBackup {
+    /// The archive's files as `(path, contents)`, manifest first
+    pub fn to_files(&self) -> Result<Vec<(String, String)>> {
+        let mut manifest = BackupManifest {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION,
+            provider: self.provider.clone(),
+            created: self.created,
+            calendars: Vec::new(),
+        };
+        let mut files = Vec::new();
+        for (index, backup) in self.calendars.iter().enumerate() {
+            let stem = format!("calendars/{:03}", index + 1);
+            let events = backup
+                .events
+                .iter()
+                .map(envelope::to_value)
+                .collect::<Result<Vec<Value>>>()?;
+            files.push((
+                format!("{}.json", stem),
+                serde_json::to_string_pretty(&events)?,
+            ));
+            files.push((
+                format!("{}.ics", stem),
+                export_calendar(&backup.calendar, &backup.events),
+            ));
+            manifest.calendars.push(ManifestCalendar {
+                calendar: envelope::to_value(&backup.calendar)?,
+                events: Some(format!("{}.json", stem)),
+                ics: Some(format!("{}.ics", stem)),
+                event_count: backup.events.len(),
+            });
+        }
+        files.insert(
+            0,
+            (
+                MANIFEST_FILE.to_string(),
+                serde_json::to_string_pretty(&manifest)?,
+            ),
+        );
+        Ok(files)
+    }
+
+    /// Read an archive from its files, keyed by path
+    ///
+    /// Events come from each calendar's JSON file, or from its iCalendar
+    /// file when there is none, e.g. in a hand-made archive.
+    pub fn from_files(files: &BTreeMap<String, String>) -> Result<Self> {
+        let file = |path: &str| {
+            files
+                .get(path)
+                .ok_or_else(|| invalid(format!("missing file {}", path)))
+        };
+        let manifest: BackupManifest = serde_json::from_str(file(MANIFEST_FILE)?)?;
+        if manifest.format != BACKUP_FORMAT {
+            return Err(invalid(format!("unknown format {:?}", manifest.format)));
+        }
+        if manifest.version > BACKUP_VERSION {
+            return Err(invalid(format!(
+                "version {} is newer than supported version {}",
+                manifest.version, BACKUP_VERSION
+            )));
+        }
+
+        let mut calendars = Vec::new();
+        for entry in &manifest.calendars {
+            let calendar: Calendar = envelope::from_value(entry.calendar.clone())?;
+            let events = match (&entry.events, &entry.ics) {
+                (Some(path), _) => {
+                    let values: Vec<Value> = serde_json::from_str(file(path)?)?;
+                    values
+                        .into_iter()
+                        .map(envelope::from_value)
+                        .collect::<Result<Vec<UnifiedCalendarEvent>>>()?
+                }
+                (None, Some(path)) => {
+                    let mut options = ImportOptions::new(calendar.source);
+                    options.default_time_zone = calendar.time_zone.clone();
+                    let result = read_calendar(file(path)?, &options);
+                    for error in &result.errors {
+                        warn!("Skipping broken component in {}: {}", path, error.message);
+                    }
+                    result.events
+                }
+                (None, None) => {
+                    return Err(invalid(format!(
+                        "calendar {} has no events file",
+                        calendar.id
+                    )))
+                }
+            };
+            calendars.push(CalendarBackup { calendar, events });
+        }
+        Ok(Self {
+            provider: manifest.provider,
+            created: manifest.created,
+            calendars,
+        })
+    }
+
+    /// Write the archive into `dir`, creating it if needed
+    pub fn write_dir(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        for (path, contents) in self.to_files()? {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+            }
+            std::fs::write(&path, contents).map_err(|e| io_error(&path, e))?;
+        }
+        Ok(())
+    }
+
+    /// Read an archive written by [`write_dir`](Self::write_dir)
+    ///
+    /// Only the files the manifest names are read, and only from inside
+    /// `dir`.
+    pub fn read_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let read = |name: &str| {
+            let relative = Path::new(name);
+            if !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+            {
+                return Err(invalid(format!("path {:?} leaves the archive", name)));
+            }
+            let path = dir.join(relative);
+            std::fs::read_to_string(&path).map_err(|e| io_error(&path, e))
+        };
+        let manifest_text = read(MANIFEST_FILE)?;
+        let manifest: BackupManifest = serde_json::from_str(&manifest_text)?;
+        let mut files = BTreeMap::new();
+        for entry in &manifest.calendars {
+            // The ICS file is only needed when there is no JSON file
+            if let Some(path) = entry.events.as_ref().or(entry.ics.as_ref()) {
+                files.insert(path.clone(), read(path)?);
+            }
+        }
+        files.insert(MANIFEST_FILE.to_string(), manifest_text);
+        Self::from_files(&files)
+    }
+}
+
+fn invalid(message: String) -> CalblendError {
+    CalblendError::InvalidData(format!("Invalid backup: {}", message))
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> CalblendError {
+    CalblendError::InternalError(format!("Backup I/O error on {}: {}", path.display(), error))
+}
+
+/// How a snapshot is replayed
+#[derive(Debug, Clone)]
+pub struct RestoreOptions {
+    /// Whether attendees are notified of the recreated events
+    pub send_invitations: bool,
+    /// Target calendar for each backed-up calendar ID; unmapped calendars
+    /// are restored into the calendar with the same ID
+    pub calendar_map: HashMap<String, String>,
+    /// Only calendars with these backed-up IDs; all when unset
+    pub calendar_ids: Option<Vec<String>>,
+    /// Leave out events whose UID (and recurrence ID) the target calendar
+    /// already has
+    pub skip_existing: bool,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        Self {
+            send_invitations: false,
+            calendar_map: HashMap::new(),
+            calendar_ids: None,
+            skip_existing: true,
+        }
+    }
+}
+
+impl RestoreOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_invitations(mut self, send: bool) -> Self {
+        self.send_invitations = send;
+        self
+    }
+
+    /// Restore the backed-up calendar `from` into `to`
+    pub fn with_calendar_mapping(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.calendar_map.insert(from.into(), to.into());
+        self
+    }
+
+    pub fn with_calendar(mut self, calendar_id: impl Into<String>) -> Self {
+        self.calendar_ids
+            .get_or_insert_with(Vec::new)
+            .push(calendar_id.into());
+        self
+    }
+
+    pub fn with_skip_existing(mut self, skip: bool) -> Self {
+        self.skip_existing = skip;
+        self
+    }
+}
+
+/// Outcome of a restore
+#[derive(Debug, Clone, Default)]
+pub struct RestoreReport {
+    /// Events created, exceptions applied to their new series included
+    pub created: usize,
+    /// Events left out because the target already had them
+    pub skipped: usize,
+    /// Events that could not be created; the restore carries on past them
+    pub errors: Vec<RestoreError>,
+}
+
+/// An event that could not be restored
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("event {event_id} in calendar {calendar_id}: {message}")]
+pub struct RestoreError {
+    /// Target calendar
+    pub calendar_id: String,
+    /// ID of the event in the backup
+    pub event_id: String,
+    pub message: String,
+}
+
+/// Replay a snapshot into `provider`
+///
+/// Events are created with new IDs. Series are created before their
+/// exceptions, which then replace or cancel the matching occurrence of the
+/// new series through [`CalendarProvider::update_occurrence`].
+/// Provider-assigned fields (`etag`, links, timestamps) and `raw` payloads
+/// are not sent.
+/// Failing to list the target calendar stops the restore; failing to
+/// create an event is reported and the restore continues. That includes
+/// events with attendees when invitations are off and the provider cannot
+/// create events without notifying them, and exceptions whose series was
+/// not restored.
+pub async fn restore_account(
+    provider: &dyn CalendarProvider,
+    backup: &AccountBackup,
+    options: &RestoreOptions,
+) -> Result<RestoreReport> {
+    let mut report = RestoreReport::default();
+    for calendar_backup in &backup.calendars {
+        let source_id = &calendar_backup.calendar.id;
+        if let Some(ids) = &options.calendar_ids {
+            if !ids.contains(source_id) {
+                continue;
+            }
+        }
+        let target = options
+            .calendar_map
+            .get(source_id)
+            .unwrap_or(source_id)
+            .clone();
+        debug!("Restoring calendar {} into {}", source_id, target);
+
+        // Existing events by identity, and existing series by UID
+        let mut existing: HashMap<EventKey, String> = HashMap::new();
+        if options.skip_existing {
+            for event in provider.list_events_unexpanded(&target, None, None).await? {
+                existing.insert(EventKey::of(&event), event.id.clone());
+            }
+        }
+
+        // Series and single events first, so exceptions can find their series
+        let mut events: Vec<&UnifiedCalendarEvent> = calendar_backup.events.iter().collect();
+        events.sort_by_key(|event| event.is_exception());
+        let mut new_ids: HashMap<&str, String> = HashMap::new();
+        for event in events {
+            let key = EventKey::of(event);
+            if let Some(id) = existing.get(&key) {
+                report.skipped += 1;
+                new_ids.insert(&event.id, id.clone());
+                continue;
+            }
+            let mut restored = event.clone();
+            restored.id = String::new();
+            restored.calendar_id = Some(target.clone());
+            restored.etag = None;
+            restored.html_link = None;
+            restored.created = None;
+            restored.updated = None;
+            restored.raw = None;
+            let result = match &event.series_id {
+                Some(series_id) if event.is_exception() => {
+                    let series = new_ids.get(series_id.as_str()).cloned().or_else(|| {
+                        // A series already in the target but not in this backup
+                        existing.get(&EventKey::series(event)).cloned()
+                    });
+                    match series {
+                        Some(series) => {
+                            provider
+                                .update_occurrence(
+                                    &target,
+                                    &series,
+                                    restored,
+                                    options.send_invitations,
+                                )
+                                .await
+                        }
+                        None => Err(CalblendError::InvalidData(format!(
+                            "series {} was not restored",
+                            series_id
+                        ))),
+                    }
+                }
+                _ => {
+                    // The source series' ID means nothing in the target
+                    restored.series_id = None;
+                    provider
+                        .create_event_with_notifications(
+                            &target,
+                            restored,
+                            options.send_invitations,
+                        )
+                        .await
+                }
+            };
+
+            match result {
+                Ok(created) => {
+                    report.created += 1;
+                    if options.skip_existing {
+                        existing.insert(key, created.id.clone());
+                    }
+                    new_ids.insert(&event.id, created.id);
+                }
+                Err(e) => report.errors.push(RestoreError {
+                    calendar_id: target.clone(),
+                    event_id: event.id.clone(),
+                    message: e.to_string(),
+                }),
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Identity of an event across providers: its UID, or its title and start
+/// when it has none, plus the start of the occurrence it overrides
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct EventKey {
+    identity: String,
+    occurrence: Option<String>,
+}
+
+impl EventKey {
+    fn of(event: &UnifiedCalendarEvent) -> Self {
+        let identity = match &event.ical_uid {
+            Some(uid) => format!("uid:{}", uid),
+            None => format!(
+                "event:{}@{}",
+                event.title.as_deref().unwrap_or_default(),
+                moment_key(&event.start)
+            ),
+        };
+        Self {
+            identity,
+            occurrence: event.original_start.as_ref().map(moment_key),
+        }
+    }
+
+    /// Key of the series an exception belongs to
+    fn series(event: &UnifiedCalendarEvent) -> Self {
+        Self {
+            occurrence: None,
+            ..Self::of(event)
+        }
+    }
+}
+
+/// A moment as an instant or date, so offsets do not matter
+fn moment_key(moment: &EventMoment) -> String {
+    match moment.date_time() {
+        Some(date_time) => date_time.with_timezone(&Utc).to_rfc3339(),
+        None => moment.date().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CalendarSource;
+    use crate::FreeBusyPeriod;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Provider keeping events in memory, with no control over notifications
+    #[derive(Default)]
+    struct MemoryProvider {
+        calendars: Vec<Calendar>,
+        events: Mutex<Vec<(String, UnifiedCalendarEvent)>>,
+    }
+
+    #[async_trait]
+    impl CalendarProvider for MemoryProvider {
+        fn name(&self) -> &'static str {
+            "memory"
+        }
+
+        async fn list_calendars(&self) -> Result<Vec<Calendar>> {
+            Ok(self.calendars.clone())
+        }
+
+        async fn list_events(
+            &self,
+            calendar_id: &str,
+            _start: Option<DateTime<Utc>>,
+            _end: Option<DateTime<Utc>>,
+        ) -> Result<Vec<UnifiedCalendarEvent>> {
+            let events = self.events.lock().unwrap();
+            Ok(events
+                .iter()
+                .filter(|(calendar, _)| calendar == calendar_id)
+                .map(|(_, event)| event.clone())
+                .collect())
+        }
+
+        async fn create_event(
+            &self,
+            calendar_id: &str,
+            event: UnifiedCalendarEvent,
+        ) -> Result<UnifiedCalendarEvent> {
+            if event.title.as_deref() == Some("Broken") {
+                return Err(CalblendError::InvalidData("broken".to_string()));
+            }
+            let mut events = self.events.lock().unwrap();
+            let mut event = event;
+            event.id = format!("new-{}", events.len() + 1);
+            events.push((calendar_id.to_string(), event.clone()));
+            Ok(event)
+        }
+
+        async fn update_event(
+            &self,
+            _calendar_id: &str,
+            _event_id: &str,
+            event: UnifiedCalendarEvent,
+        ) -> Result<UnifiedCalendarEvent> {
+            Ok(event)
+        }
+
+        async fn delete_event(&self, _calendar_id: &str, _event_id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_free_busy(
+            &self,
+            _calendar_ids: &[String],
+            _start: DateTime<Utc>,
+            _end: DateTime<Utc>,
+        ) -> Result<Vec<FreeBusyPeriod>> {
+            Ok(vec![])
+        }
+    }
+
+    fn calendar(id: &str) -> Calendar {
+        Calendar {
+            id: id.to_string(),
+            name: format!("Calendar {}", id),
+            description: None,
+            color: None,
+            is_primary: id == "primary",
+            can_write: true,
+            source: CalendarSource::Google,
+            foreground_color: None,
+            time_zone: Some("Europe/Berlin".to_string()),
+            access_role: None,
+            default_reminders: None,
+            conference_types: None,
+            hidden: None,
+            selected: None,
+        }
+    }
+
+    fn event(id: &str, uid: &str, start: &str) -> UnifiedCalendarEvent {
+        let moment = |s: &str| {
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+                Some("Europe/Berlin".to_string()),
+            )
+        };
+        let mut event = UnifiedCalendarEvent::new(
+            id.to_string(),
+            CalendarSource::Google,
+            moment(start),
+            moment(start),
+        );
+        event.ical_uid = Some(uid.to_string());
+        event.title = Some(uid.to_string());
+        event.etag = Some("\"1\"".to_string());
+        event.raw = Some(serde_json::json!({ "id": id, "kind": "calendar#event" }));
+        event
+    }
+
+    fn source() -> MemoryProvider {
+        let mut standup = event("standup", "standup", "2024-03-11T09:00:00+01:00");
+        standup.recurrence_rule = Some("FREQ=DAILY".to_string());
+        // The exception is listed before its series
+        let mut moved = event("standup_20240312", "standup", "2024-03-12T10:00:00+01:00");
+        moved.series_id = Some("standup".to_string());
+        moved.original_start = Some(standup.start.clone());
+        let events = vec![
+            ("primary".to_string(), moved),
+            ("primary".to_string(), standup),
+            (
+                "primary".to_string(),
+                event("review", "review", "2024-03-13T14:00:00+01:00"),
+            ),
+            (
+                "team".to_string(),
+                event("offsite", "offsite", "2024-04-01T09:00:00+02:00"),
+            ),
+        ];
+        MemoryProvider {
+            calendars: vec![calendar("primary"), calendar("team")],
+            events: Mutex::new(events),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backup_archive_round_trip() {
+        let backup = backup_account(&source(), &BackupOptions::new())
+            .await
+            .unwrap();
+        assert_eq!(backup.provider, "memory");
+        assert_eq!(backup.calendars.len(), 2);
+
+        let files = backup.to_files().unwrap();
+        let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "manifest.json",
+                "calendars/001.json",
+                "calendars/001.ics",
+                "calendars/002.json",
+                "calendars/002.ics",
+            ]
+        );
+        let manifest: Value = serde_json::from_str(&files[0].1).unwrap();
+        assert_eq!(manifest["format"], "calblend-backup");
+        assert_eq!(manifest["calendars"][0]["calendar"]["kind"], "calendar");
+        assert_eq!(manifest["calendars"][0]["event_count"], 3);
+        assert!(files[2].1.contains("UID:standup"));
+
+        let dir = std::env::temp_dir().join(format!("calblend-backup-{}", uuid::Uuid::new_v4()));
+        backup.write_dir(&dir).unwrap();
+        let read = AccountBackup::read_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(read.created, backup.created);
+        assert_eq!(read.calendars[0].calendar.name, "Calendar primary");
+        let review = &read.calendars[0].events[2];
+        assert_eq!(
+            review.raw,
+            Some(serde_json::json!({ "id": "review", "kind": "calendar#event" }))
+        );
+        assert_eq!(review.etag.as_deref(), Some("\"1\""));
+
+        // Without the JSON files events come from the ICS files
+        let mut files: BTreeMap<String, String> = files.into_iter().collect();
+        let mut manifest: BackupManifest = serde_json::from_str(&files[MANIFEST_FILE]).unwrap();
+        for entry in &mut manifest.calendars {
+            entry.events = None;
+        }
+        files.insert(
+            MANIFEST_FILE.to_string(),
+            serde_json::to_string(&manifest).unwrap(),
+        );
+        let read = AccountBackup::from_files(&files).unwrap();
+        assert_eq!(read.calendars[0].events.len(), 3);
+        assert_eq!(
+            read.calendars[1].events[0].ical_uid.as_deref(),
+            Some("offsite")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_rejects_bad_archives() {
+        let backup = backup_account(&source(), &BackupOptions::new().with_calendar("team"))
+            .await
+            .unwrap();
+        assert_eq!(backup.calendars.len(), 1);
+        let mut files: BTreeMap<String, String> = backup.to_files().unwrap().into_iter().collect();
+        files.remove("calendars/001.json");
+        assert!(AccountBackup::from_files(&files).is_err());
+
+        let dir = std::env::temp_dir().join(format!("calblend-backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut manifest: Value = serde_json::from_str(&files[MANIFEST_FILE]).unwrap();
+        manifest["calendars"][0]["events"] = "../../etc/passwd".into();
+        std::fs::write(dir.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+        let err = AccountBackup::read_dir(&dir).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(err.to_string().contains("leaves the archive"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_restore_account() {
+        let backup = backup_account(&source(), &BackupOptions::new())
+            .await
+            .unwrap();
+        let target = MemoryProvider {
+            calendars: vec![calendar("work")],
+            ..Default::default()
+        };
+        // The target already has the review
+        target
+            .create_event(
+                "work",
+                event("existing", "review", "2024-03-13T14:00:00+01:00"),
+            )
+            .await
+            .unwrap();
+
+        let options = RestoreOptions::new()
+            .with_calendar_mapping("primary", "work")
+            .with_calendar("primary");
+        let report = restore_account(&target, &backup, &options).await.unwrap();
+        assert_eq!((report.created, report.skipped), (2, 1));
+        assert!(report.errors.is_empty());
+
+        let events = target.list_events("work", None, None).await.unwrap();
+        let series = &events[1];
+        assert_eq!(series.ical_uid.as_deref(), Some("standup"));
+        assert!(series.series_id.is_none());
+        assert_eq!(series.calendar_id.as_deref(), Some("work"));
+        assert!(series.raw.is_none() && series.etag.is_none());
+        // The exception points at the new series
+        assert_eq!(events[2].series_id.as_deref(), Some(series.id.as_str()));
+
+        // Everything is present now, so a second run only skips
+        let report = restore_account(&target, &backup, &options).await.unwrap();
+        assert_eq!((report.created, report.skipped), (0, 3));
+
+        // Failures are reported per event
+        let mut backup = backup;
+        backup.calendars[1].events[0].title = Some("Broken".to_string());
+        let options = RestoreOptions::new()
+            .with_invitations(true)
+            .with_skip_existing(false);
+        let report = restore_account(&target, &backup, &options).await.unwrap();
+        assert_eq!(report.created, 3);
+        assert_eq!(
+            report.errors,
+            vec![RestoreError {
+                calendar_id: "team".to_string(),
+                event_id: "offsite".to_string(),
+                message: "Invalid data: broken".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restore_reports_unavoidable_invitations() {
+        let mut backup = backup_account(&source(), &BackupOptions::new().with_calendar("primary"))
+            .await
+            .unwrap();
+        let standup = backup.calendars[0]
+            .events
+            .iter_mut()
+            .find(|event| event.id == "standup")
+            .unwrap();
+        standup.attendees = Some(vec![crate::models::Participant {
+            email: Some("bob@example.com".to_string()),
+            ..Default::default()
+        }]);
+        let target = MemoryProvider {
+            calendars: vec![calendar("primary")],
+            ..Default::default()
+        };
+
+        // The series would notify Bob, so it and its exception are not created
+        let report = restore_account(&target, &backup, &RestoreOptions::new())
+            .await
+            .unwrap();
+        assert_eq!(report.created, 1);
+        let messages: Vec<(&str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| (e.event_id.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "standup",
+                    "Unsupported operation: memory cannot create events without notifying attendees"
+                ),
+                (
+                    "standup_20240312",
+                    "Invalid data: series standup was not restored"
+                ),
+            ]
+        );
+
+        let options = RestoreOptions::new().with_invitations(true);
+        let report = restore_account(&target, &backup, &options).await.unwrap();
+        assert_eq!((report.created, report.skipped), (2, 1));
+        assert!(report.errors.is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+//! Fluent construction of unified events
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+
+use crate::error::{ValidationError, ValidationErrorKind, ValidationErrors};
+use crate::models::{
+    CalendarSource, ConferenceLink, EventMoment, EventStatus, EventVisibility, Participant,
+    Reminder, ShowAs, UnifiedCalendarEvent,
+};
+use crate::recurrence::RecurrencePattern;
+
+/// Builder for [`UnifiedCalendarEvent`], created with
+/// [`UnifiedCalendarEvent::builder`]
+///
+/// `source`, `start` and `end` are required. The id may be left empty for
+/// events that the provider assigns an id to on creation.
+#[derive(Debug, Clone, Default)]
+pub struct EventBuilder {
+    id: String,
+    source: Option<CalendarSource>,
+    calendar_id: Option<String>,
+    ical_uid: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    location: Option<String>,
+    color: Option<String>,
+    categories: Vec<String>,
+    start: Option<EventMoment>,
+    end: Option<EventMoment>,
+    recurrence_rule: Option<String>,
+    recurrence: Option<RecurrencePattern>,
+    organizer: Option<Participant>,
+    attendees: Vec<Participant>,
+    status: Option<EventStatus>,
+    visibility: Option<EventVisibility>,
+    show_as: Option<ShowAs>,
+    reminders: Vec<Reminder>,
+    conference: Option<ConferenceLink>,
+}
+
+impl UnifiedCalendarEvent {
+    /// Start building an event
+    pub fn builder() -> EventBuilder {
+        EventBuilder::default()
+    }
+}
+
+impl EventBuilder {
+    /// Set the provider event id
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    /// Set the provider the event belongs to
+    pub fn with_source(mut self, source: CalendarSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Set the calendar the event belongs to
+    pub fn with_calendar_id(mut self, calendar_id: impl Into<String>) -> Self {
+        self.calendar_id = Some(calendar_id.into());
+        self
+    }
+
+    /// Set the iCalendar UID
+    pub fn with_ical_uid(mut self, ical_uid: impl Into<String>) -> Self {
+        self.ical_uid = Some(ical_uid.into());
+        self
+    }
+
+    /// Set the title
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set the location
+    pub fn with_location(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Set the color
+    pub fn with_color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    /// Add a category
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.categories.push(category.into());
+        self
+    }
+
+    /// Set the start
+    pub fn with_start(mut self, start: EventMoment) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Set the end
+    pub fn with_end(mut self, end: EventMoment) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// Set a timed start and end
+    pub fn with_times(self, start: DateTime<FixedOffset>, end: DateTime<FixedOffset>) -> Self {
+        self.with_start(EventMoment::timed(start, None))
+            .with_end(EventMoment::timed(end, None))
+    }
+
+    /// Set an all-day span, `end` being exclusive
+    pub fn with_dates(self, start: NaiveDate, end: NaiveDate) -> Self {
+        self.with_start(EventMoment::all_day(start))
+            .with_end(EventMoment::all_day(end))
+    }
+
+    /// Set the RRULE value, checked when the event is built
+    pub fn with_recurrence_rule(mut self, rule: impl Into<String>) -> Self {
+        self.recurrence_rule = Some(rule.into());
+        self
+    }
+
+    /// Set the typed recurrence, which takes precedence over a raw RRULE
+    pub fn with_recurrence(mut self, recurrence: RecurrencePattern) -> Self {
+        self.recurrence = Some(recurrence);
+        self
+    }
+
+    /// Set the organizer
+    pub fn with_organizer(mut self, organizer: Participant) -> Self {
+        self.organizer = Some(organizer);
+        self
+    }
+
+    /// Add an attendee
+    pub fn with_attendee(mut self, attendee: Participant) -> Self {
+        self.attendees.push(attendee);
+        self
+    }
+
+    /// Set the status
+    pub fn with_status(mut self, status: EventStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Set the visibility
+    pub fn with_visibility(mut self, visibility: EventVisibility) -> Self {
+        self.visibility = Some(visibility);
+        self
+    }
+
+    /// Set how the event shows on free/busy
+    pub fn with_show_as(mut self, show_as: ShowAs) -> Self {
+        self.show_as = Some(show_as);
+        self
+    }
+
+    /// Add a reminder
+    pub fn with_reminder(mut self, reminder: Reminder) -> Self {
+        self.reminders.push(reminder);
+        self
+    }
+
+    /// Set the conference link
+    pub fn with_conference(mut self, conference: ConferenceLink) -> Self {
+        self.conference = Some(conference);
+        self
+    }
+
+    /// Build and validate the event
+    pub fn build(self) -> std::result::Result<UnifiedCalendarEvent, ValidationErrors> {
+        let (source, start, end) = match (self.source, self.start, self.end) {
+            (Some(source), Some(start), Some(end)) => (source, start, end),
+            (source, start, end) => {
+                let missing = [
+                    ("source", source.is_none()),
+                    ("start", start.is_none()),
+                    ("end", end.is_none()),
+                ];
+                return Err(ValidationErrors(
+                    missing
+                        .into_iter()
+                        .filter(|(_, missing)| *missing)
+                        .map(|(field, _)| {
+                            ValidationError::new(
+                                field,
+                                ValidationErrorKind::Required,
+                                "Value is required",
+                            )
+                        })
+                        .collect(),
+                ));
+            }
+        };
+
+        let mut event = UnifiedCalendarEvent::new(self.id, source, start, end);
+        event.calendar_id = self.calendar_id;
+        event.ical_uid = self.ical_uid;
+        event.title = self.title;
+        event.description = self.description;
+        event.location = self.location;
+        event.color = self.color;
+        event.categories = non_empty(self.categories);
+        event.organizer = self.organizer;
+        event.attendees = non_empty(self.attendees);
+        event.status = self.status;
+        event.visibility = self.visibility;
+        event.show_as = self.show_as;
+        event.reminders = non_empty(self.reminders);
+        event.conference = self.conference;
+        match self.recurrence {
+            Some(pattern) => event.set_recurrence(Some(pattern)),
+            None => event.recurrence_rule = self.recurrence_rule,
+        }
+
+        event.validate()?;
+        Ok(event)
+    }
+}
+
+fn non_empty<T>(items: Vec<T>) -> Option<Vec<T>> {
+    if items.is_empty() {
+        None
+    } else {
+        Some(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recurrence::{Frequency, RecurrenceRule};
+
+    fn at(value: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(value).unwrap()
+    }
+
+    #[test]
+    fn test_builds_event() {
+        let event = UnifiedCalendarEvent::builder()
+            .with_source(CalendarSource::Google)
+            .with_title("Standup")
+            .with_times(
+                at("2024-03-10T09:00:00-05:00"),
+                at("2024-03-10T09:15:00-05:00"),
+            )
+            .with_recurrence(RecurrencePattern::new(RecurrenceRule::new(
+                Frequency::Daily,
+            )))
+            .with_attendee(Participant {
+                email: Some("ada@example.com".to_string()),
+                ..Default::default()
+            })
+            .with_category("Team")
+            .build()
+            .unwrap();
+
+        assert_eq!(event.id, "");
+        assert_eq!(event.title.as_deref(), Some("Standup"));
+        assert_eq!(event.recurrence_rule.as_deref(), Some("FREQ=DAILY"));
+        assert_eq!(event.attendees.unwrap().len(), 1);
+        assert_eq!(event.categories, Some(vec!["Team".to_string()]));
+        assert!(event.reminders.is_none());
+    }
+
+    #[test]
+    fn test_missing_required_fields() {
+        let errors = UnifiedCalendarEvent::builder()
+            .with_start(EventMoment::all_day(
+                NaiveDate::from_ymd_opt(2024, 3, 10).unwrap(),
+            ))
+            .build()
+            .unwrap_err();
+        let fields: Vec<_> = errors.0.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["source", "end"]);
+        assert!(errors
+            .0
+            .iter()
+            .all(|e| e.kind == ValidationErrorKind::Required));
+    }
+
+    #[test]
+    fn test_build_validates() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let errors = UnifiedCalendarEvent::builder()
+            .with_source(CalendarSource::Outlook)
+            .with_dates(day(12), day(10))
+            .with_recurrence_rule("FREQ=WEEKLY;BYDAY=XX")
+            .build()
+            .unwrap_err();
+        assert!(errors.has(ValidationErrorKind::EndBeforeStart));
+        assert!(errors.has(ValidationErrorKind::InvalidRecurrence));
+    }
+}
@@ -62,22 +62,42 @@ impl CalendarCache {
         *cache = Some(CacheEntry::new(calendars, self.default_ttl));
     }
 
+    /// Create cache key based on calendar ID, date range and request variant
+    ///
+    /// `variant` distinguishes requests for the same range that return
+    /// different data (e.g. partial responses or server-side filters).
+    fn events_key(
+        calendar_id: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        variant: &str,
+    ) -> String {
+        format!(
+            "{}_{}_{}_{}",
+            calendar_id,
+            start.map(|d| d.timestamp()).unwrap_or(0),
+            end.map(|d| d.timestamp()).unwrap_or(0),
+            variant
+        )
+    }
+
+    /// Invalidate cached calendars
+    pub async fn invalidate_calendars(&self) {
+        let mut cache = self.calendars.write().await;
+        *cache = None;
+    }
+
     /// Get cached events for a calendar
     pub async fn get_events(
         &self,
         calendar_id: &str,
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
+        variant: &str,
     ) -> Option<Vec<UnifiedCalendarEvent>> {
         let cache = self.events.read().await;
         
-        // Create cache key based on calendar ID and date range
-        let cache_key = format!(
-            "{}_{}_{}", 
-            calendar_id,
-            start.map(|d| d.timestamp()).unwrap_or(0),
-            end.map(|d| d.timestamp()).unwrap_or(0)
-        );
+        let cache_key = Self::events_key(calendar_id, start, end, variant);
         
         cache.get(&cache_key)
             .filter(|entry| !entry.is_expired())
@@ -90,16 +110,12 @@ impl CalendarCache {
         calendar_id: &str,
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
+        variant: &str,
         events: Vec<UnifiedCalendarEvent>,
     ) {
         let mut cache = self.events.write().await;
         
-        let cache_key = format!(
-            "{}_{}_{}", 
-            calendar_id,
-            start.map(|d| d.timestamp()).unwrap_or(0),
-            end.map(|d| d.timestamp()).unwrap_or(0)
-        );
+        let cache_key = Self::events_key(calendar_id, start, end, variant);
         
         // Use shorter TTL for events (5 minutes)
         let ttl = Duration::minutes(5);
@@ -114,6 +130,12 @@ impl CalendarCache {
         cache.retain(|key, _| !key.starts_with(calendar_id));
     }
 
+    /// Invalidate events cache for every calendar
+    pub async fn invalidate_all_events(&self) {
+        let mut cache = self.events.write().await;
+        cache.clear();
+    }
+
     /// Get cached free/busy data
     pub async fn get_free_busy(
         &self,
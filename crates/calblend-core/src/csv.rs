@@ -0,0 +1,1008 @@
+//! CSV import and export of events
+//!
+//! A [`CsvFormat`] maps columns to event fields and says how dates, times,
+//! booleans and lists are written. [`CsvFormat::google`] and
+//! [`CsvFormat::outlook`] match the files those products import and export;
+//! [`CsvFormat::default`] covers most event fields with ISO dates. Columns
+//! are matched by header on import, so their order does not matter and
+//! unknown columns are ignored.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+
+use crate::error::{CalblendError, Result};
+use crate::models::{
+    CalendarSource, ConferenceLink, EventMoment, EventStatus, EventVisibility, Participant,
+    Reminder, ReminderMethod, ShowAs, UnifiedCalendarEvent,
+};
+use crate::recurrence::RecurrencePattern;
+use crate::timezone::{parse_time_zone, resolve_local, windows_to_iana};
+use crate::validation::check_date_time_formats;
+
+/// Time formats tried after the format's own when reading
+const FALLBACK_TIME_FORMATS: [&str; 4] = ["%H:%M", "%H:%M:%S", "%I:%M %p", "%I:%M:%S %p"];
+
+/// Event field held by a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvField {
+    /// iCalendar UID
+    Uid,
+    Title,
+    Description,
+    Location,
+    /// Categories, separated by the list separator
+    Categories,
+    StartDate,
+    StartTime,
+    EndDate,
+    EndTime,
+    AllDay,
+    /// IANA or Windows zone name of the row's times
+    TimeZone,
+    /// Boolean, private or confidential visibility
+    Private,
+    /// Outlook sensitivity: `Normal`, `Personal`, `Private` or `Confidential`
+    Sensitivity,
+    /// `Confirmed`, `Tentative` or `Cancelled`
+    Status,
+    /// `Busy`, `Free`, `Oof`, `WorkingElsewhere` or `Unknown`
+    ShowAs,
+    /// Outlook "Show time as" code: 0 free, 1 tentative, 2 busy, 3 out of
+    /// office, 4 working elsewhere
+    ShowAsCode,
+    /// Organizer email, or name when there is none
+    Organizer,
+    /// Attendees that are neither optional nor resources
+    RequiredAttendees,
+    OptionalAttendees,
+    /// Rooms and other resources
+    Resources,
+    /// Boolean, whether the event has a reminder
+    ReminderOn,
+    /// Date of the first reminder
+    ReminderDate,
+    /// Time of the first reminder
+    ReminderTime,
+    /// Minutes before the start of the first reminder
+    ReminderMinutes,
+    /// RRULE value, e.g. `FREQ=WEEKLY;BYDAY=MO`
+    RecurrenceRule,
+    ConferenceUrl,
+    /// Written empty and ignored on import
+    Ignore,
+}
+
+/// A column: its header and the field it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvColumn {
+    pub header: String,
+    pub field: CsvField,
+}
+
+impl CsvColumn {
+    pub fn new(header: impl Into<String>, field: CsvField) -> Self {
+        Self {
+            header: header.into(),
+            field,
+        }
+    }
+}
+
+/// Column mapping and value formats of a CSV file
+#[derive(Debug, Clone)]
+pub struct CsvFormat {
+    pub columns: Vec<CsvColumn>,
+    pub delimiter: u8,
+    /// chrono format of dates
+    pub date_format: String,
+    /// chrono format of times
+    pub time_format: String,
+    /// Separator of list values such as attendees
+    pub list_separator: char,
+    /// Words written for true and false; reading also accepts `yes`, `1`, ...
+    pub true_value: String,
+    pub false_value: String,
+    /// Whether the end date of an all-day event is its last day rather than
+    /// the day after
+    pub inclusive_all_day_end: bool,
+    /// Zone times are written in and read as, unless a row names its own;
+    /// events keep their own zone on export and UTC is used on import when
+    /// unset
+    pub time_zone: Option<String>,
+}
+
+impl Default for CsvFormat {
+    /// Most event fields, with ISO dates and 24-hour times
+    fn default() -> Self {
+        use CsvField::*;
+        Self::new(vec![
+            CsvColumn::new("UID", Uid),
+            CsvColumn::new("Title", Title),
+            CsvColumn::new("Start Date", StartDate),
+            CsvColumn::new("Start Time", StartTime),
+            CsvColumn::new("End Date", EndDate),
+            CsvColumn::new("End Time", EndTime),
+            CsvColumn::new("All Day", AllDay),
+            CsvColumn::new("Time Zone", TimeZone),
+            CsvColumn::new("Location", Location),
+            CsvColumn::new("Description", Description),
+            CsvColumn::new("Categories", Categories),
+            CsvColumn::new("Organizer", Organizer),
+            CsvColumn::new("Attendees", RequiredAttendees),
+            CsvColumn::new("Optional Attendees", OptionalAttendees),
+            CsvColumn::new("Resources", Resources),
+            CsvColumn::new("Status", Status),
+            CsvColumn::new("Show As", ShowAs),
+            CsvColumn::new("Private", Private),
+            CsvColumn::new("Reminder Minutes", ReminderMinutes),
+            CsvColumn::new("Recurrence", RecurrenceRule),
+            CsvColumn::new("Conference URL", ConferenceUrl),
+        ])
+    }
+}
+
+impl CsvFormat {
+    /// A format with the given columns, ISO dates and 24-hour times
+    pub fn new(columns: Vec<CsvColumn>) -> Self {
+        Self {
+            columns,
+            delimiter: b',',
+            date_format: "%Y-%m-%d".to_string(),
+            time_format: "%H:%M".to_string(),
+            list_separator: ';',
+            true_value: "true".to_string(),
+            false_value: "false".to_string(),
+            inclusive_all_day_end: false,
+            time_zone: None,
+        }
+    }
+
+    /// Google Calendar's import format
+    pub fn google() -> Self {
+        use CsvField::*;
+        Self {
+            date_format: "%m/%d/%Y".to_string(),
+            time_format: "%I:%M %p".to_string(),
+            true_value: "True".to_string(),
+            false_value: "False".to_string(),
+            inclusive_all_day_end: true,
+            ..Self::new(vec![
+                CsvColumn::new("Subject", Title),
+                CsvColumn::new("Start Date", StartDate),
+                CsvColumn::new("Start Time", StartTime),
+                CsvColumn::new("End Date", EndDate),
+                CsvColumn::new("End Time", EndTime),
+                CsvColumn::new("All Day Event", AllDay),
+                CsvColumn::new("Description", Description),
+                CsvColumn::new("Location", Location),
+                CsvColumn::new("Private", Private),
+            ])
+        }
+    }
+
+    /// Outlook's export and import format
+    pub fn outlook() -> Self {
+        use CsvField::*;
+        Self {
+            date_format: "%-m/%-d/%Y".to_string(),
+            time_format: "%-I:%M:%S %p".to_string(),
+            true_value: "True".to_string(),
+            false_value: "False".to_string(),
+            ..Self::new(vec![
+                CsvColumn::new("Subject", Title),
+                CsvColumn::new("Start Date", StartDate),
+                CsvColumn::new("Start Time", StartTime),
+                CsvColumn::new("End Date", EndDate),
+                CsvColumn::new("End Time", EndTime),
+                CsvColumn::new("All day event", AllDay),
+                CsvColumn::new("Reminder on/off", ReminderOn),
+                CsvColumn::new("Reminder Date", ReminderDate),
+                CsvColumn::new("Reminder Time", ReminderTime),
+                CsvColumn::new("Meeting Organizer", Organizer),
+                CsvColumn::new("Required Attendees", RequiredAttendees),
+                CsvColumn::new("Optional Attendees", OptionalAttendees),
+                CsvColumn::new("Meeting Resources", Resources),
+                CsvColumn::new("Billing Information", Ignore),
+                CsvColumn::new("Categories", Categories),
+                CsvColumn::new("Description", Description),
+                CsvColumn::new("Location", Location),
+                CsvColumn::new("Mileage", Ignore),
+                CsvColumn::new("Priority", Ignore),
+                CsvColumn::new("Private", Private),
+                CsvColumn::new("Sensitivity", Sensitivity),
+                CsvColumn::new("Show time as", ShowAsCode),
+            ])
+        }
+    }
+
+    pub fn with_column(mut self, header: impl Into<String>, field: CsvField) -> Self {
+        self.columns.push(CsvColumn::new(header, field));
+        self
+    }
+
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_date_format(mut self, format: impl Into<String>) -> Self {
+        self.date_format = format.into();
+        self
+    }
+
+    pub fn with_time_format(mut self, format: impl Into<String>) -> Self {
+        self.time_format = format.into();
+        self
+    }
+
+    pub fn with_time_zone(mut self, time_zone: impl Into<String>) -> Self {
+        self.time_zone = Some(time_zone.into());
+        self
+    }
+}
+
+/// A row that could not be imported
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("row {row}{}: {message}", .column.as_ref().map(|c| format!(", column {:?}", c)).unwrap_or_default())]
+pub struct CsvRowError {
+    /// Line the row starts on, counting the header as line 1
+    pub row: usize,
+    /// Header of the offending column
+    pub column: Option<String>,
+    pub message: String,
+}
+
+/// Events read from a CSV file
+#[derive(Debug, Clone, Default)]
+pub struct CsvImportResult {
+    pub events: Vec<UnifiedCalendarEvent>,
+    /// Rows that were skipped, with the reason
+    pub errors: Vec<CsvRowError>,
+}
+
+/// Write events as CSV with a header row
+pub fn write_csv(events: &[UnifiedCalendarEvent], format: &CsvFormat) -> Result<String> {
+    check_date_time_formats(&format.date_format, &format.time_format)?;
+    let zone = match &format.time_zone {
+        Some(name) => Some(zone(name)?),
+        None => None,
+    };
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(format.delimiter)
+        .from_writer(Vec::new());
+    writer
+        .write_record(format.columns.iter().map(|c| c.header.as_str()))
+        .map_err(csv_error)?;
+    for event in events {
+        let row = RowWriter::new(event, format, zone);
+        writer
+            .write_record(format.columns.iter().map(|c| row.value(c.field)))
+            .map_err(csv_error)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| CalblendError::InternalError(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| CalblendError::InternalError(e.to_string()))
+}
+
+fn csv_error(error: ::csv::Error) -> CalblendError {
+    CalblendError::InternalError(format!("CSV error: {}", error))
+}
+
+fn zone(name: &str) -> Result<Tz> {
+    parse_time_zone(name).or_else(|e| match windows_to_iana(name) {
+        Some(iana) => parse_time_zone(iana),
+        None => Err(e),
+    })
+}
+
+/// Values of one event, rendered on the wall clock of the export zone
+struct RowWriter<'a> {
+    event: &'a UnifiedCalendarEvent,
+    format: &'a CsvFormat,
+    zone_name: Option<String>,
+    start: (NaiveDate, Option<NaiveTime>),
+    end: (NaiveDate, Option<NaiveTime>),
+}
+
+impl<'a> RowWriter<'a> {
+    fn new(event: &'a UnifiedCalendarEvent, format: &'a CsvFormat, zone: Option<Tz>) -> Self {
+        let event_zone = event
+            .start
+            .time_zone()
+            .and_then(|name| parse_time_zone(name).ok());
+        let zone = zone.or(event_zone);
+        let local = |moment: &EventMoment| match moment {
+            EventMoment::Date { date, .. } => (*date, None),
+            EventMoment::DateTime { date_time, .. } => {
+                let local = match zone {
+                    Some(tz) => date_time.with_timezone(&tz).naive_local(),
+                    None => date_time.naive_utc(),
+                };
+                (local.date(), Some(local.time()))
+            }
+        };
+        let mut end = local(&event.end);
+        if event.is_all_day() && format.inclusive_all_day_end {
+            end.0 = (end.0 - Duration::days(1)).max(local(&event.start).0);
+        }
+        Self {
+            event,
+            format,
+            zone_name: match (&format.time_zone, zone) {
+                (Some(name), _) => Some(name.clone()),
+                (None, Some(tz)) => Some(tz.name().to_string()),
+                (None, None) if event.is_all_day() => None,
+                (None, None) => Some("UTC".to_string()),
+            },
+            start: local(&event.start),
+            end,
+        }
+    }
+
+    fn value(&self, field: CsvField) -> String {
+        let event = self.event;
+        let date = |date: NaiveDate| date.format(&self.format.date_format).to_string();
+        let time = |time: Option<NaiveTime>| {
+            time.map(|t| t.format(&self.format.time_format).to_string())
+                .unwrap_or_default()
+        };
+        let boolean = |value: bool| {
+            if value {
+                self.format.true_value.clone()
+            } else {
+                self.format.false_value.clone()
+            }
+        };
+        let list = |values: Vec<String>| values.join(&format!("{} ", self.format.list_separator));
+        let reminder = event.reminders.as_ref().and_then(|r| r.first());
+        let reminder_at = reminder.map(|reminder| {
+            let start = self
+                .start
+                .0
+                .and_time(self.start.1.unwrap_or(NaiveTime::MIN));
+            start - Duration::minutes(i64::from(reminder.minutes_before))
+        });
+
+        match field {
+            CsvField::Uid => event.ical_uid.clone().unwrap_or_default(),
+            CsvField::Title => event.title.clone().unwrap_or_default(),
+            CsvField::Description => event.description.clone().unwrap_or_default(),
+            CsvField::Location => event.location.clone().unwrap_or_default(),
+            CsvField::Categories => list(event.categories.clone().unwrap_or_default()),
+            CsvField::StartDate => date(self.start.0),
+            CsvField::StartTime => time(self.start.1),
+            CsvField::EndDate => date(self.end.0),
+            CsvField::EndTime => time(self.end.1),
+            CsvField::AllDay => boolean(event.is_all_day()),
+            CsvField::TimeZone => self.zone_name.clone().unwrap_or_default(),
+            CsvField::Private => boolean(matches!(
+                event.visibility,
+                Some(EventVisibility::Private | EventVisibility::Confidential)
+            )),
+            CsvField::Sensitivity => match event.visibility {
+                Some(EventVisibility::Private) => "Private",
+                Some(EventVisibility::Confidential) => "Confidential",
+                _ => "Normal",
+            }
+            .to_string(),
+            CsvField::Status => match event.status {
+                Some(EventStatus::Confirmed) => "Confirmed",
+                Some(EventStatus::Tentative) => "Tentative",
+                Some(EventStatus::Cancelled) => "Cancelled",
+                None => "",
+            }
+            .to_string(),
+            CsvField::ShowAs => event
+                .show_as
+                .as_ref()
+                .map(|show_as| format!("{:?}", show_as))
+                .unwrap_or_default(),
+            CsvField::ShowAsCode => {
+                let code = match (&event.show_as, &event.status) {
+                    (Some(ShowAs::Free), _) => 0,
+                    (_, Some(EventStatus::Tentative)) => 1,
+                    (Some(ShowAs::Oof), _) => 3,
+                    (Some(ShowAs::WorkingElsewhere), _) => 4,
+                    _ => 2,
+                };
+                code.to_string()
+            }
+            CsvField::Organizer => event
+                .organizer
+                .as_ref()
+                .and_then(|o| o.email.clone().or_else(|| o.name.clone()))
+                .unwrap_or_default(),
+            CsvField::RequiredAttendees | CsvField::OptionalAttendees | CsvField::Resources => {
+                let addresses = event
+                    .attendees
+                    .iter()
+                    .flatten()
+                    .filter(|a| a.organizer != Some(true) && attendee_field(a) == field)
+                    .filter_map(|a| a.email.clone().or_else(|| a.name.clone()))
+                    .collect();
+                list(addresses)
+            }
+            CsvField::ReminderOn => boolean(reminder.is_some()),
+            CsvField::ReminderDate => reminder_at.map(|at| date(at.date())).unwrap_or_default(),
+            CsvField::ReminderTime => reminder_at
+                .map(|at| time(Some(at.time())))
+                .unwrap_or_default(),
+            CsvField::ReminderMinutes => reminder
+                .map(|r| r.minutes_before.to_string())
+                .unwrap_or_default(),
+            CsvField::RecurrenceRule => event.recurrence_rule.clone().unwrap_or_default(),
+            CsvField::ConferenceUrl => event
+                .conference
+                .as_ref()
+                .and_then(|c| c.url.clone())
+                .unwrap_or_default(),
+            CsvField::Ignore => String::new(),
+        }
+    }
+}
+
+/// Which attendee column a participant belongs in
+fn attendee_field(attendee: &Participant) -> CsvField {
+    if attendee.resource == Some(true) {
+        CsvField::Resources
+    } else if attendee.optional == Some(true) {
+        CsvField::OptionalAttendees
+    } else {
+        CsvField::RequiredAttendees
+    }
+}
+
+/// Read events from CSV with a header row
+///
+/// Rows that cannot be read are skipped and reported with their line, so one
+/// bad row does not stop the import. A missing start date column fails the
+/// whole file.
+pub fn read_csv(text: &str, format: &CsvFormat, source: CalendarSource) -> Result<CsvImportResult> {
+    check_date_time_formats(&format.date_format, &format.time_format)?;
+    let default_zone = match &format.time_zone {
+        Some(name) => Some((name.clone(), zone(name)?)),
+        None => None,
+    };
+    let text = text.trim_start_matches('\u{feff}');
+    // The reader does not count blank lines, and a record's position is
+    // where the blank lines before it start
+    let line = |position: Option<&::csv::Position>| {
+        position.map_or(0, |p| {
+            let before = &text.as_bytes()[..p.byte() as usize];
+            let blank = text.as_bytes()[before.len()..]
+                .iter()
+                .take_while(|b| matches!(b, b'\r' | b'\n'))
+                .filter(|b| **b == b'\n')
+                .count();
+            before.iter().filter(|b| **b == b'\n').count() + blank + 1
+        })
+    };
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(format.delimiter)
+        .flexible(true)
+        .trim(::csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let columns: Vec<Option<&CsvColumn>> = headers
+        .iter()
+        .map(|header| {
+            format
+                .columns
+                .iter()
+                .find(|c| c.header.eq_ignore_ascii_case(header) && c.field != CsvField::Ignore)
+        })
+        .collect();
+    if !columns
+        .iter()
+        .flatten()
+        .any(|c| c.field == CsvField::StartDate)
+    {
+        return Err(CalblendError::InvalidData(
+            "CSV has no start date column".to_string(),
+        ));
+    }
+
+    let mut result = CsvImportResult::default();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                result.errors.push(CsvRowError {
+                    row: line(e.position()),
+                    column: None,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let row = line(record.position());
+        let values: Vec<(&CsvColumn, &str)> = columns
+            .iter()
+            .zip(record.iter())
+            .filter_map(|(column, value)| Some(((*column)?, value)))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        let reader = RowReader {
+            format,
+            values: &values,
+            default_zone: &default_zone,
+        };
+        match reader.event(source) {
+            Ok(event) => result.events.push(event),
+            Err((column, message)) => result.errors.push(CsvRowError {
+                row,
+                column,
+                message,
+            }),
+        }
+    }
+    Ok(result)
+}
+
+/// Error of a row: the column at fault, if one is, and what was wrong
+type RowError = (Option<String>, String);
+
+struct RowReader<'a> {
+    format: &'a CsvFormat,
+    values: &'a [(&'a CsvColumn, &'a str)],
+    default_zone: &'a Option<(String, Tz)>,
+}
+
+impl RowReader<'_> {
+    fn get(&self, field: CsvField) -> Option<(&CsvColumn, &str)> {
+        self.values
+            .iter()
+            .find(|(column, _)| column.field == field)
+            .map(|(column, value)| (*column, *value))
+    }
+
+    fn text(&self, field: CsvField) -> Option<String> {
+        self.get(field).map(|(_, value)| value.to_string())
+    }
+
+    fn list(&self, field: CsvField) -> Vec<String> {
+        self.get(field)
+            .map(|(_, value)| {
+                value
+                    .split(self.format.list_separator)
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn parse<T>(
+        &self,
+        field: CsvField,
+        parse: impl Fn(&str) -> Option<T>,
+        expected: &str,
+    ) -> std::result::Result<Option<T>, RowError> {
+        match self.get(field) {
+            None => Ok(None),
+            Some((column, value)) => parse(value).map(Some).ok_or_else(|| {
+                (
+                    Some(column.header.clone()),
+                    format!("Invalid {} {:?}", expected, value),
+                )
+            }),
+        }
+    }
+
+    fn date(&self, field: CsvField) -> std::result::Result<Option<NaiveDate>, RowError> {
+        let format = self.format.date_format.replace("%-", "%");
+        self.parse(
+            field,
+            |value| {
+                NaiveDate::parse_from_str(value, &format)
+                    .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d"))
+                    .ok()
+            },
+            "date",
+        )
+    }
+
+    fn time(&self, field: CsvField) -> std::result::Result<Option<NaiveTime>, RowError> {
+        let format = self.format.time_format.replace("%-", "%");
+        self.parse(
+            field,
+            |value| {
+                std::iter::once(format.as_str())
+                    .chain(FALLBACK_TIME_FORMATS)
+                    .find_map(|f| NaiveTime::parse_from_str(&value.to_uppercase(), f).ok())
+            },
+            "time",
+        )
+    }
+
+    fn boolean(&self, field: CsvField) -> std::result::Result<Option<bool>, RowError> {
+        let format = self.format;
+        self.parse(
+            field,
+            |value| {
+                if value.eq_ignore_ascii_case(&format.true_value) {
+                    return Some(true);
+                }
+                if value.eq_ignore_ascii_case(&format.false_value) {
+                    return Some(false);
+                }
+                match value.to_ascii_lowercase().as_str() {
+                    "true" | "yes" | "y" | "1" | "x" => Some(true),
+                    "false" | "no" | "n" | "0" => Some(false),
+                    _ => None,
+                }
+            },
+            "boolean",
+        )
+    }
+
+    fn event(&self, source: CalendarSource) -> std::result::Result<UnifiedCalendarEvent, RowError> {
+        let start_date = self
+            .date(CsvField::StartDate)?
+            .ok_or_else(|| (None, "Missing start date".to_string()))?;
+        let start_time = self.time(CsvField::StartTime)?;
+        let all_day = self
+            .boolean(CsvField::AllDay)?
+            .unwrap_or(start_time.is_none());
+        let end_date = self.date(CsvField::EndDate)?;
+        let end_time = self.time(CsvField::EndTime)?;
+
+        let zone = match self.get(CsvField::TimeZone) {
+            Some((column, name)) => {
+                Some(zone(name).map(|tz| (name.to_string(), tz)).map_err(|_| {
+                    (
+                        Some(column.header.clone()),
+                        format!("Unknown time zone {:?}", name),
+                    )
+                })?)
+            }
+            None => self.default_zone.clone(),
+        };
+        let moment = |local: NaiveDateTime| match &zone {
+            Some((name, tz)) => EventMoment::timed(resolve_local(*tz, local), Some(name.clone())),
+            None => EventMoment::timed(local.and_utc().fixed_offset(), None),
+        };
+
+        let (start, end) = if all_day {
+            let mut end = end_date.unwrap_or(start_date);
+            if self.format.inclusive_all_day_end || end <= start_date {
+                end += Duration::days(1);
+            }
+            if end <= start_date {
+                return Err((None, "End date is before the start date".to_string()));
+            }
+            (EventMoment::all_day(start_date), EventMoment::all_day(end))
+        } else {
+            let start_time = start_time.unwrap_or(NaiveTime::MIN);
+            let start = start_date.and_time(start_time);
+            let end = end_date
+                .unwrap_or(start_date)
+                .and_time(end_time.unwrap_or(start_time));
+            if end < start {
+                return Err((None, "End is before the start".to_string()));
+            }
+            (moment(start), moment(end))
+        };
+
+        let mut event = UnifiedCalendarEvent::new(String::new(), source, start, end);
+        event.ical_uid = self.text(CsvField::Uid);
+        event.title = self.text(CsvField::Title);
+        event.description = self.text(CsvField::Description);
+        event.location = self.text(CsvField::Location);
+        let categories = self.list(CsvField::Categories);
+        event.categories = (!categories.is_empty()).then_some(categories);
+        event.conference = self
+            .text(CsvField::ConferenceUrl)
+            .map(|url| ConferenceLink {
+                url: Some(url),
+                provider: None,
+            });
+
+        if self.boolean(CsvField::Private)? == Some(true) {
+            event.visibility = Some(EventVisibility::Private);
+        }
+        event.visibility = self
+            .parse(
+                CsvField::Sensitivity,
+                |value| match value.to_ascii_lowercase().as_str() {
+                    "normal" => Some(event.visibility.clone()),
+                    "personal" | "private" => Some(Some(EventVisibility::Private)),
+                    "confidential" => Some(Some(EventVisibility::Confidential)),
+                    _ => None,
+                },
+                "sensitivity",
+            )?
+            .unwrap_or(event.visibility.clone());
+        event.status = self.parse(
+            CsvField::Status,
+            |value| match value.to_ascii_lowercase().as_str() {
+                "confirmed" => Some(EventStatus::Confirmed),
+                "tentative" => Some(EventStatus::Tentative),
+                "cancelled" | "canceled" => Some(EventStatus::Cancelled),
+                _ => None,
+            },
+            "status",
+        )?;
+        event.show_as = self.parse(CsvField::ShowAs, show_as, "show-as value")?;
+        if let Some(code) = self.parse(CsvField::ShowAsCode, |v| v.parse::<u8>().ok(), "code")? {
+            event.show_as = Some(match code {
+                0 => ShowAs::Free,
+                3 => ShowAs::Oof,
+                4 => ShowAs::WorkingElsewhere,
+                _ => ShowAs::Busy,
+            });
+            if code == 1 {
+                event.status = Some(EventStatus::Tentative);
+            }
+        }
+
+        event.organizer = self.text(CsvField::Organizer).map(|organizer| Participant {
+            organizer: Some(true),
+            ..participant(&organizer)
+        });
+        let mut attendees = Vec::new();
+        for field in [
+            CsvField::RequiredAttendees,
+            CsvField::OptionalAttendees,
+            CsvField::Resources,
+        ] {
+            attendees.extend(self.list(field).iter().map(|value| Participant {
+                optional: (field == CsvField::OptionalAttendees).then_some(true),
+                resource: (field == CsvField::Resources).then_some(true),
+                ..participant(value)
+            }));
+        }
+        event.attendees = (!attendees.is_empty()).then_some(attendees);
+
+        let minutes = self.parse(
+            CsvField::ReminderMinutes,
+            |v| v.parse::<i32>().ok(),
+            "number of minutes",
+        )?;
+        let reminder_date = self.date(CsvField::ReminderDate)?;
+        let reminder_time = self.time(CsvField::ReminderTime)?;
+        let absolute = reminder_date.map(|date| {
+            let start = start_date.and_time(start_time.unwrap_or(NaiveTime::MIN));
+            let at = date.and_time(reminder_time.unwrap_or(NaiveTime::MIN));
+            i32::try_from((start - at).num_minutes()).unwrap_or(0)
+        });
+        let reminder_on = self.boolean(CsvField::ReminderOn)?;
+        let minutes = match reminder_on {
+            Some(false) => None,
+            Some(true) => Some(minutes.or(absolute).unwrap_or(15)),
+            None => minutes.or(absolute),
+        };
+        if let Some(minutes_before) = minutes {
+            event.reminders = Some(vec![Reminder {
+                minutes_before,
+                method: Some(ReminderMethod::Popup),
+            }]);
+        }
+
+        if let Some((column, rule)) = self.get(CsvField::RecurrenceRule) {
+            let rule = rule.trim_start_matches("RRULE:");
+            let pattern = RecurrencePattern::parse([rule])
+                .map_err(|e| (Some(column.header.clone()), e.to_string()))?;
+            event.set_recurrence(Some(pattern));
+        }
+        Ok(event)
+    }
+}
+
+fn show_as(value: &str) -> Option<ShowAs> {
+    match value
+        .to_ascii_lowercase()
+        .replace([' ', '-', '_'], "")
+        .as_str()
+    {
+        "busy" => Some(ShowAs::Busy),
+        "free" => Some(ShowAs::Free),
+        "oof" | "outofoffice" | "away" => Some(ShowAs::Oof),
+        "workingelsewhere" => Some(ShowAs::WorkingElsewhere),
+        "unknown" => Some(ShowAs::Unknown),
+        _ => None,
+    }
+}
+
+/// Participant for an email address or, without an `@`, a display name
+fn participant(value: &str) -> Participant {
+    if value.contains('@') {
+        Participant {
+            email: Some(value.to_string()),
+            ..Default::default()
+        }
+    } else {
+        Participant {
+            name: Some(value.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn berlin(s: &str) -> EventMoment {
+        EventMoment::timed(
+            DateTime::parse_from_rfc3339(s).unwrap(),
+            Some("Europe/Berlin".to_string()),
+        )
+    }
+
+    fn review() -> UnifiedCalendarEvent {
+        let mut event = UnifiedCalendarEvent::new(
+            String::new(),
+            CalendarSource::Google,
+            berlin("2024-03-11T14:00:00+01:00"),
+            berlin("2024-03-11T15:30:00+01:00"),
+        );
+        event.ical_uid = Some("review@example.com".to_string());
+        event.title = Some("Review, part 2".to_string());
+        event.location = Some("Room 1".to_string());
+        event.categories = Some(vec!["Team".to_string(), "Q1".to_string()]);
+        event.status = Some(EventStatus::Tentative);
+        event.visibility = Some(EventVisibility::Confidential);
+        event.organizer = Some(Participant {
+            email: Some("ada@example.com".to_string()),
+            organizer: Some(true),
+            ..Default::default()
+        });
+        event.attendees = Some(vec![
+            participant("grace@example.com"),
+            Participant {
+                optional: Some(true),
+                ..participant("alan@example.com")
+            },
+            Participant {
+                resource: Some(true),
+                ..participant("Room 1")
+            },
+        ]);
+        event.reminders = Some(vec![Reminder {
+            minutes_before: 30,
+            method: Some(ReminderMethod::Popup),
+        }]);
+        event
+    }
+
+    #[test]
+    fn test_default_round_trip() {
+        let event = review();
+        let text = write_csv(std::slice::from_ref(&event), &CsvFormat::default()).unwrap();
+        assert!(text.contains("2024-03-11,14:00,2024-03-11,15:30,false,Europe/Berlin"));
+        assert!(text.contains("\"Review, part 2\""));
+
+        let result = read_csv(&text, &CsvFormat::default(), CalendarSource::Google).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let read = &result.events[0];
+        assert_eq!(read.start, event.start);
+        assert_eq!(read.end, event.end);
+        assert_eq!(read.title, event.title);
+        assert_eq!(read.ical_uid, event.ical_uid);
+        assert_eq!(read.categories, event.categories);
+        assert!(matches!(read.status, Some(EventStatus::Tentative)));
+        assert_eq!(read.reminders.as_ref().unwrap()[0].minutes_before, 30);
+        let attendees = read.attendees.as_ref().unwrap();
+        assert_eq!(attendees.len(), 3);
+        assert_eq!(attendees[1].optional, Some(true));
+        assert_eq!(attendees[2].name.as_deref(), Some("Room 1"));
+        assert_eq!(attendees[2].resource, Some(true));
+    }
+
+    #[test]
+    fn test_outlook_preset() {
+        let text = write_csv(&[review()], &CsvFormat::outlook()).unwrap();
+        let mut lines = text.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("Subject,Start Date,Start Time"));
+        let row = lines.next().unwrap();
+        assert!(row
+            .contains("3/11/2024,2:00:00 PM,3/11/2024,3:30:00 PM,False,True,3/11/2024,1:30:00 PM"));
+        assert!(row.ends_with(",True,Confidential,1"));
+
+        let result = read_csv(
+            &text,
+            &CsvFormat::outlook().with_time_zone("Europe/Berlin"),
+            CalendarSource::Outlook,
+        )
+        .unwrap();
+        let read = &result.events[0];
+        assert_eq!(read.start, review().start);
+        assert!(matches!(read.status, Some(EventStatus::Tentative)));
+        assert!(matches!(read.show_as, Some(ShowAs::Busy)));
+        assert!(matches!(
+            read.visibility,
+            Some(EventVisibility::Confidential)
+        ));
+        assert_eq!(read.reminders.as_ref().unwrap()[0].minutes_before, 30);
+    }
+
+    #[test]
+    fn test_google_all_day_and_zones() {
+        let text = "Subject,Start Date,End Date,All Day Event,Private\n\
+                    Offsite,05/02/2024,05/03/2024,True,True\n";
+        let result = read_csv(text, &CsvFormat::google(), CalendarSource::Google).unwrap();
+        let event = &result.events[0];
+        assert_eq!(
+            event.start,
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 5, 2).unwrap())
+        );
+        // Google's end date is the last day of the event
+        assert_eq!(
+            event.end,
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 5, 4).unwrap())
+        );
+        assert!(matches!(event.visibility, Some(EventVisibility::Private)));
+        let written = write_csv(&result.events, &CsvFormat::google()).unwrap();
+        assert!(written.contains("Offsite,05/02/2024,,05/03/2024,,True"));
+
+        // A Windows zone in the row wins over the format's zone
+        let format = CsvFormat::default().with_time_zone("UTC");
+        let text = "Title,Start Date,Start Time,Time Zone\nCall,2024-07-01,09:00,W. Europe Standard Time\n";
+        let event = &read_csv(text, &format, CalendarSource::Google)
+            .unwrap()
+            .events[0];
+        assert_eq!(
+            event.start.date_time().unwrap().to_rfc3339(),
+            "2024-07-01T09:00:00+02:00"
+        );
+        assert_eq!(event.end, event.start);
+    }
+
+    #[test]
+    fn test_row_errors() {
+        let text = "Title,Start Date,Start Time,End Date,End Time,Time Zone,Recurrence\n\
+                    Good,2024-01-02,10:00,2024-01-02,11:00,,FREQ=WEEKLY\n\
+                    Bad date,2024-13-40,10:00,,,,\n\
+                    Backwards,2024-01-02,10:00,2024-01-01,10:00,,\n\
+                    Zone,2024-01-02,10:00,,,Mars/Olympus,\n\
+                    \n\
+                    Rule,2024-01-02,10:00,,,,FREQ=SOMETIMES\n";
+        let result = read_csv(text, &CsvFormat::default(), CalendarSource::Google).unwrap();
+        assert_eq!(result.events.len(), 1);
+        assert!(result.events[0].recurrence_rule.is_some());
+        let errors: Vec<(usize, Option<&str>)> = result
+            .errors
+            .iter()
+            .map(|e| (e.row, e.column.as_deref()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (3, Some("Start Date")),
+                (4, None),
+                (5, Some("Time Zone")),
+                (7, Some("Recurrence")),
+            ]
+        );
+        assert_eq!(
+            result.errors[0].to_string(),
+            "row 3, column \"Start Date\": Invalid date \"2024-13-40\""
+        );
+
+        assert!(read_csv(
+            "Title\nNo dates\n",
+            &CsvFormat::default(),
+            CalendarSource::Google
+        )
+        .is_err());
+        for format in [
+            CsvFormat::default().with_date_format("%Y-%m-%Q"),
+            CsvFormat::default().with_date_format("%Y-%m-%d %H"),
+            CsvFormat::default().with_time_format("%H:%M %Y"),
+            CsvFormat::default().with_time_format("%H:%M %z"),
+        ] {
+            assert!(write_csv(&[], &format).is_err());
+            assert!(read_csv("Title\n", &format, CalendarSource::Google).is_err());
+        }
+    }
+}
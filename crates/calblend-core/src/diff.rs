@@ -0,0 +1,480 @@
+//! Field-level comparison and three-way merge of unified events
+//!
+//! Field values are compared in their serialized JSON form, so a change is
+//! reported under the same name and shape the event has when serialized.
+//! Attendees are matched by email, case-insensitively. The recurrence is
+//! compared as one `recurrence` field holding its content lines, however
+//! the two events store it.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{CalblendError, Result};
+use crate::models::{Participant, UnifiedCalendarEvent};
+use crate::recurrence::RecurrencePattern;
+
+/// Name under which attendee list changes are reported
+const ATTENDEES: &str = "attendees";
+/// Name under which recurrence changes are reported
+const RECURRENCE: &str = "recurrence";
+/// Serialized fields that together hold the recurrence
+const RECURRENCE_FIELDS: [&str; 3] = ["recurrence", "recurrence_rule", "recurrence_exceptions"];
+/// Serialized fields that are never compared
+const SKIPPED_FIELDS: [&str; 1] = ["raw"];
+
+/// One field that differs between two versions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Change to a single attendee, matched by email
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AttendeeChange {
+    Added {
+        attendee: Participant,
+    },
+    Removed {
+        attendee: Participant,
+    },
+    Changed {
+        key: String,
+        changes: Vec<FieldChange>,
+    },
+}
+
+/// Differences between two versions of an event
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventDiff {
+    /// Changed fields other than attendees, ordered by name
+    pub fields: Vec<FieldChange>,
+    /// Added, removed and changed attendees
+    pub attendees: Vec<AttendeeChange>,
+}
+
+impl EventDiff {
+    /// Whether the two versions are the same
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.attendees.is_empty()
+    }
+
+    /// Names of the changed fields, `attendees` included
+    pub fn changed_fields(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.fields.iter().map(|c| c.field.as_str()).collect();
+        if !self.attendees.is_empty() {
+            names.push(ATTENDEES);
+        }
+        names
+    }
+
+    /// The change to a field, if it changed
+    pub fn field(&self, name: &str) -> Option<&FieldChange> {
+        self.fields.iter().find(|c| c.field == name)
+    }
+}
+
+/// A field both sides changed in different ways
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    /// Field name, or `attendees[<email>]` for a single attendee
+    pub field: String,
+    pub base: Value,
+    pub ours: Value,
+    pub theirs: Value,
+}
+
+/// Outcome of a three-way merge
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    /// Ours with every non-conflicting change from theirs applied
+    ///
+    /// Conflicting fields keep our value until the caller resolves them.
+    pub merged: UnifiedCalendarEvent,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeResult {
+    /// Whether the merge needs no manual resolution
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Compare two versions of an event field by field
+pub fn diff(old: &UnifiedCalendarEvent, new: &UnifiedCalendarEvent) -> EventDiff {
+    let old_fields = comparable_fields(old);
+    let new_fields = comparable_fields(new);
+    let fields = diff_objects(&old_fields, &new_fields);
+
+    let old_attendees = keyed_attendees(old);
+    let new_attendees = keyed_attendees(new);
+    let mut attendees = Vec::new();
+    for (key, before) in &old_attendees {
+        match find(&new_attendees, key) {
+            None => attendees.push(AttendeeChange::Removed {
+                attendee: (*before).clone(),
+            }),
+            Some(after) => {
+                let changes = diff_objects(&to_object(before), &to_object(after));
+                if !changes.is_empty() {
+                    attendees.push(AttendeeChange::Changed {
+                        key: key.clone(),
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+    for (key, after) in &new_attendees {
+        if find(&old_attendees, key).is_none() {
+            attendees.push(AttendeeChange::Added {
+                attendee: (*after).clone(),
+            });
+        }
+    }
+
+    EventDiff { fields, attendees }
+}
+
+/// Three-way merge of two versions that both started from `base`
+///
+/// A field changed on one side only takes that side's value. A field both
+/// sides changed to the same value is kept. A field both sides changed to
+/// different values is reported as a conflict. Attendees are merged one by
+/// one, so adding different people on each side does not conflict.
+pub fn merge(
+    base: &UnifiedCalendarEvent,
+    ours: &UnifiedCalendarEvent,
+    theirs: &UnifiedCalendarEvent,
+) -> Result<MergeResult> {
+    let base_fields = comparable_fields(base);
+    let our_fields = comparable_fields(ours);
+    let their_fields = comparable_fields(theirs);
+
+    let mut merged = to_object(ours);
+    let theirs_object = to_object(theirs);
+    let mut conflicts = Vec::new();
+
+    for (field, their_value) in &their_fields {
+        let base_value = base_fields.get(field).unwrap_or(&Value::Null);
+        let our_value = our_fields.get(field).unwrap_or(&Value::Null);
+        if their_value == base_value || their_value == our_value {
+            continue;
+        }
+        if our_value == base_value {
+            if field == RECURRENCE {
+                for name in RECURRENCE_FIELDS {
+                    merged.insert(
+                        name.to_string(),
+                        theirs_object.get(name).cloned().unwrap_or(Value::Null),
+                    );
+                }
+            } else {
+                merged.insert(field.clone(), their_value.clone());
+            }
+        } else {
+            conflicts.push(MergeConflict {
+                field: field.clone(),
+                base: base_value.clone(),
+                ours: our_value.clone(),
+                theirs: their_value.clone(),
+            });
+        }
+    }
+
+    let attendees = merge_attendees(base, ours, theirs, &mut conflicts);
+    merged.insert(ATTENDEES.to_string(), serde_json::to_value(attendees)?);
+
+    let merged = serde_json::from_value(Value::Object(merged))
+        .map_err(|e| CalblendError::InvalidData(format!("Merged event is invalid: {}", e)))?;
+    Ok(MergeResult { merged, conflicts })
+}
+
+fn merge_attendees(
+    base: &UnifiedCalendarEvent,
+    ours: &UnifiedCalendarEvent,
+    theirs: &UnifiedCalendarEvent,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Option<Vec<Participant>> {
+    let base = keyed_attendees(base);
+    let ours = keyed_attendees(ours);
+    let theirs = keyed_attendees(theirs);
+
+    let mut keys: Vec<&String> = ours.iter().map(|(key, _)| key).collect();
+    for (key, _) in theirs.iter().chain(base.iter()) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let mut merged = Vec::new();
+    for key in keys {
+        let base_value = find(&base, key);
+        let our_value = find(&ours, key);
+        let their_value = find(&theirs, key);
+        let chosen = if their_value == base_value || their_value == our_value {
+            our_value
+        } else if our_value == base_value {
+            their_value
+        } else {
+            let value = |p: Option<&Participant>| {
+                p.map(|p| Value::Object(to_object(p)))
+                    .unwrap_or(Value::Null)
+            };
+            conflicts.push(MergeConflict {
+                field: format!("{}[{}]", ATTENDEES, key),
+                base: value(base_value),
+                ours: value(our_value),
+                theirs: value(their_value),
+            });
+            our_value
+        };
+        merged.extend(chosen.cloned());
+    }
+
+    if merged.is_empty() {
+        None
+    } else {
+        Some(merged)
+    }
+}
+
+/// Serialized event fields that take part in comparisons
+fn comparable_fields(event: &UnifiedCalendarEvent) -> Map<String, Value> {
+    let mut fields = to_object(event);
+    for name in SKIPPED_FIELDS
+        .iter()
+        .chain(&RECURRENCE_FIELDS)
+        .chain(&[ATTENDEES])
+    {
+        fields.remove(*name);
+    }
+    fields.insert(RECURRENCE.to_string(), recurrence_lines(event));
+    fields
+}
+
+/// Recurrence as content lines, falling back to the raw strings when they
+/// do not parse
+fn recurrence_lines(event: &UnifiedCalendarEvent) -> Value {
+    let lines = match RecurrencePattern::from_event(event) {
+        Ok(pattern) => pattern.map(|p| p.to_lines()),
+        Err(_) => Some(
+            event
+                .recurrence_rule
+                .iter()
+                .chain(event.recurrence_exceptions.iter().flatten())
+                .cloned()
+                .collect(),
+        ),
+    };
+    lines.map(Value::from).unwrap_or(Value::Null)
+}
+
+fn diff_objects(old: &Map<String, Value>, new: &Map<String, Value>) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    for (field, old_value) in old {
+        let new_value = new.get(field).unwrap_or(&Value::Null);
+        if old_value != new_value {
+            changes.push(FieldChange {
+                field: field.clone(),
+                old: old_value.clone(),
+                new: new_value.clone(),
+            });
+        }
+    }
+    for (field, new_value) in new {
+        if !old.contains_key(field) && !new_value.is_null() {
+            changes.push(FieldChange {
+                field: field.clone(),
+                old: Value::Null,
+                new: new_value.clone(),
+            });
+        }
+    }
+    changes
+}
+
+/// Attendees in list order with their match keys
+///
+/// The key is the lowercased email, else the name, else the list position.
+fn keyed_attendees(event: &UnifiedCalendarEvent) -> Vec<(String, &Participant)> {
+    event
+        .attendees
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(i, attendee)| {
+            let key = match (&attendee.email, &attendee.name) {
+                (Some(email), _) => email.to_lowercase(),
+                (None, Some(name)) => name.clone(),
+                (None, None) => format!("#{}", i),
+            };
+            (key, attendee)
+        })
+        .collect()
+}
+
+fn find<'a>(attendees: &[(String, &'a Participant)], key: &str) -> Option<&'a Participant> {
+    attendees.iter().find(|(k, _)| k == key).map(|(_, p)| *p)
+}
+
+fn to_object<T: Serialize>(value: &T) -> Map<String, Value> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CalendarSource, EventMoment, ParticipantStatus};
+    use chrono::DateTime;
+
+    fn person(email: &str) -> Participant {
+        Participant {
+            email: Some(email.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn base() -> UnifiedCalendarEvent {
+        let mut event = UnifiedCalendarEvent::new(
+            "evt".to_string(),
+            CalendarSource::Google,
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-03-10T09:00:00Z").unwrap(),
+                None,
+            ),
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-03-10T10:00:00Z").unwrap(),
+                None,
+            ),
+        );
+        event.title = Some("Planning".to_string());
+        event.location = Some("Room 1".to_string());
+        event.attendees = Some(vec![person("ada@example.com"), person("bob@example.com")]);
+        event
+    }
+
+    #[test]
+    fn test_identical_events_have_no_diff() {
+        let event = base();
+        let mut copy = event.clone();
+        copy.raw = Some(serde_json::json!({ "ignored": true }));
+        assert!(diff(&event, &copy).is_empty());
+    }
+
+    #[test]
+    fn test_diff_lists_fields_and_attendees() {
+        let old = base();
+        let mut new = base();
+        new.location = Some("Room 2".to_string());
+        new.attendees = Some(vec![
+            Participant {
+                response_status: Some(ParticipantStatus::Accepted),
+                ..person("ADA@example.com")
+            },
+            person("cy@example.com"),
+        ]);
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changed_fields(), vec!["location", "attendees"]);
+        let location = diff.field("location").unwrap();
+        assert_eq!(location.old, "Room 1");
+        assert_eq!(location.new, "Room 2");
+
+        assert_eq!(diff.attendees.len(), 3);
+        match &diff.attendees[0] {
+            AttendeeChange::Changed { key, changes } => {
+                assert_eq!(key, "ada@example.com");
+                let fields: Vec<_> = changes.iter().map(|c| c.field.as_str()).collect();
+                assert_eq!(fields, vec!["email", "response_status"]);
+            }
+            other => panic!("unexpected change {:?}", other),
+        }
+        assert!(
+            matches!(&diff.attendees[1], AttendeeChange::Removed { attendee } if attendee.email.as_deref() == Some("bob@example.com"))
+        );
+        assert!(
+            matches!(&diff.attendees[2], AttendeeChange::Added { attendee } if attendee.email.as_deref() == Some("cy@example.com"))
+        );
+    }
+
+    #[test]
+    fn test_recurrence_compared_by_meaning() {
+        let mut old = base();
+        old.recurrence_rule = Some("FREQ=WEEKLY;BYDAY=MO".to_string());
+        let mut new = base();
+        new.set_recurrence(RecurrencePattern::from_event(&old).unwrap());
+        assert!(diff(&old, &new).is_empty());
+
+        new.set_recurrence(Some(
+            "RRULE:FREQ=DAILY"
+                .parse::<crate::recurrence::RecurrenceRule>()
+                .map(RecurrencePattern::new)
+                .unwrap(),
+        ));
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changed_fields(), vec!["recurrence"]);
+        assert_eq!(diff.fields[0].new, serde_json::json!(["RRULE:FREQ=DAILY"]));
+    }
+
+    #[test]
+    fn test_merge_combines_independent_changes() {
+        let base = base();
+        let mut ours = base.clone();
+        ours.title = Some("Quarterly planning".to_string());
+        ours.attendees
+            .as_mut()
+            .unwrap()
+            .push(person("cy@example.com"));
+        let mut theirs = base.clone();
+        theirs.location = Some("Room 2".to_string());
+        theirs.recurrence_rule = Some("FREQ=MONTHLY".to_string());
+        theirs.attendees = Some(vec![person("ada@example.com"), person("dee@example.com")]);
+
+        let result = merge(&base, &ours, &theirs).unwrap();
+        assert!(result.is_clean());
+        let merged = result.merged;
+        assert_eq!(merged.title.as_deref(), Some("Quarterly planning"));
+        assert_eq!(merged.location.as_deref(), Some("Room 2"));
+        assert_eq!(merged.recurrence_rule.as_deref(), Some("FREQ=MONTHLY"));
+        let emails: Vec<_> = merged
+            .attendees
+            .unwrap()
+            .into_iter()
+            .filter_map(|a| a.email)
+            .collect();
+        assert_eq!(
+            emails,
+            vec!["ada@example.com", "cy@example.com", "dee@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_merge_reports_conflicts() {
+        let base = base();
+        let mut ours = base.clone();
+        ours.location = Some("Room 2".to_string());
+        ours.attendees.as_mut().unwrap()[0].response_status = Some(ParticipantStatus::Accepted);
+        let mut theirs = base.clone();
+        theirs.location = Some("Room 3".to_string());
+        theirs.attendees.as_mut().unwrap()[0].response_status = Some(ParticipantStatus::Declined);
+        // Same change on both sides is not a conflict
+        ours.title = Some("Renamed".to_string());
+        theirs.title = Some("Renamed".to_string());
+
+        let result = merge(&base, &ours, &theirs).unwrap();
+        let fields: Vec<_> = result.conflicts.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["location", "attendees[ada@example.com]"]);
+        assert_eq!(result.conflicts[0].base, "Room 1");
+        assert_eq!(result.conflicts[0].ours, "Room 2");
+        assert_eq!(result.conflicts[0].theirs, "Room 3");
+        assert_eq!(result.merged.location.as_deref(), Some("Room 2"));
+        assert_eq!(result.merged.title.as_deref(), Some("Renamed"));
+    }
+}
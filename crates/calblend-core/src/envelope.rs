@@ -0,0 +1,354 @@
+//! Versioned storage format for unified models
+//!
+//! Values are wrapped as `{"kind": "event", "version": 2, "data": {...}}`.
+//! Reading an older version runs the registered migrations on the raw JSON,
+//! one version step at a time, before deserializing it. Plain payloads
+//! without an envelope are read as [`LEGACY_VERSION`].
+
+use std::collections::HashMap;
+
+use schemars::gen::SchemaSettings;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{CalblendError, Result};
+use crate::models::UnifiedCalendarEvent;
+use crate::recurrence::RecurrencePattern;
+use crate::{Calendar, FreeBusyPeriod};
+
+/// Version written by this build
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Version assumed for payloads stored without an envelope
+///
+/// This is the shape from before all-day moments became dates, when
+/// they were stored as `{"date_time": ..., "all_day": true}`.
+pub const LEGACY_VERSION: u32 = 1;
+
+/// A stored value with its kind and format version
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Envelope<T> {
+    /// What `data` holds, e.g. `event` or `calendar`
+    pub kind: String,
+    /// Format version `data` was written with
+    pub version: u32,
+    pub data: T,
+}
+
+/// A model type that can be stored in an [`Envelope`]
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Value of the envelope's `kind`
+    const KIND: &'static str;
+}
+
+impl Versioned for UnifiedCalendarEvent {
+    const KIND: &'static str = "event";
+}
+
+impl Versioned for Calendar {
+    const KIND: &'static str = "calendar";
+}
+
+/// Rewrites the `data` of one kind from one version to the next
+pub type Migration = Box<dyn Fn(&mut Value) -> Result<()> + Send + Sync>;
+
+/// Upgrades stored values to [`CURRENT_VERSION`]
+///
+/// A version with no migration registered for a kind is taken to have the
+/// same shape as the next version.
+pub struct Migrator {
+    migrations: HashMap<(String, u32), Migration>,
+}
+
+impl Default for Migrator {
+    fn default() -> Self {
+        Self::empty().with_migration(UnifiedCalendarEvent::KIND, 1, migrate_event_v1)
+    }
+}
+
+impl Migrator {
+    /// Migrator with the built-in migrations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Migrator without any migrations
+    pub fn empty() -> Self {
+        Self {
+            migrations: HashMap::new(),
+        }
+    }
+
+    /// Register the step from `from_version` to `from_version + 1`,
+    /// replacing any existing step
+    pub fn with_migration<F>(mut self, kind: &str, from_version: u32, migration: F) -> Self
+    where
+        F: Fn(&mut Value) -> Result<()> + Send + Sync + 'static,
+    {
+        self.migrations
+            .insert((kind.to_string(), from_version), Box::new(migration));
+        self
+    }
+
+    /// Bring `data` of the given kind from `version` to [`CURRENT_VERSION`]
+    pub fn migrate(&self, kind: &str, version: u32, mut data: Value) -> Result<Value> {
+        if version > CURRENT_VERSION {
+            return Err(CalblendError::InvalidData(format!(
+                "Unsupported {} version {} (newest is {})",
+                kind, version, CURRENT_VERSION
+            )));
+        }
+        for from in version..CURRENT_VERSION {
+            if let Some(migration) = self.migrations.get(&(kind.to_string(), from)) {
+                migration(&mut data)?;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Read a stored value, enveloped or legacy
+    pub fn from_value<T: Versioned>(&self, value: Value) -> Result<T> {
+        let (kind, version, data) = match value {
+            Value::Object(mut map) if map.contains_key("version") && map.contains_key("data") => {
+                let version = map
+                    .get("version")
+                    .and_then(Value::as_u64)
+                    .and_then(|v| u32::try_from(v).ok())
+                    .ok_or_else(|| {
+                        CalblendError::InvalidData("Invalid envelope version".to_string())
+                    })?;
+                let kind = match map.get("kind") {
+                    Some(Value::String(kind)) => kind.clone(),
+                    None => T::KIND.to_string(),
+                    Some(other) => {
+                        return Err(CalblendError::InvalidData(format!(
+                            "Invalid envelope kind: {}",
+                            other
+                        )))
+                    }
+                };
+                (kind, version, map.remove("data").unwrap_or(Value::Null))
+            }
+            legacy => (T::KIND.to_string(), LEGACY_VERSION, legacy),
+        };
+        if kind != T::KIND {
+            return Err(CalblendError::InvalidData(format!(
+                "Expected a stored {}, found {}",
+                T::KIND,
+                kind
+            )));
+        }
+        Ok(serde_json::from_value(self.migrate(&kind, version, data)?)?)
+    }
+
+    /// Read a stored value from its JSON text
+    pub fn from_str<T: Versioned>(&self, json: &str) -> Result<T> {
+        self.from_value(serde_json::from_str(json)?)
+    }
+}
+
+/// Wrap a value in an envelope at [`CURRENT_VERSION`]
+pub fn to_value<T: Versioned>(value: &T) -> Result<Value> {
+    Ok(serde_json::to_value(Envelope {
+        kind: T::KIND.to_string(),
+        version: CURRENT_VERSION,
+        data: value,
+    })?)
+}
+
+/// Wrap a value in an envelope and render it as JSON text
+pub fn to_string<T: Versioned>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(&to_value(value)?)?)
+}
+
+/// Read a stored value with the built-in migrations
+pub fn from_value<T: Versioned>(value: Value) -> Result<T> {
+    Migrator::default().from_value(value)
+}
+
+/// Read a stored value from JSON text with the built-in migrations
+pub fn from_str<T: Versioned>(json: &str) -> Result<T> {
+    Migrator::default().from_str(json)
+}
+
+/// JSON Schema of a stored event envelope
+///
+/// The definitions cover every public model type, so other services can
+/// also validate calendars, free/busy periods and recurrences against it.
+pub fn json_schema() -> RootSchema {
+    let mut gen = SchemaSettings::draft07().into_generator();
+    gen.subschema_for::<Calendar>();
+    gen.subschema_for::<FreeBusyPeriod>();
+    gen.subschema_for::<RecurrencePattern>();
+    gen.into_root_schema_for::<Envelope<UnifiedCalendarEvent>>()
+}
+
+/// Version 1 stored all-day moments as midnight date-times flagged with
+/// `all_day`; version 2 stores them as dates
+fn migrate_event_v1(data: &mut Value) -> Result<()> {
+    for field in ["start", "end", "original_start"] {
+        let Some(Value::Object(moment)) = data.get_mut(field) else {
+            continue;
+        };
+        let all_day = moment.remove("all_day") == Some(Value::Bool(true));
+        if !all_day {
+            continue;
+        }
+        let date = match moment.remove("date_time") {
+            Some(Value::String(date_time)) => date_time.get(..10).map(str::to_string),
+            _ => None,
+        }
+        .ok_or_else(|| CalblendError::InvalidData(format!("Invalid legacy all-day {}", field)))?;
+        moment.insert("date".to_string(), Value::String(date));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CalendarSource, EventMoment, Participant};
+    use chrono::{DateTime, NaiveDate};
+    use serde_json::json;
+
+    fn event() -> UnifiedCalendarEvent {
+        let mut event = UnifiedCalendarEvent::new(
+            "evt".to_string(),
+            CalendarSource::Google,
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-03-10T09:00:00-05:00").unwrap(),
+                Some("America/New_York".to_string()),
+            ),
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-03-10T10:00:00-05:00").unwrap(),
+                Some("America/New_York".to_string()),
+            ),
+        );
+        event.attendees = Some(vec![Participant {
+            email: Some("ada@example.com".to_string()),
+            is_self: Some(true),
+            ..Default::default()
+        }]);
+        event
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = to_string(&event()).unwrap();
+        let stored: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(stored["kind"], "event");
+        assert_eq!(stored["version"], CURRENT_VERSION);
+        assert_eq!(stored["data"]["attendees"][0]["self"], true);
+
+        let read: UnifiedCalendarEvent = from_str(&json).unwrap();
+        assert!(crate::diff(&event(), &read).is_empty());
+    }
+
+    #[test]
+    fn test_reads_legacy_all_day_event() {
+        let legacy = json!({
+            "id": "evt",
+            "source": "Google",
+            "start": { "date_time": "2024-03-10T00:00:00+00:00", "time_zone": null, "all_day": true },
+            "end": { "date_time": "2024-03-11T00:00:00+00:00", "time_zone": null, "all_day": true }
+        });
+        let event: UnifiedCalendarEvent = from_value(legacy).unwrap();
+        assert_eq!(
+            event.start,
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap())
+        );
+        assert_eq!(
+            event.end,
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 3, 11).unwrap())
+        );
+
+        // Timed legacy moments keep their value
+        let legacy = json!({
+            "kind": "event",
+            "version": 1,
+            "data": {
+                "id": "evt",
+                "source": "Google",
+                "start": { "date_time": "2024-03-10T09:00:00Z", "time_zone": null, "all_day": false },
+                "end": { "date_time": "2024-03-10T10:00:00Z", "time_zone": null, "all_day": false }
+            }
+        });
+        let event: UnifiedCalendarEvent = from_value(legacy).unwrap();
+        assert!(!event.is_all_day());
+    }
+
+    #[test]
+    fn test_custom_migration_hook() {
+        // An older payload that called the title `summary`
+        let migrator = Migrator::new().with_migration("calendar", 1, |data| {
+            if let Some(map) = data.as_object_mut() {
+                if let Some(summary) = map.remove("summary") {
+                    map.insert("name".to_string(), summary);
+                }
+            }
+            Ok(())
+        });
+        let stored = json!({
+            "kind": "calendar",
+            "version": 1,
+            "data": {
+                "id": "primary",
+                "summary": "Personal",
+                "is_primary": true,
+                "can_write": true,
+                "source": "Google"
+            }
+        });
+        let calendar: Calendar = migrator.from_value(stored).unwrap();
+        assert_eq!(calendar.name, "Personal");
+    }
+
+    #[test]
+    fn test_rejects_wrong_kind_and_newer_versions() {
+        let stored = to_value(&event()).unwrap();
+        assert!(from_value::<Calendar>(stored.clone()).is_err());
+
+        let mut newer = stored;
+        newer["version"] = json!(CURRENT_VERSION + 1);
+        let err = from_value::<UnifiedCalendarEvent>(newer).unwrap_err();
+        assert!(err.to_string().contains("Unsupported event version"));
+    }
+
+    #[test]
+    fn test_schema_describes_models() {
+        let schema = serde_json::to_value(json_schema()).unwrap();
+        let definitions = schema["definitions"].as_object().unwrap();
+        for name in [
+            "UnifiedCalendarEvent",
+            "Participant",
+            "EventMoment",
+            "Calendar",
+            "RecurrenceRule",
+        ] {
+            assert!(definitions.contains_key(name), "missing {}", name);
+        }
+        assert_eq!(schema["required"], json!(["data", "kind", "version"]));
+        // Serde renames carry over to the schema
+        assert!(definitions["Participant"]["properties"]["self"].is_object());
+    }
+
+    #[test]
+    fn test_checked_in_schema_is_current() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../schema/calblend.schema.json"
+        );
+        let generated = serde_json::to_string_pretty(&json_schema()).unwrap() + "\n";
+        if std::env::var_os("CALBLEND_UPDATE_SCHEMA").is_some() {
+            std::fs::write(path, &generated).unwrap();
+        }
+        let checked_in = std::fs::read_to_string(path).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "schema/calblend.schema.json is out of date; rerun with CALBLEND_UPDATE_SCHEMA=1"
+        );
+    }
+}
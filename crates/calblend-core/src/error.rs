@@ -29,6 +29,9 @@ pub enum CalblendError {
     
     #[error("Event not found: {0}")]
     EventNotFound(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
     
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
@@ -50,6 +53,111 @@ pub enum CalblendError {
     
     #[error("Deserialization error: {0}")]
     Deserialization(String),
+    
+    #[error("Conversion error: {0}")]
+    Conversion(#[from] ConversionError),
+
+    #[error("Validation failed: {0}")]
+    Validation(#[from] ValidationErrors),
+}
+
+/// Provider payload that could not be converted to the unified model
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{field}: {reason}{}", .value.as_ref().map(|v| format!(" (got {:?})", v)).unwrap_or_default())]
+pub struct ConversionError {
+    /// Provider ID of the offending item, if it has one
+    pub item_id: Option<String>,
+    /// Provider field path, e.g. `start.dateTime`
+    pub field: String,
+    /// Raw value found in the payload, `None` if the field was missing
+    pub value: Option<String>,
+    /// What was wrong with the value
+    pub reason: String,
+}
+
+impl ConversionError {
+    /// A required field was absent
+    pub fn missing(field: impl Into<String>) -> Self {
+        Self {
+            item_id: None,
+            field: field.into(),
+            value: None,
+            reason: "missing required field".to_string(),
+        }
+    }
+
+    /// A field was present but could not be parsed
+    pub fn invalid(field: impl Into<String>, value: impl Into<String>, reason: impl ToString) -> Self {
+        Self {
+            item_id: None,
+            field: field.into(),
+            value: Some(value.into()),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Attach the provider ID of the item being converted
+    pub fn for_item(mut self, item_id: Option<String>) -> Self {
+        self.item_id = item_id;
+        self
+    }
+}
+
+/// Why a field failed validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValidationErrorKind {
+    /// A required value is missing or empty
+    Required,
+    /// The event ends before it starts
+    EndBeforeStart,
+    /// One end is all-day and the other is timed
+    MixedAllDay,
+    /// The recurrence does not parse
+    InvalidRecurrence,
+    /// Not a usable email address
+    InvalidEmail,
+    /// Not a known time zone
+    InvalidTimeZone,
+    /// A number is outside its allowed range
+    OutOfRange,
+}
+
+/// A single failed validation check
+#[derive(Error, Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[error("{field}: {message}")]
+pub struct ValidationError {
+    /// Field path, e.g. `attendees[2].email`
+    pub field: String,
+    pub kind: ValidationErrorKind,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(field: impl Into<String>, kind: ValidationErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+/// Every check an event failed
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct ValidationErrors(pub Vec<ValidationError>);
+
+impl ValidationErrors {
+    /// Errors reported for the given field
+    pub fn for_field<'a>(&'a self, field: &'a str) -> impl Iterator<Item = &'a ValidationError> {
+        self.0.iter().filter(move |e| e.field == field)
+    }
+
+    /// Whether any error has the given kind
+    pub fn has(&self, kind: ValidationErrorKind) -> bool {
+        self.0.iter().any(|e| e.kind == kind)
+    }
 }
 
 impl CalblendError {
@@ -72,6 +180,7 @@ impl CalblendError {
             CalblendError::RateLimitExceeded => 4002,
             CalblendError::CalendarNotFound(_) => 5001,
             CalblendError::EventNotFound(_) => 5002,
+            CalblendError::Conflict(_) => 5003,
             CalblendError::SerializationError(_) => 6001,
             CalblendError::TokenStorageError(_) => 7001,
             CalblendError::UnsupportedOperation(_) => 8001,
@@ -79,6 +188,8 @@ impl CalblendError {
             CalblendError::Configuration(_) => 10001,
             CalblendError::Http(_) => 10002,
             CalblendError::Deserialization(_) => 10003,
+            CalblendError::Conversion(_) => 3002,
+            CalblendError::Validation(_) => 3003,
         }
     }
 }
\ No newline at end of file
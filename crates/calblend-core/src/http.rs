@@ -18,9 +18,11 @@ impl HttpClient {
     /// Create a new HTTP client with retry middleware
     pub fn new(config: &CalblendConfig) -> Result<Self> {
         // Create the base reqwest client
+        // gzip is negotiated via Accept-Encoding and decoded transparently
         let client = Client::builder()
             .timeout(Duration::from_secs(config.timeout_secs))
             .user_agent(&config.user_agent)
+            .gzip(true)
             .build()
             .map_err(|e| CalblendError::InternalError(e.to_string()))?;
 
@@ -101,6 +103,9 @@ pub fn map_google_error(status: reqwest::StatusCode, body: &str) -> CalblendErro
         reqwest::StatusCode::FORBIDDEN => CalblendError::PermissionDenied("Insufficient permissions".to_string()),
         reqwest::StatusCode::NOT_FOUND => CalblendError::EventNotFound("Resource not found".to_string()),
         reqwest::StatusCode::TOO_MANY_REQUESTS => CalblendError::RateLimitExceeded,
+        reqwest::StatusCode::PRECONDITION_FAILED => {
+            CalblendError::Conflict("Event changed since it was read".to_string())
+        }
         _ => {
             // Try to parse error from response body
             if let Ok(error_response) = serde_json::from_str::<GoogleErrorResponse>(body) {
@@ -123,8 +128,10 @@ struct GoogleErrorResponse {
 
 #[derive(Debug, serde::Deserialize)]
 struct GoogleError {
+    #[allow(dead_code)]
     code: u16,
     message: String,
+    #[allow(dead_code)]
     status: Option<String>,
 }
 
@@ -0,0 +1,759 @@
+//! Rendering unified events as an iCalendar `VCALENDAR`
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+
+use super::itip::ItipMethod;
+use super::vtimezone::write_vtimezone;
+use super::{
+    escape_text, format_duration, param_value, ContentWriter, DATE_FORMAT, DATE_TIME_FORMAT,
+};
+use crate::models::{
+    CalendarUserType, EventMoment, EventStatus, EventVisibility, Participant, ParticipantRole,
+    ParticipantStatus, ReminderMethod, ShowAs, UnifiedCalendarEvent,
+};
+use crate::recurrence::{RecurrenceDate, RecurrencePattern, RecurrenceUntil};
+use crate::timezone::{canonical_name, resolve_local};
+use crate::Calendar;
+
+/// Calendar-level properties of an export
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Calendar name (`X-WR-CALNAME`)
+    pub name: Option<String>,
+    /// Calendar description (`X-WR-CALDESC`)
+    pub description: Option<String>,
+    /// Default zone clients show the calendar in (`X-WR-TIMEZONE`)
+    pub time_zone: Option<String>,
+    /// iTIP method (`METHOD`), for scheduling messages
+    pub method: Option<ItipMethod>,
+    /// Also write the event fields iCalendar has no property for, as
+    /// `X-CALBLEND-*` properties that [`ImportOptions::calblend_properties`]
+    /// reads back
+    ///
+    /// [`ImportOptions::calblend_properties`]: super::ImportOptions::calblend_properties
+    pub calblend_properties: bool,
+}
+
+impl ExportOptions {
+    /// Options describing a provider calendar
+    pub fn for_calendar(calendar: &Calendar) -> Self {
+        Self {
+            name: Some(calendar.name.clone()),
+            description: calendar.description.clone(),
+            time_zone: calendar.time_zone.clone(),
+            method: None,
+            calblend_properties: false,
+        }
+    }
+
+    /// Set the calendar name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the calendar description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set the default time zone
+    pub fn with_time_zone(mut self, time_zone: impl Into<String>) -> Self {
+        self.time_zone = Some(time_zone.into());
+        self
+    }
+
+    /// Mark the document as an iTIP message
+    pub fn with_method(mut self, method: ItipMethod) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// Write the `X-CALBLEND-*` properties
+    pub fn with_calblend_properties(mut self) -> Self {
+        self.calblend_properties = true;
+        self
+    }
+}
+
+/// Render a provider calendar and its events as a `.ics` document
+pub fn export_calendar(calendar: &Calendar, events: &[UnifiedCalendarEvent]) -> String {
+    write_calendar(events, &ExportOptions::for_calendar(calendar))
+}
+
+/// Render events as a `VCALENDAR`
+///
+/// Times with a known zone are written as local times with a `TZID`, and
+/// a `VTIMEZONE` is included for each zone. Times without a zone are written
+/// in UTC and all-day events as dates. Overridden occurrences share their
+/// series' `UID` and carry a `RECURRENCE-ID`.
+pub fn write_calendar(events: &[UnifiedCalendarEvent], options: &ExportOptions) -> String {
+    let mut out = ContentWriter::new();
+    out.begin("VCALENDAR");
+    out.property("VERSION", "2.0");
+    out.property(
+        "PRODID",
+        &format!("-//Calblend//Calblend {}//EN", env!("CARGO_PKG_VERSION")),
+    );
+    out.property("CALSCALE", "GREGORIAN");
+    if let Some(method) = options.method {
+        out.property("METHOD", method.as_str());
+    }
+    if let Some(name) = &options.name {
+        out.text("X-WR-CALNAME", name);
+    }
+    if let Some(description) = &options.description {
+        out.text("X-WR-CALDESC", description);
+    }
+    let default_zone = options.time_zone.as_deref().and_then(zone);
+    if let Some(tz) = default_zone {
+        out.property("X-WR-TIMEZONE", tz.name());
+    }
+
+    let prepared: Vec<PreparedEvent> = events.iter().map(PreparedEvent::new).collect();
+
+    // Years per zone, so each VTIMEZONE covers every time that uses it
+    let mut zones: BTreeMap<&'static str, (Tz, i32, i32)> = BTreeMap::new();
+    for event in &prepared {
+        for (tz, year) in &event.zone_years {
+            let entry = zones.entry(tz.name()).or_insert((*tz, *year, *year));
+            entry.1 = entry.1.min(*year);
+            entry.2 = entry.2.max(*year);
+        }
+    }
+    for (tz, from_year, to_year) in zones.into_values() {
+        // Start a year early so the first observance precedes every event
+        write_vtimezone(&mut out, tz, from_year - 1, to_year);
+    }
+
+    for event in &prepared {
+        write_event(&mut out, event, options.calblend_properties);
+    }
+    out.end("VCALENDAR");
+    out.finish()
+}
+
+/// An event with its zone and recurrence resolved for output
+struct PreparedEvent<'a> {
+    event: &'a UnifiedCalendarEvent,
+    /// Zone of the start, used for zone-less recurrence values
+    zone: Option<Tz>,
+    recurrence: Vec<String>,
+    /// Every zone used by the event's times, with the years it is used in
+    zone_years: Vec<(Tz, i32)>,
+}
+
+impl<'a> PreparedEvent<'a> {
+    fn new(event: &'a UnifiedCalendarEvent) -> Self {
+        let zone = event.start.time_zone().and_then(self::zone);
+        let mut zone_years = Vec::new();
+        for moment in [
+            Some(&event.start),
+            Some(&event.end),
+            event.original_start.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let (Some(tz), EventMoment::DateTime { date_time, .. }) =
+                (moment_zone(moment, zone), moment)
+            {
+                zone_years.push((tz, date_time.with_timezone(&tz).year()));
+            }
+        }
+
+        let recurrence = match RecurrencePattern::from_event(event) {
+            Ok(Some(pattern)) => {
+                let pattern = localize_pattern(pattern, event, zone);
+                for date in pattern.rdates.iter().chain(&pattern.exdates) {
+                    if let RecurrenceDate::Local {
+                        date_time,
+                        tzid: Some(tzid),
+                    } = date
+                    {
+                        if let Some(tz) = self::zone(tzid) {
+                            zone_years.push((tz, date_time.year()));
+                        }
+                    }
+                }
+                if let Some(tz) = zone {
+                    // Offsets of later occurrences come from the VTIMEZONE
+                    // rules, so the last year of a bounded series must be covered
+                    for rule in &pattern.rules {
+                        let until_year = match rule.until {
+                            Some(RecurrenceUntil::DateTime(until)) => {
+                                Some(until.with_timezone(&tz).year())
+                            }
+                            Some(RecurrenceUntil::Date(date)) => Some(date.year()),
+                            Some(RecurrenceUntil::Floating(date_time)) => Some(date_time.year()),
+                            None => None,
+                        };
+                        zone_years.extend(until_year.map(|year| (tz, year)));
+                    }
+                }
+                pattern.to_lines()
+            }
+            Ok(None) => Vec::new(),
+            // Pass on what the provider gave us rather than dropping it
+            Err(_) => event
+                .recurrence_rule
+                .iter()
+                .map(|rule| {
+                    if rule.starts_with("RRULE:") {
+                        rule.clone()
+                    } else {
+                        format!("RRULE:{}", rule)
+                    }
+                })
+                .chain(
+                    event
+                        .recurrence_exceptions
+                        .iter()
+                        .flatten()
+                        .map(|exception| {
+                            if exception.contains(':') {
+                                exception.clone()
+                            } else {
+                                format!("EXDATE:{}", exception)
+                            }
+                        }),
+                )
+                .collect(),
+        };
+
+        Self {
+            event,
+            zone,
+            recurrence,
+            zone_years,
+        }
+    }
+}
+
+fn write_event(out: &mut ContentWriter, prepared: &PreparedEvent, calblend_properties: bool) {
+    let event = prepared.event;
+    out.begin("VEVENT");
+    out.text("UID", &uid(event));
+    let stamp = event
+        .updated
+        .or(event.created)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+    out.property("DTSTAMP", &utc_value(stamp));
+    write_moment(out, "DTSTART", &event.start, prepared.zone);
+    write_moment(out, "DTEND", &event.end, prepared.zone);
+    if let Some(original_start) = &event.original_start {
+        write_moment(out, "RECURRENCE-ID", original_start, prepared.zone);
+    }
+    for line in &prepared.recurrence {
+        out.line(line);
+    }
+    if let Some(sequence) = event.sequence {
+        out.property("SEQUENCE", &sequence.to_string());
+    }
+    if let Some(title) = &event.title {
+        out.text("SUMMARY", title);
+    }
+    if let Some(description) = &event.description {
+        out.text("DESCRIPTION", description);
+    }
+    if let Some(location) = &event.location {
+        out.text("LOCATION", location);
+    }
+    if let Some(categories) = event.categories.as_ref().filter(|c| !c.is_empty()) {
+        let values: Vec<String> = categories.iter().map(|c| escape_text(c)).collect();
+        out.property("CATEGORIES", &values.join(","));
+    }
+    if let Some(status) = &event.status {
+        out.property(
+            "STATUS",
+            match status {
+                EventStatus::Confirmed => "CONFIRMED",
+                EventStatus::Tentative => "TENTATIVE",
+                EventStatus::Cancelled => "CANCELLED",
+            },
+        );
+    }
+    match event.visibility {
+        Some(EventVisibility::Public) => out.property("CLASS", "PUBLIC"),
+        Some(EventVisibility::Private) => out.property("CLASS", "PRIVATE"),
+        Some(EventVisibility::Confidential) => out.property("CLASS", "CONFIDENTIAL"),
+        Some(EventVisibility::Default) | None => {}
+    }
+    if let Some(show_as) = &event.show_as {
+        let transparent = matches!(show_as, ShowAs::Free);
+        out.property("TRANSP", if transparent { "TRANSPARENT" } else { "OPAQUE" });
+    }
+    if let Some(created) = event.created {
+        out.property("CREATED", &utc_value(created.with_timezone(&Utc)));
+    }
+    if let Some(updated) = event.updated {
+        out.property("LAST-MODIFIED", &utc_value(updated.with_timezone(&Utc)));
+    }
+    if let Some(link) = &event.html_link {
+        out.property("URL", link);
+    }
+    if let Some(url) = event.conference.as_ref().and_then(|c| c.url.as_ref()) {
+        out.property("CONFERENCE;VALUE=URI;FEATURE=VIDEO", url);
+    }
+
+    if let Some(organizer) = &event.organizer {
+        if let Some(email) = &organizer.email {
+            let mut name = "ORGANIZER".to_string();
+            if let Some(cn) = &organizer.name {
+                name.push_str(&format!(";CN={}", param_value(cn)));
+            }
+            out.property(&name, &format!("mailto:{}", email));
+        }
+    }
+    for attendee in event.attendees.iter().flatten() {
+        write_attendee(out, attendee);
+    }
+
+    for reminder in event.reminders.iter().flatten() {
+        out.begin("VALARM");
+        let organizer_email = event.organizer.as_ref().and_then(|o| o.email.as_ref());
+        match (&reminder.method, organizer_email) {
+            (Some(ReminderMethod::Email), Some(email)) => {
+                out.property("ACTION", "EMAIL");
+                out.text("SUMMARY", event.title.as_deref().unwrap_or("Reminder"));
+                out.text("DESCRIPTION", event.title.as_deref().unwrap_or("Reminder"));
+                out.property("ATTENDEE", &format!("mailto:{}", email));
+            }
+            _ => {
+                out.property("ACTION", "DISPLAY");
+                out.text("DESCRIPTION", event.title.as_deref().unwrap_or("Reminder"));
+            }
+        }
+        out.property("TRIGGER", &trigger(reminder.minutes_before));
+        out.end("VALARM");
+    }
+    if calblend_properties {
+        write_calblend_properties(out, event);
+    }
+    out.end("VEVENT");
+}
+
+fn write_attendee(out: &mut ContentWriter, attendee: &Participant) {
+    let Some(email) = &attendee.email else {
+        return;
+    };
+    let mut name = "ATTENDEE".to_string();
+    if let Some(cn) = &attendee.name {
+        name.push_str(&format!(";CN={}", param_value(cn)));
+    }
+    let user_type = attendee.user_type.or_else(|| {
+        attendee
+            .resource
+            .filter(|resource| *resource)
+            .map(|_| CalendarUserType::Resource)
+    });
+    if let Some(user_type) = user_type {
+        name.push_str(match user_type {
+            CalendarUserType::Individual => ";CUTYPE=INDIVIDUAL",
+            CalendarUserType::Group => ";CUTYPE=GROUP",
+            CalendarUserType::Resource => ";CUTYPE=RESOURCE",
+            CalendarUserType::Room => ";CUTYPE=ROOM",
+            CalendarUserType::Unknown => ";CUTYPE=UNKNOWN",
+        });
+    }
+    let role = attendee.role.or(match attendee.optional {
+        Some(true) => Some(ParticipantRole::Optional),
+        Some(false) => Some(ParticipantRole::Required),
+        None => None,
+    });
+    if let Some(role) = role {
+        name.push_str(match role {
+            ParticipantRole::Chair => ";ROLE=CHAIR",
+            ParticipantRole::Required => ";ROLE=REQ-PARTICIPANT",
+            ParticipantRole::Optional => ";ROLE=OPT-PARTICIPANT",
+            ParticipantRole::NonParticipant => ";ROLE=NON-PARTICIPANT",
+        });
+    }
+    if let Some(status) = &attendee.response_status {
+        name.push_str(match status {
+            ParticipantStatus::Accepted => ";PARTSTAT=ACCEPTED",
+            ParticipantStatus::Tentative => ";PARTSTAT=TENTATIVE",
+            ParticipantStatus::Declined => ";PARTSTAT=DECLINED",
+            ParticipantStatus::NeedsAction => ";PARTSTAT=NEEDS-ACTION;RSVP=TRUE",
+        });
+    }
+    for (param, addresses) in [
+        ("DELEGATED-TO", &attendee.delegated_to),
+        ("DELEGATED-FROM", &attendee.delegated_from),
+    ] {
+        if let Some(addresses) = addresses.as_ref().filter(|a| !a.is_empty()) {
+            let quoted: Vec<String> = addresses
+                .iter()
+                .map(|a| format!("\"{}\"", mailto(a)))
+                .collect();
+            name.push_str(&format!(";{}={}", param, quoted.join(",")));
+        }
+    }
+    if let Some(guests) = attendee.additional_guests.filter(|g| *g > 0) {
+        name.push_str(&format!(";X-NUM-GUESTS={}", guests));
+    }
+    if let Some(comment) = attendee.comment.as_ref().filter(|c| !c.is_empty()) {
+        name.push_str(&format!(";X-RESPONSE-COMMENT={}", param_value(comment)));
+    }
+    out.property(&name, &mailto(email));
+}
+
+fn write_moment(out: &mut ContentWriter, name: &str, moment: &EventMoment, event_zone: Option<Tz>) {
+    match moment {
+        EventMoment::Date { date, .. } => {
+            out.property(
+                &format!("{};VALUE=DATE", name),
+                &date.format(DATE_FORMAT).to_string(),
+            );
+        }
+        EventMoment::DateTime { date_time, .. } => match moment_zone(moment, event_zone) {
+            Some(tz) => out.property(
+                &format!("{};TZID={}", name, tz.name()),
+                &date_time
+                    .with_timezone(&tz)
+                    .format(DATE_TIME_FORMAT)
+                    .to_string(),
+            ),
+            None => out.property(name, &utc_value(date_time.with_timezone(&Utc))),
+        },
+    }
+}
+
+/// Make recurrence values agree with how `DTSTART` is written
+///
+/// Zone-less local dates take the event's zone and zone names become
+/// canonical. `UNTIL` must be a date for all-day events and UTC for timed
+/// ones.
+fn localize_pattern(
+    mut pattern: RecurrencePattern,
+    event: &UnifiedCalendarEvent,
+    zone: Option<Tz>,
+) -> RecurrencePattern {
+    for date in pattern.rdates.iter_mut().chain(pattern.exdates.iter_mut()) {
+        if let RecurrenceDate::Local { date_time, tzid } = date {
+            let tz = tzid.as_deref().and_then(self::zone).or(zone);
+            *date = match tz {
+                Some(tz) => RecurrenceDate::Local {
+                    date_time: *date_time,
+                    tzid: Some(tz.name().to_string()),
+                },
+                None => RecurrenceDate::Utc(date_time.and_utc()),
+            };
+        }
+    }
+    for rule in &mut pattern.rules {
+        rule.until = rule.until.map(|until| match (&event.start, until) {
+            (EventMoment::Date { .. }, RecurrenceUntil::DateTime(dt)) => {
+                RecurrenceUntil::Date(match zone {
+                    Some(tz) => dt.with_timezone(&tz).date_naive(),
+                    None => dt.date_naive(),
+                })
+            }
+            (EventMoment::Date { .. }, RecurrenceUntil::Floating(dt)) => {
+                RecurrenceUntil::Date(dt.date())
+            }
+            (EventMoment::DateTime { .. }, RecurrenceUntil::Floating(dt)) => {
+                RecurrenceUntil::DateTime(to_utc(dt, zone))
+            }
+            (EventMoment::DateTime { .. }, RecurrenceUntil::Date(date)) => {
+                RecurrenceUntil::DateTime(to_utc(date.and_time(end_of_day()), zone))
+            }
+            (_, until) => until,
+        });
+    }
+    pattern
+}
+
+fn to_utc(local: chrono::NaiveDateTime, zone: Option<Tz>) -> DateTime<Utc> {
+    match zone {
+        Some(tz) => resolve_local(tz, local).with_timezone(&Utc),
+        None => local.and_utc(),
+    }
+}
+
+fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 59).unwrap_or(NaiveTime::MIN)
+}
+
+/// Zone a timed moment is written in: its own, else the event's
+fn moment_zone(moment: &EventMoment, event_zone: Option<Tz>) -> Option<Tz> {
+    match moment.time_zone() {
+        Some(name) => zone(name),
+        None => event_zone,
+    }
+}
+
+/// Canonical zone for any accepted zone name
+fn zone(name: &str) -> Option<Tz> {
+    canonical_name(name).ok().and_then(|name| name.parse().ok())
+}
+
+/// Event fields without an iCalendar property, as `X-CALBLEND-*`
+fn write_calblend_properties(out: &mut ContentWriter, event: &UnifiedCalendarEvent) {
+    if !event.id.is_empty() {
+        out.text("X-CALBLEND-ID", &event.id);
+    }
+    if let Some(calendar_id) = &event.calendar_id {
+        out.text("X-CALBLEND-CALENDAR-ID", calendar_id);
+    }
+    if let Ok(Value::String(source)) = serde_json::to_value(event.source) {
+        out.property("X-CALBLEND-SOURCE", &source);
+    }
+    if let Some(color) = &event.color {
+        out.text("X-CALBLEND-COLOR", color);
+    }
+    if let Some(etag) = &event.etag {
+        out.text("X-CALBLEND-ETAG", etag);
+    }
+    if let Some(raw) = &event.raw {
+        out.text("X-CALBLEND-RAW", &raw.to_string());
+    }
+}
+
+fn uid(event: &UnifiedCalendarEvent) -> String {
+    if let Some(uid) = &event.ical_uid {
+        return uid.clone();
+    }
+    match (&event.series_id, event.id.is_empty()) {
+        (Some(series_id), _) => series_id.clone(),
+        (None, false) => event.id.clone(),
+        (None, true) => format!("{}@calblend", uuid::Uuid::new_v4()),
+    }
+}
+
+fn utc_value(dt: DateTime<Utc>) -> String {
+    format!("{}Z", dt.format(DATE_TIME_FORMAT))
+}
+
+fn mailto(address: &str) -> String {
+    if address.to_ascii_lowercase().starts_with("mailto:") {
+        address.to_string()
+    } else {
+        format!("mailto:{}", address)
+    }
+}
+
+/// Relative `TRIGGER` for a reminder, e.g. `-PT15M` or `-P1D`
+fn trigger(minutes_before: i32) -> String {
+    format_duration(-Duration::minutes(i64::from(minutes_before)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CalendarSource, ConferenceLink, Reminder};
+    use chrono::{DateTime, NaiveDate};
+
+    fn meeting() -> UnifiedCalendarEvent {
+        let mut event = UnifiedCalendarEvent::new(
+            "evt1".to_string(),
+            CalendarSource::Google,
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-03-04T09:00:00-05:00").unwrap(),
+                Some("America/New_York".to_string()),
+            ),
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-03-04T10:00:00-05:00").unwrap(),
+                Some("America/New_York".to_string()),
+            ),
+        );
+        event.ical_uid = Some("evt1@google.com".to_string());
+        event.title = Some("Planning, Q2; draft".to_string());
+        event.description = Some("Agenda:\n1. Budget".to_string());
+        event.updated = Some(DateTime::parse_from_rfc3339("2024-02-01T12:00:00Z").unwrap());
+        event.recurrence_rule = Some("FREQ=WEEKLY;BYDAY=MO;UNTIL=20240401T000000".to_string());
+        event.recurrence_exceptions = Some(vec!["20240318T090000".to_string()]);
+        event.organizer = Some(Participant {
+            email: Some("ada@example.com".to_string()),
+            name: Some("Lovelace, Ada".to_string()),
+            ..Default::default()
+        });
+        event.attendees = Some(vec![
+            Participant {
+                email: Some("bob@example.com".to_string()),
+                name: Some("Bob".to_string()),
+                role: Some(ParticipantRole::Required),
+                response_status: Some(ParticipantStatus::Accepted),
+                additional_guests: Some(1),
+                ..Default::default()
+            },
+            Participant {
+                email: Some("room-1@resource.example.com".to_string()),
+                optional: Some(true),
+                user_type: Some(CalendarUserType::Room),
+                response_status: Some(ParticipantStatus::NeedsAction),
+                ..Default::default()
+            },
+            Participant {
+                name: Some("No email".to_string()),
+                ..Default::default()
+            },
+        ]);
+        event.reminders = Some(vec![
+            Reminder {
+                minutes_before: 10,
+                method: Some(ReminderMethod::Popup),
+            },
+            Reminder {
+                minutes_before: 1440,
+                method: Some(ReminderMethod::Email),
+            },
+        ]);
+        event.conference = Some(ConferenceLink {
+            url: Some("https://meet.example.com/abc".to_string()),
+            provider: None,
+        });
+        event
+    }
+
+    fn unfolded_lines(ics: &str) -> Vec<String> {
+        ics.replace("\r\n ", "")
+            .split("\r\n")
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_event_export() {
+        let ics = write_calendar(&[meeting()], &ExportOptions::default().with_name("Team"));
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Calblend//"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+
+        let lines = unfolded_lines(&ics);
+        for expected in [
+            "X-WR-CALNAME:Team",
+            "TZID:America/New_York",
+            "UID:evt1@google.com",
+            "DTSTAMP:20240201T120000Z",
+            "DTSTART;TZID=America/New_York:20240304T090000",
+            "DTEND;TZID=America/New_York:20240304T100000",
+            "RRULE:FREQ=WEEKLY;UNTIL=20240401T040000Z;BYDAY=MO",
+            "EXDATE;TZID=America/New_York:20240318T090000",
+            "SUMMARY:Planning\\, Q2\\; draft",
+            "DESCRIPTION:Agenda:\\n1. Budget",
+            "CONFERENCE;VALUE=URI;FEATURE=VIDEO:https://meet.example.com/abc",
+            "ORGANIZER;CN=\"Lovelace, Ada\":mailto:ada@example.com",
+            "ATTENDEE;CN=Bob;ROLE=REQ-PARTICIPANT;PARTSTAT=ACCEPTED;X-NUM-GUESTS=1:mailto:bob@example.com",
+            "ATTENDEE;CUTYPE=ROOM;ROLE=OPT-PARTICIPANT;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:room-1@resource.example.com",
+            "TRIGGER:-PT10M",
+            "TRIGGER:-P1D",
+            "ACTION:EMAIL",
+        ] {
+            assert!(lines.iter().any(|line| line == expected), "missing {}\n{}", expected, ics);
+        }
+        // Attendees need an address
+        assert_eq!(
+            lines.iter().filter(|l| l.starts_with("ATTENDEE;")).count(),
+            2
+        );
+        assert_eq!(ics.matches("BEGIN:VTIMEZONE").count(), 1);
+        assert!(ics.find("BEGIN:VTIMEZONE").unwrap() < ics.find("BEGIN:VEVENT").unwrap());
+    }
+
+    #[test]
+    fn test_raw_values_cannot_inject_lines() {
+        let mut event = meeting();
+        event.html_link = Some("https://example.com/e\r\nATTENDEE:mailto:eve@example.com".to_string());
+        event.conference.as_mut().unwrap().url = Some("https://meet.example.com/abc\nX-INJECTED:1".to_string());
+        event.organizer.as_mut().unwrap().email = Some("ada@example.com\r\nMETHOD:CANCEL".to_string());
+        event.organizer.as_mut().unwrap().name = Some("Ada\r\nX-INJECTED:2".to_string());
+
+        let ics = write_calendar(&[event], &ExportOptions::default());
+        let lines = unfolded_lines(&ics);
+        assert!(!lines.iter().any(|l| l.starts_with("X-INJECTED") || l.starts_with("METHOD")), "{}", ics);
+        assert!(!lines.iter().any(|l| l.contains("eve@example.com") && !l.starts_with("URL:")));
+        assert!(lines.contains(&"URL:https://example.com/eATTENDEE:mailto:eve@example.com".to_string()));
+        assert!(lines.contains(&"ORGANIZER;CN=\"AdaX-INJECTED:2\":mailto:ada@example.comMETHOD:CANCEL".to_string()));
+        assert!(!ics.replace("\r\n", "").contains(['\r', '\n']));
+    }
+
+    #[test]
+    fn test_all_day_and_utc_events() {
+        let mut holiday = UnifiedCalendarEvent::new(
+            "h".to_string(),
+            CalendarSource::Outlook,
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()),
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 12, 26).unwrap()),
+        );
+        holiday.recurrence_rule = Some("FREQ=YEARLY;UNTIL=20301225T000000Z".to_string());
+        let call = UnifiedCalendarEvent::new(
+            "c".to_string(),
+            CalendarSource::Outlook,
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-06-01T15:30:00+02:00").unwrap(),
+                None,
+            ),
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-06-01T16:00:00+02:00").unwrap(),
+                None,
+            ),
+        );
+
+        let lines = unfolded_lines(&write_calendar(&[holiday, call], &ExportOptions::default()));
+        assert!(lines.contains(&"DTSTART;VALUE=DATE:20241225".to_string()));
+        assert!(lines.contains(&"DTEND;VALUE=DATE:20241226".to_string()));
+        assert!(lines.contains(&"RRULE:FREQ=YEARLY;UNTIL=20301225".to_string()));
+        assert!(lines.contains(&"DTSTART:20240601T133000Z".to_string()));
+        assert!(!lines.iter().any(|l| l == "BEGIN:VTIMEZONE"));
+    }
+
+    #[test]
+    fn test_overridden_occurrence_shares_uid() {
+        let mut moved = meeting();
+        moved.id = "evt1_20240311T140000Z".to_string();
+        moved.ical_uid = None;
+        moved.series_id = Some("evt1".to_string());
+        moved.recurrence_rule = None;
+        moved.recurrence_exceptions = None;
+        moved.original_start = Some(EventMoment::timed(
+            DateTime::parse_from_rfc3339("2024-03-11T10:00:00-04:00").unwrap(),
+            None,
+        ));
+
+        let lines = unfolded_lines(&write_calendar(&[moved], &ExportOptions::default()));
+        assert!(lines.contains(&"UID:evt1".to_string()));
+        assert!(lines.contains(&"RECURRENCE-ID;TZID=America/New_York:20240311T100000".to_string()));
+        assert!(!lines.iter().any(|l| l.starts_with("RRULE:FREQ=WEEKLY")));
+    }
+
+    #[test]
+    fn test_calendar_options() {
+        let calendar = Calendar {
+            id: "primary".to_string(),
+            name: "Personal".to_string(),
+            description: Some("Home, family".to_string()),
+            color: None,
+            is_primary: true,
+            can_write: true,
+            source: CalendarSource::Google,
+            foreground_color: None,
+            time_zone: Some("Europe/Berlin".to_string()),
+            access_role: None,
+            default_reminders: None,
+            conference_types: None,
+            hidden: None,
+            selected: None,
+        };
+        let ics = export_calendar(&calendar, &[]);
+        assert!(ics.contains("X-WR-CALNAME:Personal\r\n"));
+        assert!(ics.contains("X-WR-CALDESC:Home\\, family\r\n"));
+        assert!(ics.contains("X-WR-TIMEZONE:Europe/Berlin\r\n"));
+    }
+
+    #[test]
+    fn test_trigger() {
+        assert_eq!(trigger(0), "PT0S");
+        assert_eq!(trigger(15), "-PT15M");
+        assert_eq!(trigger(90), "-PT1H30M");
+        assert_eq!(trigger(10080), "-P1W");
+        assert_eq!(trigger(1500), "-P1DT1H");
+        assert_eq!(trigger(-5), "PT5M");
+    }
+}
@@ -0,0 +1,475 @@
+//! `VFREEBUSY` export and import
+//!
+//! Availability is shared as busy periods only, without any event details.
+//! Periods are coalesced first: overlapping and touching periods merge, and
+//! where periods of different kinds overlap the busiest kind wins.
+
+use chrono::{DateTime, NaiveDateTime, NaiveTime, Utc};
+
+use super::import::{address, Zones};
+use super::parse::{parse_components, Component};
+use super::{parse_duration, ContentWriter, ImportError, DATE_TIME_FORMAT};
+use crate::{BusyStatus, FreeBusyPeriod};
+
+/// Availability of one calendar user over a time range
+#[derive(Debug, Clone, Default)]
+pub struct FreeBusy {
+    /// `UID` of the component, generated on export when unset
+    pub uid: Option<String>,
+    /// Email of the calendar user who published or requested the data
+    /// (`ORGANIZER`)
+    pub organizer: Option<String>,
+    /// Email of the calendar user whose time this is, in replies
+    /// (`ATTENDEE`)
+    pub attendee: Option<String>,
+    /// Start of the covered range, the earliest period when unset
+    pub start: Option<DateTime<Utc>>,
+    /// End of the covered range, the latest period when unset
+    pub end: Option<DateTime<Utc>>,
+    pub periods: Vec<FreeBusyPeriod>,
+}
+
+impl FreeBusy {
+    pub fn new(periods: Vec<FreeBusyPeriod>) -> Self {
+        Self {
+            periods,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_uid(mut self, uid: impl Into<String>) -> Self {
+        self.uid = Some(uid.into());
+        self
+    }
+
+    pub fn with_organizer(mut self, email: impl Into<String>) -> Self {
+        self.organizer = Some(email.into());
+        self
+    }
+
+    pub fn with_attendee(mut self, email: impl Into<String>) -> Self {
+        self.attendee = Some(email.into());
+        self
+    }
+
+    /// Limit the data to a range; periods are clipped to it on export
+    pub fn with_range(mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self.end = Some(end);
+        self
+    }
+}
+
+/// `VFREEBUSY` components read from a document
+#[derive(Debug, Clone, Default)]
+pub struct FreeBusyImport {
+    pub entries: Vec<FreeBusy>,
+    /// Properties that could not be read; the rest of their component is kept
+    pub errors: Vec<ImportError>,
+}
+
+/// Merge overlapping and touching periods
+///
+/// Where periods overlap, the busiest status wins: out of office over busy
+/// over tentative over free. The result is sorted and free of overlaps.
+pub fn coalesce_free_busy(periods: &[FreeBusyPeriod]) -> Vec<FreeBusyPeriod> {
+    let mut bounds: Vec<DateTime<Utc>> = periods
+        .iter()
+        .filter(|p| p.start < p.end)
+        .flat_map(|p| [p.start, p.end])
+        .collect();
+    bounds.sort();
+    bounds.dedup();
+
+    let mut merged: Vec<FreeBusyPeriod> = Vec::new();
+    for window in bounds.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let status = periods
+            .iter()
+            .filter(|p| p.start <= start && p.end >= end)
+            .map(|p| &p.status)
+            .max_by_key(|status| rank(status));
+        let Some(status) = status else { continue };
+        match merged.last_mut() {
+            Some(last) if last.end == start && rank(&last.status) == rank(status) => {
+                last.end = end;
+            }
+            _ => merged.push(FreeBusyPeriod {
+                start,
+                end,
+                status: status.clone(),
+            }),
+        }
+    }
+    merged
+}
+
+fn rank(status: &BusyStatus) -> u8 {
+    match status {
+        BusyStatus::Free => 0,
+        BusyStatus::Tentative => 1,
+        BusyStatus::Busy => 2,
+        BusyStatus::OutOfOffice => 3,
+    }
+}
+
+/// Render availability as a `VCALENDAR` with one `VFREEBUSY`
+///
+/// Periods are coalesced and clipped to the range, and written as one
+/// `FREEBUSY` line per status.
+pub fn write_free_busy(free_busy: &FreeBusy) -> String {
+    let mut periods = coalesce_free_busy(&free_busy.periods);
+    for period in &mut periods {
+        if let Some(start) = free_busy.start {
+            period.start = period.start.max(start);
+        }
+        if let Some(end) = free_busy.end {
+            period.end = period.end.min(end);
+        }
+    }
+    periods.retain(|p| p.start < p.end);
+
+    let mut out = ContentWriter::new();
+    out.begin("VCALENDAR");
+    out.property("VERSION", "2.0");
+    out.property(
+        "PRODID",
+        &format!("-//Calblend//Calblend {}//EN", env!("CARGO_PKG_VERSION")),
+    );
+    out.property("METHOD", "PUBLISH");
+    out.begin("VFREEBUSY");
+    let uid = free_busy
+        .uid
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    out.text("UID", &uid);
+    out.property("DTSTAMP", &utc(Utc::now()));
+    if let Some(organizer) = &free_busy.organizer {
+        out.property("ORGANIZER", &format!("mailto:{}", organizer));
+    }
+    if let Some(attendee) = &free_busy.attendee {
+        out.property("ATTENDEE", &format!("mailto:{}", attendee));
+    }
+    let start = free_busy.start.or(periods.first().map(|p| p.start));
+    let end = free_busy.end.or(periods.iter().map(|p| p.end).max());
+    if let Some(start) = start {
+        out.property("DTSTART", &utc(start));
+    }
+    if let Some(end) = end {
+        out.property("DTEND", &utc(end));
+    }
+    for status in [
+        BusyStatus::Busy,
+        BusyStatus::Tentative,
+        BusyStatus::OutOfOffice,
+        BusyStatus::Free,
+    ] {
+        let values: Vec<String> = periods
+            .iter()
+            .filter(|p| rank(&p.status) == rank(&status))
+            .map(|p| format!("{}/{}", utc(p.start), utc(p.end)))
+            .collect();
+        if !values.is_empty() {
+            out.property(
+                &format!("FREEBUSY;FBTYPE={}", fb_type(&status)),
+                &values.join(","),
+            );
+        }
+    }
+    out.end("VFREEBUSY");
+    out.end("VCALENDAR");
+    out.finish()
+}
+
+fn fb_type(status: &BusyStatus) -> &'static str {
+    match status {
+        BusyStatus::Free => "FREE",
+        BusyStatus::Busy => "BUSY",
+        BusyStatus::Tentative => "BUSY-TENTATIVE",
+        BusyStatus::OutOfOffice => "BUSY-UNAVAILABLE",
+    }
+}
+
+fn utc(instant: DateTime<Utc>) -> String {
+    format!("{}Z", instant.format(DATE_TIME_FORMAT))
+}
+
+/// Read the `VFREEBUSY` components of a document
+///
+/// Periods of each component are coalesced. Unknown `FBTYPE` values are
+/// read as busy, as RFC 5545 asks.
+pub fn read_free_busy(text: &str) -> FreeBusyImport {
+    let (roots, problems) = parse_components(text);
+    let mut result = FreeBusyImport {
+        errors: problems.into_iter().map(Into::into).collect(),
+        ..Default::default()
+    };
+    for root in &roots {
+        let (container, components): (&[Component], Vec<&Component>) = match root.name.as_str() {
+            "VCALENDAR" => (
+                &root.components,
+                root.components_named("VFREEBUSY").collect(),
+            ),
+            "VFREEBUSY" => (&[], vec![root]),
+            _ => continue,
+        };
+        let timezones: Vec<&Component> =
+            container.iter().filter(|c| c.name == "VTIMEZONE").collect();
+        let zones = Zones::new(&timezones, None);
+        for component in components {
+            result
+                .entries
+                .push(read_component(component, &zones, &mut result.errors));
+        }
+    }
+    result
+}
+
+fn read_component(component: &Component, zones: &Zones, errors: &mut Vec<ImportError>) -> FreeBusy {
+    let uid = component
+        .property("UID")
+        .map(|p| p.value.trim().to_string());
+    let mut error = |line: usize, message: String| {
+        errors.push(ImportError {
+            component: component.name.clone(),
+            uid: uid.clone(),
+            line,
+            message,
+        })
+    };
+
+    let mut free_busy = FreeBusy {
+        uid: uid.clone(),
+        organizer: component
+            .property("ORGANIZER")
+            .and_then(|p| address(&p.value)),
+        attendee: component
+            .property("ATTENDEE")
+            .and_then(|p| address(&p.value)),
+        ..Default::default()
+    };
+    for (name, slot) in [
+        ("DTSTART", &mut free_busy.start),
+        ("DTEND", &mut free_busy.end),
+    ] {
+        if let Some(property) = component.property(name) {
+            match zones.moment(property) {
+                Ok(moment) => {
+                    *slot = Some(match moment.date_time() {
+                        Some(instant) => instant.with_timezone(&Utc),
+                        None => moment.date().and_time(NaiveTime::MIN).and_utc(),
+                    })
+                }
+                Err(message) => error(property.line, message),
+            }
+        }
+    }
+
+    let mut periods = Vec::new();
+    for property in component.properties_named("FREEBUSY") {
+        let status = match property
+            .param("FBTYPE")
+            .map(str::to_ascii_uppercase)
+            .as_deref()
+        {
+            Some("FREE") => BusyStatus::Free,
+            Some("BUSY-TENTATIVE") => BusyStatus::Tentative,
+            Some("BUSY-UNAVAILABLE") => BusyStatus::OutOfOffice,
+            _ => BusyStatus::Busy,
+        };
+        for value in property.value.split(',') {
+            match period(value) {
+                Some((start, end)) => periods.push(FreeBusyPeriod {
+                    start,
+                    end,
+                    status: status.clone(),
+                }),
+                None => error(
+                    property.line,
+                    format!("Invalid FREEBUSY period {:?}", value.trim()),
+                ),
+            }
+        }
+    }
+    free_busy.periods = coalesce_free_busy(&periods);
+    free_busy
+}
+
+/// A `start/end` or `start/duration` period; times are UTC
+fn period(value: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (start, end) = value.trim().split_once('/')?;
+    let start = instant(start)?;
+    let end = if end.trim_start().starts_with(['P', '+', '-']) {
+        let duration = parse_duration(end).filter(|d| *d > chrono::Duration::zero())?;
+        start.checked_add_signed(duration)?
+    } else {
+        instant(end)?
+    };
+    (start < end).then_some((start, end))
+}
+
+fn instant(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    let local = value.strip_suffix('Z').unwrap_or(value);
+    NaiveDateTime::parse_from_str(local, DATE_TIME_FORMAT)
+        .ok()
+        .map(|local| local.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn period(start: &str, end: &str, status: BusyStatus) -> FreeBusyPeriod {
+        FreeBusyPeriod {
+            start: at(start),
+            end: at(end),
+            status,
+        }
+    }
+
+    fn summary(periods: &[FreeBusyPeriod]) -> Vec<(String, String, u8)> {
+        periods
+            .iter()
+            .map(|p| {
+                (
+                    p.start.format("%H:%M").to_string(),
+                    p.end.format("%H:%M").to_string(),
+                    rank(&p.status),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let periods = [
+            period(
+                "2024-03-11T10:00:00Z",
+                "2024-03-11T11:00:00Z",
+                BusyStatus::Busy,
+            ),
+            period(
+                "2024-03-11T09:00:00Z",
+                "2024-03-11T10:00:00Z",
+                BusyStatus::Busy,
+            ),
+            period(
+                "2024-03-11T10:30:00Z",
+                "2024-03-11T12:00:00Z",
+                BusyStatus::Tentative,
+            ),
+            period(
+                "2024-03-11T11:30:00Z",
+                "2024-03-11T11:45:00Z",
+                BusyStatus::OutOfOffice,
+            ),
+            period(
+                "2024-03-11T14:00:00Z",
+                "2024-03-11T13:00:00Z",
+                BusyStatus::Busy,
+            ),
+        ];
+        assert_eq!(
+            summary(&coalesce_free_busy(&periods)),
+            [
+                ("09:00".into(), "11:00".into(), 2),
+                ("11:00".into(), "11:30".into(), 1),
+                ("11:30".into(), "11:45".into(), 3),
+                ("11:45".into(), "12:00".into(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_free_busy() {
+        let free_busy = FreeBusy::new(vec![
+            period(
+                "2024-03-11T08:00:00Z",
+                "2024-03-11T10:00:00Z",
+                BusyStatus::Busy,
+            ),
+            period(
+                "2024-03-11T09:30:00Z",
+                "2024-03-11T11:00:00Z",
+                BusyStatus::Busy,
+            ),
+            period(
+                "2024-03-11T13:00:00Z",
+                "2024-03-11T14:00:00Z",
+                BusyStatus::Tentative,
+            ),
+            period(
+                "2024-03-12T00:00:00Z",
+                "2024-03-13T00:00:00Z",
+                BusyStatus::OutOfOffice,
+            ),
+        ])
+        .with_uid("avail-1")
+        .with_organizer("ada@example.com")
+        .with_range(at("2024-03-11T09:00:00Z"), at("2024-03-12T12:00:00Z"));
+        let ics = write_free_busy(&free_busy);
+        for line in [
+            "METHOD:PUBLISH",
+            "UID:avail-1",
+            "ORGANIZER:mailto:ada@example.com",
+            "DTSTART:20240311T090000Z",
+            "DTEND:20240312T120000Z",
+            "FREEBUSY;FBTYPE=BUSY:20240311T090000Z/20240311T110000Z",
+            "FREEBUSY;FBTYPE=BUSY-TENTATIVE:20240311T130000Z/20240311T140000Z",
+            "FREEBUSY;FBTYPE=BUSY-UNAVAILABLE:20240312T000000Z/20240312T120000Z",
+        ] {
+            assert!(
+                ics.contains(&format!("{}\r\n", line)),
+                "{} in {}",
+                line,
+                ics
+            );
+        }
+        assert!(!ics.contains("ATTENDEE"));
+
+        let read = read_free_busy(&ics);
+        assert!(read.errors.is_empty(), "{:?}", read.errors);
+        let entry = &read.entries[0];
+        assert_eq!(entry.uid.as_deref(), Some("avail-1"));
+        assert_eq!(entry.organizer.as_deref(), Some("ada@example.com"));
+        assert_eq!(entry.start, Some(at("2024-03-11T09:00:00Z")));
+        assert_eq!(entry.periods.len(), 3);
+    }
+
+    #[test]
+    fn test_read_free_busy() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VFREEBUSY\r\n\
+                   UID:x\r\n\
+                   ATTENDEE;CN=Bob:mailto:bob@example.com\r\n\
+                   DTSTART:20240311T000000Z\r\n\
+                   DTEND:20240312T000000Z\r\n\
+                   FREEBUSY:20240311T090000Z/PT1H,20240311T100000Z/20240311T103000Z\r\n\
+                   FREEBUSY;FBTYPE=X-WORKING-ELSEWHERE:20240311T150000Z/PT30M\r\n\
+                   FREEBUSY;FBTYPE=FREE:20240311T120000Z/PT1H,garbage\r\n\
+                   FREEBUSY:20240310T090000Z/P99999999999D\r\n\
+                   END:VFREEBUSY\r\n\
+                   END:VCALENDAR\r\n";
+        let read = read_free_busy(ics);
+        let entry = &read.entries[0];
+        assert_eq!(entry.attendee.as_deref(), Some("bob@example.com"));
+        assert_eq!(
+            summary(&entry.periods),
+            [
+                ("09:00".into(), "10:30".into(), 2),
+                ("12:00".into(), "13:00".into(), 0),
+                ("15:00".into(), "15:30".into(), 2),
+            ]
+        );
+        assert_eq!(read.errors.len(), 2);
+        assert_eq!(read.errors[0].line, 9);
+        assert!(read.errors[0].message.contains("garbage"));
+        assert_eq!(read.errors[1].line, 10);
+        assert!(read.errors[1].message.contains("P99999999999D"));
+    }
+}
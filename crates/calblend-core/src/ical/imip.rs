@@ -0,0 +1,356 @@
+//! iMIP (RFC 6047): iTIP messages sent as email
+
+use std::borrow::Cow;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+
+use super::itip::{ItipMessage, ItipMethod};
+use crate::models::{EventMoment, ParticipantStatus};
+use crate::{CalblendError, Result};
+
+/// Longest base64 line allowed by RFC 2045
+const BASE64_LINE: usize = 76;
+/// Bytes of header text per RFC 2047 encoded word, keeping words under
+/// the 75 character limit
+const ENCODED_WORD_BYTES: usize = 45;
+
+/// Render a message as `multipart/alternative` with a plain-text summary and
+/// the `text/calendar` part clients act on
+///
+/// Fails when the sender or a recipient is not a plain ASCII address, as
+/// those go into the headers as they are.
+pub(super) fn write_imip(message: &ItipMessage) -> Result<String> {
+    for address in std::iter::once(&message.from).chain(&message.recipients) {
+        check_address(address)?;
+    }
+    let boundary = format!("calblend-{}", uuid::Uuid::new_v4().simple());
+    let mut out = String::new();
+    for (name, value) in [
+        ("From", message.from.clone()),
+        ("To", message.recipients.join(", ")),
+        ("Subject", encode_header(&subject(message))),
+        ("Date", Utc::now().to_rfc2822()),
+        ("Message-ID", format!("<{}@calblend>", uuid::Uuid::new_v4())),
+        ("MIME-Version", "1.0".to_string()),
+        (
+            "Content-Type",
+            format!("multipart/alternative; boundary=\"{}\"", boundary),
+        ),
+    ] {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    out.push_str("\r\n");
+    write_part(
+        &mut out,
+        &boundary,
+        "text/plain; charset=utf-8",
+        &summary(message),
+    );
+    write_part(
+        &mut out,
+        &boundary,
+        &format!("text/calendar; charset=utf-8; method={}", message.method),
+        &message.to_ics(),
+    );
+    out.push_str(&format!("--{}--\r\n", boundary));
+    Ok(out)
+}
+
+/// Reject addresses that could break out of an address header: control
+/// characters and line breaks, spaces, list separators and angle brackets
+fn check_address(address: &str) -> Result<()> {
+    let valid = address.contains('@')
+        && address
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !matches!(c, ',' | ';' | '<' | '>' | '"' | '(' | ')'));
+    if valid {
+        Ok(())
+    } else {
+        Err(CalblendError::InvalidData(format!("Invalid email address {:?}", address)))
+    }
+}
+
+fn write_part(out: &mut String, boundary: &str, content_type: &str, body: &str) {
+    out.push_str(&format!(
+        "--{}\r\nContent-Type: {}\r\nContent-Transfer-Encoding: base64\r\n\r\n",
+        boundary, content_type
+    ));
+    let encoded = STANDARD.encode(body);
+    // Base64 output is ASCII, so any byte offset is a char boundary
+    let mut rest = encoded.as_str();
+    while !rest.is_empty() {
+        let (line, next) = rest.split_at(rest.len().min(BASE64_LINE));
+        out.push_str(line);
+        out.push_str("\r\n");
+        rest = next;
+    }
+}
+
+fn subject(message: &ItipMessage) -> String {
+    let event = &message.event;
+    let prefix = match message.method {
+        ItipMethod::Request if event.sequence.unwrap_or(0) > 0 => "Updated invitation",
+        ItipMethod::Request => "Invitation",
+        ItipMethod::Cancel => "Cancelled",
+        ItipMethod::Counter => "New time proposed",
+        ItipMethod::Reply => {
+            let status = event
+                .attendees
+                .iter()
+                .flatten()
+                .next()
+                .and_then(|a| a.response_status.as_ref());
+            match status {
+                Some(ParticipantStatus::Accepted) => "Accepted",
+                Some(ParticipantStatus::Tentative) => "Tentatively accepted",
+                Some(ParticipantStatus::Declined) => "Declined",
+                _ => "Reply",
+            }
+        }
+    };
+    format!(
+        "{}: {}",
+        prefix,
+        event.title.as_deref().unwrap_or("(No title)")
+    )
+}
+
+fn summary(message: &ItipMessage) -> String {
+    let event = &message.event;
+    let mut lines = vec![event
+        .title
+        .clone()
+        .unwrap_or_else(|| "(No title)".to_string())];
+    lines.push(String::new());
+    lines.push(format!(
+        "When: {} - {}",
+        describe(&event.start),
+        describe(&event.end)
+    ));
+    if let Some(location) = &event.location {
+        lines.push(format!("Where: {}", location));
+    }
+    if let Some(organizer) = event.organizer.as_ref().and_then(|o| o.email.as_ref()) {
+        lines.push(format!("Organizer: {}", organizer));
+    }
+    let comments = match message.method {
+        ItipMethod::Reply | ItipMethod::Counter => event
+            .attendees
+            .iter()
+            .flatten()
+            .filter_map(|a| a.comment.as_deref())
+            .collect(),
+        _ => Vec::new(),
+    };
+    if !comments.is_empty() {
+        lines.push(String::new());
+        lines.extend(comments.into_iter().map(str::to_string));
+    }
+    lines.join("\r\n") + "\r\n"
+}
+
+fn describe(moment: &EventMoment) -> String {
+    match moment {
+        EventMoment::Date { date, .. } => date.format("%a %e %b %Y").to_string(),
+        EventMoment::DateTime {
+            date_time,
+            time_zone,
+        } => {
+            let when = date_time.format("%a %e %b %Y %H:%M").to_string();
+            match time_zone {
+                Some(zone) => format!("{} ({})", when, zone),
+                None => format!("{} (UTC{})", when, date_time.format("%:z")),
+            }
+        }
+    }
+}
+
+/// Header value, as RFC 2047 encoded words when it is not plain ASCII
+fn encode_header(value: &str) -> String {
+    if value.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+        return value.to_string();
+    }
+    let mut words = Vec::new();
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        if i + c.len_utf8() - start > ENCODED_WORD_BYTES {
+            words.push(&value[start..i]);
+            start = i;
+        }
+    }
+    words.push(&value[start..]);
+    words
+        .into_iter()
+        .map(|word| format!("=?UTF-8?B?{}?=", STANDARD.encode(word)))
+        .collect::<Vec<_>>()
+        .join("\r\n ")
+}
+
+/// The iCalendar text in a message
+///
+/// Plain iCalendar is returned as is; a MIME message is searched for its
+/// first `text/calendar` part, decoding base64 and quoted-printable bodies.
+pub(super) fn calendar_part(message: &str) -> Option<String> {
+    let trimmed = message.trim_start();
+    if trimmed
+        .get(..15)
+        .is_some_and(|start| start.eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+    {
+        return Some(trimmed.to_string());
+    }
+
+    let (headers, body) = split_entity(message);
+    let content_type = header(&headers, "Content-Type").unwrap_or("text/plain");
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if media_type.starts_with("multipart/") {
+        let delimiter = format!("--{}", mime_param(content_type, "boundary")?);
+        return body
+            .split(delimiter.as_str())
+            .skip(1)
+            .take_while(|part| !part.starts_with("--"))
+            .find_map(|part| {
+                let part = part
+                    .strip_prefix("\r\n")
+                    .or_else(|| part.strip_prefix('\n'));
+                calendar_part(part.unwrap_or_default())
+            });
+    }
+    if media_type != "text/calendar" && media_type != "application/ics" {
+        return None;
+    }
+    let encoding = header(&headers, "Content-Transfer-Encoding")
+        .unwrap_or("7bit")
+        .trim()
+        .to_ascii_lowercase();
+    let decoded: Cow<'_, [u8]> = match encoding.as_str() {
+        "base64" => {
+            let compact: String = body.split_whitespace().collect();
+            Cow::Owned(STANDARD.decode(compact).ok()?)
+        }
+        "quoted-printable" => Cow::Owned(decode_quoted_printable(body)),
+        _ => Cow::Borrowed(body.as_bytes()),
+    };
+    Some(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+/// Unfolded headers and the body of a MIME entity
+fn split_entity(entity: &str) -> (Vec<(String, String)>, &str) {
+    let (head, body) = match (entity.find("\r\n\r\n"), entity.find("\n\n")) {
+        (Some(crlf), Some(lf)) if lf < crlf => (&entity[..lf], &entity[lf + 2..]),
+        (Some(crlf), _) => (&entity[..crlf], &entity[crlf + 4..]),
+        (None, Some(lf)) => (&entity[..lf], &entity[lf + 2..]),
+        (None, None) => (entity, ""),
+    };
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    (headers, body)
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// A parameter of a header value, e.g. the `boundary` of a `Content-Type`
+fn mime_param<'a>(value: &'a str, name: &str) -> Option<&'a str> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+fn decode_quoted_printable(body: &str) -> Vec<u8> {
+    let bytes = body.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        // Soft line breaks join lines
+        if bytes[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+        } else if bytes[i + 1..].starts_with(b"\n") {
+            i += 2;
+        } else if let Some(byte) = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(b'=');
+            i += 1;
+        }
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_header() {
+        assert_eq!(encode_header("Invitation: Review"), "Invitation: Review");
+        let encoded = encode_header(&format!("Invitation: {}", "Überprüfung ".repeat(6)));
+        assert!(encoded.starts_with("=?UTF-8?B?"));
+        assert!(encoded.split("\r\n ").all(|word| word.len() <= 75));
+    }
+
+    #[test]
+    fn test_calendar_part_of_mime_message() {
+        let mime = "From: bob@example.com\r\n\
+                    Content-Type: multipart/mixed;\r\n boundary=\"outer\"\r\n\r\n\
+                    --outer\r\n\
+                    Content-Type: multipart/alternative; boundary=inner\r\n\r\n\
+                    --inner\r\n\
+                    Content-Type: text/plain\r\n\r\n\
+                    Bob has accepted\r\n\
+                    --inner\r\n\
+                    Content-Type: text/calendar; method=REPLY; charset=utf-8\r\n\
+                    Content-Transfer-Encoding: quoted-printable\r\n\r\n\
+                    BEGIN:VCALENDAR\r\n\
+                    SUMMARY:Caf=C3=A9 =\r\n\
+                    chat\r\n\
+                    END:VCALENDAR\r\n\
+                    --inner--\r\n\
+                    --outer--\r\n";
+        let ics = calendar_part(mime).unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nSUMMARY:Café chat\r\n"));
+
+        assert_eq!(
+            calendar_part("BEGIN:VCALENDAR\nEND:VCALENDAR\n").as_deref(),
+            Some("BEGIN:VCALENDAR\nEND:VCALENDAR\n")
+        );
+        assert_eq!(calendar_part("Subject: hi\r\n\r\nHello"), None);
+    }
+
+    #[test]
+    fn test_quoted_printable() {
+        assert_eq!(decode_quoted_printable("a=3Db=\r\nc=ZZ="), b"a=bc=ZZ=");
+    }
+}
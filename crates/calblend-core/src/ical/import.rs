@@ -0,0 +1,1203 @@
+//! Reading iCalendar files into unified events
+//!
+//! Files come from many tools and are rarely strictly valid, so reading is
+//! lenient: a `VEVENT` that cannot be read is reported in
+//! [`ImportResult::errors`] and the rest of the file is still imported.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, Utc};
+use chrono_tz::Tz;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+use super::parse::{parse_components, Component, ParseProblem, Property};
+use super::{parse_duration, split_text_list, unescape_text, DATE_FORMAT, DATE_TIME_FORMAT};
+use crate::models::{
+    CalendarSource, CalendarUserType, ConferenceLink, EventMoment, EventStatus, EventVisibility,
+    Participant, ParticipantRole, ParticipantStatus, Reminder, ReminderMethod, ShowAs,
+    UnifiedCalendarEvent,
+};
+use crate::recurrence::{
+    expand_rule, ExpandBounds, Frequency, RecurrencePattern, RecurrenceRule, RecurrenceUntil,
+};
+use crate::timezone::{canonical_name, resolve_local};
+
+/// How to read an iCalendar file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// Source the imported events are tagged with
+    pub source: CalendarSource,
+    /// Zone for floating times, in place of the file's `X-WR-TIMEZONE`
+    pub default_time_zone: Option<String>,
+    /// Restore the fields written as `X-CALBLEND-*` properties, ids
+    /// included, in place of the defaults
+    pub calblend_properties: bool,
+}
+
+impl ImportOptions {
+    pub fn new(source: CalendarSource) -> Self {
+        Self {
+            source,
+            default_time_zone: None,
+            calblend_properties: false,
+        }
+    }
+
+    /// Read floating times in this zone
+    pub fn with_default_time_zone(mut self, time_zone: impl Into<String>) -> Self {
+        self.default_time_zone = Some(time_zone.into());
+        self
+    }
+
+    /// Read the `X-CALBLEND-*` properties
+    pub fn with_calblend_properties(mut self) -> Self {
+        self.calblend_properties = true;
+        self
+    }
+}
+
+/// Events read from an iCalendar file
+#[derive(Debug, Clone, Default)]
+pub struct ImportResult {
+    /// Calendar name (`X-WR-CALNAME`)
+    pub name: Option<String>,
+    /// Calendar description (`X-WR-CALDESC`)
+    pub description: Option<String>,
+    /// Calendar zone (`X-WR-TIMEZONE`)
+    pub time_zone: Option<String>,
+    /// Events in file order, with empty ids until they are created
+    pub events: Vec<UnifiedCalendarEvent>,
+    /// Components that were skipped or only partly read
+    pub errors: Vec<ImportError>,
+}
+
+/// A problem with one component of an imported file
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{component} at line {line}: {message}")]
+pub struct ImportError {
+    /// Component name, e.g. `VEVENT`
+    pub component: String,
+    /// `UID` of the event the problem is in, when known
+    pub uid: Option<String>,
+    /// Line the component or property starts on
+    pub line: usize,
+    pub message: String,
+}
+
+impl From<ParseProblem> for ImportError {
+    fn from(problem: ParseProblem) -> Self {
+        Self {
+            component: problem.component,
+            uid: None,
+            line: problem.line,
+            message: problem.message,
+        }
+    }
+}
+
+/// Read the `VEVENT`s of an iCalendar file
+///
+/// Other components (`VTODO`, `VJOURNAL`, ...) are ignored. `X-`
+/// properties of each event are kept in its `raw` value as
+/// `{"x_properties": [{"name", "params", "value"}]}`.
+pub fn read_calendar(text: &str, options: &ImportOptions) -> ImportResult {
+    let (components, problems) = parse_components(text);
+    let mut result = ImportResult {
+        errors: problems.into_iter().map(ImportError::from).collect(),
+        ..Default::default()
+    };
+
+    for component in &components {
+        match component.name.as_str() {
+            "VCALENDAR" => read_vcalendar(component, options, &mut result),
+            // Some tools write bare events without the calendar around them
+            "VEVENT" => {
+                let zones = Zones::new(&[], floating_zone(None, component, options, &mut result));
+                read_vevent(component, &zones, options, &mut result);
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+fn read_vcalendar(calendar: &Component, options: &ImportOptions, result: &mut ImportResult) {
+    let text = |name: &str| calendar.property(name).map(|p| unescape_text(&p.value));
+    result.name = result.name.take().or_else(|| text("X-WR-CALNAME"));
+    result.description = result.description.take().or_else(|| text("X-WR-CALDESC"));
+    let calendar_zone = text("X-WR-TIMEZONE");
+    if result.time_zone.is_none() {
+        result.time_zone = calendar_zone
+            .as_deref()
+            .map(|name| known_zone(name).map_or(name.to_string(), |tz| tz.name().to_string()));
+    }
+
+    let floating = floating_zone(calendar_zone.as_deref(), calendar, options, result);
+    let timezones: Vec<&Component> = calendar.components_named("VTIMEZONE").collect();
+    let zones = Zones::new(&timezones, floating);
+    for event in calendar.components_named("VEVENT") {
+        read_vevent(event, &zones, options, result);
+    }
+}
+
+/// Zone for floating times: the configured default, else the calendar's
+fn floating_zone(
+    calendar_zone: Option<&str>,
+    calendar: &Component,
+    options: &ImportOptions,
+    result: &mut ImportResult,
+) -> Option<Tz> {
+    let name = options.default_time_zone.as_deref().or(calendar_zone)?;
+    let zone = known_zone(name);
+    if zone.is_none() {
+        result.errors.push(ImportError {
+            component: calendar.name.clone(),
+            uid: None,
+            line: calendar.line,
+            message: format!(
+                "Unknown time zone {:?}, floating times are read as UTC",
+                name
+            ),
+        });
+    }
+    zone
+}
+
+fn read_vevent(
+    component: &Component,
+    zones: &Zones,
+    options: &ImportOptions,
+    result: &mut ImportResult,
+) {
+    let uid = component
+        .property("UID")
+        .map(|p| unescape_text(p.value.trim()))
+        .filter(|uid| !uid.is_empty());
+    let mut reader = EventReader {
+        zones,
+        uid: uid.clone(),
+        errors: Vec::new(),
+    };
+    match reader.event(component, options) {
+        Ok(event) => result.events.push(event),
+        Err(message) => result.errors.push(ImportError {
+            component: component.name.clone(),
+            uid,
+            line: component.line,
+            message,
+        }),
+    }
+    result.errors.append(&mut reader.errors);
+}
+
+/// Reads one `VEVENT`, collecting problems that do not stop it
+struct EventReader<'a> {
+    zones: &'a Zones,
+    uid: Option<String>,
+    errors: Vec<ImportError>,
+}
+
+impl EventReader<'_> {
+    fn event(
+        &mut self,
+        component: &Component,
+        options: &ImportOptions,
+    ) -> Result<UnifiedCalendarEvent, String> {
+        let start = component
+            .property("DTSTART")
+            .ok_or_else(|| "Missing DTSTART".to_string())
+            .and_then(|p| self.zones.moment(p))?;
+        let end = match (component.property("DTEND"), component.property("DURATION")) {
+            (Some(end), _) => self.zones.moment(end)?,
+            (None, Some(duration)) => {
+                parse_duration(&duration.value)
+                    .and_then(|d| add_duration(&start, d))
+                    .ok_or_else(|| format!("Invalid DURATION {:?}", duration.value))?
+            }
+            (None, None) => match &start {
+                EventMoment::Date { date, .. } => EventMoment::all_day(
+                    date.succ_opt().ok_or_else(|| "DTSTART out of range".to_string())?,
+                ),
+                timed => timed.clone(),
+            },
+        };
+        if start.is_all_day() != end.is_all_day() {
+            return Err("DTSTART and DTEND must both be dates or both date-times".to_string());
+        }
+
+        let mut event = UnifiedCalendarEvent::new(String::new(), options.source, start, end);
+        event.ical_uid = self.uid.clone();
+        if let Some(original_start) = component.property("RECURRENCE-ID") {
+            event.original_start = Some(self.zones.moment(original_start)?);
+            event.series_id = self.uid.clone();
+        }
+        event.set_recurrence(self.recurrence(component)?);
+
+        let text = |name: &str| {
+            component
+                .property(name)
+                .map(|p| unescape_text(&p.value))
+                .filter(|value| !value.is_empty())
+        };
+        event.title = text("SUMMARY");
+        event.description = text("DESCRIPTION");
+        event.location = text("LOCATION");
+        event.html_link = text("URL");
+        event.sequence = component
+            .property("SEQUENCE")
+            .and_then(|p| p.value.trim().parse().ok());
+        let categories: Vec<String> = component
+            .properties_named("CATEGORIES")
+            .flat_map(|p| split_text_list(&p.value))
+            .map(|category| category.trim().to_string())
+            .filter(|category| !category.is_empty())
+            .collect();
+        event.categories = (!categories.is_empty()).then_some(categories);
+
+        let keyword = |name: &str| {
+            component
+                .property(name)
+                .map(|p| p.value.trim().to_ascii_uppercase())
+        };
+        event.status = keyword("STATUS").and_then(|status| match status.as_str() {
+            "CONFIRMED" => Some(EventStatus::Confirmed),
+            "TENTATIVE" => Some(EventStatus::Tentative),
+            "CANCELLED" => Some(EventStatus::Cancelled),
+            _ => None,
+        });
+        event.visibility = keyword("CLASS").map(|class| match class.as_str() {
+            "PUBLIC" => EventVisibility::Public,
+            "PRIVATE" => EventVisibility::Private,
+            "CONFIDENTIAL" => EventVisibility::Confidential,
+            _ => EventVisibility::Default,
+        });
+        event.show_as = keyword("TRANSP").map(|transp| match transp.as_str() {
+            "TRANSPARENT" => ShowAs::Free,
+            _ => ShowAs::Busy,
+        });
+        event.conference = component.property("CONFERENCE").map(|p| ConferenceLink {
+            url: Some(p.value.trim().to_string()),
+            provider: None,
+        });
+        event.created = self.instant(component.property("CREATED"));
+        event.updated = self.instant(component.property("LAST-MODIFIED"));
+
+        event.organizer = component.property("ORGANIZER").map(|p| Participant {
+            organizer: Some(true),
+            ..participant(p)
+        });
+        let organizer_email = event.organizer.as_ref().and_then(|o| o.email.clone());
+        let attendees: Vec<Participant> = component
+            .properties_named("ATTENDEE")
+            .map(|p| {
+                let mut attendee = participant(p);
+                if let (Some(email), Some(organizer)) = (&attendee.email, &organizer_email) {
+                    if email.eq_ignore_ascii_case(organizer) {
+                        attendee.organizer = Some(true);
+                    }
+                }
+                attendee
+            })
+            .collect();
+        event.attendees = (!attendees.is_empty()).then_some(attendees);
+
+        let mut reminders = Vec::new();
+        for alarm in component.components_named("VALARM") {
+            match self.reminder(alarm, &event) {
+                Ok(Some(reminder)) => reminders.push(reminder),
+                Ok(None) => {}
+                Err(message) => self.error(alarm, message),
+            }
+        }
+        event.reminders = (!reminders.is_empty()).then_some(reminders);
+
+        let x_properties: Vec<Value> = component
+            .properties
+            .iter()
+            .filter(|p| p.name.starts_with("X-"))
+            .filter(|p| !(options.calblend_properties && p.name.starts_with("X-CALBLEND-")))
+            .map(|p| {
+                let params: Map<String, Value> = p
+                    .params
+                    .iter()
+                    .map(|(name, values)| (name.clone(), json!(values)))
+                    .collect();
+                json!({ "name": p.name, "params": params, "value": p.value })
+            })
+            .collect();
+        if !x_properties.is_empty() {
+            event.raw = Some(json!({ "x_properties": x_properties }));
+        }
+        if options.calblend_properties {
+            self.calblend_properties(component, &mut event)?;
+        }
+        Ok(event)
+    }
+
+    /// Fields written by [`ExportOptions::calblend_properties`]
+    ///
+    /// [`ExportOptions::calblend_properties`]: super::ExportOptions::calblend_properties
+    fn calblend_properties(
+        &self,
+        component: &Component,
+        event: &mut UnifiedCalendarEvent,
+    ) -> Result<(), String> {
+        let text = |name: &str| component.property(name).map(|p| unescape_text(&p.value));
+        if let Some(id) = text("X-CALBLEND-ID") {
+            event.id = id;
+        }
+        if let Some(calendar_id) = text("X-CALBLEND-CALENDAR-ID") {
+            event.calendar_id = Some(calendar_id);
+        }
+        if let Some(source) = text("X-CALBLEND-SOURCE") {
+            event.source = serde_json::from_value(Value::String(source.clone()))
+                .map_err(|_| format!("Invalid X-CALBLEND-SOURCE {:?}", source))?;
+        }
+        if let Some(color) = text("X-CALBLEND-COLOR") {
+            event.color = Some(color);
+        }
+        if let Some(etag) = text("X-CALBLEND-ETAG") {
+            event.etag = Some(etag);
+        }
+        if let Some(raw) = text("X-CALBLEND-RAW") {
+            event.raw = Some(
+                serde_json::from_str(&raw).map_err(|e| format!("Invalid X-CALBLEND-RAW: {}", e))?,
+            );
+        }
+        Ok(())
+    }
+
+    /// `RRULE`, `RDATE` and `EXDATE` with zone names the tz database knows
+    ///
+    /// Dates in a zone defined only by an embedded `VTIMEZONE` become UTC.
+    fn recurrence(&self, component: &Component) -> Result<Option<RecurrencePattern>, String> {
+        let mut lines = Vec::new();
+        for property in &component.properties {
+            match property.name.as_str() {
+                "RRULE" => lines.push(format!("RRULE:{}", property.value.trim())),
+                "RDATE" | "EXDATE" => {
+                    for value in property.value.split(',') {
+                        lines.push(self.date_line(property, value.trim()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if lines.is_empty() {
+            return Ok(None);
+        }
+        RecurrencePattern::parse(&lines)
+            .map(Some)
+            .map_err(|e| format!("Invalid recurrence: {}", e))
+    }
+
+    fn date_line(&self, property: &Property, value: &str) -> String {
+        let local = value.split('/').next().unwrap_or(value);
+        let tzid = property
+            .param("TZID")
+            .filter(|_| local.contains('T') && !local.ends_with('Z'));
+        let Some(tzid) = tzid else {
+            return format!("{}:{}", property.name, value);
+        };
+        match (self.zones.zone(Some(tzid)), parse_date_time(local)) {
+            (ZoneRef::Tz(tz), _) => format!("{};TZID={}:{}", property.name, tz.name(), value),
+            (zone, Some(local)) => {
+                let utc = zone.resolve(local).with_timezone(&Utc);
+                format!("{}:{}Z", property.name, utc.format(DATE_TIME_FORMAT))
+            }
+            // Left for the recurrence parser to report
+            (_, None) => format!("{}:{}", property.name, value),
+        }
+    }
+
+    /// Reminder for a `VALARM`, `None` for actions without an equivalent
+    fn reminder(
+        &self,
+        alarm: &Component,
+        event: &UnifiedCalendarEvent,
+    ) -> Result<Option<Reminder>, String> {
+        let action = alarm
+            .property("ACTION")
+            .map(|p| p.value.trim().to_ascii_uppercase())
+            .unwrap_or_default();
+        let method = match action.as_str() {
+            "DISPLAY" | "AUDIO" => ReminderMethod::Popup,
+            "EMAIL" => ReminderMethod::Email,
+            _ => return Ok(None),
+        };
+        let trigger = alarm
+            .property("TRIGGER")
+            .ok_or_else(|| "Missing TRIGGER".to_string())?;
+        let start = self.zones.instant(&event.start);
+        let absolute = trigger
+            .param("VALUE")
+            .is_some_and(|v| v.eq_ignore_ascii_case("DATE-TIME"));
+        let before = if absolute {
+            let at = self.zones.moment(trigger)?;
+            start - self.zones.instant(&at)
+        } else {
+            let offset = parse_duration(&trigger.value)
+                .ok_or_else(|| format!("Invalid TRIGGER {:?}", trigger.value))?;
+            let related_to_end = trigger
+                .param("RELATED")
+                .is_some_and(|r| r.eq_ignore_ascii_case("END"));
+            let anchor = if related_to_end {
+                self.zones.instant(&event.end) - start
+            } else {
+                Duration::zero()
+            };
+            -anchor
+                .checked_add(&offset)
+                .ok_or_else(|| format!("TRIGGER out of range: {:?}", trigger.value))?
+        };
+        let minutes_before = i32::try_from(before.num_minutes())
+            .map_err(|_| format!("TRIGGER out of range: {:?}", trigger.value))?;
+        Ok(Some(Reminder {
+            minutes_before,
+            method: Some(method),
+        }))
+    }
+
+    fn instant(&self, property: Option<&Property>) -> Option<DateTime<FixedOffset>> {
+        property
+            .and_then(|p| self.zones.moment(p).ok())
+            .and_then(|moment| moment.date_time())
+    }
+
+    fn error(&mut self, component: &Component, message: String) {
+        self.errors.push(ImportError {
+            component: component.name.clone(),
+            uid: self.uid.clone(),
+            line: component.line,
+            message,
+        });
+    }
+}
+
+pub(super) fn participant(property: &Property) -> Participant {
+    let param = |name: &str| property.param(name).map(str::to_ascii_uppercase);
+    let role = param("ROLE").map(|role| match role.as_str() {
+        "CHAIR" => ParticipantRole::Chair,
+        "OPT-PARTICIPANT" => ParticipantRole::Optional,
+        "NON-PARTICIPANT" => ParticipantRole::NonParticipant,
+        _ => ParticipantRole::Required,
+    });
+    let user_type = param("CUTYPE").map(|cutype| match cutype.as_str() {
+        "INDIVIDUAL" => CalendarUserType::Individual,
+        "GROUP" => CalendarUserType::Group,
+        "RESOURCE" => CalendarUserType::Resource,
+        "ROOM" => CalendarUserType::Room,
+        _ => CalendarUserType::Unknown,
+    });
+    let addresses = |name: &str| {
+        let addresses: Vec<String> = property
+            .param_values(name)
+            .iter()
+            .filter_map(|a| address(a))
+            .collect();
+        (!addresses.is_empty()).then_some(addresses)
+    };
+    Participant {
+        email: address(&property.value),
+        name: property.param("CN").map(str::to_string),
+        optional: role.map(|role| role == ParticipantRole::Optional),
+        response_status: param("PARTSTAT").map(|partstat| match partstat.as_str() {
+            "ACCEPTED" => ParticipantStatus::Accepted,
+            "TENTATIVE" => ParticipantStatus::Tentative,
+            "DECLINED" => ParticipantStatus::Declined,
+            _ => ParticipantStatus::NeedsAction,
+        }),
+        resource: user_type
+            .filter(|t| matches!(t, CalendarUserType::Resource | CalendarUserType::Room))
+            .map(|_| true),
+        role,
+        user_type,
+        delegated_to: addresses("DELEGATED-TO"),
+        delegated_from: addresses("DELEGATED-FROM"),
+        comment: property.param("X-RESPONSE-COMMENT").map(str::to_string),
+        additional_guests: property
+            .param("X-NUM-GUESTS")
+            .and_then(|n| n.trim().parse().ok()),
+        ..Default::default()
+    }
+}
+
+/// Email address of a `CAL-ADDRESS`, without its `mailto:`
+pub(super) fn address(value: &str) -> Option<String> {
+    let value = value.trim();
+    let email = match value.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => &value[7..],
+        _ => value,
+    };
+    (!email.is_empty()).then(|| email.to_string())
+}
+
+/// End of an event lasting `duration`, `None` when it falls outside chrono's
+/// range
+fn add_duration(start: &EventMoment, duration: Duration) -> Option<EventMoment> {
+    Some(match start {
+        EventMoment::Date { date, time_zone } => EventMoment::Date {
+            date: date.checked_add_signed(Duration::days(duration.num_days().max(1)))?,
+            time_zone: time_zone.clone(),
+        },
+        EventMoment::DateTime {
+            date_time,
+            time_zone,
+        } => EventMoment::DateTime {
+            date_time: date_time.checked_add_signed(duration)?,
+            time_zone: time_zone.clone(),
+        },
+    })
+}
+
+fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim(), DATE_TIME_FORMAT).ok()
+}
+
+/// A tz database zone for a `TZID`
+///
+/// Also accepts names with a vendor prefix, such as
+/// `/mozilla.org/20050126_1/Europe/Berlin`, by dropping leading segments.
+fn known_zone(tzid: &str) -> Option<Tz> {
+    let mut candidate = tzid.trim().trim_start_matches('/');
+    loop {
+        if let Ok(name) = canonical_name(candidate) {
+            return name.parse().ok();
+        }
+        candidate = candidate.split_once('/')?.1;
+    }
+}
+
+/// Zones of one calendar, by `TZID`
+pub(super) struct Zones {
+    by_tzid: HashMap<String, Zone>,
+    /// Zone for floating times, UTC when `None`
+    floating: Option<Tz>,
+}
+
+enum Zone {
+    Tz(Tz),
+    /// Known only from the file's `VTIMEZONE`
+    Embedded(Vec<Observance>),
+}
+
+/// How a local time is mapped to an instant
+#[derive(Clone, Copy)]
+enum ZoneRef<'a> {
+    Tz(Tz),
+    Embedded(&'a [Observance]),
+    Utc,
+}
+
+impl Zones {
+    pub(super) fn new(timezones: &[&Component], floating: Option<Tz>) -> Self {
+        let by_tzid = timezones
+            .iter()
+            .filter_map(|component| {
+                let tzid = component.property("TZID")?.value.trim().to_string();
+                let zone = known_zone(&tzid)
+                    .or_else(|| {
+                        component
+                            .property("X-LIC-LOCATION")
+                            .and_then(|p| known_zone(&p.value))
+                    })
+                    .map(Zone::Tz)
+                    .or_else(|| {
+                        let observances: Vec<Observance> = component
+                            .components
+                            .iter()
+                            .filter(|c| c.name == "STANDARD" || c.name == "DAYLIGHT")
+                            .filter_map(Observance::from_component)
+                            .collect();
+                        (!observances.is_empty()).then_some(Zone::Embedded(observances))
+                    })?;
+                Some((tzid, zone))
+            })
+            .collect();
+        Self { by_tzid, floating }
+    }
+
+    /// Zone for a `TZID`, the floating zone without one or when it is unknown
+    fn zone(&self, tzid: Option<&str>) -> ZoneRef<'_> {
+        let floating = self.floating.map_or(ZoneRef::Utc, ZoneRef::Tz);
+        let Some(tzid) = tzid.map(str::trim) else {
+            return floating;
+        };
+        match self.by_tzid.get(tzid) {
+            Some(Zone::Tz(tz)) => ZoneRef::Tz(*tz),
+            Some(Zone::Embedded(observances)) => ZoneRef::Embedded(observances),
+            None => known_zone(tzid).map_or(floating, ZoneRef::Tz),
+        }
+    }
+
+    /// DATE or DATE-TIME value of a property
+    pub(super) fn moment(&self, property: &Property) -> Result<EventMoment, String> {
+        let value = property.value.trim();
+        let is_date = property
+            .param("VALUE")
+            .is_some_and(|v| v.eq_ignore_ascii_case("DATE"))
+            || !value.contains('T');
+        if is_date {
+            return NaiveDate::parse_from_str(value, DATE_FORMAT)
+                .map(EventMoment::all_day)
+                .map_err(|_| format!("Invalid {} date {:?}", property.name, value));
+        }
+        let (local, zone) = match value.strip_suffix('Z') {
+            Some(utc) => (utc, ZoneRef::Utc),
+            None => (value, self.zone(property.param("TZID"))),
+        };
+        let local = parse_date_time(local)
+            .ok_or_else(|| format!("Invalid {} date-time {:?}", property.name, value))?;
+        let time_zone = match zone {
+            ZoneRef::Tz(tz) => Some(tz.name().to_string()),
+            _ => None,
+        };
+        Ok(EventMoment::timed(zone.resolve(local), time_zone))
+    }
+
+    /// Instant of a moment, dates starting at midnight in the floating zone
+    fn instant(&self, moment: &EventMoment) -> DateTime<FixedOffset> {
+        match moment {
+            EventMoment::DateTime { date_time, .. } => *date_time,
+            EventMoment::Date { date, .. } => self
+                .zone(None)
+                .resolve(date.and_time(chrono::NaiveTime::MIN)),
+        }
+    }
+}
+
+impl ZoneRef<'_> {
+    fn resolve(self, local: NaiveDateTime) -> DateTime<FixedOffset> {
+        let offset = match self {
+            ZoneRef::Tz(tz) => return resolve_local(tz, local),
+            ZoneRef::Utc => 0,
+            ZoneRef::Embedded(observances) => observances
+                .iter()
+                .filter_map(|o| o.last_onset(local).map(|onset| (onset, o.offset_to)))
+                .max_by_key(|(onset, _)| *onset)
+                .map(|(_, offset)| offset)
+                // Before the first onset the earliest observance's old offset applies
+                .or_else(|| {
+                    observances
+                        .iter()
+                        .min_by_key(|o| o.dtstart)
+                        .map(|o| o.offset_from)
+                })
+                .unwrap_or(0),
+        };
+        let offset = FixedOffset::east_opt(offset).unwrap_or(Utc.fix());
+        (local - Duration::seconds(offset.local_minus_utc().into()))
+            .and_utc()
+            .with_timezone(&offset)
+    }
+}
+
+/// Most steps an onset lookup takes; daily rules with a `COUNT` walk from
+/// `DTSTART`, which takes a few tens of thousands of steps for old zones
+const MAX_ONSET_STEPS: u32 = 100_000;
+
+/// A `STANDARD` or `DAYLIGHT` block of an embedded `VTIMEZONE`
+struct Observance {
+    /// First onset, in local time before the change
+    dtstart: NaiveDateTime,
+    offset_from: i32,
+    offset_to: i32,
+    rules: Vec<RecurrenceRule>,
+    rdates: Vec<NaiveDateTime>,
+}
+
+impl Observance {
+    fn from_component(component: &Component) -> Option<Self> {
+        let offset = |name: &str| parse_offset(&component.property(name)?.value);
+        let rules: Vec<RecurrenceRule> = component
+            .properties_named("RRULE")
+            .filter_map(|p| p.value.trim().parse().ok())
+            .collect();
+        // Offsets change at most a few times a year; a sub-daily rule would
+        // make every onset lookup walk millions of periods
+        if rules
+            .iter()
+            .any(|rule| matches!(rule.frequency, Frequency::Secondly | Frequency::Minutely | Frequency::Hourly))
+        {
+            return None;
+        }
+        Some(Self {
+            dtstart: parse_date_time(&component.property("DTSTART")?.value)?,
+            offset_from: offset("TZOFFSETFROM")?,
+            offset_to: offset("TZOFFSETTO")?,
+            rules,
+            rdates: component
+                .properties_named("RDATE")
+                .flat_map(|p| p.value.split(','))
+                .filter_map(parse_date_time)
+                .collect(),
+        })
+    }
+
+    /// Latest onset at or before `local`
+    fn last_onset(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.dtstart > local {
+            return None;
+        }
+        let mut latest = self.dtstart;
+        for rdate in &self.rdates {
+            if *rdate <= local {
+                latest = latest.max(*rdate);
+            }
+        }
+        for rule in &self.rules {
+            let past_until = |onset: NaiveDateTime| match &rule.until {
+                None => false,
+                Some(RecurrenceUntil::Date(date)) => onset.date() > *date,
+                Some(RecurrenceUntil::Floating(until)) => onset > *until,
+                Some(RecurrenceUntil::DateTime(until)) => {
+                    (onset - Duration::seconds(self.offset_from.into())).and_utc() > *until
+                }
+            };
+            // A rule too dense to expand is ignored like an unparsable one
+            let Ok(onsets) = expand_rule(
+                rule,
+                &ExpandBounds {
+                    dtstart: self.dtstart,
+                    limit: local,
+                    // Onsets are at most yearly
+                    skip_before: Some(local - Duration::days(400)),
+                    past_until: &past_until,
+                    max_steps: MAX_ONSET_STEPS,
+                },
+            ) else {
+                continue;
+            };
+            if let Some(onset) = onsets.into_iter().filter(|o| *o <= local).max() {
+                latest = latest.max(onset);
+            }
+        }
+        Some(latest)
+    }
+}
+
+/// UTC offset in seconds from `+HHMM` or `-HHMMSS`
+fn parse_offset(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let sign = match value.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = &value[1..];
+    if !(digits.len() == 4 || digits.len() == 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let part = |range: std::ops::Range<usize>| digits.get(range).map_or(Ok(0), str::parse::<i32>);
+    let (hours, minutes, seconds) = (part(0..2).ok()?, part(2..4).ok()?, part(4..6).ok()?);
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ical::{write_calendar, ExportOptions};
+
+    fn read(text: &str) -> ImportResult {
+        read_calendar(text, &ImportOptions::new(CalendarSource::Google))
+    }
+
+    fn calendar(body: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\n{}END:VCALENDAR\r\n",
+            body
+        )
+    }
+
+    #[test]
+    fn test_event_import() {
+        let ics = calendar(
+            "X-WR-CALNAME:Team\r\n\
+             BEGIN:VTIMEZONE\r\n\
+             TZID:/mozilla.org/20050126_1/Europe/Berlin\r\n\
+             END:VTIMEZONE\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:standup@example.com\r\n\
+             DTSTART;TZID=/mozilla.org/20050126_1/Europe/Berlin:20240311T090000\r\n\
+             DTEND;TZID=/mozilla.org/20050126_1/Europe/Berlin:20240311T093000\r\n\
+             RRULE:FREQ=WEEKLY;BYDAY=MO\r\n\
+             EXDATE;TZID=/mozilla.org/20050126_1/Europe/Berlin:20240318T090000,20240325T090000\r\n\
+             SUMMARY:Stand-up\\, daily\r\n\
+             DESCRIPTION:Agenda:\\nUpdates\r\n\
+             CATEGORIES:Work,Meetings\r\n\
+             CATEGORIES:Team\r\n\
+             CLASS:PRIVATE\r\n\
+             TRANSP:TRANSPARENT\r\n\
+             SEQUENCE:3\r\n\
+             ORGANIZER;CN=\"Lovelace, Ada\":mailto:ada@example.com\r\n\
+             ATTENDEE;CN=Ada;PARTSTAT=ACCEPTED;ROLE=CHAIR:mailto:ada@example.com\r\n\
+             ATTENDEE;CUTYPE=ROOM;ROLE=NON-PARTICIPANT;PARTSTAT=NEEDS-ACTION:MAILTO:room1@example.com\r\n\
+             ATTENDEE;ROLE=OPT-PARTICIPANT;PARTSTAT=DELEGATED;DELEGATED-TO=\"mailto:carol@example.com\";X-NUM-GUESTS=2:mailto:bob@example.com\r\n\
+             X-GOOGLE-CONFERENCE;X-LABEL=Meet:https://meet.google.com/abc\r\n\
+             BEGIN:VALARM\r\n\
+             ACTION:DISPLAY\r\n\
+             TRIGGER:-PT10M\r\n\
+             END:VALARM\r\n\
+             BEGIN:VALARM\r\n\
+             ACTION:EMAIL\r\n\
+             TRIGGER;RELATED=END:-P1D\r\n\
+             END:VALARM\r\n\
+             END:VEVENT\r\n",
+        );
+        let result = read(&ics);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.name.as_deref(), Some("Team"));
+        let event = &result.events[0];
+
+        assert!(event.id.is_empty());
+        assert_eq!(event.ical_uid.as_deref(), Some("standup@example.com"));
+        assert_eq!(
+            event.start,
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-03-11T09:00:00+01:00").unwrap(),
+                Some("Europe/Berlin".to_string())
+            )
+        );
+        assert_eq!(
+            event.recurrence_rule.as_deref(),
+            Some("FREQ=WEEKLY;BYDAY=MO")
+        );
+        assert_eq!(
+            event.recurrence_exceptions,
+            Some(vec![
+                "EXDATE;TZID=Europe/Berlin:20240318T090000".to_string(),
+                "EXDATE;TZID=Europe/Berlin:20240325T090000".to_string(),
+            ])
+        );
+        assert_eq!(event.title.as_deref(), Some("Stand-up, daily"));
+        assert_eq!(event.description.as_deref(), Some("Agenda:\nUpdates"));
+        assert_eq!(
+            event.categories,
+            Some(vec![
+                "Work".to_string(),
+                "Meetings".to_string(),
+                "Team".to_string()
+            ])
+        );
+        assert!(matches!(event.visibility, Some(EventVisibility::Private)));
+        assert!(matches!(event.show_as, Some(ShowAs::Free)));
+        assert_eq!(event.sequence, Some(3));
+
+        let organizer = event.organizer.as_ref().unwrap();
+        assert_eq!(organizer.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(organizer.name.as_deref(), Some("Lovelace, Ada"));
+        let attendees = event.attendees.as_ref().unwrap();
+        assert_eq!(attendees[0].organizer, Some(true));
+        assert_eq!(attendees[0].role, Some(ParticipantRole::Chair));
+        assert_eq!(attendees[1].email.as_deref(), Some("room1@example.com"));
+        assert_eq!(attendees[1].user_type, Some(CalendarUserType::Room));
+        assert_eq!(attendees[1].resource, Some(true));
+        assert_eq!(attendees[2].optional, Some(true));
+        assert_eq!(
+            attendees[2].response_status,
+            Some(ParticipantStatus::NeedsAction)
+        );
+        assert_eq!(
+            attendees[2].delegated_to,
+            Some(vec!["carol@example.com".to_string()])
+        );
+        assert_eq!(attendees[2].additional_guests, Some(2));
+
+        let reminders = event.reminders.as_ref().unwrap();
+        assert_eq!(reminders[0].minutes_before, 10);
+        assert!(matches!(reminders[0].method, Some(ReminderMethod::Popup)));
+        // A day before the end of a 30 minute event
+        assert_eq!(reminders[1].minutes_before, 24 * 60 - 30);
+        assert!(matches!(reminders[1].method, Some(ReminderMethod::Email)));
+
+        assert_eq!(
+            event.raw,
+            Some(json!({ "x_properties": [{
+                "name": "X-GOOGLE-CONFERENCE",
+                "params": { "X-LABEL": ["Meet"] },
+                "value": "https://meet.google.com/abc"
+            }] }))
+        );
+    }
+
+    #[test]
+    fn test_embedded_vtimezone() {
+        let ics = calendar(
+            "BEGIN:VTIMEZONE\r\n\
+             TZID:Custom Central\r\n\
+             BEGIN:STANDARD\r\n\
+             DTSTART:19701025T030000\r\n\
+             TZOFFSETFROM:+0200\r\n\
+             TZOFFSETTO:+0100\r\n\
+             RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r\n\
+             END:STANDARD\r\n\
+             BEGIN:DAYLIGHT\r\n\
+             DTSTART:19700329T020000\r\n\
+             TZOFFSETFROM:+0100\r\n\
+             TZOFFSETTO:+0200\r\n\
+             RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\n\
+             END:DAYLIGHT\r\n\
+             END:VTIMEZONE\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:a\r\n\
+             DTSTART;TZID=Custom Central:20240115T090000\r\n\
+             DTEND;TZID=Custom Central:20240701T090000\r\n\
+             EXDATE;TZID=Custom Central:20240401T090000\r\n\
+             RRULE:FREQ=DAILY;COUNT=200\r\n\
+             END:VEVENT\r\n",
+        );
+        let result = read(&ics);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let event = &result.events[0];
+        assert_eq!(
+            event.start.date_time().unwrap().to_rfc3339(),
+            "2024-01-15T09:00:00+01:00"
+        );
+        assert_eq!(
+            event.end.date_time().unwrap().to_rfc3339(),
+            "2024-07-01T09:00:00+02:00"
+        );
+        assert_eq!(event.start.time_zone(), None);
+        assert_eq!(
+            event.recurrence_exceptions,
+            Some(vec!["EXDATE:20240401T070000Z".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_sub_daily_vtimezone_rules_are_rejected() {
+        let ics = calendar(
+            "BEGIN:VTIMEZONE\r\n\
+             TZID:Busy\r\n\
+             BEGIN:STANDARD\r\n\
+             DTSTART:19700101T000000\r\n\
+             TZOFFSETFROM:+0100\r\n\
+             TZOFFSETTO:+0100\r\n\
+             RRULE:FREQ=SECONDLY\r\n\
+             END:STANDARD\r\n\
+             END:VTIMEZONE\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:a\r\n\
+             DTSTART;TZID=Busy:20240115T090000\r\n\
+             DTEND;TZID=Busy:20240115T100000\r\n\
+             END:VEVENT\r\n",
+        );
+        let result = read(&ics);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        // The zone is unusable, so the time is read as floating (UTC)
+        assert_eq!(
+            result.events[0].start.date_time().unwrap().to_rfc3339(),
+            "2024-01-15T09:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_dates_durations_and_floating_times() {
+        let ics = calendar(
+            "X-WR-TIMEZONE:America/New_York\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:holiday\r\n\
+             DTSTART;VALUE=DATE:20240704\r\n\
+             BEGIN:VALARM\r\n\
+             ACTION:DISPLAY\r\n\
+             TRIGGER;VALUE=DATE-TIME:20240703T210000Z\r\n\
+             END:VALARM\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:trip\r\n\
+             DTSTART:20240710\r\n\
+             DURATION:P3D\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:call\r\n\
+             DTSTART:20240710T150000\r\n\
+             DURATION:PT45M\r\n\
+             END:VEVENT\r\n",
+        );
+        let result = read(&ics);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.time_zone.as_deref(), Some("America/New_York"));
+        let date = |y, m, d| EventMoment::all_day(NaiveDate::from_ymd_opt(y, m, d).unwrap());
+
+        let holiday = &result.events[0];
+        assert_eq!(
+            (&holiday.start, &holiday.end),
+            (&date(2024, 7, 4), &date(2024, 7, 5))
+        );
+        // 17:00 the evening before, New York time
+        assert_eq!(
+            holiday.reminders.as_ref().unwrap()[0].minutes_before,
+            7 * 60
+        );
+
+        let trip = &result.events[1];
+        assert_eq!(
+            (&trip.start, &trip.end),
+            (&date(2024, 7, 10), &date(2024, 7, 13))
+        );
+
+        let call = &result.events[2];
+        assert_eq!(call.start.time_zone(), Some("America/New_York"));
+        assert_eq!(
+            call.end.date_time().unwrap().to_rfc3339(),
+            "2024-07-10T15:45:00-04:00"
+        );
+
+        // The configured zone wins over the file's
+        let options =
+            ImportOptions::new(CalendarSource::Google).with_default_time_zone("Europe/Paris");
+        let call = &read_calendar(&ics, &options).events[2];
+        assert_eq!(
+            call.start.date_time().unwrap().to_rfc3339(),
+            "2024-07-10T15:00:00+02:00"
+        );
+    }
+
+    #[test]
+    fn test_errors_are_per_component() {
+        let ics = calendar(
+            "BEGIN:VEVENT\r\n\
+             UID:no-start\r\n\
+             SUMMARY:Broken\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:bad-rule\r\n\
+             DTSTART:20240101T090000Z\r\n\
+             RRULE:FREQ=SOMETIMES\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:ok\r\n\
+             DTSTART:20240101T090000Z\r\n\
+             DTEND:20240101T100000Z\r\n\
+             this line is broken\r\n\
+             BEGIN:VALARM\r\n\
+             ACTION:DISPLAY\r\n\
+             END:VALARM\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VTODO\r\n\
+             UID:task\r\n\
+             END:VTODO\r\n",
+        );
+        let result = read(&ics);
+        assert_eq!(result.events.len(), 1);
+        let event = &result.events[0];
+        assert_eq!(event.ical_uid.as_deref(), Some("ok"));
+        assert_eq!(event.start.time_zone(), None);
+        assert!(event.reminders.is_none());
+
+        let errors: Vec<(&str, Option<&str>, usize)> = result
+            .errors
+            .iter()
+            .map(|e| (e.component.as_str(), e.uid.as_deref(), e.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("VEVENT", None, 17),
+                ("VEVENT", Some("no-start"), 4),
+                ("VEVENT", Some("bad-rule"), 8),
+                ("VALARM", Some("ok"), 18),
+            ]
+        );
+        assert_eq!(
+            result.errors[1].to_string(),
+            "VEVENT at line 4: Missing DTSTART"
+        );
+        assert!(result.errors[2].message.starts_with("Invalid recurrence"));
+        assert_eq!(result.errors[3].message, "Missing TRIGGER");
+    }
+
+    #[test]
+    fn test_huge_durations_are_errors() {
+        let ics = calendar(
+            "BEGIN:VEVENT\r\n\
+             UID:days\r\n\
+             DTSTART:20240310T090000Z\r\n\
+             DURATION:P999999999D\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:weeks\r\n\
+             DTSTART:20240310T090000Z\r\n\
+             DURATION:P99999999999999W\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:all-day\r\n\
+             DTSTART;VALUE=DATE:20240310\r\n\
+             DURATION:P999999999D\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:alarm\r\n\
+             DTSTART:20240310T090000Z\r\n\
+             DURATION:PT1H\r\n\
+             BEGIN:VALARM\r\n\
+             ACTION:DISPLAY\r\n\
+             TRIGGER:-P99999999999999W\r\n\
+             END:VALARM\r\n\
+             BEGIN:VALARM\r\n\
+             ACTION:DISPLAY\r\n\
+             TRIGGER;RELATED=END:-P99999999D\r\n\
+             END:VALARM\r\n\
+             END:VEVENT\r\n",
+        );
+        let result = read(&ics);
+        let uids: Vec<&str> = result.events.iter().filter_map(|e| e.ical_uid.as_deref()).collect();
+        assert_eq!(uids, vec!["alarm"]);
+        assert!(result.events[0].reminders.is_none());
+        let messages: Vec<&str> = result.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Invalid DURATION \"P999999999D\"",
+                "Invalid DURATION \"P99999999999999W\"",
+                "Invalid DURATION \"P999999999D\"",
+                "Invalid TRIGGER \"-P99999999999999W\"",
+                "TRIGGER out of range: \"-P99999999D\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_round_trip_with_export() {
+        let ics = calendar(
+            "BEGIN:VEVENT\r\n\
+             UID:review@example.com\r\n\
+             DTSTART;TZID=America/New_York:20240311T090000\r\n\
+             DTEND;TZID=America/New_York:20240311T100000\r\n\
+             RRULE:FREQ=MONTHLY;BYDAY=2MO;UNTIL=20241231T235959Z\r\n\
+             SUMMARY:Review\r\n\
+             LOCATION:Room 1\\; East\r\n\
+             STATUS:TENTATIVE\r\n\
+             ORGANIZER;CN=Ada:mailto:ada@example.com\r\n\
+             ATTENDEE;CN=Bob;PARTSTAT=TENTATIVE;ROLE=REQ-PARTICIPANT:mailto:bob@example.com\r\n\
+             BEGIN:VALARM\r\n\
+             ACTION:DISPLAY\r\n\
+             TRIGGER:-PT15M\r\n\
+             END:VALARM\r\n\
+             END:VEVENT\r\n",
+        );
+        let imported = read(&ics).events;
+        let exported = write_calendar(&imported, &ExportOptions::default());
+        let result = read(&exported);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert!(crate::diff(&imported[0], &result.events[0]).is_empty());
+    }
+
+    #[test]
+    fn test_known_zone_and_offsets() {
+        assert_eq!(known_zone("Europe/Berlin"), Some(chrono_tz::Europe::Berlin));
+        assert_eq!(
+            known_zone("/citadel.org/20190914_1/America/Argentina/Buenos_Aires"),
+            Some(chrono_tz::America::Argentina::Buenos_Aires)
+        );
+        assert_eq!(
+            known_zone("W. Europe Standard Time"),
+            Some(chrono_tz::Europe::Berlin)
+        );
+        assert_eq!(known_zone("Custom Central"), None);
+
+        assert_eq!(parse_offset("+0530"), Some(5 * 3600 + 30 * 60));
+        assert_eq!(parse_offset("-001730"), Some(-(17 * 60 + 30)));
+        assert_eq!(parse_offset("0100"), None);
+    }
+}
@@ -0,0 +1,706 @@
+//! iTIP (RFC 5546) scheduling messages
+//!
+//! The organizer sends a `REQUEST` when an event is created or changed and a
+//! `CANCEL` when it is cancelled or attendees are removed. Attendees answer
+//! with a `REPLY`, or propose another time with a `COUNTER`. Messages are
+//! rendered as iCalendar with [`ItipMessage::to_ics`] or as iMIP email with
+//! [`ItipMessage::to_imip`].
+
+use std::fmt;
+
+use super::imip::{calendar_part, write_imip};
+use super::import::{participant, Zones};
+use super::parse::parse_components;
+use super::{unescape_text, write_calendar, ExportOptions};
+use crate::error::{CalblendError, Result};
+use crate::models::{
+    EventMoment, EventStatus, Participant, ParticipantStatus, UnifiedCalendarEvent,
+};
+
+/// Fields whose change makes attendees answer again (RFC 5546 section 2.1.4)
+const RESCHEDULE_FIELDS: [&str; 4] = ["start", "end", "recurrence", "status"];
+
+/// iTIP method of a scheduling message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItipMethod {
+    Request,
+    Reply,
+    Cancel,
+    Counter,
+}
+
+impl ItipMethod {
+    /// Value of the `METHOD` property
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItipMethod::Request => "REQUEST",
+            ItipMethod::Reply => "REPLY",
+            ItipMethod::Cancel => "CANCEL",
+            ItipMethod::Counter => "COUNTER",
+        }
+    }
+}
+
+impl fmt::Display for ItipMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One scheduling message and who it goes to
+#[derive(Debug, Clone)]
+pub struct ItipMessage {
+    pub method: ItipMethod,
+    /// Address of the sender
+    pub from: String,
+    /// Addresses of the recipients
+    pub recipients: Vec<String>,
+    /// The event as sent; organizers should store its `sequence`
+    pub event: UnifiedCalendarEvent,
+}
+
+impl ItipMessage {
+    /// The message as an iCalendar document with a `METHOD`
+    pub fn to_ics(&self) -> String {
+        write_calendar(
+            std::slice::from_ref(&self.event),
+            &ExportOptions::default().with_method(self.method),
+        )
+    }
+
+    /// The message as an iMIP email: a `multipart/alternative` MIME message
+    /// with a plain-text summary and the `text/calendar` part
+    ///
+    /// Fails when `from` or a recipient is not a plain email address.
+    pub fn to_imip(&self) -> Result<String> {
+        write_imip(self)
+    }
+}
+
+/// Messages the organizer sends after creating or changing an event
+///
+/// Pass the previously sent version as `previous`, or `None` for a new
+/// event. The sequence is bumped when the time, recurrence or status
+/// changed, unless `current` already carries a higher one, and attendees
+/// are then asked to answer again. Current attendees get a `REQUEST` (or a
+/// `CANCEL` once the event is cancelled) and removed attendees a `CANCEL`.
+pub fn organizer_messages(
+    previous: Option<&UnifiedCalendarEvent>,
+    current: &UnifiedCalendarEvent,
+) -> Result<Vec<ItipMessage>> {
+    let organizer = organizer_email(current)?;
+    let mut event = scheduling_object(current);
+    let cancelled = matches!(event.status, Some(EventStatus::Cancelled));
+
+    if let Some(previous) = previous {
+        let diff = crate::diff(previous, current);
+        let rescheduled = diff
+            .changed_fields()
+            .iter()
+            .any(|field| RESCHEDULE_FIELDS.contains(field));
+        let sent = previous.sequence.unwrap_or(0);
+        let sequence = current.sequence.unwrap_or(0);
+        event.sequence = Some(if sequence > sent {
+            sequence
+        } else if rescheduled {
+            sent + 1
+        } else {
+            sent
+        });
+        if rescheduled && !cancelled {
+            for attendee in event.attendees.iter_mut().flatten() {
+                if !is_address(attendee, &organizer) {
+                    attendee.response_status = Some(ParticipantStatus::NeedsAction);
+                }
+            }
+        }
+    } else {
+        event.sequence = Some(current.sequence.unwrap_or(0));
+    }
+
+    let mut messages = Vec::new();
+    let method = if cancelled {
+        ItipMethod::Cancel
+    } else {
+        ItipMethod::Request
+    };
+    let invited = recipients(event.attendees.iter().flatten(), &organizer);
+    if !invited.is_empty() {
+        messages.push(ItipMessage {
+            method,
+            from: organizer.clone(),
+            recipients: invited,
+            event: event.clone(),
+        });
+    }
+
+    let removed: Vec<Participant> = previous
+        .and_then(|previous| previous.attendees.as_ref())
+        .into_iter()
+        .flatten()
+        .filter(|old| {
+            !current
+                .attendees
+                .iter()
+                .flatten()
+                .any(|new| same_participant(old, new))
+        })
+        .cloned()
+        .collect();
+    let removed_recipients = recipients(removed.iter(), &organizer);
+    if !removed_recipients.is_empty() {
+        let mut cancelled = event;
+        cancelled.status = Some(EventStatus::Cancelled);
+        cancelled.attendees = Some(removed);
+        messages.push(ItipMessage {
+            method: ItipMethod::Cancel,
+            from: organizer,
+            recipients: removed_recipients,
+            event: cancelled,
+        });
+    }
+    Ok(messages)
+}
+
+/// `CANCEL` of a whole event, sent by its organizer to every attendee
+pub fn cancel(event: &UnifiedCalendarEvent) -> Result<Option<ItipMessage>> {
+    let mut cancelled = event.clone();
+    cancelled.status = Some(EventStatus::Cancelled);
+    Ok(organizer_messages(Some(event), &cancelled)?
+        .into_iter()
+        .next())
+}
+
+/// An attendee's answer to an invitation
+pub fn reply(
+    event: &UnifiedCalendarEvent,
+    attendee_email: &str,
+    status: ParticipantStatus,
+    comment: Option<&str>,
+) -> Result<ItipMessage> {
+    let mut attendee = find_attendee(event, attendee_email);
+    attendee.response_status = Some(status);
+    attendee.comment = comment.map(str::to_string);
+    attendee_message(ItipMethod::Reply, event, attendee, |_| {})
+}
+
+/// An attendee's proposal to move an event
+pub fn counter(
+    event: &UnifiedCalendarEvent,
+    attendee_email: &str,
+    start: EventMoment,
+    end: EventMoment,
+    comment: Option<&str>,
+) -> Result<ItipMessage> {
+    let mut attendee = find_attendee(event, attendee_email);
+    attendee.comment = comment.map(str::to_string);
+    attendee_message(ItipMethod::Counter, event, attendee, |proposal| {
+        proposal.start = start;
+        proposal.end = end;
+    })
+}
+
+/// An attendee's answer read from an iTIP `REPLY`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplyUpdate {
+    /// `UID` of the event answered
+    pub uid: String,
+    /// The occurrence answered, `None` for the whole event
+    pub recurrence_id: Option<EventMoment>,
+    /// Sequence of the version answered
+    pub sequence: u32,
+    pub email: String,
+    pub status: ParticipantStatus,
+    pub comment: Option<String>,
+    pub delegated_to: Option<Vec<String>>,
+}
+
+impl ReplyUpdate {
+    /// Record the answer on the organizer's copy of the event
+    ///
+    /// Returns `false`, leaving the event alone, when the reply is for
+    /// another event or occurrence, answers an older sequence, or comes from
+    /// someone who is neither an attendee nor a delegate of one.
+    ///
+    /// A `REPLY` can name any attendee, so the caller must first check that
+    /// the message really comes from [`ReplyUpdate::email`], e.g. that it
+    /// matches the authenticated sender of the email.
+    pub fn apply(&self, event: &mut UnifiedCalendarEvent) -> bool {
+        self.apply_to(event, false)
+    }
+
+    /// Like [`ReplyUpdate::apply`], but adds people who were not invited
+    /// as attendees instead of ignoring their replies
+    pub fn apply_allowing_uninvited(&self, event: &mut UnifiedCalendarEvent) -> bool {
+        self.apply_to(event, true)
+    }
+
+    fn apply_to(&self, event: &mut UnifiedCalendarEvent, allow_uninvited: bool) -> bool {
+        let same_event = [
+            event.ical_uid.as_ref(),
+            event.series_id.as_ref(),
+            Some(&event.id),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|uid| *uid == self.uid);
+        let same_occurrence = match (&self.recurrence_id, &event.original_start) {
+            (None, None) => true,
+            (Some(answered), Some(original)) => {
+                match (answered.date_time(), original.date_time()) {
+                    (Some(answered), Some(original)) => answered == original,
+                    (None, None) => answered.date() == original.date(),
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+        if !same_event || !same_occurrence || self.sequence < event.sequence.unwrap_or(0) {
+            return false;
+        }
+
+        let current = event.attendees.as_deref().unwrap_or_default();
+        // Delegates join the event through their delegator's reply
+        let delegator = current
+            .iter()
+            .find(|a| {
+                a.delegated_to
+                    .iter()
+                    .flatten()
+                    .any(|to| to.trim_start_matches("mailto:").eq_ignore_ascii_case(&self.email))
+            })
+            .and_then(|a| a.email.clone());
+        let found = current.iter().position(|a| is_address(a, &self.email));
+        if found.is_none() && delegator.is_none() && !allow_uninvited {
+            return false;
+        }
+
+        let attendees = event.attendees.get_or_insert_with(Vec::new);
+        let index = found.unwrap_or_else(|| {
+            attendees.push(Participant {
+                email: Some(self.email.clone()),
+                delegated_from: delegator.map(|email| vec![email]),
+                ..Default::default()
+            });
+            attendees.len() - 1
+        });
+        let attendee = &mut attendees[index];
+        attendee.response_status = Some(self.status.clone());
+        if self.comment.is_some() {
+            attendee.comment = self.comment.clone();
+        }
+        if self.delegated_to.is_some() {
+            attendee.delegated_to = self.delegated_to.clone();
+        }
+        true
+    }
+}
+
+/// Read the answers in an iTIP `REPLY`, given as iCalendar or iMIP email
+///
+/// Each `ATTENDEE` of each `VEVENT` gives one update; events without a
+/// `UID` are skipped.
+pub fn read_reply(message: &str) -> Result<Vec<ReplyUpdate>> {
+    let text = calendar_part(message)
+        .ok_or_else(|| CalblendError::InvalidData("No iCalendar data in message".to_string()))?;
+    let (components, _) = parse_components(&text);
+    let calendar = components
+        .iter()
+        .find(|c| c.name == "VCALENDAR")
+        .ok_or_else(|| CalblendError::InvalidData("Missing VCALENDAR".to_string()))?;
+    let method = calendar
+        .property("METHOD")
+        .map(|p| p.value.trim().to_ascii_uppercase());
+    if method.as_deref() != Some(ItipMethod::Reply.as_str()) {
+        return Err(CalblendError::InvalidData(format!(
+            "Expected an iTIP REPLY, found {}",
+            method.as_deref().unwrap_or("no METHOD")
+        )));
+    }
+
+    let timezones: Vec<_> = calendar.components_named("VTIMEZONE").collect();
+    let zones = Zones::new(&timezones, None);
+    let mut updates = Vec::new();
+    for event in calendar.components_named("VEVENT") {
+        let Some(uid) = event.property("UID").map(|p| unescape_text(p.value.trim())) else {
+            continue;
+        };
+        let recurrence_id = match event.property("RECURRENCE-ID").map(|p| zones.moment(p)) {
+            Some(Ok(moment)) => Some(moment),
+            Some(Err(_)) => continue,
+            None => None,
+        };
+        let sequence = event
+            .property("SEQUENCE")
+            .and_then(|p| p.value.trim().parse().ok())
+            .unwrap_or(0);
+        let comment = event.property("COMMENT").map(|p| unescape_text(&p.value));
+        for attendee in event.properties_named("ATTENDEE").map(participant) {
+            let Some(email) = attendee.email else {
+                continue;
+            };
+            updates.push(ReplyUpdate {
+                uid: uid.clone(),
+                recurrence_id: recurrence_id.clone(),
+                sequence,
+                email,
+                status: attendee
+                    .response_status
+                    .unwrap_or(ParticipantStatus::NeedsAction),
+                comment: attendee.comment.or_else(|| comment.clone()),
+                delegated_to: attendee.delegated_to,
+            });
+        }
+    }
+    Ok(updates)
+}
+
+/// A message from one attendee to the organizer, about the version of the
+/// event the attendee has
+fn attendee_message(
+    method: ItipMethod,
+    event: &UnifiedCalendarEvent,
+    attendee: Participant,
+    change: impl FnOnce(&mut UnifiedCalendarEvent),
+) -> Result<ItipMessage> {
+    let organizer = organizer_email(event)?;
+    let from = attendee.email.clone().unwrap_or_default();
+    let mut event = scheduling_object(event);
+    event.sequence = Some(event.sequence.unwrap_or(0));
+    event.attendees = Some(vec![attendee]);
+    change(&mut event);
+    Ok(ItipMessage {
+        method,
+        from,
+        recipients: vec![organizer],
+        event,
+    })
+}
+
+/// The attendee entry for `email`, or a new one when not invited
+fn find_attendee(event: &UnifiedCalendarEvent, email: &str) -> Participant {
+    event
+        .attendees
+        .iter()
+        .flatten()
+        .find(|a| is_address(a, email))
+        .cloned()
+        .unwrap_or_else(|| Participant {
+            email: Some(email.to_string()),
+            ..Default::default()
+        })
+}
+
+/// The event as it is shared with attendees: without personal reminders
+fn scheduling_object(event: &UnifiedCalendarEvent) -> UnifiedCalendarEvent {
+    let mut event = event.clone();
+    event.reminders = None;
+    event
+}
+
+fn organizer_email(event: &UnifiedCalendarEvent) -> Result<String> {
+    event
+        .organizer
+        .as_ref()
+        .and_then(|o| o.email.clone())
+        .ok_or_else(|| {
+            CalblendError::InvalidData(
+                "iTIP messages need an organizer with an email address".to_string(),
+            )
+        })
+}
+
+/// Emails of the participants, without the organizer
+fn recipients<'a>(
+    participants: impl Iterator<Item = &'a Participant>,
+    organizer: &str,
+) -> Vec<String> {
+    participants
+        .filter(|p| !is_address(p, organizer))
+        .filter_map(|p| p.email.clone())
+        .collect()
+}
+
+fn is_address(participant: &Participant, email: &str) -> bool {
+    participant
+        .email
+        .as_deref()
+        .is_some_and(|own| own.eq_ignore_ascii_case(email))
+}
+
+fn same_participant(a: &Participant, b: &Participant) -> bool {
+    a.email.as_deref().is_some_and(|email| is_address(b, email))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CalendarSource;
+    use chrono::DateTime;
+
+    fn attendee(email: &str, status: ParticipantStatus) -> Participant {
+        Participant {
+            email: Some(email.to_string()),
+            response_status: Some(status),
+            ..Default::default()
+        }
+    }
+
+    fn meeting() -> UnifiedCalendarEvent {
+        let at = |s: &str| {
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+                Some("Europe/Berlin".to_string()),
+            )
+        };
+        let mut event = UnifiedCalendarEvent::new(
+            String::new(),
+            CalendarSource::Google,
+            at("2024-03-11T09:00:00+01:00"),
+            at("2024-03-11T10:00:00+01:00"),
+        );
+        event.ical_uid = Some("review@example.com".to_string());
+        event.title = Some("Review".to_string());
+        event.organizer = Some(Participant {
+            email: Some("ada@example.com".to_string()),
+            ..Default::default()
+        });
+        event.attendees = Some(vec![
+            attendee("ada@example.com", ParticipantStatus::Accepted),
+            attendee("bob@example.com", ParticipantStatus::Accepted),
+            attendee("carol@example.com", ParticipantStatus::NeedsAction),
+        ]);
+        event
+    }
+
+    #[test]
+    fn test_new_event_request() {
+        let messages = organizer_messages(None, &meeting()).unwrap();
+        assert_eq!(messages.len(), 1);
+        let request = &messages[0];
+        assert_eq!(request.method, ItipMethod::Request);
+        assert_eq!(request.from, "ada@example.com");
+        assert_eq!(request.recipients, ["bob@example.com", "carol@example.com"]);
+        assert_eq!(request.event.sequence, Some(0));
+
+        let ics = request.to_ics();
+        assert!(ics.contains("METHOD:REQUEST\r\n"));
+        assert!(ics.contains("SEQUENCE:0\r\n"));
+        assert!(ics.contains("UID:review@example.com\r\n"));
+    }
+
+    #[test]
+    fn test_sequence_handling() {
+        let sent = organizer_messages(None, &meeting())
+            .unwrap()
+            .remove(0)
+            .event;
+
+        // A new title does not need new answers
+        let mut renamed = sent.clone();
+        renamed.title = Some("Design review".to_string());
+        let request = organizer_messages(Some(&sent), &renamed).unwrap().remove(0);
+        assert_eq!(request.event.sequence, Some(0));
+        let attendees = request.event.attendees.unwrap();
+        assert_eq!(
+            attendees[1].response_status,
+            Some(ParticipantStatus::Accepted)
+        );
+
+        // Moving it does, except from the organizer
+        let mut moved = sent.clone();
+        moved.end = EventMoment::timed(
+            DateTime::parse_from_rfc3339("2024-03-11T11:00:00+01:00").unwrap(),
+            Some("Europe/Berlin".to_string()),
+        );
+        let request = organizer_messages(Some(&sent), &moved).unwrap().remove(0);
+        assert_eq!(request.event.sequence, Some(1));
+        let statuses: Vec<_> = request
+            .event
+            .attendees
+            .unwrap()
+            .into_iter()
+            .map(|a| a.response_status.unwrap())
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ParticipantStatus::Accepted,
+                ParticipantStatus::NeedsAction,
+                ParticipantStatus::NeedsAction
+            ]
+        );
+
+        // A sequence already bumped by the caller is kept
+        moved.sequence = Some(5);
+        let request = organizer_messages(Some(&sent), &moved).unwrap().remove(0);
+        assert_eq!(request.event.sequence, Some(5));
+    }
+
+    #[test]
+    fn test_removed_attendees_and_cancel() {
+        let sent = meeting();
+        let mut current = sent.clone();
+        current.attendees.as_mut().unwrap().pop();
+        let messages = organizer_messages(Some(&sent), &current).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].recipients, ["bob@example.com"]);
+        let uninvite = &messages[1];
+        assert_eq!(uninvite.method, ItipMethod::Cancel);
+        assert_eq!(uninvite.recipients, ["carol@example.com"]);
+        assert_eq!(uninvite.event.attendees.as_ref().unwrap().len(), 1);
+
+        let cancel = cancel(&sent).unwrap().unwrap();
+        assert_eq!(cancel.method, ItipMethod::Cancel);
+        assert_eq!(cancel.recipients, ["bob@example.com", "carol@example.com"]);
+        assert_eq!(cancel.event.sequence, Some(1));
+        let ics = cancel.to_ics();
+        assert!(ics.contains("METHOD:CANCEL\r\n"));
+        assert!(ics.contains("STATUS:CANCELLED\r\n"));
+    }
+
+    #[test]
+    fn test_reply_round_trip() {
+        let mut event = meeting();
+        event.sequence = Some(2);
+        let message = reply(
+            &event,
+            "Carol@example.com",
+            ParticipantStatus::Tentative,
+            Some("Running late, 10 min"),
+        )
+        .unwrap();
+        assert_eq!(message.method, ItipMethod::Reply);
+        assert_eq!(message.from, "carol@example.com");
+        assert_eq!(message.recipients, ["ada@example.com"]);
+        assert_eq!(message.event.attendees.as_ref().unwrap().len(), 1);
+
+        let updates = read_reply(&message.to_imip().unwrap()).unwrap();
+        assert_eq!(
+            updates,
+            vec![ReplyUpdate {
+                uid: "review@example.com".to_string(),
+                recurrence_id: None,
+                sequence: 2,
+                email: "carol@example.com".to_string(),
+                status: ParticipantStatus::Tentative,
+                comment: Some("Running late, 10 min".to_string()),
+                delegated_to: None,
+            }]
+        );
+
+        assert!(updates[0].apply(&mut event));
+        let carol = &event.attendees.as_ref().unwrap()[2];
+        assert_eq!(carol.response_status, Some(ParticipantStatus::Tentative));
+        assert_eq!(carol.comment.as_deref(), Some("Running late, 10 min"));
+
+        // Answers to an older version are ignored
+        event.sequence = Some(3);
+        assert!(!updates[0].apply(&mut event));
+    }
+
+    #[test]
+    fn test_reply_for_uninvited_attendee_and_occurrence() {
+        let ics = "BEGIN:VCALENDAR\r\nMETHOD:REPLY\r\nBEGIN:VEVENT\r\n\
+                   UID:review@example.com\r\n\
+                   RECURRENCE-ID;TZID=Europe/Berlin:20240311T090000\r\n\
+                   ATTENDEE;PARTSTAT=DECLINED:mailto:dave@example.com\r\n\
+                   COMMENT:On holiday\r\n\
+                   END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let updates = read_reply(ics).unwrap();
+        let mut event = meeting();
+        assert!(!updates[0].apply_allowing_uninvited(&mut event));
+
+        event.series_id = event.ical_uid.clone();
+        event.original_start = Some(event.start.clone());
+        // Dave was not invited
+        assert!(!updates[0].apply(&mut event));
+        let invited = event.attendees.as_ref().unwrap().len();
+        assert!(updates[0].apply_allowing_uninvited(&mut event));
+        assert_eq!(event.attendees.as_ref().unwrap().len(), invited + 1);
+        let dave = event.attendees.as_ref().unwrap().last().unwrap();
+        assert_eq!(dave.email.as_deref(), Some("dave@example.com"));
+        assert_eq!(dave.response_status, Some(ParticipantStatus::Declined));
+        assert_eq!(dave.comment.as_deref(), Some("On holiday"));
+
+        // A rejected reply leaves the event untouched
+        event.attendees = None;
+        assert!(!updates[0].apply(&mut event));
+        assert!(event.attendees.is_none());
+    }
+
+    #[test]
+    fn test_reply_from_delegate() {
+        let mut event = meeting();
+        let delegation = ReplyUpdate {
+            uid: "review@example.com".to_string(),
+            recurrence_id: None,
+            sequence: 0,
+            email: "carol@example.com".to_string(),
+            status: ParticipantStatus::Declined,
+            comment: None,
+            delegated_to: Some(vec!["mailto:erin@example.com".to_string()]),
+        };
+        let delegate = ReplyUpdate {
+            email: "erin@example.com".to_string(),
+            status: ParticipantStatus::Accepted,
+            delegated_to: None,
+            ..delegation.clone()
+        };
+
+        // Erin is only expected once Carol has delegated to her
+        assert!(!delegate.apply(&mut event));
+        assert!(delegation.apply(&mut event));
+        assert!(delegate.apply(&mut event));
+        let erin = event.attendees.as_ref().unwrap().last().unwrap();
+        assert_eq!(erin.email.as_deref(), Some("erin@example.com"));
+        assert_eq!(erin.delegated_from, Some(vec!["carol@example.com".to_string()]));
+        assert_eq!(erin.response_status, Some(ParticipantStatus::Accepted));
+    }
+
+    #[test]
+    fn test_imip_rejects_header_injection() {
+        let mut message = reply(&meeting(), "carol@example.com", ParticipantStatus::Accepted, None).unwrap();
+        assert!(message.to_imip().is_ok());
+
+        message.recipients.push("eve@example.com\r\nBcc: mallory@example.com".to_string());
+        assert!(matches!(message.to_imip(), Err(CalblendError::InvalidData(_))));
+
+        message.recipients.pop();
+        message.from = "Ada <ada@example.com>".to_string();
+        assert!(message.to_imip().is_err());
+    }
+
+    #[test]
+    fn test_counter_and_errors() {
+        let start = EventMoment::timed(
+            DateTime::parse_from_rfc3339("2024-03-12T09:00:00+01:00").unwrap(),
+            Some("Europe/Berlin".to_string()),
+        );
+        let end = EventMoment::timed(
+            DateTime::parse_from_rfc3339("2024-03-12T10:00:00+01:00").unwrap(),
+            Some("Europe/Berlin".to_string()),
+        );
+        let message = counter(&meeting(), "bob@example.com", start.clone(), end, None).unwrap();
+        assert_eq!(message.method, ItipMethod::Counter);
+        assert_eq!(message.event.start, start);
+        assert!(message
+            .to_ics()
+            .contains("DTSTART;TZID=Europe/Berlin:20240312T090000\r\n"));
+        assert!(read_reply(&message.to_ics())
+            .unwrap_err()
+            .to_string()
+            .contains("Expected an iTIP REPLY, found COUNTER"));
+
+        let mut unorganized = meeting();
+        unorganized.organizer = None;
+        assert!(organizer_messages(None, &unorganized).is_err());
+        assert!(reply(
+            &unorganized,
+            "bob@example.com",
+            ParticipantStatus::Accepted,
+            None
+        )
+        .is_err());
+    }
+}
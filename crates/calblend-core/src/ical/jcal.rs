@@ -0,0 +1,647 @@
+//! jCal (RFC 7265): iCalendar as JSON
+//!
+//! Conversion goes through the same component and property model the
+//! iCalendar parser produces, so a document converts to jCal and back
+//! without losing properties, parameters or components, including ones
+//! Calblend does not otherwise understand. Only the order of recurrence
+//! rule parts is not kept, as jCal holds them in a JSON object. Events are converted by way of
+//! [`write_calendar`] and [`read_calendar`], with the fields iCalendar has
+//! no property for (id, calendar id, source, color, etag and raw provider
+//! data) carried as `X-CALBLEND-*` properties, so [`read_jcal`] gives back
+//! the events [`write_jcal`] was given, ids included.
+
+use serde_json::{json, Map, Number, Value};
+
+use super::parse::{parse_components, Component, Property};
+use super::{
+    escape_text, param_value, read_calendar, split_text_list, unescape_text, write_calendar,
+    ContentWriter, ExportOptions, ImportOptions, ImportResult,
+};
+use crate::error::{CalblendError, Result};
+use crate::models::UnifiedCalendarEvent;
+
+/// Recurrence rule parts with integer values
+const INTEGER_RECUR_PARTS: [&str; 10] = [
+    "COUNT",
+    "INTERVAL",
+    "BYSECOND",
+    "BYMINUTE",
+    "BYHOUR",
+    "BYMONTHDAY",
+    "BYYEARDAY",
+    "BYWEEKNO",
+    "BYMONTH",
+    "BYSETPOS",
+];
+
+/// Order recurrence rule parts are written in
+const RECUR_PART_ORDER: [&str; 14] = [
+    "FREQ",
+    "INTERVAL",
+    "COUNT",
+    "UNTIL",
+    "BYSECOND",
+    "BYMINUTE",
+    "BYHOUR",
+    "BYDAY",
+    "BYMONTHDAY",
+    "BYYEARDAY",
+    "BYWEEKNO",
+    "BYMONTH",
+    "BYSETPOS",
+    "WKST",
+];
+
+/// Render events as a jCal `vcalendar`
+pub fn write_jcal(events: &[UnifiedCalendarEvent], options: &ExportOptions) -> Result<Value> {
+    let options = options.clone().with_calblend_properties();
+    ics_to_jcal(&write_calendar(events, &options))
+}
+
+/// Read the events of a jCal `vcalendar`
+pub fn read_jcal(jcal: &Value, options: &ImportOptions) -> Result<ImportResult> {
+    let options = options.clone().with_calblend_properties();
+    Ok(read_calendar(&jcal_to_ics(jcal)?, &options))
+}
+
+/// Convert iCalendar text to jCal
+///
+/// A single `VCALENDAR` becomes one component array; several become an
+/// array of them.
+pub fn ics_to_jcal(text: &str) -> Result<Value> {
+    let (components, _) = parse_components(text);
+    let mut calendars: Vec<Value> = components
+        .iter()
+        .filter(|c| c.name == "VCALENDAR")
+        .map(component_to_jcal)
+        .collect();
+    match calendars.len() {
+        0 => Err(invalid("no VCALENDAR found")),
+        1 => Ok(calendars.remove(0)),
+        _ => Ok(Value::Array(calendars)),
+    }
+}
+
+/// Convert jCal, one component or an array of them, to iCalendar text
+pub fn jcal_to_ics(jcal: &Value) -> Result<String> {
+    let components = match jcal.as_array().and_then(|a| a.first()) {
+        Some(Value::String(_)) => vec![component_from_jcal(jcal)?],
+        _ => jcal
+            .as_array()
+            .ok_or_else(|| invalid("expected an array"))?
+            .iter()
+            .map(component_from_jcal)
+            .collect::<Result<_>>()?,
+    };
+    let mut out = ContentWriter::new();
+    for component in &components {
+        write_component(&mut out, component);
+    }
+    Ok(out.finish())
+}
+
+fn component_to_jcal(component: &Component) -> Value {
+    json!([
+        component.name.to_ascii_lowercase(),
+        component
+            .properties
+            .iter()
+            .map(property_to_jcal)
+            .collect::<Vec<_>>(),
+        component
+            .components
+            .iter()
+            .map(component_to_jcal)
+            .collect::<Vec<_>>(),
+    ])
+}
+
+fn property_to_jcal(property: &Property) -> Value {
+    let mut params = Map::new();
+    for (name, values) in &property.params {
+        if name == "VALUE" {
+            continue;
+        }
+        let value = match values.as_slice() {
+            [single] => json!(single),
+            many => json!(many),
+        };
+        params.insert(name.to_ascii_lowercase(), value);
+    }
+
+    let value_type = value_type(property);
+    let mut out = vec![
+        json!(property.name.to_ascii_lowercase()),
+        Value::Object(params),
+        json!(value_type),
+    ];
+    let raw = property.value.as_str();
+    match (value_type.as_str(), property.name.as_str()) {
+        ("text", "CATEGORIES" | "RESOURCES") => {
+            out.extend(split_text_list(raw).into_iter().map(Value::String))
+        }
+        ("float", "GEO") => out.push(Value::Array(
+            raw.split(';').map(|v| scalar_to_json("float", v)).collect(),
+        )),
+        (_, "RDATE" | "EXDATE" | "FREEBUSY") => {
+            out.extend(raw.split(',').map(|v| scalar_to_json(&value_type, v)))
+        }
+        _ => out.push(scalar_to_json(&value_type, raw)),
+    }
+    Value::Array(out)
+}
+
+/// jCal type of a property value: its `VALUE` parameter, else the
+/// property's default type
+fn value_type(property: &Property) -> String {
+    if let Some(value) = property.param("VALUE") {
+        return value.to_ascii_lowercase();
+    }
+    default_type(&property.name).to_string()
+}
+
+fn default_type(name: &str) -> &'static str {
+    match name {
+        "DTSTART" | "DTEND" | "DUE" | "RECURRENCE-ID" | "EXDATE" | "RDATE" | "DTSTAMP"
+        | "CREATED" | "LAST-MODIFIED" | "COMPLETED" => "date-time",
+        "DURATION" | "TRIGGER" => "duration",
+        "RRULE" | "EXRULE" => "recur",
+        "TZOFFSETFROM" | "TZOFFSETTO" => "utc-offset",
+        "SEQUENCE" | "PRIORITY" | "PERCENT-COMPLETE" | "REPEAT" => "integer",
+        "ORGANIZER" | "ATTENDEE" => "cal-address",
+        "URL" | "TZURL" | "CONFERENCE" | "SOURCE" => "uri",
+        "GEO" => "float",
+        "FREEBUSY" => "period",
+        name if name.starts_with("X-") => "unknown",
+        _ => "text",
+    }
+}
+
+/// Properties that must name their value type even when it is the default
+fn requires_value_param(name: &str) -> bool {
+    name == "CONFERENCE"
+}
+
+fn scalar_to_json(value_type: &str, raw: &str) -> Value {
+    match value_type {
+        "text" => Value::String(unescape_text(raw)),
+        "date" => Value::String(date_to_jcal(raw)),
+        "date-time" => Value::String(date_time_to_jcal(raw)),
+        "utc-offset" => Value::String(offset_to_jcal(raw)),
+        "period" => match raw.split_once('/') {
+            Some((start, end)) => json!([date_time_to_jcal(start), date_time_to_jcal(end)]),
+            None => Value::String(raw.to_string()),
+        },
+        "recur" => recur_to_jcal(raw),
+        "integer" => raw
+            .trim()
+            .parse::<i64>()
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(raw.to_string())),
+        "float" => raw
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(raw.to_string())),
+        "boolean" => match raw.trim().to_ascii_uppercase().as_str() {
+            "TRUE" => Value::Bool(true),
+            "FALSE" => Value::Bool(false),
+            _ => Value::String(raw.to_string()),
+        },
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+fn recur_to_jcal(raw: &str) -> Value {
+    let mut recur = Map::new();
+    for part in raw.split(';').filter(|p| !p.is_empty()) {
+        let (name, value) = part.split_once('=').unwrap_or((part, ""));
+        let name = name.trim().to_ascii_uppercase();
+        let convert = |v: &str| match name.as_str() {
+            "UNTIL" if v.contains('T') => Value::String(date_time_to_jcal(v)),
+            "UNTIL" => Value::String(date_to_jcal(v)),
+            part if INTEGER_RECUR_PARTS.contains(&part) => v
+                .parse::<i64>()
+                .map(Value::from)
+                .unwrap_or_else(|_| Value::String(v.to_string())),
+            _ => Value::String(v.to_string()),
+        };
+        let values: Vec<Value> = value.split(',').map(convert).collect();
+        let value = match <[Value; 1]>::try_from(values) {
+            Ok([single]) => single,
+            Err(values) => Value::Array(values),
+        };
+        recur.insert(name.to_ascii_lowercase(), value);
+    }
+    Value::Object(recur)
+}
+
+/// `20240311` to `2024-03-11`
+fn date_to_jcal(raw: &str) -> String {
+    let raw = raw.trim();
+    match (raw.get(..4), raw.get(4..6), raw.get(6..8)) {
+        (Some(year), Some(month), Some(day)) if raw.len() == 8 => {
+            format!("{}-{}-{}", year, month, day)
+        }
+        _ => raw.to_string(),
+    }
+}
+
+/// `20240311T090000Z` to `2024-03-11T09:00:00Z`; dates are converted as dates
+fn date_time_to_jcal(raw: &str) -> String {
+    let raw = raw.trim();
+    let Some((date, time)) = raw.split_once('T') else {
+        return date_to_jcal(raw);
+    };
+    let (time, utc) = match time.strip_suffix('Z') {
+        Some(time) => (time, "Z"),
+        None => (time, ""),
+    };
+    match (time.get(..2), time.get(2..4), time.get(4..6)) {
+        (Some(hours), Some(minutes), Some(seconds)) if time.len() == 6 => format!(
+            "{}T{}:{}:{}{}",
+            date_to_jcal(date),
+            hours,
+            minutes,
+            seconds,
+            utc
+        ),
+        _ => raw.to_string(),
+    }
+}
+
+/// `+0530` to `+05:30`
+fn offset_to_jcal(raw: &str) -> String {
+    let raw = raw.trim();
+    match raw.len() {
+        5 => format!("{}:{}", &raw[..3], &raw[3..]),
+        7 => format!("{}:{}:{}", &raw[..3], &raw[3..5], &raw[5..]),
+        _ => raw.to_string(),
+    }
+}
+
+fn component_from_jcal(jcal: &Value) -> Result<Component> {
+    let parts = jcal
+        .as_array()
+        .ok_or_else(|| invalid("component must be an array"))?;
+    let (Some(Value::String(name)), Some(Value::Array(properties)), Some(Value::Array(components))) =
+        (parts.first(), parts.get(1), parts.get(2))
+    else {
+        return Err(invalid(
+            "component must be [name, [properties], [components]]",
+        ));
+    };
+    Ok(Component {
+        name: name.to_ascii_uppercase(),
+        line: 0,
+        properties: properties
+            .iter()
+            .map(property_from_jcal)
+            .collect::<Result<_>>()?,
+        components: components
+            .iter()
+            .map(component_from_jcal)
+            .collect::<Result<_>>()?,
+    })
+}
+
+fn property_from_jcal(jcal: &Value) -> Result<Property> {
+    let parts = jcal
+        .as_array()
+        .ok_or_else(|| invalid("property must be an array"))?;
+    let (Some(Value::String(name)), Some(Value::Object(params)), Some(Value::String(value_type))) =
+        (parts.first(), parts.get(1), parts.get(2))
+    else {
+        return Err(invalid("property must be [name, {params}, type, value...]"));
+    };
+    let name = name.to_ascii_uppercase();
+    let value_type = value_type.to_ascii_lowercase();
+
+    let mut property_params = Vec::new();
+    if value_type != "unknown" && (value_type != default_type(&name) || requires_value_param(&name))
+    {
+        property_params.push(("VALUE".to_string(), vec![value_type.to_ascii_uppercase()]));
+    }
+    for (param, value) in params {
+        let values = match value {
+            Value::Array(values) => values.iter().map(json_to_string).collect(),
+            value => vec![json_to_string(value)],
+        };
+        property_params.push((param.to_ascii_uppercase(), values));
+    }
+
+    let values = &parts[3..];
+    let value = match (value_type.as_str(), name.as_str()) {
+        ("float", "GEO") => match values.first() {
+            Some(Value::Array(coordinates)) => coordinates
+                .iter()
+                .map(json_to_string)
+                .collect::<Vec<_>>()
+                .join(";"),
+            _ => join_values(&value_type, values)?,
+        },
+        _ => join_values(&value_type, values)?,
+    };
+    Ok(Property {
+        name,
+        params: property_params,
+        value,
+        line: 0,
+    })
+}
+
+fn join_values(value_type: &str, values: &[Value]) -> Result<String> {
+    Ok(values
+        .iter()
+        .map(|value| scalar_from_json(value_type, value))
+        .collect::<Result<Vec<_>>>()?
+        .join(","))
+}
+
+fn scalar_from_json(value_type: &str, value: &Value) -> Result<String> {
+    Ok(match (value_type, value) {
+        ("text", Value::String(text)) => escape_text(text),
+        ("date" | "date-time", Value::String(text)) => compact_date_time(text),
+        ("utc-offset", Value::String(text)) => text.replace(':', ""),
+        ("period", Value::Array(bounds)) => bounds
+            .iter()
+            .map(|b| compact_date_time(&json_to_string(b)))
+            .collect::<Vec<_>>()
+            .join("/"),
+        ("recur", Value::Object(parts)) => recur_from_jcal(parts),
+        ("boolean", Value::Bool(flag)) => if *flag { "TRUE" } else { "FALSE" }.to_string(),
+        (_, Value::Array(_) | Value::Object(_)) => {
+            return Err(invalid(format!(
+                "unexpected {} value {}",
+                value_type, value
+            )))
+        }
+        (_, value) => json_to_string(value),
+    })
+}
+
+fn recur_from_jcal(parts: &Map<String, Value>) -> String {
+    // JSON objects keep no order, so parts are written in the order
+    // `RecurrenceRule` uses, unknown parts last
+    let mut ordered: Vec<(&String, &Value)> = parts.iter().collect();
+    ordered.sort_by_key(|(name, _)| {
+        RECUR_PART_ORDER
+            .iter()
+            .position(|part| name.eq_ignore_ascii_case(part))
+            .unwrap_or(RECUR_PART_ORDER.len())
+    });
+    ordered
+        .into_iter()
+        .map(|(name, value)| {
+            let name = name.to_ascii_uppercase();
+            let values: Vec<String> = match value {
+                Value::Array(values) => values.iter().map(json_to_string).collect(),
+                value => vec![json_to_string(value)],
+            };
+            let values: Vec<String> = if name == "UNTIL" {
+                values.iter().map(|v| compact_date_time(v)).collect()
+            } else {
+                values
+            };
+            format!("{}={}", name, values.join(","))
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// `2024-03-11T09:00:00Z` to `20240311T090000Z`
+fn compact_date_time(value: &str) -> String {
+    value.replace(['-', ':'], "")
+}
+
+fn json_to_string(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn write_component(out: &mut ContentWriter, component: &Component) {
+    out.begin(&component.name);
+    for property in &component.properties {
+        let mut head = property.name.clone();
+        for (name, values) in &property.params {
+            let values: Vec<String> = values.iter().map(|v| param_value(v)).collect();
+            head.push_str(&format!(";{}={}", name, values.join(",")));
+        }
+        out.property(&head, &property.value);
+    }
+    for child in &component.components {
+        write_component(out, child);
+    }
+    out.end(&component.name);
+}
+
+fn invalid(message: impl std::fmt::Display) -> CalblendError {
+    CalblendError::InvalidData(format!("Invalid jCal: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        CalendarSource, EventMoment, Participant, ParticipantStatus, Reminder, ReminderMethod,
+    };
+    use crate::recurrence::RecurrencePattern;
+    use chrono::{DateTime, NaiveDate};
+
+    fn meeting() -> UnifiedCalendarEvent {
+        let at = |s: &str| {
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+                Some("Europe/Berlin".to_string()),
+            )
+        };
+        let mut event = UnifiedCalendarEvent::new(
+            String::new(),
+            CalendarSource::Google,
+            at("2024-03-11T09:00:00+01:00"),
+            at("2024-03-11T09:30:00+01:00"),
+        );
+        event.ical_uid = Some("standup@example.com".to_string());
+        event.title = Some("Stand-up, daily".to_string());
+        event.categories = Some(vec!["Work".to_string(), "Team, core".to_string()]);
+        event.set_recurrence(Some(
+            RecurrencePattern::parse(["RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10"]).unwrap(),
+        ));
+        event.organizer = Some(Participant {
+            email: Some("ada@example.com".to_string()),
+            name: Some("Lovelace, Ada".to_string()),
+            ..Default::default()
+        });
+        event.attendees = Some(vec![Participant {
+            email: Some("bob@example.com".to_string()),
+            response_status: Some(ParticipantStatus::Accepted),
+            ..Default::default()
+        }]);
+        event.reminders = Some(vec![Reminder {
+            minutes_before: 10,
+            method: Some(ReminderMethod::Popup),
+        }]);
+        event
+    }
+
+    #[test]
+    fn test_event_to_jcal() {
+        let jcal = write_jcal(&[meeting()], &ExportOptions::default()).unwrap();
+        assert_eq!(jcal[0], "vcalendar");
+        let event = jcal[2]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c[0] == "vevent")
+            .unwrap();
+        let property = |name: &str| {
+            event[1]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|p| p[0] == name)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(
+            property("dtstart"),
+            json!(["dtstart", { "tzid": "Europe/Berlin" }, "date-time", "2024-03-11T09:00:00"])
+        );
+        assert_eq!(
+            property("rrule"),
+            json!(["rrule", {}, "recur", { "freq": "WEEKLY", "byday": ["MO", "WE"], "count": 10 }])
+        );
+        assert_eq!(
+            property("categories"),
+            json!(["categories", {}, "text", "Work", "Team, core"])
+        );
+        assert_eq!(property("summary")[3], "Stand-up, daily");
+        assert_eq!(property("organizer")[1], json!({ "cn": "Lovelace, Ada" }));
+
+        let timezone = jcal[2]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c[0] == "vtimezone")
+            .unwrap();
+        let daylight = &timezone[2][0];
+        let offset = daylight[1]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p[0] == "tzoffsetto")
+            .unwrap();
+        assert_eq!(offset[3], "+02:00");
+    }
+
+    #[test]
+    fn test_lossless_round_trip() {
+        let mut all_day = UnifiedCalendarEvent::new(
+            String::new(),
+            CalendarSource::Google,
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 7, 4).unwrap()),
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 7, 5).unwrap()),
+        );
+        all_day.ical_uid = Some("holiday".to_string());
+        all_day.html_link = Some("https://example.com/e?a=1;b=2".to_string());
+        let ics = write_calendar(
+            &[meeting(), all_day],
+            &ExportOptions::default().with_name("Team"),
+        );
+
+        // Text survives iCalendar -> jCal -> iCalendar, up to the order of
+        // the VTIMEZONE rule parts
+        let jcal = ics_to_jcal(&ics).unwrap();
+        let expected = ics
+            .replace(";BYMONTH=3;BYDAY=-1SU", ";BYDAY=-1SU;BYMONTH=3")
+            .replace(";BYMONTH=10;BYDAY=-1SU", ";BYDAY=-1SU;BYMONTH=10");
+        assert_eq!(jcal_to_ics(&jcal).unwrap(), expected);
+        // And so does the JSON text
+        let reparsed: Value = serde_json::from_str(&jcal.to_string()).unwrap();
+        assert_eq!(reparsed, jcal);
+        assert_eq!(ics_to_jcal(&expected).unwrap(), jcal);
+
+        let options = ImportOptions::new(CalendarSource::Google);
+        let from_ics = read_calendar(&ics, &options);
+        let from_jcal = read_jcal(&jcal, &options).unwrap();
+        assert!(from_jcal.errors.is_empty(), "{:?}", from_jcal.errors);
+        assert_eq!(from_jcal.name.as_deref(), Some("Team"));
+        for (a, b) in from_ics.events.iter().zip(&from_jcal.events) {
+            assert!(crate::diff(a, b).is_empty());
+        }
+        assert_eq!(from_jcal.events.len(), 2);
+    }
+
+    #[test]
+    fn test_events_keep_calblend_fields() {
+        let mut event = meeting();
+        event.id = "evt-1".to_string();
+        event.source = CalendarSource::Outlook;
+        event.calendar_id = Some("team;cal".to_string());
+        event.color = Some("#a4bdfc".to_string());
+        event.etag = Some("\"3141\"".to_string());
+        event.raw = Some(json!({ "importance": "high", "lines": "a\nb" }));
+        // The importer marks the organizer's own entry
+        event.organizer.as_mut().unwrap().organizer = Some(true);
+
+        let jcal = write_jcal(std::slice::from_ref(&event), &ExportOptions::default()).unwrap();
+        let read = read_jcal(&jcal, &ImportOptions::new(CalendarSource::Google)).unwrap();
+        assert!(read.errors.is_empty(), "{:?}", read.errors);
+        let back = &read.events[0];
+        assert_eq!(back.id, "evt-1");
+        assert_eq!(back.source, CalendarSource::Outlook);
+        assert_eq!(back.calendar_id, event.calendar_id);
+        assert_eq!(back.color, event.color);
+        assert_eq!(back.etag, event.etag);
+        assert_eq!(back.raw, event.raw);
+        assert!(crate::diff(&event, back).is_empty());
+
+        // Plain iCalendar keeps them as unknown properties only
+        let ics = write_calendar(&[event], &ExportOptions::default());
+        assert!(!ics.contains("X-CALBLEND-"));
+        let read = read_calendar(
+            &jcal_to_ics(&jcal).unwrap(),
+            &ImportOptions::new(CalendarSource::Google),
+        );
+        assert_eq!(read.events[0].id, "");
+        assert_eq!(read.events[0].source, CalendarSource::Google);
+    }
+
+    #[test]
+    fn test_value_types_from_jcal() {
+        let jcal = json!(["vcalendar", [], [["vevent", [
+            ["uid", {}, "text", "x"],
+            ["dtstart", {}, "date", "2024-07-04"],
+            ["rdate", { "tzid": "Europe/Paris" }, "period", ["2024-07-05T09:00:00", "2024-07-05T10:00:00"]],
+            ["geo", {}, "float", [37.5, -122.25]],
+            ["x-custom", { "x-param": ["a", "b:c"] }, "unknown", "raw;value"],
+            ["x-flag", {}, "boolean", true]
+        ], []]]]);
+        let ics = jcal_to_ics(&jcal).unwrap();
+        assert_eq!(
+            ics,
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:x\r\nDTSTART;VALUE=DATE:20240704\r\n\
+             RDATE;VALUE=PERIOD;TZID=Europe/Paris:20240705T090000/20240705T100000\r\n\
+             GEO:37.5;-122.25\r\nX-CUSTOM;X-PARAM=a,\"b:c\":raw;value\r\n\
+             X-FLAG;VALUE=BOOLEAN:TRUE\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+        );
+        assert_eq!(ics_to_jcal(&ics).unwrap(), jcal);
+    }
+
+    #[test]
+    fn test_invalid_jcal() {
+        for jcal in [
+            json!("vcalendar"),
+            json!(["vcalendar", {}, []]),
+            json!(["vcalendar", [["summary", {}, "text", ["nested"]]], []]),
+        ] {
+            let err = jcal_to_ics(&jcal).unwrap_err();
+            assert!(err.to_string().contains("Invalid jCal"), "{}", err);
+        }
+        assert!(ics_to_jcal("BEGIN:VEVENT\r\nEND:VEVENT\r\n").is_err());
+    }
+}
@@ -0,0 +1,327 @@
+//! iCalendar (RFC 5545) support
+//!
+//! [`write_calendar`] renders unified events as a `VCALENDAR` with one
+//! `VEVENT` per event and a `VTIMEZONE` for every zone the events refer to,
+//! ready to be served as a `.ics` download or attached to an email.
+//! [`read_calendar`] goes the other way for files exported by other tools,
+//! reporting broken components instead of rejecting the whole file. The
+//! [`itip`] functions build and read the scheduling messages exchanged with
+//! attendees, and [`write_jcal`]/[`read_jcal`] carry the same data as jCal.
+//! [`write_free_busy`] and [`read_free_busy`] share availability as
+//! `VFREEBUSY` without event details.
+
+mod export;
+mod freebusy;
+mod imip;
+mod import;
+pub mod itip;
+mod jcal;
+mod parse;
+mod vtimezone;
+
+pub use export::{export_calendar, write_calendar, ExportOptions};
+pub use freebusy::{coalesce_free_busy, read_free_busy, write_free_busy, FreeBusy, FreeBusyImport};
+pub use import::{read_calendar, ImportError, ImportOptions, ImportResult};
+pub use itip::{ItipMessage, ItipMethod, ReplyUpdate};
+pub use jcal::{ics_to_jcal, jcal_to_ics, read_jcal, write_jcal};
+
+use chrono::Duration;
+
+/// RFC 5545 `DATE` value format
+const DATE_FORMAT: &str = "%Y%m%d";
+/// RFC 5545 `DATE-TIME` value format, without the `Z` suffix
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+/// Longest physical line allowed, in octets, excluding the CRLF
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Builds iCalendar text one content line at a time
+#[derive(Debug, Default)]
+pub(crate) struct ContentWriter {
+    out: String,
+}
+
+impl ContentWriter {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a component, e.g. `VEVENT`
+    pub(crate) fn begin(&mut self, component: &str) {
+        self.line(&format!("BEGIN:{}", component));
+    }
+
+    /// Close a component
+    pub(crate) fn end(&mut self, component: &str) {
+        self.line(&format!("END:{}", component));
+    }
+
+    /// Write `name` (with any parameters) and an already encoded value
+    pub(crate) fn property(&mut self, name: &str, value: &str) {
+        self.line(&format!("{}:{}", name, value));
+    }
+
+    /// Write a TEXT property, escaping the value
+    pub(crate) fn text(&mut self, name: &str, value: &str) {
+        self.property(name, &escape_text(value));
+    }
+
+    /// Write a complete content line, folding it as needed
+    ///
+    /// Control characters other than tab are dropped: a CR or LF in a raw
+    /// value such as a URL or address would otherwise end the line early
+    /// and start a property of the value's choosing.
+    pub(crate) fn line(&mut self, line: &str) {
+        if line.chars().any(is_forbidden_control) {
+            let line: String = line.chars().filter(|c| !is_forbidden_control(*c)).collect();
+            self.out.push_str(&fold_line(&line));
+        } else {
+            self.out.push_str(&fold_line(line));
+        }
+        self.out.push_str("\r\n");
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+}
+
+/// Characters RFC 5545 does not allow in a content line (`CONTROL`)
+fn is_forbidden_control(c: char) -> bool {
+    c.is_control() && c != '\t'
+}
+
+/// Escape a TEXT value: backslash, semicolon, comma and newlines
+pub(crate) fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.replace("\r\n", "\n").chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' | '\r' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Undo [`escape_text`]; unknown escapes keep the escaped character
+pub(crate) fn unescape_text(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Split a multi-valued TEXT value on unescaped commas and unescape each part
+pub(crate) fn split_text_list(value: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                parts.push(unescape_text(&value[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(unescape_text(&value[start..]));
+    parts
+}
+
+/// Parse a DURATION value such as `-PT15M`, `P1DT2H` or `P2W`
+///
+/// `None` for malformed values and for amounts beyond chrono's range.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut rest = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut in_time = false;
+    let mut any = false;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = after;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        let unit = rest[digits..].chars().next()?;
+        let part = match (in_time, unit) {
+            (false, 'W') => Duration::try_weeks(amount),
+            (false, 'D') => Duration::try_days(amount),
+            (true, 'H') => Duration::try_hours(amount),
+            (true, 'M') => Duration::try_minutes(amount),
+            (true, 'S') => Duration::try_seconds(amount),
+            _ => return None,
+        }?;
+        total = total.checked_add(&part)?;
+        any = true;
+        rest = &rest[digits + 1..];
+    }
+    any.then_some(if negative { -total } else { total })
+}
+
+/// Format a DURATION value, the inverse of [`parse_duration`]
+///
+/// Whole weeks are written as `P2W`, anything else as days and time,
+/// e.g. `-PT15M` or `P1DT2H`.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let sign = if duration < Duration::zero() { "-" } else { "" };
+    let seconds = duration.num_seconds().unsigned_abs();
+    if seconds == 0 {
+        return "PT0S".to_string();
+    }
+    if seconds.is_multiple_of(7 * 24 * 3600) {
+        return format!("{}P{}W", sign, seconds / (7 * 24 * 3600));
+    }
+    let (days, hours, minutes, seconds) = (
+        seconds / (24 * 3600),
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+    );
+    let mut value = format!("{}P", sign);
+    if days > 0 {
+        value.push_str(&format!("{}D", days));
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 {
+        value.push('T');
+        for (amount, unit) in [(hours, 'H'), (minutes, 'M'), (seconds, 'S')] {
+            if amount > 0 {
+                value.push_str(&format!("{}{}", amount, unit));
+            }
+        }
+    }
+    value
+}
+
+/// Parameter value, quoted when it contains `:`, `;` or `,`
+///
+/// Double quotes cannot be escaped in parameter values and are dropped.
+pub(crate) fn param_value(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .filter(|c| *c != '"' && !c.is_control())
+        .collect();
+    if value.contains([':', ';', ',']) {
+        format!("\"{}\"", value)
+    } else {
+        value
+    }
+}
+
+/// Fold a content line into physical lines of at most 75 octets
+///
+/// Continuation lines start with a single space. Multi-byte characters are
+/// never split.
+pub(crate) fn fold_line(line: &str) -> String {
+    if line.len() <= MAX_LINE_OCTETS {
+        return line.to_string();
+    }
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(
+            escape_text("Room 1; Floor 2, \"East\"\\West\r\nBring laptops"),
+            "Room 1\\; Floor 2\\, \"East\"\\\\West\\nBring laptops"
+        );
+    }
+
+    #[test]
+    fn test_unescape_text() {
+        let text = "Room 1; Floor 2, \"East\"\\West\nBring laptops";
+        assert_eq!(unescape_text(&escape_text(text)), text);
+        assert_eq!(unescape_text("a\\Nb\\:c\\"), "a\nb:c\\");
+    }
+
+    #[test]
+    fn test_split_text_list() {
+        assert_eq!(
+            split_text_list("Work,Travel\\, Europe,"),
+            vec!["Work", "Travel, Europe", ""]
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(
+            parse_duration("P1DT2H30M"),
+            Some(Duration::days(1) + Duration::minutes(150))
+        );
+        assert_eq!(parse_duration("+P2W"), Some(Duration::weeks(2)));
+        assert_eq!(parse_duration("PT0S"), Some(Duration::zero()));
+        for invalid in ["", "P", "15M", "PT1D", "P1H", "P99999999999999W", "P99999999999999D", "P9999999999999W9999999999999W"] {
+            assert_eq!(parse_duration(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(-15)), "-PT15M");
+        assert_eq!(format_duration(Duration::minutes(1590)), "P1DT2H30M");
+        assert_eq!(format_duration(Duration::weeks(2)), "P2W");
+        assert_eq!(format_duration(Duration::seconds(90)), "PT1M30S");
+        assert_eq!(format_duration(Duration::zero()), "PT0S");
+        for value in ["-P1D", "PT1H", "P3DT4H5M6S"] {
+            assert_eq!(format_duration(parse_duration(value).unwrap()), value);
+        }
+    }
+
+    #[test]
+    fn test_param_value() {
+        assert_eq!(param_value("Ada Lovelace"), "Ada Lovelace");
+        assert_eq!(param_value("Lovelace, Ada"), "\"Lovelace, Ada\"");
+        assert_eq!(param_value("Ada \"The Countess\""), "Ada The Countess");
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let short = "SUMMARY:Standup";
+        assert_eq!(fold_line(short), short);
+
+        let line = format!("DESCRIPTION:{}", "é".repeat(60));
+        let folded = fold_line(&line);
+        let physical: Vec<&str> = folded.split("\r\n").collect();
+        assert!(physical.len() > 1);
+        assert!(physical.iter().all(|l| l.len() <= MAX_LINE_OCTETS));
+        assert!(physical[1..].iter().all(|l| l.starts_with(' ')));
+        // Unfolding restores the original line
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
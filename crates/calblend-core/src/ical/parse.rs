@@ -0,0 +1,281 @@
+//! Tolerant content-line and component parsing
+//!
+//! Folded lines are joined, bare LF line endings are accepted and malformed
+//! lines are reported and skipped instead of aborting the parse.
+
+/// One content line: `NAME;PARAM=value:value`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Property {
+    /// Upper-cased property name
+    pub name: String,
+    /// Upper-cased parameter names with their unquoted values
+    pub params: Vec<(String, Vec<String>)>,
+    /// Raw value, still escaped
+    pub value: String,
+    /// Line number the property starts on
+    pub line: usize,
+}
+
+impl Property {
+    /// First value of a parameter
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.param_values(name).first().map(String::as_str)
+    }
+
+    /// All values of a parameter
+    pub fn param_values(&self, name: &str) -> &[String] {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, values)| values.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// A `BEGIN`/`END` block with its properties and nested components
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Component {
+    /// Upper-cased component name, e.g. `VEVENT`
+    pub name: String,
+    /// Line number of the `BEGIN`
+    pub line: usize,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    fn new(name: String, line: usize) -> Self {
+        Self {
+            name,
+            line,
+            properties: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+
+    /// First property with the given name
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    /// All properties with the given name
+    pub fn properties_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties.iter().filter(move |p| p.name == name)
+    }
+
+    /// Nested components with the given name
+    pub fn components_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Component> {
+        self.components.iter().filter(move |c| c.name == name)
+    }
+}
+
+/// Something wrong in the input that parsing skipped over
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ParseProblem {
+    /// Innermost component the problem is in, empty at the top level
+    pub component: String,
+    pub line: usize,
+    pub message: String,
+}
+
+/// Parse iCalendar text into its top-level components
+///
+/// Components left open at the end of the input are closed and reported.
+pub(crate) fn parse_components(text: &str) -> (Vec<Component>, Vec<ParseProblem>) {
+    let mut roots = Vec::new();
+    let mut stack: Vec<Component> = Vec::new();
+    let mut problems = Vec::new();
+    let component_name =
+        |stack: &[Component]| stack.last().map(|c| c.name.clone()).unwrap_or_default();
+
+    for (line, content) in unfold(text) {
+        if content.trim().is_empty() {
+            continue;
+        }
+        let Some(property) = parse_property(&content, line) else {
+            problems.push(ParseProblem {
+                component: component_name(&stack),
+                line,
+                message: format!("Malformed content line: {}", truncate(&content)),
+            });
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component::new(
+                property.value.trim().to_ascii_uppercase(),
+                line,
+            )),
+            "END" => {
+                let name = property.value.trim().to_ascii_uppercase();
+                // Close up to the matching BEGIN, tolerating missing ENDs
+                let Some(depth) = stack.iter().rposition(|c| c.name == name) else {
+                    problems.push(ParseProblem {
+                        component: component_name(&stack),
+                        line,
+                        message: format!("END:{} without BEGIN", name),
+                    });
+                    continue;
+                };
+                while stack.len() > depth {
+                    let Some(component) = stack.pop() else { break };
+                    if component.name != name {
+                        problems.push(ParseProblem {
+                            component: component.name.clone(),
+                            line: component.line,
+                            message: format!("Missing END:{}", component.name),
+                        });
+                    }
+                    match stack.last_mut() {
+                        Some(parent) => parent.components.push(component),
+                        None => roots.push(component),
+                    }
+                }
+            }
+            _ => match stack.last_mut() {
+                Some(component) => component.properties.push(property),
+                None => problems.push(ParseProblem {
+                    component: String::new(),
+                    line,
+                    message: format!("{} outside of any component", property.name),
+                }),
+            },
+        }
+    }
+
+    while let Some(component) = stack.pop() {
+        problems.push(ParseProblem {
+            component: component.name.clone(),
+            line: component.line,
+            message: format!("Missing END:{}", component.name),
+        });
+        match stack.last_mut() {
+            Some(parent) => parent.components.push(component),
+            None => roots.push(component),
+        }
+    }
+
+    (roots, problems)
+}
+
+/// Logical lines with the number of the physical line each starts on
+fn unfold(text: &str) -> Vec<(usize, String)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (index, physical) in text.split('\n').enumerate() {
+        let physical = physical.strip_suffix('\r').unwrap_or(physical);
+        match (physical.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some((_, last))) => last.push_str(continuation),
+            _ => lines.push((index + 1, physical.to_string())),
+        }
+    }
+    lines
+}
+
+/// Split a content line into name, parameters and value
+fn parse_property(content: &str, line: usize) -> Option<Property> {
+    let name_end = content.find([';', ':'])?;
+    let name = content[..name_end].trim().to_ascii_uppercase();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+
+    let mut params = Vec::new();
+    let mut rest = &content[name_end..];
+    while let Some(after) = rest.strip_prefix(';') {
+        let (param_name, after) = after.split_once('=')?;
+        let mut values = Vec::new();
+        let mut remaining = after;
+        loop {
+            let (value, next) = if let Some(quoted) = remaining.strip_prefix('"') {
+                let close = quoted.find('"')?;
+                (quoted[..close].to_string(), &quoted[close + 1..])
+            } else {
+                let end = remaining.find([',', ';', ':'])?;
+                (remaining[..end].to_string(), &remaining[end..])
+            };
+            values.push(value);
+            match next.strip_prefix(',') {
+                Some(more) => remaining = more,
+                None => {
+                    remaining = next;
+                    break;
+                }
+            }
+        }
+        params.push((param_name.trim().to_ascii_uppercase(), values));
+        rest = remaining;
+    }
+
+    let value = rest.strip_prefix(':')?;
+    Some(Property {
+        name,
+        params,
+        value: value.to_string(),
+        line,
+    })
+}
+
+fn truncate(content: &str) -> String {
+    match content.char_indices().nth(40) {
+        Some((end, _)) => format!("{}...", &content[..end]),
+        None => content.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unfold_and_params() {
+        let text = "BEGIN:VEVENT\r\nATTENDEE;CN=\"Lovelace, Ada\";DELEGATED-TO=\"mailto:a@x.com\",\"mailto:b@x.com\";\r\n ROLE=CHAIR:mailto:ada@\r\n\texample.com\r\nEND:VEVENT\r\n";
+        let (components, problems) = parse_components(text);
+        assert!(problems.is_empty());
+        let attendee = components[0].property("ATTENDEE").unwrap();
+        assert_eq!(attendee.param("CN"), Some("Lovelace, Ada"));
+        assert_eq!(
+            attendee.param_values("DELEGATED-TO"),
+            ["mailto:a@x.com", "mailto:b@x.com"]
+        );
+        assert_eq!(attendee.param("ROLE"), Some("CHAIR"));
+        assert_eq!(attendee.value, "mailto:ada@example.com");
+        assert_eq!(attendee.line, 2);
+    }
+
+    #[test]
+    fn test_value_may_contain_colons() {
+        let property = parse_property("DTSTART;TZID=Europe/Berlin:20240101T090000", 1).unwrap();
+        assert_eq!(property.value, "20240101T090000");
+        let property = parse_property("URL:https://example.com/a:b", 1).unwrap();
+        assert_eq!(property.value, "https://example.com/a:b");
+    }
+
+    #[test]
+    fn test_recovers_from_bad_structure() {
+        let text = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:One\nnot a line\nBEGIN:VEVENT\nSUMMARY:Two\nEND:VEVENT\nEND:VCALENDAR\nEND:VTODO\n";
+        let (components, problems) = parse_components(text);
+        assert_eq!(components.len(), 1);
+        // The unterminated first VEVENT is closed by END:VCALENDAR
+        let calendar = &components[0];
+        assert_eq!(calendar.components.len(), 1);
+        let first = &calendar.components[0];
+        assert_eq!(first.property("SUMMARY").unwrap().value, "One");
+        assert_eq!(
+            first.components[0].property("SUMMARY").unwrap().value,
+            "Two"
+        );
+
+        let messages: Vec<_> = problems
+            .iter()
+            .map(|p| (p.line, p.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (4, "Malformed content line: not a line"),
+                (2, "Missing END:VEVENT"),
+                (9, "END:VTODO without BEGIN"),
+            ]
+        );
+    }
+}
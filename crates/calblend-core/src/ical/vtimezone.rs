@@ -0,0 +1,267 @@
+//! `VTIMEZONE` components generated from the tz database
+//!
+//! chrono-tz has no transition table, so offset changes are found by
+//! sampling each day and bisecting to the second. Transitions are written
+//! explicitly for the years the events cover. When the last year's changes
+//! follow a yearly "nth weekday of the month" pattern that the following
+//! years confirm, they are written with an `RRULE` so clients also get the
+//! right offsets for later occurrences.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone, Weekday};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+
+use super::{ContentWriter, DATE_TIME_FORMAT};
+use crate::recurrence::weekday_code;
+
+/// Years checked past the last one before an `RRULE` is trusted
+const CONFIRM_YEARS: i32 = 2;
+
+/// One change of UTC offset
+#[derive(Debug, Clone, PartialEq)]
+struct Transition {
+    /// UTC instant of the change
+    at: NaiveDateTime,
+    from: i32,
+    to: i32,
+    dst: bool,
+    name: Option<String>,
+}
+
+impl Transition {
+    /// Wall-clock time of the change, in the offset before it
+    fn local_start(&self) -> NaiveDateTime {
+        self.at + Duration::seconds(self.from.into())
+    }
+
+    /// `(month, nth weekday)` of the change, counting from the end of the
+    /// month for changes in its last seven days
+    fn yearly_pattern(&self) -> (u32, i8, Weekday) {
+        let date = self.local_start().date();
+        let days_in_month = days_in_month(date);
+        let nth = if date.day() + 7 > days_in_month {
+            -1
+        } else {
+            ((date.day() - 1) / 7 + 1) as i8
+        };
+        (date.month(), nth, date.weekday())
+    }
+}
+
+/// Write the `VTIMEZONE` for `tz` covering `from_year` to `to_year`
+pub(super) fn write_vtimezone(out: &mut ContentWriter, tz: Tz, from_year: i32, to_year: i32) {
+    out.begin("VTIMEZONE");
+    out.property("TZID", tz.name());
+
+    let all = transitions(tz, from_year, to_year + CONFIRM_YEARS);
+    let (covered, later): (Vec<_>, Vec<_>) = all.iter().partition(|t| t.at.year() <= to_year);
+    if covered.is_empty() {
+        let start = NaiveDate::from_ymd_opt(from_year, 1, 1)
+            .unwrap_or(NaiveDate::MIN)
+            .and_time(chrono::NaiveTime::MIN);
+        let offset = tz.offset_from_utc_datetime(&start);
+        let fixed = offset.fix().local_minus_utc();
+        write_observance(
+            out,
+            &Transition {
+                // Local midnight, 1970-01-01
+                at: NaiveDate::from_ymd_opt(1970, 1, 1)
+                    .unwrap_or(NaiveDate::MIN)
+                    .and_time(chrono::NaiveTime::MIN)
+                    - Duration::seconds(fixed.into()),
+                from: fixed,
+                to: fixed,
+                dst: false,
+                name: offset.abbreviation().map(str::to_string),
+            },
+            None,
+        );
+        out.end("VTIMEZONE");
+        return;
+    }
+
+    let last_year: Vec<&Transition> = covered
+        .iter()
+        .copied()
+        .filter(|t| t.at.year() == to_year)
+        .collect();
+    let repeats = !last_year.is_empty()
+        && (1..=CONFIRM_YEARS).all(|offset| {
+            let year: Vec<&&Transition> = later
+                .iter()
+                .filter(|t| t.at.year() == to_year + offset)
+                .collect();
+            year.len() == last_year.len()
+                && year.iter().zip(&last_year).all(|(next, last)| {
+                    next.yearly_pattern() == last.yearly_pattern()
+                        && next.local_start().time() == last.local_start().time()
+                        && (next.from, next.to) == (last.from, last.to)
+                })
+        });
+
+    for transition in &covered {
+        let rule = (repeats && transition.at.year() == to_year).then(|| {
+            let (month, nth, weekday) = transition.yearly_pattern();
+            format!(
+                "FREQ=YEARLY;BYMONTH={};BYDAY={}{}",
+                month,
+                nth,
+                weekday_code(weekday)
+            )
+        });
+        write_observance(out, transition, rule.as_deref());
+    }
+    if !repeats {
+        // Spell out the changes we looked ahead at instead
+        for transition in later {
+            write_observance(out, transition, None);
+        }
+    }
+    out.end("VTIMEZONE");
+}
+
+fn write_observance(out: &mut ContentWriter, transition: &Transition, rule: Option<&str>) {
+    let component = if transition.dst {
+        "DAYLIGHT"
+    } else {
+        "STANDARD"
+    };
+    out.begin(component);
+    out.property(
+        "DTSTART",
+        &transition
+            .local_start()
+            .format(DATE_TIME_FORMAT)
+            .to_string(),
+    );
+    out.property("TZOFFSETFROM", &format_offset(transition.from));
+    out.property("TZOFFSETTO", &format_offset(transition.to));
+    if let Some(name) = &transition.name {
+        out.text("TZNAME", name);
+    }
+    if let Some(rule) = rule {
+        out.property("RRULE", rule);
+    }
+    out.end(component);
+}
+
+/// Offset changes of `tz` from the start of `from_year` to the end of `to_year`
+fn transitions(tz: Tz, from_year: i32, to_year: i32) -> Vec<Transition> {
+    let (Some(first), Some(last)) = (
+        NaiveDate::from_ymd_opt(from_year, 1, 1),
+        NaiveDate::from_ymd_opt(to_year + 1, 1, 1),
+    ) else {
+        return Vec::new();
+    };
+    let offset_at = |at: NaiveDateTime| tz.offset_from_utc_datetime(&at).fix().local_minus_utc();
+
+    let mut found = Vec::new();
+    let mut day = first.and_time(chrono::NaiveTime::MIN);
+    let end = last.and_time(chrono::NaiveTime::MIN);
+    let mut current = offset_at(day);
+    while day < end {
+        let next = day + Duration::days(1);
+        let offset = offset_at(next);
+        if offset != current {
+            // Bisect to the first second with the new offset
+            let (mut lo, mut hi) = (day, next);
+            while hi - lo > Duration::seconds(1) {
+                let mid = lo + Duration::seconds((hi - lo).num_seconds() / 2);
+                if offset_at(mid) == current {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            let after = tz.offset_from_utc_datetime(&hi);
+            found.push(Transition {
+                at: hi,
+                from: current,
+                to: offset,
+                dst: after.dst_offset() != Duration::zero(),
+                name: after.abbreviation().map(str::to_string),
+            });
+            current = offset;
+        }
+        day = next;
+    }
+    found
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(31)
+}
+
+/// UTC offset as `+HHMM`, with seconds when they are not zero
+fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes, secs) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if secs == 0 {
+        format!("{}{:02}{:02}", sign, hours, minutes)
+    } else {
+        format!("{}{:02}{:02}{:02}", sign, hours, minutes, secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(tz: Tz, from_year: i32, to_year: i32) -> String {
+        let mut out = ContentWriter::new();
+        write_vtimezone(&mut out, tz, from_year, to_year);
+        out.finish()
+    }
+
+    #[test]
+    fn test_transitions_found_to_the_second() {
+        let found = transitions(chrono_tz::America::New_York, 2024, 2024);
+        assert_eq!(found.len(), 2);
+        // 2024-03-10 02:00 EST and 2024-11-03 02:00 EDT
+        assert_eq!(found[0].local_start().to_string(), "2024-03-10 02:00:00");
+        assert_eq!(
+            (found[0].from, found[0].to, found[0].dst),
+            (-5 * 3600, -4 * 3600, true)
+        );
+        assert_eq!(found[1].local_start().to_string(), "2024-11-03 02:00:00");
+        assert_eq!(found[1].name.as_deref(), Some("EST"));
+    }
+
+    #[test]
+    fn test_regular_zone_gets_yearly_rules() {
+        let text = render(chrono_tz::Europe::Berlin, 2023, 2024);
+        assert!(text.starts_with("BEGIN:VTIMEZONE\r\nTZID:Europe/Berlin\r\n"));
+        assert!(text.contains(
+            "BEGIN:DAYLIGHT\r\nDTSTART:20240331T020000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\nTZNAME:CEST\r\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\nEND:DAYLIGHT"
+        ));
+        assert!(text.contains("RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU"));
+        // Earlier years are listed without rules
+        assert!(text.contains("DTSTART:20230326T020000\r\n"));
+        assert_eq!(text.matches("RRULE").count(), 2);
+    }
+
+    #[test]
+    fn test_zone_without_changes() {
+        let text = render(chrono_tz::Asia::Kolkata, 2024, 2024);
+        assert_eq!(
+            text,
+            "BEGIN:VTIMEZONE\r\nTZID:Asia/Kolkata\r\nBEGIN:STANDARD\r\nDTSTART:19700101T000000\r\n\
+             TZOFFSETFROM:+0530\r\nTZOFFSETTO:+0530\r\nTZNAME:IST\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\n"
+        );
+    }
+
+    #[test]
+    fn test_format_offset() {
+        assert_eq!(format_offset(-4 * 3600), "-0400");
+        assert_eq!(format_offset(5 * 3600 + 45 * 60), "+0545");
+        assert_eq!(format_offset(-(17 * 60 + 30)), "-001730");
+    }
+}
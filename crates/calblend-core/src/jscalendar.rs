@@ -0,0 +1,1621 @@
+//! JSCalendar (RFC 8984) conversion
+//!
+//! [`write_jscalendar`] turns unified events into JSCalendar `Event` objects,
+//! folding exceptions into the `recurrenceOverrides` of their series, and
+//! [`read_jscalendar`] reads such objects back. Properties without a unified
+//! counterpart, `localizations` among them, are kept in `raw` under
+//! `"jscalendar"` and written out again, so they survive a read/write cycle.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde_json::{json, Map, Value};
+
+use crate::error::{ConversionError, Result};
+use crate::ical::{format_duration, parse_duration};
+use crate::models::{
+    CalendarSource, CalendarUserType, ConferenceLink, EventMoment, EventStatus, EventVisibility,
+    Participant, ParticipantRole, ParticipantStatus, Reminder, ReminderMethod, ShowAs,
+    UnifiedCalendarEvent,
+};
+use crate::recurrence::{
+    weekday_code, Frequency, RecurrenceDate, RecurrencePattern, RecurrenceRule, RecurrenceUntil,
+    WeekdayNum,
+};
+use crate::timezone::{parse_time_zone, resolve_local};
+
+/// JSCalendar `LocalDateTime` format
+const LOCAL_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+/// JSCalendar `UTCDateTime` format
+const UTC_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+/// Zone timed events without a zone of their own are written in
+const UTC_ZONE: &str = "Etc/UTC";
+/// Key of the unmapped JSCalendar properties in `raw`
+const RAW_KEY: &str = "jscalendar";
+
+/// Event properties read into unified fields
+const KNOWN_PROPERTIES: [&str; 25] = [
+    "@type",
+    "uid",
+    "sequence",
+    "title",
+    "description",
+    "created",
+    "updated",
+    "start",
+    "timeZone",
+    "duration",
+    "showWithoutTime",
+    "recurrenceId",
+    "recurrenceIdTimeZone",
+    "recurrenceRules",
+    "recurrenceOverrides",
+    "status",
+    "privacy",
+    "freeBusyStatus",
+    "color",
+    "keywords",
+    "locations",
+    "virtualLocations",
+    "participants",
+    "replyTo",
+    "alerts",
+];
+
+/// Properties a recurrence override cannot patch
+const SERIES_PROPERTIES: [&str; 5] = [
+    "@type",
+    "uid",
+    "recurrenceRules",
+    "recurrenceOverrides",
+    "excludedRecurrenceRules",
+];
+
+/// Options for reading JSCalendar objects
+#[derive(Debug, Clone)]
+pub struct ReadOptions {
+    /// Source the read events are tagged with
+    pub source: CalendarSource,
+    /// Zone for floating times; UTC when unset
+    pub default_time_zone: Option<String>,
+    /// Language tag whose `localizations` entry is applied, e.g. `de-AT`
+    pub locale: Option<String>,
+}
+
+impl ReadOptions {
+    pub fn new(source: CalendarSource) -> Self {
+        Self {
+            source,
+            default_time_zone: None,
+            locale: None,
+        }
+    }
+
+    pub fn with_default_time_zone(mut self, time_zone: impl Into<String>) -> Self {
+        self.default_time_zone = Some(time_zone.into());
+        self
+    }
+
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+}
+
+/// Render events as JSCalendar `Event` objects
+///
+/// Exceptions whose series is among `events` become entries of the series'
+/// `recurrenceOverrides`; others are written on their own with a
+/// `recurrenceId`.
+pub fn write_jscalendar(events: &[UnifiedCalendarEvent]) -> Vec<Value> {
+    let is_master = |event: &UnifiedCalendarEvent| !event.is_exception() && event.is_recurring();
+    let series_of = |exception: &UnifiedCalendarEvent| {
+        events.iter().position(|master| {
+            is_master(master)
+                && exception.series_id.as_ref().is_some_and(|series| {
+                    (!master.id.is_empty() && *series == master.id)
+                        || master.ical_uid.as_ref() == Some(series)
+                })
+        })
+    };
+
+    let mut objects = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        if event.is_exception() && series_of(event).is_some() {
+            continue;
+        }
+        let mut object = event_object(event);
+        if is_master(event) {
+            let exceptions = events
+                .iter()
+                .filter(|e| e.is_exception() && series_of(e) == Some(index));
+            add_recurrence(&mut object, event, exceptions);
+        } else if let Some(original_start) = &event.original_start {
+            object.insert(
+                "recurrenceId".to_string(),
+                json!(local_in(original_start, &event.start)),
+            );
+        }
+        objects.push(Value::Object(object));
+    }
+    objects
+}
+
+/// Read JSCalendar events
+///
+/// `value` may be an `Event`, a `Group` of them or an array. Overrides of a
+/// recurring event come back as exception events after their series; other
+/// object types, such as `Task`, are skipped.
+pub fn read_jscalendar(value: &Value, options: &ReadOptions) -> Result<Vec<UnifiedCalendarEvent>> {
+    let mut events = Vec::new();
+    read_value(value, options, &mut events)?;
+    Ok(events)
+}
+
+fn read_value(
+    value: &Value,
+    options: &ReadOptions,
+    events: &mut Vec<UnifiedCalendarEvent>,
+) -> Result<()> {
+    match value {
+        Value::Array(entries) => {
+            for entry in entries {
+                read_value(entry, options, events)?;
+            }
+        }
+        Value::Object(object) => match object.get("@type").and_then(Value::as_str) {
+            Some("Group") => {
+                if let Some(entries) = object.get("entries") {
+                    read_value(entries, options, events)?;
+                }
+            }
+            Some("Event") | None => read_event(object, options, events)?,
+            Some(_) => {}
+        },
+        other => {
+            return Err(
+                ConversionError::invalid("@type", other.to_string(), "expected an object").into(),
+            )
+        }
+    }
+    Ok(())
+}
+
+fn event_object(event: &UnifiedCalendarEvent) -> Map<String, Value> {
+    let mut object = Map::new();
+    object.insert("@type".to_string(), json!("Event"));
+    if let Some(uid) = event
+        .ical_uid
+        .as_ref()
+        .or((!event.id.is_empty()).then_some(&event.id))
+    {
+        object.insert("uid".to_string(), json!(uid));
+    }
+    if let Some(sequence) = event.sequence {
+        object.insert("sequence".to_string(), json!(sequence));
+    }
+    let mut text = |name: &str, value: &Option<String>| {
+        if let Some(value) = value {
+            object.insert(name.to_string(), json!(value));
+        }
+    };
+    text("title", &event.title);
+    text("description", &event.description);
+    text("color", &event.color);
+    for (name, instant) in [("created", &event.created), ("updated", &event.updated)] {
+        if let Some(instant) = instant {
+            object.insert(
+                name.to_string(),
+                json!(instant
+                    .with_timezone(&Utc)
+                    .format(UTC_DATE_TIME_FORMAT)
+                    .to_string()),
+            );
+        }
+    }
+
+    object.insert(
+        "start".to_string(),
+        json!(local_in(&event.start, &event.start)),
+    );
+    if let Some(zone) = zone_name(&event.start) {
+        object.insert("timeZone".to_string(), json!(zone));
+    }
+    let duration = moment_span(event);
+    if event.is_all_day() {
+        object.insert("showWithoutTime".to_string(), json!(true));
+    }
+    if duration > Duration::zero() {
+        object.insert("duration".to_string(), json!(format_duration(duration)));
+    }
+
+    let mut locations = Map::new();
+    if let Some(location) = &event.location {
+        locations.insert(
+            "main".to_string(),
+            json!({ "@type": "Location", "name": location }),
+        );
+    }
+    let end_zone = zone_name(&event.end);
+    if !event.is_all_day() && end_zone != zone_name(&event.start) {
+        locations.insert(
+            "end".to_string(),
+            json!({ "@type": "Location", "relativeTo": "end", "timeZone": end_zone }),
+        );
+    }
+    if !locations.is_empty() {
+        object.insert("locations".to_string(), Value::Object(locations));
+    }
+    if let Some(ConferenceLink {
+        url: Some(url),
+        provider,
+    }) = &event.conference
+    {
+        let mut location = json!({ "@type": "VirtualLocation", "uri": url });
+        if let Some(provider) = provider {
+            location["name"] = json!(provider);
+        }
+        object.insert(
+            "virtualLocations".to_string(),
+            json!({ "conference": location }),
+        );
+    }
+
+    if let Some(categories) = &event.categories {
+        let keywords: Map<String, Value> = categories
+            .iter()
+            .map(|c| (c.clone(), json!(true)))
+            .collect();
+        object.insert("keywords".to_string(), Value::Object(keywords));
+    }
+    if let Some(status) = &event.status {
+        let status = match status {
+            EventStatus::Confirmed => "confirmed",
+            EventStatus::Tentative => "tentative",
+            EventStatus::Cancelled => "cancelled",
+        };
+        object.insert("status".to_string(), json!(status));
+    }
+    let privacy = match event.visibility {
+        Some(EventVisibility::Public) => Some("public"),
+        Some(EventVisibility::Private) => Some("private"),
+        Some(EventVisibility::Confidential) => Some("secret"),
+        Some(EventVisibility::Default) | None => None,
+    };
+    if let Some(privacy) = privacy {
+        object.insert("privacy".to_string(), json!(privacy));
+    }
+    if let Some(show_as) = &event.show_as {
+        let status = if matches!(show_as, ShowAs::Free) {
+            "free"
+        } else {
+            "busy"
+        };
+        object.insert("freeBusyStatus".to_string(), json!(status));
+    }
+
+    write_participants(&mut object, event);
+    if let Some(reminders) = event.reminders.as_ref().filter(|r| !r.is_empty()) {
+        let alerts: Map<String, Value> = reminders
+            .iter()
+            .enumerate()
+            .map(|(i, reminder)| (format!("alert-{}", i + 1), alert(reminder)))
+            .collect();
+        object.insert("alerts".to_string(), Value::Object(alerts));
+    }
+
+    // Properties kept from a previous read, e.g. `localizations`
+    if let Some(Value::Object(extra)) = event.raw.as_ref().and_then(|raw| raw.get(RAW_KEY)) {
+        for (name, value) in extra {
+            if !KNOWN_PROPERTIES.contains(&name.as_str()) {
+                object.insert(name.clone(), value.clone());
+            }
+        }
+    }
+    object
+}
+
+fn write_participants(object: &mut Map<String, Value>, event: &UnifiedCalendarEvent) {
+    let organizer_email = event
+        .organizer
+        .as_ref()
+        .and_then(|o| o.email.as_deref())
+        .map(str::to_lowercase);
+    let is_organizer = |participant: &Participant| {
+        organizer_email.is_some()
+            && participant.email.as_deref().map(str::to_lowercase) == organizer_email
+    };
+
+    // Organizer first, merged with its attendee entry
+    let attendees = event.attendees.as_deref().unwrap_or_default();
+    let mut entries: Vec<(&Participant, bool, bool)> = Vec::new();
+    if let Some(organizer) = &event.organizer {
+        match attendees.iter().find(|a| is_organizer(a)) {
+            Some(attendee) => entries.push((attendee, true, true)),
+            None => entries.push((organizer, true, false)),
+        }
+    }
+    entries.extend(
+        attendees
+            .iter()
+            .filter(|a| !is_organizer(a))
+            .map(|a| (a, false, true)),
+    );
+    if entries.is_empty() {
+        return;
+    }
+
+    let mut ids: Vec<String> = Vec::new();
+    for (index, (participant, _, _)) in entries.iter().enumerate() {
+        let id = participant
+            .id
+            .clone()
+            .filter(|id| is_valid_id(id) && !ids.contains(id))
+            .unwrap_or_else(|| format!("p{}", index + 1));
+        ids.push(id);
+    }
+    let id_of = |address: &str| {
+        let address = address.trim_start_matches("mailto:").to_lowercase();
+        entries
+            .iter()
+            .position(|(p, _, _)| {
+                p.email.as_deref().map(str::to_lowercase) == Some(address.clone())
+            })
+            .map(|i| (ids[i].clone(), json!(true)))
+    };
+
+    let mut participants = Map::new();
+    for ((participant, owner, attendee), id) in entries.iter().zip(&ids) {
+        let mut entry = json!({ "@type": "Participant" });
+        if let Some(name) = &participant.name {
+            entry["name"] = json!(name);
+        }
+        if let Some(email) = &participant.email {
+            entry["email"] = json!(email);
+            entry["sendTo"] = json!({ "imip": format!("mailto:{}", email) });
+        }
+        let kind = match participant.user_type {
+            Some(CalendarUserType::Individual) => Some("individual"),
+            Some(CalendarUserType::Group) => Some("group"),
+            Some(CalendarUserType::Resource) => Some("resource"),
+            Some(CalendarUserType::Room) => Some("location"),
+            Some(CalendarUserType::Unknown) => None,
+            None => participant.resource.unwrap_or(false).then_some("resource"),
+        };
+        if let Some(kind) = kind {
+            entry["kind"] = json!(kind);
+        }
+
+        let mut roles = Map::new();
+        let role = participant.role;
+        for (name, has) in [
+            ("owner", *owner),
+            ("attendee", *attendee),
+            ("chair", role == Some(ParticipantRole::Chair)),
+            (
+                "optional",
+                role == Some(ParticipantRole::Optional) || participant.optional == Some(true),
+            ),
+            (
+                "informational",
+                role == Some(ParticipantRole::NonParticipant),
+            ),
+        ] {
+            if has {
+                roles.insert(name.to_string(), json!(true));
+            }
+        }
+        entry["roles"] = Value::Object(roles);
+
+        if *attendee {
+            if let Some(status) = &participant.response_status {
+                entry["participationStatus"] = json!(match status {
+                    ParticipantStatus::Accepted => "accepted",
+                    ParticipantStatus::Tentative => "tentative",
+                    ParticipantStatus::Declined => "declined",
+                    ParticipantStatus::NeedsAction => "needs-action",
+                });
+            }
+            if let Some(comment) = &participant.comment {
+                entry["participationComment"] = json!(comment);
+            }
+            for (name, addresses) in [
+                ("delegatedTo", &participant.delegated_to),
+                ("delegatedFrom", &participant.delegated_from),
+            ] {
+                let delegates: Map<String, Value> = addresses
+                    .iter()
+                    .flatten()
+                    .filter_map(|address| id_of(address))
+                    .collect();
+                if !delegates.is_empty() {
+                    entry[name] = Value::Object(delegates);
+                }
+            }
+        }
+        participants.insert(id.clone(), entry);
+    }
+    object.insert("participants".to_string(), Value::Object(participants));
+    if let Some(email) = event.organizer.as_ref().and_then(|o| o.email.as_ref()) {
+        object.insert(
+            "replyTo".to_string(),
+            json!({ "imip": format!("mailto:{}", email) }),
+        );
+    }
+}
+
+/// JSCalendar `Id`: 1-255 characters from the base64url alphabet
+fn is_valid_id(id: &str) -> bool {
+    (1..=255).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn alert(reminder: &Reminder) -> Value {
+    let action = match reminder.method {
+        Some(ReminderMethod::Email) => "email",
+        _ => "display",
+    };
+    json!({
+        "@type": "Alert",
+        "trigger": {
+            "@type": "OffsetTrigger",
+            "offset": format_duration(-Duration::minutes(i64::from(reminder.minutes_before))),
+        },
+        "action": action,
+    })
+}
+
+/// Add `recurrenceRules` and `recurrenceOverrides` for a series
+fn add_recurrence<'a>(
+    object: &mut Map<String, Value>,
+    master: &UnifiedCalendarEvent,
+    exceptions: impl Iterator<Item = &'a UnifiedCalendarEvent>,
+) {
+    let pattern = RecurrencePattern::from_event(master)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    if !pattern.rules.is_empty() {
+        let rules = pattern
+            .rules
+            .iter()
+            .map(|rule| rule_object(rule, &master.start))
+            .collect();
+        object.insert("recurrenceRules".to_string(), Value::Array(rules));
+    }
+
+    let mut overrides = Map::new();
+    for rdate in &pattern.rdates {
+        overrides.insert(date_key(rdate, &master.start), json!({}));
+    }
+    for exdate in &pattern.exdates {
+        overrides.insert(date_key(exdate, &master.start), json!({ "excluded": true }));
+    }
+    let base = event_object(master);
+    for exception in exceptions {
+        let Some(original_start) = &exception.original_start else {
+            continue;
+        };
+        let changed = event_object(exception);
+        let mut patch = Map::new();
+        for name in base.keys().chain(changed.keys()) {
+            if SERIES_PROPERTIES.contains(&name.as_str()) || patch.contains_key(name) {
+                continue;
+            }
+            match changed.get(name) {
+                Some(value) if base.get(name) != Some(value) => {
+                    patch.insert(name.clone(), value.clone());
+                }
+                Some(_) => {}
+                None => {
+                    patch.insert(name.clone(), Value::Null);
+                }
+            }
+        }
+        // Always name the start, so the patch is never mistaken for an
+        // added date
+        patch.insert("start".to_string(), changed["start"].clone());
+        overrides.insert(
+            local_in(original_start, &master.start),
+            Value::Object(patch),
+        );
+    }
+    if !overrides.is_empty() {
+        object.insert("recurrenceOverrides".to_string(), Value::Object(overrides));
+    }
+}
+
+fn rule_object(rule: &RecurrenceRule, start: &EventMoment) -> Value {
+    let mut object = json!({
+        "@type": "RecurrenceRule",
+        "frequency": format!("{:?}", rule.frequency).to_lowercase(),
+    });
+    if rule.interval != 1 {
+        object["interval"] = json!(rule.interval);
+    }
+    if let Some(count) = rule.count {
+        object["count"] = json!(count);
+    }
+    if let Some(until) = &rule.until {
+        let until = match until {
+            RecurrenceUntil::Date(date) if start.is_all_day() => date.and_time(NaiveTime::MIN),
+            RecurrenceUntil::Date(date) => date.and_hms_opt(23, 59, 59).unwrap_or_default(),
+            RecurrenceUntil::DateTime(instant) => {
+                wall_clock(instant.fixed_offset(), zone_name(start).as_deref())
+            }
+            RecurrenceUntil::Floating(local) => *local,
+        };
+        object["until"] = json!(until.format(LOCAL_DATE_TIME_FORMAT).to_string());
+    }
+    if rule.week_start != chrono::Weekday::Mon {
+        object["firstDayOfWeek"] = json!(weekday_code(rule.week_start).to_ascii_lowercase());
+    }
+    if !rule.by_day.is_empty() {
+        let days: Vec<Value> = rule
+            .by_day
+            .iter()
+            .map(|day| {
+                let mut nday = json!({
+                    "@type": "NDay",
+                    "day": weekday_code(day.weekday).to_ascii_lowercase(),
+                });
+                if let Some(ordinal) = day.ordinal {
+                    nday["nthOfPeriod"] = json!(ordinal);
+                }
+                nday
+            })
+            .collect();
+        object["byDay"] = Value::Array(days);
+    }
+    if !rule.by_month.is_empty() {
+        let months: Vec<String> = rule.by_month.iter().map(u8::to_string).collect();
+        object["byMonth"] = json!(months);
+    }
+    let mut list = |name: &str, values: Vec<i64>| {
+        if !values.is_empty() {
+            object[name] = json!(values);
+        }
+    };
+    list(
+        "byMonthDay",
+        rule.by_month_day.iter().map(|&v| v.into()).collect(),
+    );
+    list(
+        "byYearDay",
+        rule.by_year_day.iter().map(|&v| v.into()).collect(),
+    );
+    list(
+        "byWeekNo",
+        rule.by_week_no.iter().map(|&v| v.into()).collect(),
+    );
+    list("byHour", rule.by_hour.iter().map(|&v| v.into()).collect());
+    list(
+        "byMinute",
+        rule.by_minute.iter().map(|&v| v.into()).collect(),
+    );
+    list(
+        "bySecond",
+        rule.by_second.iter().map(|&v| v.into()).collect(),
+    );
+    list(
+        "bySetPosition",
+        rule.by_set_pos.iter().map(|&v| v.into()).collect(),
+    );
+    object
+}
+
+/// Override key of an `RDATE` or `EXDATE` value, on the wall clock of `start`
+fn date_key(date: &RecurrenceDate, start: &EventMoment) -> String {
+    let zone = zone_name(start);
+    let local = match date {
+        RecurrenceDate::Date(date) => date.and_time(NaiveTime::MIN),
+        RecurrenceDate::Utc(instant) => wall_clock(instant.fixed_offset(), zone.as_deref()),
+        RecurrenceDate::Local { date_time, tzid } => {
+            match tzid
+                .as_deref()
+                .filter(|tzid| Some(*tzid) != zone.as_deref())
+            {
+                Some(other) => match parse_time_zone(other) {
+                    Ok(tz) => wall_clock(resolve_local(tz, *date_time), zone.as_deref()),
+                    Err(_) => *date_time,
+                },
+                None => *date_time,
+            }
+        }
+    };
+    local.format(LOCAL_DATE_TIME_FORMAT).to_string()
+}
+
+/// `moment` as a `LocalDateTime` on the wall clock of `reference`
+fn local_in(moment: &EventMoment, reference: &EventMoment) -> String {
+    let local = match moment {
+        EventMoment::Date { date, .. } => date.and_time(NaiveTime::MIN),
+        EventMoment::DateTime { date_time, .. } => {
+            wall_clock(*date_time, zone_name(reference).as_deref())
+        }
+    };
+    local.format(LOCAL_DATE_TIME_FORMAT).to_string()
+}
+
+/// JSCalendar `timeZone` of a moment; timed moments without one are in UTC
+fn zone_name(moment: &EventMoment) -> Option<String> {
+    match moment {
+        EventMoment::Date { time_zone, .. } => time_zone.clone(),
+        EventMoment::DateTime { time_zone, .. } => {
+            Some(time_zone.clone().unwrap_or_else(|| UTC_ZONE.to_string()))
+        }
+    }
+}
+
+fn wall_clock(instant: DateTime<FixedOffset>, zone: Option<&str>) -> NaiveDateTime {
+    match zone.and_then(|zone| parse_time_zone(zone).ok()) {
+        Some(tz) => instant.with_timezone(&tz).naive_local(),
+        None => instant.naive_utc(),
+    }
+}
+
+fn read_event(
+    object: &Map<String, Value>,
+    options: &ReadOptions,
+    events: &mut Vec<UnifiedCalendarEvent>,
+) -> Result<()> {
+    let uid = object
+        .get("uid")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let fail = |error: ConversionError| error.for_item(uid.clone());
+    let Some(uid) = uid.clone() else {
+        return Err(ConversionError::missing("uid").into());
+    };
+
+    let mut localized;
+    let mut source = object;
+    if let Some(patch) = options
+        .locale
+        .as_deref()
+        .and_then(|l| localization(object, l))
+    {
+        localized = object.clone();
+        apply_patch(&mut localized, patch);
+        source = &localized;
+    }
+    let reader = EventReader::new(source, options).map_err(fail)?;
+    let mut master = reader.event(source, options).map_err(fail)?;
+    master.ical_uid = Some(uid.clone());
+
+    let mut pattern = RecurrencePattern::default();
+    if let Some(rules) = source.get("recurrenceRules").and_then(Value::as_array) {
+        for (index, rule) in rules.iter().enumerate() {
+            pattern.rules.push(
+                reader
+                    .rule(rule, &format!("recurrenceRules[{}]", index))
+                    .map_err(fail)?,
+            );
+        }
+    }
+
+    let mut exceptions = Vec::new();
+    if let Some(overrides) = source.get("recurrenceOverrides").and_then(Value::as_object) {
+        for (key, patch) in overrides {
+            let field = format!("recurrenceOverrides[{}]", key);
+            let local = reader.local(key, &field).map_err(fail)?;
+            let patch = patch.as_object().ok_or_else(|| {
+                fail(ConversionError::invalid(
+                    &field,
+                    patch.to_string(),
+                    "expected an object",
+                ))
+            })?;
+            if patch.get("excluded") == Some(&Value::Bool(true)) {
+                pattern.exdates.push(reader.recurrence_date(local));
+            } else if patch.is_empty() {
+                pattern.rdates.push(reader.recurrence_date(local));
+            } else {
+                let mut instance = source.clone();
+                for name in SERIES_PROPERTIES {
+                    instance.remove(name);
+                }
+                instance.insert(
+                    "start".to_string(),
+                    json!(local.format(LOCAL_DATE_TIME_FORMAT).to_string()),
+                );
+                apply_patch(&mut instance, patch);
+                let instance_reader = EventReader::new(&instance, options).map_err(fail)?;
+                let mut exception = instance_reader.event(&instance, options).map_err(fail)?;
+                exception.ical_uid = Some(uid.clone());
+                exception.series_id = Some(uid.clone());
+                exception.original_start = Some(reader.moment(local));
+                exceptions.push(exception);
+            }
+        }
+    }
+    if !pattern.rules.is_empty() || !pattern.rdates.is_empty() {
+        master.set_recurrence(Some(pattern));
+    }
+    events.push(master);
+    events.append(&mut exceptions);
+    Ok(())
+}
+
+/// The `localizations` patch for a language tag, falling back to shorter
+/// tags: `de-AT` uses `de` when there is no `de-AT` entry
+fn localization<'a>(
+    object: &'a Map<String, Value>,
+    locale: &str,
+) -> Option<&'a Map<String, Value>> {
+    let localizations = object.get("localizations")?.as_object()?;
+    let mut tag = locale;
+    loop {
+        let patch = localizations
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(tag))
+            .and_then(|(_, patch)| patch.as_object());
+        if patch.is_some() {
+            return patch;
+        }
+        tag = &tag[..tag.rfind('-')?];
+    }
+}
+
+/// Apply a JSCalendar `PatchObject`: keys are JSON pointers, `null` removes
+fn apply_patch(target: &mut Map<String, Value>, patch: &Map<String, Value>) {
+    for (pointer, value) in patch {
+        let segments: Vec<String> = pointer
+            .trim_start_matches('/')
+            .split('/')
+            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        set_pointer(target, &segments, value);
+    }
+}
+
+fn set_pointer(target: &mut Map<String, Value>, segments: &[String], value: &Value) {
+    match segments {
+        [] => {}
+        [last] if value.is_null() => {
+            target.remove(last);
+        }
+        [last] => {
+            target.insert(last.clone(), value.clone());
+        }
+        [first, rest @ ..] => {
+            let child = target
+                .entry(first.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !child.is_object() {
+                *child = Value::Object(Map::new());
+            }
+            if let Value::Object(child) = child {
+                set_pointer(child, rest, value);
+            }
+        }
+    }
+}
+
+/// Reads one event object, resolving its local times
+struct EventReader {
+    all_day: bool,
+    /// `timeZone` as written
+    time_zone: Option<String>,
+    /// Zone local times resolve in; `None` for UTC
+    zone: Option<(String, Tz)>,
+}
+
+type ReadResult<T> = std::result::Result<T, ConversionError>;
+
+impl EventReader {
+    fn new(object: &Map<String, Value>, options: &ReadOptions) -> ReadResult<Self> {
+        let all_day = object.get("showWithoutTime") == Some(&Value::Bool(true));
+        let time_zone = match object.get("timeZone") {
+            Some(Value::String(zone)) => Some(zone.clone()),
+            None | Some(Value::Null) => None,
+            Some(other) => {
+                return Err(ConversionError::invalid(
+                    "timeZone",
+                    other.to_string(),
+                    "expected a string",
+                ))
+            }
+        };
+        let zone_name = time_zone
+            .clone()
+            .or_else(|| options.default_time_zone.clone().filter(|_| !all_day));
+        let zone = match zone_name {
+            Some(name) if matches!(name.as_str(), "Etc/UTC" | "UTC" | "Etc/GMT") => None,
+            Some(name) => {
+                let tz = parse_time_zone(&name)
+                    .map_err(|e| ConversionError::invalid("timeZone", name.as_str(), e))?;
+                Some((name, tz))
+            }
+            None => None,
+        };
+        Ok(Self {
+            all_day,
+            time_zone,
+            zone,
+        })
+    }
+
+    fn local(&self, value: &str, field: &str) -> ReadResult<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(value, LOCAL_DATE_TIME_FORMAT)
+            .map_err(|e| ConversionError::invalid(field, value, e))
+    }
+
+    fn moment(&self, local: NaiveDateTime) -> EventMoment {
+        if self.all_day {
+            return EventMoment::Date {
+                date: local.date(),
+                time_zone: self.time_zone.clone(),
+            };
+        }
+        match &self.zone {
+            Some((name, tz)) => EventMoment::timed(resolve_local(*tz, local), Some(name.clone())),
+            None => EventMoment::timed(local.and_utc().fixed_offset(), None),
+        }
+    }
+
+    fn recurrence_date(&self, local: NaiveDateTime) -> RecurrenceDate {
+        match (&self.zone, self.all_day) {
+            (_, true) => RecurrenceDate::Date(local.date()),
+            (Some((name, _)), false) => RecurrenceDate::Local {
+                date_time: local,
+                tzid: Some(name.clone()),
+            },
+            (None, false) => RecurrenceDate::Utc(local.and_utc()),
+        }
+    }
+
+    fn event(
+        &self,
+        object: &Map<String, Value>,
+        options: &ReadOptions,
+    ) -> ReadResult<UnifiedCalendarEvent> {
+        let text = |name: &str| object.get(name).and_then(Value::as_str).map(str::to_string);
+        let start = text("start").ok_or_else(|| ConversionError::missing("start"))?;
+        let start_local = self.local(&start, "start")?;
+        let start = self.moment(start_local);
+
+        let duration = match text("duration") {
+            Some(value) => parse_duration(&value)
+                .filter(|d| *d >= Duration::zero())
+                .ok_or_else(|| ConversionError::invalid("duration", value, "not a duration"))?,
+            None => Duration::zero(),
+        };
+        let out_of_range =
+            || ConversionError::invalid("duration", format_duration(duration), "out of range");
+        let locations = object.get("locations").and_then(Value::as_object);
+        let end = match &start {
+            EventMoment::Date { date, time_zone } => EventMoment::Date {
+                date: Duration::try_days(duration.num_days().max(1))
+                    .and_then(|days| date.checked_add_signed(days))
+                    .ok_or_else(out_of_range)?,
+                time_zone: time_zone.clone(),
+            },
+            EventMoment::DateTime {
+                date_time,
+                time_zone,
+            } => {
+                let end_zone = locations
+                    .into_iter()
+                    .flat_map(|l| l.values())
+                    .filter(|l| l.get("relativeTo").and_then(Value::as_str) == Some("end"))
+                    .find_map(|l| l.get("timeZone").and_then(Value::as_str));
+                let end = date_time
+                    .checked_add_signed(duration)
+                    .ok_or_else(out_of_range)?;
+                match end_zone {
+                    Some(zone) if Some(zone) != time_zone.as_deref() => {
+                        let tz = parse_time_zone(zone)
+                            .map_err(|e| ConversionError::invalid("locations.timeZone", zone, e))?;
+                        let utc = matches!(zone, "Etc/UTC" | "UTC" | "Etc/GMT");
+                        EventMoment::timed(
+                            end.with_timezone(&tz).fixed_offset(),
+                            (!utc).then(|| zone.to_string()),
+                        )
+                    }
+                    _ => EventMoment::timed(end, time_zone.clone()),
+                }
+            }
+        };
+
+        let mut event = UnifiedCalendarEvent::new(String::new(), options.source, start, end);
+        event.title = text("title");
+        event.description = text("description");
+        event.color = text("color");
+        event.sequence = object
+            .get("sequence")
+            .and_then(Value::as_u64)
+            .and_then(|s| u32::try_from(s).ok());
+        event.created = self.instant(object, "created")?;
+        event.updated = self.instant(object, "updated")?;
+        if let Some(recurrence_id) = text("recurrenceId") {
+            let local = self.local(&recurrence_id, "recurrenceId")?;
+            event.original_start = Some(self.moment(local));
+            event.series_id = text("uid");
+        }
+
+        event.location = locations
+            .into_iter()
+            .flat_map(|l| l.values())
+            .find_map(|l| l.get("name").and_then(Value::as_str))
+            .map(str::to_string);
+        event.conference = object
+            .get("virtualLocations")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flat_map(|l| l.values())
+            .find_map(|l| {
+                Some(ConferenceLink {
+                    url: Some(l.get("uri")?.as_str()?.to_string()),
+                    provider: l.get("name").and_then(Value::as_str).map(str::to_string),
+                })
+            });
+        event.categories = object
+            .get("keywords")
+            .and_then(Value::as_object)
+            .map(|keywords| {
+                keywords
+                    .iter()
+                    .filter(|(_, set)| set.as_bool() == Some(true))
+                    .map(|(keyword, _)| keyword.clone())
+                    .collect()
+            });
+        event.status = text("status").and_then(|status| match status.as_str() {
+            "confirmed" => Some(EventStatus::Confirmed),
+            "tentative" => Some(EventStatus::Tentative),
+            "cancelled" => Some(EventStatus::Cancelled),
+            _ => None,
+        });
+        event.visibility = text("privacy").map(|privacy| match privacy.as_str() {
+            "public" => EventVisibility::Public,
+            "private" => EventVisibility::Private,
+            "secret" => EventVisibility::Confidential,
+            _ => EventVisibility::Default,
+        });
+        event.show_as = text("freeBusyStatus").map(|status| match status.as_str() {
+            "free" => ShowAs::Free,
+            _ => ShowAs::Busy,
+        });
+
+        self.participants(object, &mut event);
+        if let Some(alerts) = object.get("alerts").and_then(Value::as_object) {
+            event.reminders = Some(
+                alerts
+                    .values()
+                    .filter_map(|alert| self.reminder(alert, &event))
+                    .collect(),
+            );
+        }
+
+        let extra: Map<String, Value> = object
+            .iter()
+            .filter(|(name, _)| !KNOWN_PROPERTIES.contains(&name.as_str()))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        if !extra.is_empty() {
+            event.raw = Some(json!({ RAW_KEY: extra }));
+        }
+        Ok(event)
+    }
+
+    fn instant(
+        &self,
+        object: &Map<String, Value>,
+        name: &str,
+    ) -> ReadResult<Option<DateTime<FixedOffset>>> {
+        object
+            .get(name)
+            .and_then(Value::as_str)
+            .map(|value| {
+                DateTime::parse_from_rfc3339(value)
+                    .map_err(|e| ConversionError::invalid(name, value, e))
+            })
+            .transpose()
+    }
+
+    fn participants(&self, object: &Map<String, Value>, event: &mut UnifiedCalendarEvent) {
+        let Some(participants) = object.get("participants").and_then(Value::as_object) else {
+            return;
+        };
+        let email_of = |entry: &Value| {
+            entry
+                .get("email")
+                .and_then(Value::as_str)
+                .or_else(|| {
+                    entry
+                        .get("sendTo")?
+                        .get("imip")?
+                        .as_str()
+                        .map(|uri| uri.trim_start_matches("mailto:"))
+                })
+                .map(str::to_string)
+        };
+        let addresses = |entry: &Value, name: &str| -> Option<Vec<String>> {
+            let ids = entry.get(name)?.as_object()?;
+            let addresses: Vec<String> = ids
+                .keys()
+                .filter_map(|id| participants.get(id).and_then(email_of))
+                .collect();
+            (!addresses.is_empty()).then_some(addresses)
+        };
+
+        let mut attendees = Vec::new();
+        for (id, entry) in participants {
+            let has_role = |role: &str| {
+                entry
+                    .get("roles")
+                    .and_then(|roles| roles.get(role))
+                    .and_then(Value::as_bool)
+                    == Some(true)
+            };
+            let name = entry
+                .get("name")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if has_role("owner") {
+                event.organizer = Some(Participant {
+                    id: Some(id.clone()),
+                    email: email_of(entry),
+                    name: name.clone(),
+                    organizer: Some(true),
+                    ..Default::default()
+                });
+            }
+            if !has_role("attendee") && has_role("owner") {
+                continue;
+            }
+            let user_type = match entry.get("kind").and_then(Value::as_str) {
+                Some("individual") => Some(CalendarUserType::Individual),
+                Some("group") => Some(CalendarUserType::Group),
+                Some("resource") => Some(CalendarUserType::Resource),
+                Some("location") => Some(CalendarUserType::Room),
+                Some(_) => Some(CalendarUserType::Unknown),
+                None => None,
+            };
+            let role = if has_role("chair") {
+                Some(ParticipantRole::Chair)
+            } else if has_role("optional") {
+                Some(ParticipantRole::Optional)
+            } else if has_role("informational") {
+                Some(ParticipantRole::NonParticipant)
+            } else {
+                None
+            };
+            let status = entry.get("participationStatus").and_then(Value::as_str);
+            attendees.push(Participant {
+                id: Some(id.clone()),
+                email: email_of(entry),
+                name,
+                optional: has_role("optional").then_some(true),
+                response_status: status.map(|status| match status {
+                    "accepted" => ParticipantStatus::Accepted,
+                    "tentative" => ParticipantStatus::Tentative,
+                    "declined" => ParticipantStatus::Declined,
+                    _ => ParticipantStatus::NeedsAction,
+                }),
+                resource: matches!(
+                    user_type,
+                    Some(CalendarUserType::Resource | CalendarUserType::Room)
+                )
+                .then_some(true),
+                organizer: has_role("owner").then_some(true),
+                role,
+                user_type,
+                delegated_to: addresses(entry, "delegatedTo"),
+                delegated_from: addresses(entry, "delegatedFrom"),
+                comment: entry
+                    .get("participationComment")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                ..Default::default()
+            });
+        }
+        if event.organizer.is_none() {
+            event.organizer = object
+                .get("replyTo")
+                .and_then(|reply_to| reply_to.get("imip"))
+                .and_then(Value::as_str)
+                .map(|uri| Participant {
+                    email: Some(uri.trim_start_matches("mailto:").to_string()),
+                    organizer: Some(true),
+                    ..Default::default()
+                });
+        }
+        if !attendees.is_empty() {
+            event.attendees = Some(attendees);
+        }
+    }
+
+    /// Reminder for an alert; alerts with triggers that cannot be expressed
+    /// as minutes before the start are skipped
+    fn reminder(&self, alert: &Value, event: &UnifiedCalendarEvent) -> Option<Reminder> {
+        let trigger = alert.get("trigger")?;
+        let before = match trigger.get("@type").and_then(Value::as_str) {
+            Some("OffsetTrigger") => {
+                let offset = parse_duration(trigger.get("offset")?.as_str()?)?;
+                let offset = match trigger.get("relativeTo").and_then(Value::as_str) {
+                    Some("end") => offset.checked_add(&moment_span(event))?,
+                    _ => offset,
+                };
+                -offset
+            }
+            Some("AbsoluteTrigger") => {
+                let when = DateTime::parse_from_rfc3339(trigger.get("when")?.as_str()?).ok()?;
+                event.start.date_time()? - when
+            }
+            _ => return None,
+        };
+        let method = match alert.get("action").and_then(Value::as_str) {
+            Some("email") => ReminderMethod::Email,
+            _ => ReminderMethod::Popup,
+        };
+        Some(Reminder {
+            minutes_before: i32::try_from(before.num_minutes()).ok()?,
+            method: Some(method),
+        })
+    }
+
+    fn rule(&self, value: &Value, field: &str) -> ReadResult<RecurrenceRule> {
+        let invalid = |part: &str, value: &Value, reason: &str| {
+            ConversionError::invalid(format!("{}.{}", field, part), value.to_string(), reason)
+        };
+        let frequency = value
+            .get("frequency")
+            .ok_or_else(|| ConversionError::missing(format!("{}.frequency", field)))?;
+        let mut rule = RecurrenceRule::new(
+            frequency
+                .as_str()
+                .and_then(|f| f.parse::<Frequency>().ok())
+                .ok_or_else(|| invalid("frequency", frequency, "unknown frequency"))?,
+        );
+        if let Some(rscale) = value.get("rscale") {
+            if rscale.as_str() != Some("gregorian") {
+                return Err(invalid("rscale", rscale, "only gregorian is supported"));
+            }
+        }
+        if let Some(skip) = value.get("skip") {
+            if skip.as_str() != Some("omit") {
+                return Err(invalid("skip", skip, "only omit is supported"));
+            }
+        }
+        if let Some(interval) = value.get("interval") {
+            rule.interval = interval
+                .as_u64()
+                .and_then(|i| u32::try_from(i).ok())
+                .ok_or_else(|| invalid("interval", interval, "expected a positive number"))?;
+        }
+        if let Some(count) = value.get("count") {
+            rule.count = Some(
+                count
+                    .as_u64()
+                    .and_then(|c| u32::try_from(c).ok())
+                    .ok_or_else(|| invalid("count", count, "expected a positive number"))?,
+            );
+        }
+        if let Some(until) = value.get("until") {
+            let local = until
+                .as_str()
+                .and_then(|u| NaiveDateTime::parse_from_str(u, LOCAL_DATE_TIME_FORMAT).ok())
+                .ok_or_else(|| invalid("until", until, "expected a LocalDateTime"))?;
+            rule.until = Some(match (&self.zone, self.all_day) {
+                (_, true) => RecurrenceUntil::Date(local.date()),
+                (Some((_, tz)), false) => {
+                    RecurrenceUntil::DateTime(resolve_local(*tz, local).with_timezone(&Utc))
+                }
+                (None, false) => RecurrenceUntil::DateTime(local.and_utc()),
+            });
+        }
+        let weekday = |value: &Value| {
+            value
+                .as_str()
+                .and_then(|day| day.to_ascii_uppercase().parse::<WeekdayNum>().ok())
+                .filter(|day| day.ordinal.is_none())
+                .map(|day| day.weekday)
+        };
+        if let Some(first) = value.get("firstDayOfWeek") {
+            rule.week_start =
+                weekday(first).ok_or_else(|| invalid("firstDayOfWeek", first, "unknown day"))?;
+        }
+        if let Some(days) = value.get("byDay") {
+            rule.by_day = days
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|nday| {
+                    let day = nday
+                        .get("day")
+                        .and_then(weekday)
+                        .ok_or_else(|| invalid("byDay", nday, "unknown day"))?;
+                    let ordinal = match nday.get("nthOfPeriod") {
+                        Some(n) => Some(
+                            n.as_i64()
+                                .and_then(|n| i8::try_from(n).ok())
+                                .ok_or_else(|| invalid("byDay", nday, "invalid nthOfPeriod"))?,
+                        ),
+                        None => None,
+                    };
+                    Ok(WeekdayNum {
+                        ordinal,
+                        weekday: day,
+                    })
+                })
+                .collect::<ReadResult<_>>()?;
+        }
+        if let Some(months) = value.get("byMonth") {
+            rule.by_month = months
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|month| {
+                    month
+                        .as_str()
+                        .and_then(|m| m.parse().ok())
+                        .ok_or_else(|| invalid("byMonth", month, "leap months are not supported"))
+                })
+                .collect::<ReadResult<_>>()?;
+        }
+        fn numbers<T: TryFrom<i64>>(value: &Value, name: &str) -> Option<ReadResult<Vec<T>>> {
+            let list = value.get(name)?;
+            Some(
+                list.as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|n| {
+                        n.as_i64().and_then(|n| T::try_from(n).ok()).ok_or_else(|| {
+                            ConversionError::invalid(name, list.to_string(), "number out of range")
+                        })
+                    })
+                    .collect(),
+            )
+        }
+        if let Some(values) = numbers(value, "byMonthDay") {
+            rule.by_month_day = values?;
+        }
+        if let Some(values) = numbers(value, "byYearDay") {
+            rule.by_year_day = values?;
+        }
+        if let Some(values) = numbers(value, "byWeekNo") {
+            rule.by_week_no = values?;
+        }
+        if let Some(values) = numbers(value, "byHour") {
+            rule.by_hour = values?;
+        }
+        if let Some(values) = numbers(value, "byMinute") {
+            rule.by_minute = values?;
+        }
+        if let Some(values) = numbers(value, "bySecond") {
+            rule.by_second = values?;
+        }
+        if let Some(values) = numbers(value, "bySetPosition") {
+            rule.by_set_pos = values?;
+        }
+        rule.validate()
+            .map_err(|e| ConversionError::invalid(field, value.to_string(), e))?;
+        Ok(rule)
+    }
+}
+
+/// Length of an event, zero for timed events without a known instant
+fn moment_span(event: &UnifiedCalendarEvent) -> Duration {
+    match (&event.start, &event.end) {
+        (EventMoment::Date { date: start, .. }, end) => end.date().signed_duration_since(*start),
+        (start, end) => match (start.date_time(), end.date_time()) {
+            (Some(start), Some(end)) => end - start,
+            _ => Duration::zero(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn berlin(s: &str) -> EventMoment {
+        EventMoment::timed(
+            DateTime::parse_from_rfc3339(s).unwrap(),
+            Some("Europe/Berlin".to_string()),
+        )
+    }
+
+    fn person(id: &str, email: &str) -> Participant {
+        Participant {
+            id: Some(id.to_string()),
+            email: Some(email.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn standup() -> UnifiedCalendarEvent {
+        let mut event = UnifiedCalendarEvent::new(
+            String::new(),
+            CalendarSource::Google,
+            berlin("2024-03-11T09:00:00+01:00"),
+            berlin("2024-03-11T09:30:00+01:00"),
+        );
+        event.ical_uid = Some("standup@example.com".to_string());
+        event.sequence = Some(2);
+        event.title = Some("Stand-up".to_string());
+        event.location = Some("Room 1".to_string());
+        event.categories = Some(vec!["Team".to_string()]);
+        event.status = Some(EventStatus::Confirmed);
+        event.visibility = Some(EventVisibility::Private);
+        event.show_as = Some(ShowAs::Busy);
+        event.conference = Some(ConferenceLink {
+            url: Some("https://meet.example.com/abc".to_string()),
+            provider: Some("Meet".to_string()),
+        });
+        event.reminders = Some(vec![Reminder {
+            minutes_before: 10,
+            method: Some(ReminderMethod::Popup),
+        }]);
+        event.set_recurrence(Some(
+            RecurrencePattern::parse([
+                "RRULE:FREQ=MONTHLY;BYDAY=-1FR;UNTIL=20241231T230000Z",
+                "EXDATE;TZID=Europe/Berlin:20240329T090000",
+            ])
+            .unwrap(),
+        ));
+        event.organizer = Some(Participant {
+            name: Some("Ada".to_string()),
+            organizer: Some(true),
+            ..person("ada", "ada@example.com")
+        });
+        event.attendees = Some(vec![
+            Participant {
+                name: Some("Ada".to_string()),
+                organizer: Some(true),
+                role: Some(ParticipantRole::Chair),
+                response_status: Some(ParticipantStatus::Accepted),
+                ..person("ada", "ada@example.com")
+            },
+            Participant {
+                optional: Some(true),
+                role: Some(ParticipantRole::Optional),
+                response_status: Some(ParticipantStatus::Declined),
+                delegated_to: Some(vec!["cy@example.com".to_string()]),
+                comment: Some("Away".to_string()),
+                ..person("bob", "bob@example.com")
+            },
+            Participant {
+                response_status: Some(ParticipantStatus::NeedsAction),
+                delegated_from: Some(vec!["bob@example.com".to_string()]),
+                ..person("cy", "cy@example.com")
+            },
+        ]);
+        event
+    }
+
+    fn moved_occurrence() -> UnifiedCalendarEvent {
+        let mut exception = standup();
+        exception.set_recurrence(None);
+        exception.series_id = exception.ical_uid.clone();
+        exception.original_start = Some(berlin("2024-04-26T09:00:00+02:00"));
+        exception.start = berlin("2024-04-25T10:00:00+02:00");
+        exception.end = berlin("2024-04-25T10:30:00+02:00");
+        exception.location = None;
+        exception
+    }
+
+    #[test]
+    fn test_event_to_jscalendar() {
+        let objects = write_jscalendar(&[standup(), moved_occurrence()]);
+        assert_eq!(objects.len(), 1);
+        let event = &objects[0];
+        assert_eq!(event["@type"], "Event");
+        assert_eq!(event["uid"], "standup@example.com");
+        assert_eq!(event["start"], "2024-03-11T09:00:00");
+        assert_eq!(event["timeZone"], "Europe/Berlin");
+        assert_eq!(event["duration"], "PT30M");
+        assert_eq!(event["privacy"], "private");
+        assert_eq!(event["keywords"], json!({ "Team": true }));
+        assert_eq!(event["locations"]["main"]["name"], "Room 1");
+        assert_eq!(
+            event["virtualLocations"]["conference"],
+            json!({ "@type": "VirtualLocation", "uri": "https://meet.example.com/abc", "name": "Meet" })
+        );
+        assert_eq!(
+            event["alerts"]["alert-1"]["trigger"],
+            json!({ "@type": "OffsetTrigger", "offset": "-PT10M" })
+        );
+        assert_eq!(
+            event["recurrenceRules"],
+            json!([{
+                "@type": "RecurrenceRule",
+                "frequency": "monthly",
+                "until": "2025-01-01T00:00:00",
+                "byDay": [{ "@type": "NDay", "day": "fr", "nthOfPeriod": -1 }],
+            }])
+        );
+        assert_eq!(
+            event["recurrenceOverrides"],
+            json!({
+                "2024-03-29T09:00:00": { "excluded": true },
+                "2024-04-26T09:00:00": {
+                    "start": "2024-04-25T10:00:00",
+                    "locations": null,
+                },
+            })
+        );
+
+        let participants = &event["participants"];
+        assert_eq!(
+            participants["ada"]["roles"],
+            json!({ "owner": true, "attendee": true, "chair": true })
+        );
+        assert_eq!(
+            participants["ada"]["sendTo"]["imip"],
+            "mailto:ada@example.com"
+        );
+        assert_eq!(
+            participants["bob"]["roles"],
+            json!({ "attendee": true, "optional": true })
+        );
+        assert_eq!(participants["bob"]["participationStatus"], "declined");
+        assert_eq!(participants["bob"]["delegatedTo"], json!({ "cy": true }));
+        assert_eq!(participants["cy"]["delegatedFrom"], json!({ "bob": true }));
+        assert_eq!(
+            event["replyTo"],
+            json!({ "imip": "mailto:ada@example.com" })
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let events = [standup(), moved_occurrence()];
+        let objects = write_jscalendar(&events);
+        let read = read_jscalendar(
+            &Value::Array(objects),
+            &ReadOptions::new(CalendarSource::Google),
+        )
+        .unwrap();
+        assert_eq!(read.len(), 2);
+        for (before, after) in events.iter().zip(&read) {
+            let diff = crate::diff(before, after);
+            assert!(diff.is_empty(), "{:?}", diff);
+        }
+        assert_eq!(read[0].recurrence, events[0].recurrence);
+
+        let mut all_day = UnifiedCalendarEvent::new(
+            String::new(),
+            CalendarSource::Outlook,
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 7, 4).unwrap()),
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 7, 6).unwrap()),
+        );
+        all_day.ical_uid = Some("trip".to_string());
+        let object = &write_jscalendar(std::slice::from_ref(&all_day))[0];
+        assert_eq!(object["start"], "2024-07-04T00:00:00");
+        assert_eq!(object["showWithoutTime"], true);
+        assert_eq!(object["duration"], "P2D");
+        let read = read_jscalendar(object, &ReadOptions::new(CalendarSource::Outlook)).unwrap();
+        assert!(crate::diff(&all_day, &read[0]).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_properties_and_localizations() {
+        let object = json!({
+            "@type": "Event",
+            "uid": "talk",
+            "title": "Keynote",
+            "start": "2024-05-02T14:00:00",
+            "timeZone": "America/New_York",
+            "duration": "PT1H",
+            "locale": "en",
+            "priority": 1,
+            "example.com:track": "main",
+            "localizations": {
+                "de": { "title": "Eröffnungsrede", "locations/main/name": "Saal 1" },
+            },
+            "locations": { "main": { "@type": "Location", "name": "Hall 1" } },
+            "alerts": {
+                "a": {
+                    "@type": "Alert",
+                    "trigger": { "@type": "OffsetTrigger", "offset": "PT0S", "relativeTo": "end" },
+                    "action": "email",
+                },
+            },
+        });
+        let options = ReadOptions::new(CalendarSource::Google);
+        let event = &read_jscalendar(&object, &options).unwrap()[0];
+        assert_eq!(event.title.as_deref(), Some("Keynote"));
+        assert_eq!(
+            event.start.date_time().unwrap().to_rfc3339(),
+            "2024-05-02T14:00:00-04:00"
+        );
+        let reminders = event.reminders.as_ref().unwrap();
+        assert_eq!(reminders[0].minutes_before, -60);
+        assert!(matches!(reminders[0].method, Some(ReminderMethod::Email)));
+        assert_eq!(
+            event.raw.as_ref().unwrap()["jscalendar"]["example.com:track"],
+            "main"
+        );
+
+        // Unknown properties, localizations included, are written back as read
+        let written = Value::Object(event_object(event));
+        for name in [
+            "locale",
+            "priority",
+            "example.com:track",
+            "localizations",
+            "title",
+        ] {
+            assert_eq!(written[name], object[name], "{}", name);
+        }
+
+        let localized = &read_jscalendar(&object, &options.with_locale("de-AT")).unwrap()[0];
+        assert_eq!(localized.title.as_deref(), Some("Eröffnungsrede"));
+        assert_eq!(localized.location.as_deref(), Some("Saal 1"));
+    }
+
+    #[test]
+    fn test_groups_floating_times_and_errors() {
+        let group = json!({
+            "@type": "Group",
+            "entries": [
+                { "@type": "Task", "uid": "todo" },
+                { "@type": "Event", "uid": "a", "start": "2024-01-01T09:00:00" },
+            ],
+        });
+        let options = ReadOptions::new(CalendarSource::Google).with_default_time_zone("Asia/Tokyo");
+        let events = read_jscalendar(&group, &options).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].start.time_zone(), Some("Asia/Tokyo"));
+        assert_eq!(events[0].end, events[0].start);
+
+        let missing = read_jscalendar(
+            &json!({ "@type": "Event", "start": "2024-01-01T09:00:00" }),
+            &options,
+        )
+        .unwrap_err();
+        assert!(missing.to_string().contains("uid"), "{}", missing);
+        let bad_rule = json!({
+            "@type": "Event",
+            "uid": "b",
+            "start": "2024-01-01T09:00:00",
+            "recurrenceRules": [{ "@type": "RecurrenceRule", "frequency": "fortnightly" }],
+        });
+        let err = read_jscalendar(&bad_rule, &options).unwrap_err();
+        assert!(
+            err.to_string().contains("recurrenceRules[0].frequency"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_huge_durations_are_errors() {
+        let options = ReadOptions::new(CalendarSource::Google);
+        for (start, all_day) in [("2024-03-10T09:00:00", false), ("2024-03-10T00:00:00", true)] {
+            let event = json!({
+                "@type": "Event",
+                "uid": "huge",
+                "start": start,
+                "showWithoutTime": all_day,
+                "duration": "P99999999999D",
+            });
+            let err = read_jscalendar(&event, &options).unwrap_err();
+            assert!(err.to_string().contains("duration"), "{}", err);
+        }
+
+        // An offset from the end that overflows drops only the alert
+        let event = json!({
+            "@type": "Event",
+            "uid": "alert",
+            "start": "2024-03-10T09:00:00",
+            "duration": "P1D",
+            "alerts": {
+                "a": {
+                    "@type": "Alert",
+                    "trigger": {
+                        "@type": "OffsetTrigger",
+                        "offset": "P106751991167D",
+                        "relativeTo": "end",
+                    },
+                },
+            },
+        });
+        let events = read_jscalendar(&event, &options).unwrap();
+        assert_eq!(events[0].reminders.as_deref().map(<[_]>::len), Some(0));
+    }
+}
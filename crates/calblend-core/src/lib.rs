@@ -10,10 +10,25 @@ pub mod auth;
 pub mod sync;
 pub mod http;
 pub mod cache;
+pub mod recurrence;
+pub mod timezone;
+pub mod builder;
+pub mod diff;
+pub mod envelope;
+pub mod ical;
+pub mod jscalendar;
+pub mod csv;
+pub mod agenda;
+pub mod backup;
+mod validation;
 
 pub use models::*;
-pub use error::{CalblendError, Result};
+pub use error::{
+    CalblendError, ConversionError, Result, ValidationError, ValidationErrorKind, ValidationErrors,
+};
 pub use auth::TokenStorage;
+pub use builder::EventBuilder;
+pub use diff::{diff, merge, EventDiff, MergeResult};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -34,6 +49,22 @@ pub trait CalendarProvider: Send + Sync {
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
     ) -> Result<Vec<UnifiedCalendarEvent>>;
+
+    /// Get events with each recurring series as its master event and the
+    /// occurrences that were changed, rather than one event per occurrence
+    ///
+    /// Occurrences removed from a series are listed as events with `status`
+    /// [`EventStatus::Cancelled`] and their `original_start`. Providers that
+    /// only list occurrences return what [`list_events`](Self::list_events)
+    /// does.
+    async fn list_events_unexpanded(
+        &self,
+        calendar_id: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<UnifiedCalendarEvent>> {
+        self.list_events(calendar_id, start, end).await
+    }
     
     /// Create a new event
     async fn create_event(
@@ -41,6 +72,49 @@ pub trait CalendarProvider: Send + Sync {
         calendar_id: &str,
         event: UnifiedCalendarEvent,
     ) -> Result<UnifiedCalendarEvent>;
+
+    /// Create a new event, choosing whether attendees are notified
+    ///
+    /// Providers that cannot control notifications create the event as
+    /// [`create_event`](Self::create_event) does, and fail with
+    /// [`CalblendError::UnsupportedOperation`] when `notify` is false and
+    /// the event has attendees who might be notified.
+    async fn create_event_with_notifications(
+        &self,
+        calendar_id: &str,
+        event: UnifiedCalendarEvent,
+        notify: bool,
+    ) -> Result<UnifiedCalendarEvent> {
+        if !notify && event.attendees.as_ref().is_some_and(|a| !a.is_empty()) {
+            return Err(CalblendError::UnsupportedOperation(format!(
+                "{} cannot create events without notifying attendees",
+                self.name()
+            )));
+        }
+        self.create_event(calendar_id, event).await
+    }
+
+    /// Replace one occurrence of the series `series_id` with `event`,
+    /// choosing whether attendees are notified
+    ///
+    /// `event.original_start` says which occurrence; a `status` of
+    /// [`EventStatus::Cancelled`] removes it. Providers that cannot
+    /// address occurrences create `event` linked to the series by its
+    /// `series_id`, as
+    /// [`create_event_with_notifications`](Self::create_event_with_notifications)
+    /// does.
+    async fn update_occurrence(
+        &self,
+        calendar_id: &str,
+        series_id: &str,
+        event: UnifiedCalendarEvent,
+        notify: bool,
+    ) -> Result<UnifiedCalendarEvent> {
+        let mut event = event;
+        event.series_id = Some(series_id.to_string());
+        self.create_event_with_notifications(calendar_id, event, notify)
+            .await
+    }
     
     /// Update an existing event
     async fn update_event(
@@ -67,7 +141,7 @@ pub trait CalendarProvider: Send + Sync {
 }
 
 /// Calendar metadata
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct Calendar {
     pub id: String,
     pub name: String,
@@ -76,17 +150,49 @@ pub struct Calendar {
     pub is_primary: bool,
     pub can_write: bool,
     pub source: CalendarSource,
+    /// Text color to use on `color`
+    pub foreground_color: Option<String>,
+    /// IANA zone used for floating and all-day events of this calendar
+    pub time_zone: Option<String>,
+    /// What the current user may do with the calendar
+    pub access_role: Option<AccessRole>,
+    /// Reminders applied to events that do not set their own
+    pub default_reminders: Option<Vec<Reminder>>,
+    /// Conference solutions events on this calendar may use, e.g. `hangoutsMeet`
+    pub conference_types: Option<Vec<String>>,
+    /// Hidden from the user's calendar list
+    pub hidden: Option<bool>,
+    /// Shown in the user's calendar UI
+    pub selected: Option<bool>,
+}
+
+/// Access the current user has to a calendar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessRole {
+    /// Can only see free/busy information
+    FreeBusyReader,
+    Reader,
+    Writer,
+    Owner,
+}
+
+impl AccessRole {
+    /// Whether events can be created and edited
+    pub fn can_write(&self) -> bool {
+        matches!(self, AccessRole::Writer | AccessRole::Owner)
+    }
 }
 
 /// Free/busy time period
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct FreeBusyPeriod {
     pub start: DateTime<Utc>,
     pub end: DateTime<Utc>,
     pub status: BusyStatus,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub enum BusyStatus {
     Free,
     Busy,
@@ -1,10 +1,13 @@
 //! Unified calendar data models
 
-use chrono::{DateTime, FixedOffset};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::recurrence::RecurrencePattern;
 
 /// Participant in an event (attendee, organizer, resource)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Participant {
     pub id: Option<String>,
     pub email: Option<String>,
@@ -15,9 +18,21 @@ pub struct Participant {
     pub is_self: Option<bool>,
     pub resource: Option<bool>,
     pub organizer: Option<bool>,
+    /// Role in the meeting (RFC 5545 `ROLE`)
+    pub role: Option<ParticipantRole>,
+    /// What kind of calendar user this is (RFC 5545 `CUTYPE`)
+    pub user_type: Option<CalendarUserType>,
+    /// Addresses the participant delegated their attendance to
+    pub delegated_to: Option<Vec<String>>,
+    /// Addresses that delegated their attendance to this participant
+    pub delegated_from: Option<Vec<String>>,
+    /// Note left by the participant with their response
+    pub comment: Option<String>,
+    /// Number of extra guests the participant brings
+    pub additional_guests: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum ParticipantStatus {
     Accepted,
@@ -26,14 +41,35 @@ pub enum ParticipantStatus {
     NeedsAction,
 }
 
+/// Participation role (RFC 5545 `ROLE`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ParticipantRole {
+    Chair,
+    Required,
+    Optional,
+    NonParticipant,
+}
+
+/// Kind of calendar user (RFC 5545 `CUTYPE`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum CalendarUserType {
+    Individual,
+    Group,
+    Resource,
+    Room,
+    Unknown,
+}
+
 /// Alarm/reminder
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Reminder {
     pub minutes_before: i32,
     pub method: Option<ReminderMethod>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum ReminderMethod {
     Popup,
@@ -42,31 +78,46 @@ pub enum ReminderMethod {
 }
 
 /// Conference/online-meeting link
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ConferenceLink {
     pub url: Option<String>,
     pub provider: Option<String>,
 }
 
 /// Core unified event
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UnifiedCalendarEvent {
     // Identity
     pub id: String,
     pub source: CalendarSource,
     pub calendar_id: Option<String>,
+    /// Global iCalendar UID, shared by every copy of the event
+    pub ical_uid: Option<String>,
+    /// iCalendar SEQUENCE, bumped on significant changes
+    pub sequence: Option<u32>,
 
     // Content
     pub title: Option<String>,
     pub description: Option<String>,
     pub location: Option<String>,
     pub color: Option<String>,
+    pub categories: Option<Vec<String>>,
 
     // Timing
     pub start: EventMoment,
     pub end: EventMoment,
+    /// Main RRULE value, without the `RRULE:` prefix
     pub recurrence_rule: Option<String>,
+    /// `EXDATE` content lines (or bare EXDATE values) of the series; extra
+    /// RRULEs and RDATEs live only in the typed `recurrence`
     pub recurrence_exceptions: Option<Vec<String>>,
+    /// Typed form of the recurrence, kept in sync by [`UnifiedCalendarEvent::set_recurrence`]
+    pub recurrence: Option<RecurrencePattern>,
+    /// Id of the recurring event this is an occurrence of
+    pub series_id: Option<String>,
+    /// Start the occurrence had in its series (RFC 5545 `RECURRENCE-ID`);
+    /// set only on overrides, plain generated occurrences start there anyway
+    pub original_start: Option<EventMoment>,
 
     // Participation
     pub organizer: Option<Participant>,
@@ -85,16 +136,109 @@ pub struct UnifiedCalendarEvent {
     pub raw: Option<serde_json::Value>,
     pub created: Option<DateTime<FixedOffset>>,
     pub updated: Option<DateTime<FixedOffset>>,
+    /// Provider version tag of the event
+    pub etag: Option<String>,
+    /// Link to the event in the provider's web UI
+    pub html_link: Option<String>,
+}
+
+/// Start or end of an event
+///
+/// All-day events use [`EventMoment::Date`]. As an event end a date is
+/// exclusive: a single all-day event on 2024-03-10 starts on 2024-03-10 and
+/// ends on 2024-03-11.
+///
+/// Deserializing also accepts the older `{"date_time": ..., "all_day": true}`
+/// shape and reads it as a date.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum EventMoment {
+    /// Calendar date without a time of day (all-day events)
+    Date {
+        date: NaiveDate,
+        time_zone: Option<String>,
+    },
+    /// Specific point in time with its UTC offset
+    DateTime {
+        date_time: DateTime<FixedOffset>,
+        time_zone: Option<String>,
+    },
+}
+
+impl<'de> Deserialize<'de> for EventMoment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Raw {
+            date: Option<NaiveDate>,
+            date_time: Option<DateTime<FixedOffset>>,
+            time_zone: Option<String>,
+            all_day: Option<bool>,
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        match (raw.date, raw.date_time) {
+            (Some(date), _) => Ok(EventMoment::Date { date, time_zone: raw.time_zone }),
+            (None, Some(date_time)) if raw.all_day == Some(true) => Ok(EventMoment::Date {
+                date: date_time.date_naive(),
+                time_zone: raw.time_zone,
+            }),
+            (None, Some(date_time)) => Ok(EventMoment::DateTime { date_time, time_zone: raw.time_zone }),
+            (None, None) => Err(serde::de::Error::custom("event moment needs date or date_time")),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EventMoment {
-    pub date_time: DateTime<FixedOffset>,
-    pub time_zone: Option<String>,
-    pub all_day: Option<bool>,
+impl EventMoment {
+    /// Create a timed moment
+    pub fn timed(date_time: DateTime<FixedOffset>, time_zone: Option<String>) -> Self {
+        EventMoment::DateTime { date_time, time_zone }
+    }
+
+    /// Create a date-only (all-day) moment
+    pub fn all_day(date: NaiveDate) -> Self {
+        EventMoment::Date { date, time_zone: None }
+    }
+
+    /// Whether this is a date-only value
+    pub fn is_all_day(&self) -> bool {
+        matches!(self, EventMoment::Date { .. })
+    }
+
+    /// IANA time zone name, if known
+    pub fn time_zone(&self) -> Option<&str> {
+        match self {
+            EventMoment::Date { time_zone, .. } | EventMoment::DateTime { time_zone, .. } => {
+                time_zone.as_deref()
+            }
+        }
+    }
+
+    /// The instant of a timed moment, `None` for dates
+    pub fn date_time(&self) -> Option<DateTime<FixedOffset>> {
+        match self {
+            EventMoment::DateTime { date_time, .. } => Some(*date_time),
+            EventMoment::Date { .. } => None,
+        }
+    }
+
+    /// Calendar date, in the moment's own offset for timed values
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            EventMoment::Date { date, .. } => *date,
+            EventMoment::DateTime { date_time, .. } => date_time.date_naive(),
+        }
+    }
+
+    /// Wall-clock value, midnight for dates
+    pub fn naive_local(&self) -> NaiveDateTime {
+        match self {
+            EventMoment::Date { date, .. } => date.and_time(chrono::NaiveTime::MIN),
+            EventMoment::DateTime { date_time, .. } => date_time.naive_local(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub enum CalendarSource {
     Google,
@@ -103,7 +247,7 @@ pub enum CalendarSource {
     Android,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub enum EventStatus {
     Confirmed,
@@ -111,7 +255,7 @@ pub enum EventStatus {
     Cancelled,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub enum EventVisibility {
     Default,
@@ -120,7 +264,7 @@ pub enum EventVisibility {
     Confidential,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub enum ShowAs {
     Busy,
@@ -131,20 +275,45 @@ pub enum ShowAs {
 }
 
 impl UnifiedCalendarEvent {
+    /// Whether the event spans whole days
+    pub fn is_all_day(&self) -> bool {
+        self.start.is_all_day()
+    }
+
+    /// Whether the event has a recurrence of its own (a series master)
+    pub fn is_recurring(&self) -> bool {
+        self.recurrence.is_some() || self.recurrence_rule.is_some()
+    }
+
+    /// Whether the event stands in for one occurrence of a series
+    ///
+    /// Such an event overrides the occurrence that started at
+    /// `original_start`, whether it was moved, edited or cancelled.
+    /// Occurrences generated from the series rule only carry `series_id`.
+    pub fn is_exception(&self) -> bool {
+        self.series_id.is_some() && self.original_start.is_some()
+    }
+
     /// Create a new event with minimal required fields
     pub fn new(id: String, source: CalendarSource, start: EventMoment, end: EventMoment) -> Self {
         Self {
             id,
             source,
             calendar_id: None,
+            ical_uid: None,
+            sequence: None,
             title: None,
             description: None,
             location: None,
             color: None,
+            categories: None,
             start,
             end,
             recurrence_rule: None,
             recurrence_exceptions: None,
+            recurrence: None,
+            series_id: None,
+            original_start: None,
             organizer: None,
             attendees: None,
             status: None,
@@ -155,6 +324,85 @@ impl UnifiedCalendarEvent {
             raw: None,
             created: None,
             updated: None,
+            etag: None,
+            html_link: None,
         }
     }
-}
\ No newline at end of file
+
+    /// Set the recurrence, updating the raw strings to match
+    ///
+    /// `recurrence_rule` gets the main RRULE value and
+    /// `recurrence_exceptions` the EXDATE lines.
+    pub fn set_recurrence(&mut self, pattern: Option<RecurrencePattern>) {
+        self.recurrence_rule = pattern
+            .as_ref()
+            .and_then(RecurrencePattern::rule)
+            .map(ToString::to_string);
+        let exdates: Vec<String> = pattern
+            .iter()
+            .flat_map(|p| &p.exdates)
+            .map(|d| d.to_line("EXDATE"))
+            .collect();
+        self.recurrence_exceptions = if exdates.is_empty() { None } else { Some(exdates) };
+        self.recurrence = pattern;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_moment_serde() {
+        let date = EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap());
+        let json = serde_json::to_value(&date).unwrap();
+        assert_eq!(json, serde_json::json!({ "date": "2024-03-10", "time_zone": null }));
+        assert_eq!(serde_json::from_value::<EventMoment>(json).unwrap(), date);
+
+        // Timed moments keep their previous shape
+        let timed: EventMoment = serde_json::from_value(serde_json::json!({
+            "date_time": "2024-03-10T09:00:00-05:00",
+            "time_zone": "America/New_York",
+            "all_day": false
+        }))
+        .unwrap();
+        assert!(!timed.is_all_day());
+        assert_eq!(timed.time_zone(), Some("America/New_York"));
+        assert_eq!(timed.date(), NaiveDate::from_ymd_opt(2024, 3, 10).unwrap());
+
+        // Older data flagged midnight date-times as all-day
+        let legacy: EventMoment = serde_json::from_value(serde_json::json!({
+            "date_time": "2024-03-10T00:00:00-05:00",
+            "time_zone": null,
+            "all_day": true
+        }))
+        .unwrap();
+        assert_eq!(legacy, date);
+
+        assert!(serde_json::from_value::<EventMoment>(serde_json::json!({ "time_zone": null })).is_err());
+    }
+
+    #[test]
+    fn test_set_recurrence_keeps_only_exdates_raw() {
+        let day = EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap());
+        let mut event = UnifiedCalendarEvent::new("a".to_string(), CalendarSource::Google, day.clone(), day);
+        let pattern = RecurrencePattern::parse([
+            "RRULE:FREQ=WEEKLY;BYDAY=SU",
+            "RRULE:FREQ=MONTHLY;BYMONTHDAY=1",
+            "RDATE;VALUE=DATE:20240313",
+            "EXDATE;VALUE=DATE:20240317",
+        ])
+        .unwrap();
+        event.set_recurrence(Some(pattern.clone()));
+        assert_eq!(event.recurrence_rule.as_deref(), Some("FREQ=WEEKLY;BYDAY=SU"));
+        assert_eq!(
+            event.recurrence_exceptions,
+            Some(vec!["EXDATE;VALUE=DATE:20240317".to_string()])
+        );
+        assert_eq!(event.recurrence, Some(pattern));
+
+        event.set_recurrence(None);
+        assert_eq!(event.recurrence_rule, None);
+        assert_eq!(event.recurrence_exceptions, None);
+    }
+}
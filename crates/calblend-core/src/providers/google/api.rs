@@ -11,28 +11,33 @@ use crate::{
 };
 
 use super::auth::GoogleAuth;
-use super::models::{GoogleCalendar, GoogleEvent, GoogleFreeBusyRequest, GoogleFreeBusyResponse, GoogleFreeBusyItem};
+use super::endpoints::GoogleEndpoints;
+use super::options::ListEventsOptions;
+use super::models::{
+    GoogleCalendar, GoogleEvent, GoogleFreeBusyRequest, GoogleFreeBusyResponse, GoogleFreeBusyItem,
+    GoogleSetting,
+};
 
 /// Google Calendar API client
 pub struct GoogleCalendarApi {
     auth: Arc<GoogleAuth>,
     pub(crate) http: HttpClient,
+    base_url: String,
     rate_limiter: RateLimiter,
 }
 
 impl GoogleCalendarApi {
-    const BASE_URL: &'static str = "https://www.googleapis.com/calendar/v3";
-    
     /// Google API rate limits: 1,000,000 quota units per day
     /// Most read operations cost 1 unit, writes cost 50 units
     /// We'll limit to 100 requests per second to be safe
     const RATE_LIMIT_MAX_REQUESTS: u32 = 100;
     const RATE_LIMIT_WINDOW_SECS: u64 = 1;
 
-    pub fn new(auth: Arc<GoogleAuth>, http_client: HttpClient) -> Self {
+    pub fn new(auth: Arc<GoogleAuth>, http_client: HttpClient, endpoints: &GoogleEndpoints) -> Self {
         Self {
             auth,
             http: http_client,
+            base_url: endpoints.api_base_url.clone(),
             rate_limiter: RateLimiter::new(
                 Self::RATE_LIMIT_MAX_REQUESTS,
                 Self::RATE_LIMIT_WINDOW_SECS,
@@ -95,20 +100,25 @@ impl GoogleCalendarApi {
             .map_err(|e| CalblendError::InternalError(e.to_string()))
     }
 
-    /// Make an authenticated PUT request
+    /// Make an authenticated PUT request, conditional on `if_match` when set
     #[instrument(skip(self, body))]
     async fn put<T: Serialize, R: for<'de> Deserialize<'de>>(
         &self,
         url: &str,
         body: &T,
+        if_match: Option<&str>,
     ) -> Result<R> {
         self.rate_limiter.check_rate_limit().await;
         
         let access_token = self.auth.get_access_token().await?;
-        let response = self.http.client()
+        let mut request = self.http.client()
             .put(url)
             .bearer_auth(&access_token)
-            .json(body)
+            .json(body);
+        if let Some(etag) = if_match {
+            request = request.header(reqwest::header::IF_MATCH, etag);
+        }
+        let response = request
             .send()
             .await
             .map_err(|e| CalblendError::InternalError(e.to_string()))?;
@@ -147,10 +157,15 @@ impl GoogleCalendarApi {
         Ok(())
     }
 
+    /// Calendar API base URL this client talks to
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
     /// List user's calendars
     #[instrument(skip(self))]
     pub async fn list_calendars(&self) -> Result<Vec<GoogleCalendar>> {
-        let url = format!("{}/users/me/calendarList", Self::BASE_URL);
+        let url = format!("{}/users/me/calendarList", self.base_url);
         
         #[derive(Deserialize)]
         struct CalendarListResponse {
@@ -181,6 +196,41 @@ impl GoogleCalendarApi {
         Ok(calendars)
     }
 
+    /// List user's settings (time zone, locale, ...)
+    #[instrument(skip(self))]
+    pub async fn list_settings(&self) -> Result<Vec<GoogleSetting>> {
+        let url = format!("{}/users/me/settings", self.base_url);
+
+        #[derive(Deserialize)]
+        struct SettingsResponse {
+            #[serde(default)]
+            items: Vec<GoogleSetting>,
+            #[serde(rename = "nextPageToken")]
+            next_page_token: Option<String>,
+        }
+
+        let mut settings = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut url = url.clone();
+            if let Some(token) = &page_token {
+                url.push_str(&format!("?pageToken={}", token));
+            }
+
+            let response: SettingsResponse = self.get(&url).await?;
+            settings.extend(response.items);
+
+            match response.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+
+        debug!("Listed {} settings", settings.len());
+        Ok(settings)
+    }
+
     /// List events from a calendar
     #[instrument(skip(self))]
     pub async fn list_events(
@@ -188,38 +238,73 @@ impl GoogleCalendarApi {
         calendar_id: &str,
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
+        options: &ListEventsOptions,
     ) -> Result<Vec<GoogleEvent>> {
-        let mut url = format!("{}/calendars/{}/events", Self::BASE_URL, calendar_id);
+        let mut url = format!("{}/calendars/{}/events", self.base_url, calendar_id);
         let mut params = Vec::new();
 
         if let Some(start) = start {
-            params.push(format!("timeMin={}", start.to_rfc3339()));
+            params.push(format!("timeMin={}", urlencoding::encode(&start.to_rfc3339())));
         }
         if let Some(end) = end {
-            params.push(format!("timeMax={}", end.to_rfc3339()));
+            params.push(format!("timeMax={}", urlencoding::encode(&end.to_rfc3339())));
         }
-        params.push("singleEvents=true".to_string());
-        params.push("orderBy=startTime".to_string());
+        // Google only orders by start time when series are expanded
+        if options.single_events {
+            params.push("orderBy=startTime".to_string());
+        }
+        params.extend(options.query_params());
 
         if !params.is_empty() {
             url.push('?');
             url.push_str(&params.join("&"));
         }
 
+        let events = self.get_all_events(&url).await?;
+        debug!("Listed {} events", events.len());
+        Ok(events)
+    }
+
+    /// List the occurrences of a recurring event that overlap a window
+    #[instrument(skip(self))]
+    pub async fn list_instances(
+        &self,
+        calendar_id: &str,
+        event_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<GoogleEvent>> {
+        let url = format!(
+            "{}/calendars/{}/events/{}/instances?timeMin={}&timeMax={}",
+            self.base_url,
+            calendar_id,
+            event_id,
+            urlencoding::encode(&start.to_rfc3339()),
+            urlencoding::encode(&end.to_rfc3339()),
+        );
+
+        self.get_all_events(&url).await
+    }
+
+    /// Fetch every page of an event list
+    async fn get_all_events(&self, url: &str) -> Result<Vec<GoogleEvent>> {
         #[derive(Deserialize)]
         struct EventListResponse {
+            // Partial responses omit `items` when nothing matched
+            #[serde(default)]
             items: Vec<GoogleEvent>,
             #[serde(rename = "nextPageToken")]
             next_page_token: Option<String>,
         }
 
+        let separator = if url.contains('?') { '&' } else { '?' };
         let mut events = Vec::new();
         let mut page_token: Option<String> = None;
 
         loop {
-            let mut paginated_url = url.clone();
+            let mut paginated_url = url.to_string();
             if let Some(token) = &page_token {
-                paginated_url.push_str(&format!("&pageToken={}", token));
+                paginated_url.push_str(&format!("{}pageToken={}", separator, token));
             }
 
             let response: EventListResponse = self.get(&paginated_url).await?;
@@ -231,37 +316,51 @@ impl GoogleCalendarApi {
             }
         }
 
-        debug!("Listed {} events", events.len());
         Ok(events)
     }
 
     /// Create a new event
+    ///
+    /// `send_updates` is the `sendUpdates` parameter: `all`, `externalOnly`
+    /// or `none`; Google's default applies when unset.
     #[instrument(skip(self, event))]
     pub async fn create_event(
         &self,
         calendar_id: &str,
         event: GoogleEvent,
+        send_updates: Option<&str>,
     ) -> Result<GoogleEvent> {
-        let url = format!("{}/calendars/{}/events", Self::BASE_URL, calendar_id);
+        let mut url = format!("{}/calendars/{}/events", self.base_url, calendar_id);
+        if let Some(send_updates) = send_updates {
+            url.push_str(&format!("?sendUpdates={}", send_updates));
+        }
         self.post(&url, &event).await
     }
 
     /// Update an existing event
+    ///
+    /// `send_updates` is passed as for [`Self::create_event`].
     #[instrument(skip(self, event))]
     pub async fn update_event(
         &self,
         calendar_id: &str,
         event_id: &str,
         event: GoogleEvent,
+        send_updates: Option<&str>,
     ) -> Result<GoogleEvent> {
-        let url = format!("{}/calendars/{}/events/{}", Self::BASE_URL, calendar_id, event_id);
-        self.put(&url, &event).await
+        let mut url = format!("{}/calendars/{}/events/{}", self.base_url, calendar_id, event_id);
+        if let Some(send_updates) = send_updates {
+            url.push_str(&format!("?sendUpdates={}", send_updates));
+        }
+        // Fail rather than overwrite changes made since the event was read
+        let etag = event.etag.clone();
+        self.put(&url, &event, etag.as_deref()).await
     }
 
     /// Delete an event
     #[instrument(skip(self))]
     pub async fn delete_event(&self, calendar_id: &str, event_id: &str) -> Result<()> {
-        let url = format!("{}/calendars/{}/events/{}", Self::BASE_URL, calendar_id, event_id);
+        let url = format!("{}/calendars/{}/events/{}", self.base_url, calendar_id, event_id);
         self.delete(&url).await
     }
 
@@ -273,7 +372,7 @@ impl GoogleCalendarApi {
         start: DateTime<Utc>,
         end: DateTime<Utc>,
     ) -> Result<Vec<FreeBusyPeriod>> {
-        let url = format!("{}/freeBusy", Self::BASE_URL);
+        let url = format!("{}/freeBusy", self.base_url);
         
         let request = GoogleFreeBusyRequest {
             time_min: start.to_rfc3339(),
@@ -14,21 +14,18 @@ use crate::{
     CalblendError, CalendarSource, Result, auth::TokenData, TokenStorage,
     http::HttpClient,
 };
+use super::endpoints::GoogleEndpoints;
 
 /// Google OAuth2 authentication handler
 pub struct GoogleAuth {
     oauth_client: BasicClient,
     token_storage: Arc<dyn TokenStorage>,
     http_client: HttpClient,
+    revoke_url: String,
     pkce_verifier: RwLock<Option<PkceCodeVerifier>>,
 }
 
 impl GoogleAuth {
-    /// OAuth2 endpoints
-    const AUTH_URL: &'static str = "https://accounts.google.com/o/oauth2/v2/auth";
-    const TOKEN_URL: &'static str = "https://oauth2.googleapis.com/token";
-    const REVOKE_URL: &'static str = "https://oauth2.googleapis.com/revoke";
-    
     /// Required OAuth2 scopes for Google Calendar
     const SCOPES: &'static [&'static str] = &[
         "https://www.googleapis.com/auth/calendar",
@@ -42,22 +39,30 @@ impl GoogleAuth {
         redirect_uri: String,
         token_storage: Arc<dyn TokenStorage>,
         http_client: HttpClient,
-    ) -> Self {
+        endpoints: &GoogleEndpoints,
+    ) -> Result<Self> {
+        let invalid_url = |name: &str, e: url::ParseError| {
+            CalblendError::Configuration(format!("Invalid {}: {}", name, e))
+        };
+
         let oauth_client = BasicClient::new(
             ClientId::new(client_id),
             Some(ClientSecret::new(client_secret)),
-            AuthUrl::new(Self::AUTH_URL.to_string()).unwrap(),
-            Some(TokenUrl::new(Self::TOKEN_URL.to_string()).unwrap()),
+            AuthUrl::new(endpoints.auth_url.clone()).map_err(|e| invalid_url("auth URL", e))?,
+            Some(TokenUrl::new(endpoints.token_url.clone()).map_err(|e| invalid_url("token URL", e))?),
         )
-        .set_redirect_uri(RedirectUrl::new(redirect_uri).unwrap())
-        .set_revocation_uri(RevocationUrl::new(Self::REVOKE_URL.to_string()).unwrap());
+        .set_redirect_uri(RedirectUrl::new(redirect_uri).map_err(|e| invalid_url("redirect URI", e))?)
+        .set_revocation_uri(
+            RevocationUrl::new(endpoints.revoke_url.clone()).map_err(|e| invalid_url("revoke URL", e))?,
+        );
 
-        Self {
+        Ok(Self {
             oauth_client,
             token_storage,
             http_client,
+            revoke_url: endpoints.revoke_url.clone(),
             pkce_verifier: RwLock::new(None),
-        }
+        })
     }
 
     /// Generate authorization URL with PKCE
@@ -197,7 +202,7 @@ impl GoogleAuth {
             .ok_or_else(|| CalblendError::Authentication("No token found".to_string()))?;
 
         // Revoke the token with Google
-        let revoke_url = format!("{}?token={}", Self::REVOKE_URL, token_data.access_token);
+        let revoke_url = format!("{}?token={}", self.revoke_url, token_data.access_token);
         let response = self.http_client.client()
             .post(&revoke_url)
             .send()
@@ -0,0 +1,76 @@
+//! Google API endpoint configuration
+
+/// Base URLs used by the Google provider
+///
+/// Defaults to Google's production endpoints. Override to point the provider
+/// (including OAuth token refresh and push notification channels) at an
+/// emulator, a staging proxy or a local test server. The setters drop
+/// trailing `/` from the URLs they are given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoogleEndpoints {
+    /// Calendar API base, e.g. `https://www.googleapis.com/calendar/v3`
+    pub api_base_url: String,
+    /// OAuth2 authorization endpoint
+    pub auth_url: String,
+    /// OAuth2 token endpoint
+    pub token_url: String,
+    /// OAuth2 token revocation endpoint
+    pub revoke_url: String,
+}
+
+impl Default for GoogleEndpoints {
+    fn default() -> Self {
+        Self {
+            api_base_url: "https://www.googleapis.com/calendar/v3".to_string(),
+            auth_url: "https://accounts.google.com/o/oauth2/v2/auth".to_string(),
+            token_url: "https://oauth2.googleapis.com/token".to_string(),
+            revoke_url: "https://oauth2.googleapis.com/revoke".to_string(),
+        }
+    }
+}
+
+impl GoogleEndpoints {
+    /// Serve every endpoint from a single host, keeping Google's path layout
+    ///
+    /// `with_base_url("http://127.0.0.1:8080")` yields
+    /// `http://127.0.0.1:8080/calendar/v3`, `http://127.0.0.1:8080/o/oauth2/v2/auth`,
+    /// `http://127.0.0.1:8080/token` and `http://127.0.0.1:8080/revoke`.
+    pub fn with_base_url(base_url: &str) -> Self {
+        let base = base_url.trim_end_matches('/');
+        Self {
+            api_base_url: format!("{}/calendar/v3", base),
+            auth_url: format!("{}/o/oauth2/v2/auth", base),
+            token_url: format!("{}/token", base),
+            revoke_url: format!("{}/revoke", base),
+        }
+    }
+
+    /// Set the Calendar API base URL
+    pub fn with_api_base_url(mut self, url: impl Into<String>) -> Self {
+        self.api_base_url = normalize(url.into());
+        self
+    }
+
+    /// Set the OAuth2 authorization URL
+    pub fn with_auth_url(mut self, url: impl Into<String>) -> Self {
+        self.auth_url = normalize(url.into());
+        self
+    }
+
+    /// Set the OAuth2 token URL
+    pub fn with_token_url(mut self, url: impl Into<String>) -> Self {
+        self.token_url = normalize(url.into());
+        self
+    }
+
+    /// Set the OAuth2 revocation URL
+    pub fn with_revoke_url(mut self, url: impl Into<String>) -> Self {
+        self.revoke_url = normalize(url.into());
+        self
+    }
+}
+
+/// A URL without trailing slashes, so paths can be appended with `/`
+fn normalize(url: String) -> String {
+    url.trim_end_matches('/').to_string()
+}
@@ -2,7 +2,9 @@
 
 mod auth;
 mod api;
+mod endpoints;
 mod models;
+mod options;
 mod webhooks;
 
 #[cfg(test)]
@@ -10,25 +12,83 @@ mod tests;
 
 pub use auth::GoogleAuth;
 pub use api::GoogleCalendarApi;
-pub use webhooks::{GoogleWebhookManager, WatchChannel, PushNotification};
+pub use endpoints::GoogleEndpoints;
+pub use options::ListEventsOptions;
+pub use webhooks::{
+    GoogleWebhookManager, NotificationChange, PushNotification, WatchChannel, WatchResource,
+};
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
 use std::sync::Arc;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 
 use crate::{
-    CalendarProvider, Result, UnifiedCalendarEvent, CalblendError,
-    Calendar, FreeBusyPeriod, TokenStorage, CalblendConfig, http::HttpClient,
+    CalendarProvider, Result, UnifiedCalendarEvent, CalblendError, ConversionError,
+    Calendar, CalendarSource, EventMoment, EventStatus, FreeBusyPeriod, TokenStorage, CalblendConfig, http::HttpClient,
     cache::CalendarCache,
 };
 
-use self::models::GoogleEvent;
+use self::models::{parse_event_time, GoogleEvent};
+
+/// Events returned by [`GoogleCalendarProvider::list_events_detailed`]
+#[derive(Debug, Clone, Default)]
+pub struct EventListing {
+    /// Successfully converted events
+    pub events: Vec<UnifiedCalendarEvent>,
+    /// Items left out because they could not be converted (lenient mode only)
+    pub skipped: Vec<ConversionError>,
+    /// Deleted events that Google reports by ID only
+    /// ([`ListEventsOptions::show_deleted`] only, except for occurrences
+    /// cancelled from a series, which unexpanded listings always include)
+    pub cancelled: Vec<CancelledEvent>,
+}
+
+/// Deletion marker for an event or a series occurrence
+#[derive(Debug, Clone, PartialEq)]
+pub struct CancelledEvent {
+    pub id: String,
+    /// Series the cancelled occurrence belonged to
+    pub series_id: Option<String>,
+    /// Start of the cancelled occurrence
+    pub original_start: Option<EventMoment>,
+}
+
+impl CancelledEvent {
+    /// The marker as a cancelled occurrence of its series, if it is one
+    ///
+    /// Google keeps no times for it, so it starts and ends at its original
+    /// start.
+    pub fn into_occurrence(self) -> Option<UnifiedCalendarEvent> {
+        let series_id = self.series_id?;
+        let original_start = self.original_start?;
+        let mut event = UnifiedCalendarEvent::new(
+            self.id,
+            CalendarSource::Google,
+            original_start.clone(),
+            original_start.clone(),
+        );
+        event.status = Some(EventStatus::Cancelled);
+        event.series_id = Some(series_id);
+        event.original_start = Some(original_start);
+        Some(event)
+    }
+}
+
+/// Whether two moments denote the same date or instant, whatever their zones
+fn same_moment(a: &EventMoment, b: &EventMoment) -> bool {
+    match (a, b) {
+        (EventMoment::Date { date: a, .. }, EventMoment::Date { date: b, .. }) => a == b,
+        (EventMoment::DateTime { date_time: a, .. }, EventMoment::DateTime { date_time: b, .. }) => a == b,
+        _ => false,
+    }
+}
 
 /// Google Calendar provider
 pub struct GoogleCalendarProvider {
     auth: Arc<GoogleAuth>,
     api: Arc<GoogleCalendarApi>,
+    #[allow(dead_code)]
     token_storage: Arc<dyn TokenStorage>,
     webhook_manager: Option<Arc<GoogleWebhookManager>>,
     cache: Option<CalendarCache>,
@@ -42,6 +102,28 @@ impl GoogleCalendarProvider {
         redirect_uri: String,
         token_storage: Arc<dyn TokenStorage>,
         config: CalblendConfig,
+    ) -> Result<Self> {
+        Self::with_endpoints(
+            client_id,
+            client_secret,
+            redirect_uri,
+            token_storage,
+            config,
+            GoogleEndpoints::default(),
+        )
+    }
+
+    /// Create a new Google Calendar provider talking to custom endpoints
+    ///
+    /// Every request the provider makes (Calendar API, OAuth token exchange,
+    /// refresh and revocation, watch channels) uses the given base URLs.
+    pub fn with_endpoints(
+        client_id: String,
+        client_secret: String,
+        redirect_uri: String,
+        token_storage: Arc<dyn TokenStorage>,
+        config: CalblendConfig,
+        endpoints: GoogleEndpoints,
     ) -> Result<Self> {
         let http_client = HttpClient::new(&config)?;
         let auth = Arc::new(GoogleAuth::new(
@@ -50,10 +132,12 @@ impl GoogleCalendarProvider {
             redirect_uri,
             Arc::clone(&token_storage),
             http_client.clone(),
-        ));
+            &endpoints,
+        )?);
         let api = Arc::new(GoogleCalendarApi::new(
             Arc::clone(&auth),
             http_client,
+            &endpoints,
         ));
 
         Ok(Self {
@@ -70,6 +154,7 @@ impl GoogleCalendarProvider {
         self.webhook_manager = Some(Arc::new(GoogleWebhookManager::new(
             Arc::clone(&self.auth),
             self.api.http.clone(),
+            self.api.base_url().to_string(),
             webhook_endpoint,
         )));
         self
@@ -98,8 +183,129 @@ impl GoogleCalendarProvider {
     }
 
     /// Convert Google event to unified format
-    fn convert_to_unified(&self, google_event: GoogleEvent) -> UnifiedCalendarEvent {
-        google_event.into_unified()
+    fn convert_to_unified(&self, google_event: GoogleEvent) -> Result<UnifiedCalendarEvent> {
+        Ok(google_event.into_unified()?)
+    }
+
+    /// Insert an event, passing `send_updates` through when set
+    async fn insert_event(
+        &self,
+        calendar_id: &str,
+        event: UnifiedCalendarEvent,
+        send_updates: Option<&str>,
+    ) -> Result<UnifiedCalendarEvent> {
+        debug!("Creating event in calendar: {}", calendar_id);
+        event.validate()?;
+        let mut google_event = GoogleEvent::from_unified(&event)?;
+        // Google links instances to their series itself; a new event cannot
+        // join an existing one
+        google_event.recurring_event_id = None;
+        google_event.original_start_time = None;
+        let created = self.api.create_event(calendar_id, google_event, send_updates).await?;
+
+        // Invalidate events cache for this calendar
+        if let Some(cache) = &self.cache {
+            cache.invalidate_events(calendar_id).await;
+        }
+
+        self.convert_to_unified(created)
+    }
+
+    /// List events with Google-specific request options
+    ///
+    /// Use this to request partial responses (`fields`), tune the page size
+    /// or skip copying provider payloads into `raw`. Deletion markers are
+    /// left out; [`Self::list_events_detailed`] returns them.
+    #[instrument(skip(self))]
+    pub async fn list_events_with_options(
+        &self,
+        calendar_id: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        options: &ListEventsOptions,
+    ) -> Result<Vec<UnifiedCalendarEvent>> {
+        let listing = self.list_events_detailed(calendar_id, start, end, options).await?;
+        for error in &listing.skipped {
+            warn!("Skipped event that failed to convert: {}", error);
+        }
+        if !listing.cancelled.is_empty() {
+            debug!("Left out {} deletion markers", listing.cancelled.len());
+        }
+        Ok(listing.events)
+    }
+
+    /// List events and report items that could not be converted
+    ///
+    /// With [`ListEventsOptions::lenient`] events that fail to convert are
+    /// left out and returned in [`EventListing::skipped`]; otherwise the
+    /// first conversion failure fails the whole call. Cancelled items that
+    /// carry no times are returned in [`EventListing::cancelled`].
+    ///
+    /// Occurrences of a series carry its `series_id`. When series are
+    /// expanded Google does not mark which occurrences were edited, so only
+    /// those moved away from their original start get an `original_start`
+    /// and count as exceptions. Without [`ListEventsOptions::single_events`]
+    /// only edited occurrences are listed, and all of them keep it.
+    #[instrument(skip(self))]
+    pub async fn list_events_detailed(
+        &self,
+        calendar_id: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        options: &ListEventsOptions,
+    ) -> Result<EventListing> {
+        debug!("Listing events for calendar: {}", calendar_id);
+        let cache_variant = options.cache_key();
+        
+        // Check cache first
+        if let Some(cache) = &self.cache {
+            if let Some(cached) = cache.get_events(calendar_id, start, end, &cache_variant).await {
+                debug!("Returning cached events");
+                return Ok(EventListing { events: cached, ..Default::default() });
+            }
+        }
+        
+        // Fetch from API
+        let events = self.api.list_events(calendar_id, start, end, options).await?;
+        let mut listing = EventListing::default();
+        for event in events {
+            if event.is_tombstone() {
+                match event.into_tombstone() {
+                    Ok(tombstone) => listing.cancelled.push(tombstone),
+                    Err(error) if options.lenient => listing.skipped.push(error),
+                    Err(error) => return Err(error.into()),
+                }
+                continue;
+            }
+            let converted = if options.skip_raw {
+                event.into_unified_without_raw()
+            } else {
+                event.into_unified()
+            };
+            match converted {
+                Ok(mut event) => {
+                    // Expanded listings link every occurrence to its series;
+                    // only those no longer at their original start are
+                    // recognisably overrides
+                    if options.single_events && event.original_start.as_ref().is_some_and(|original| same_moment(original, &event.start)) {
+                        event.original_start = None;
+                    }
+                    listing.events.push(event)
+                }
+                Err(error) if options.lenient => listing.skipped.push(error),
+                Err(error) => return Err(error.into()),
+            }
+        }
+        
+        // Cache the result; listings with skipped or cancelled items are not
+        // cached so that every call reports them
+        if let Some(cache) = &self.cache {
+            if listing.skipped.is_empty() && listing.cancelled.is_empty() {
+                cache.set_events(calendar_id, start, end, &cache_variant, listing.events.clone()).await;
+            }
+        }
+        
+        Ok(listing)
     }
 
     /// Watch a calendar for changes
@@ -109,13 +315,25 @@ impl GoogleCalendarProvider {
         token: Option<String>,
         ttl_hours: Option<i64>,
     ) -> Result<WatchChannel> {
-        let manager = self.webhook_manager
-            .as_ref()
-            .ok_or_else(|| CalblendError::Configuration(
-                "Webhook endpoint not configured. Use with_webhook_endpoint()".to_string()
-            ))?;
-        
-        manager.watch_calendar(calendar_id, token, ttl_hours).await
+        self.webhook_manager()?.watch_calendar(calendar_id, token, ttl_hours).await
+    }
+
+    /// Watch the user's calendar list (subscriptions, hidden and deleted calendars)
+    pub async fn watch_calendar_list(
+        &self,
+        token: Option<String>,
+        ttl_hours: Option<i64>,
+    ) -> Result<WatchChannel> {
+        self.webhook_manager()?.watch(&WatchResource::CalendarList, token, ttl_hours).await
+    }
+
+    /// Watch the user's settings (e.g. time zone changes)
+    pub async fn watch_settings(
+        &self,
+        token: Option<String>,
+        ttl_hours: Option<i64>,
+    ) -> Result<WatchChannel> {
+        self.webhook_manager()?.watch(&WatchResource::Settings, token, ttl_hours).await
     }
 
     /// Stop watching a calendar
@@ -124,53 +342,90 @@ impl GoogleCalendarProvider {
         channel_id: &str,
         resource_id: &str,
     ) -> Result<()> {
-        let manager = self.webhook_manager
-            .as_ref()
-            .ok_or_else(|| CalblendError::Configuration(
-                "Webhook endpoint not configured. Use with_webhook_endpoint()".to_string()
-            ))?;
-        
-        manager.stop_watch(channel_id, resource_id).await
+        self.webhook_manager()?.stop_watch(channel_id, resource_id).await
     }
 
     /// Process a webhook notification
+    ///
+    /// Refreshes the cache entries affected by the change and returns the
+    /// refreshed data for the notified resource.
     pub async fn process_notification(
         &self,
         notification: &PushNotification,
         expected_token: Option<&str>,
-    ) -> Result<Vec<UnifiedCalendarEvent>> {
-        let manager = self.webhook_manager
-            .as_ref()
-            .ok_or_else(|| CalblendError::Configuration(
-                "Webhook endpoint not configured. Use with_webhook_endpoint()".to_string()
-            ))?;
+    ) -> Result<NotificationChange> {
+        let manager = self.webhook_manager()?;
 
         // Verify the notification
         if !manager.verify_notification(notification, expected_token) {
             return Err(CalblendError::Authentication("Invalid webhook token".to_string()));
         }
 
-        // Extract calendar ID from resource URI
-        // Format: https://www.googleapis.com/calendar/v3/calendars/{calendarId}/events
-        let calendar_id = notification.resource_uri
-            .split("/calendars/")
-            .nth(1)
-            .and_then(|s| s.split("/events").next())
-            .ok_or_else(|| CalblendError::Provider(
-                "Invalid resource URI format".to_string()
-            ))?;
-
-        // For sync event, fetch recent changes
-        if notification.resource_state == "sync" {
-            debug!("Received sync notification for calendar: {}", calendar_id);
-            return Ok(vec![]);
+        let resource = notification.resource()?;
+
+        // For sync event, nothing has changed yet
+        if notification.is_sync() {
+            debug!("Received sync notification for {:?}", resource);
+            return Ok(NotificationChange::Sync { resource });
         }
 
-        // Fetch recent events (last 24 hours)
-        let start = Some(Utc::now() - chrono::Duration::hours(24));
-        let end = Some(Utc::now() + chrono::Duration::hours(24));
-        
-        self.list_events(calendar_id, start, end).await
+        match resource {
+            WatchResource::Events { calendar_id } => {
+                if let Some(cache) = &self.cache {
+                    cache.invalidate_events(&calendar_id).await;
+                }
+
+                // Fetch recent events (last 24 hours)
+                let start = Some(Utc::now() - chrono::Duration::hours(24));
+                let end = Some(Utc::now() + chrono::Duration::hours(24));
+                let events = self.list_events(&calendar_id, start, end).await?;
+
+                Ok(NotificationChange::Events { calendar_id, events })
+            }
+            WatchResource::CalendarList => {
+                let previous = match &self.cache {
+                    Some(cache) => {
+                        let previous = cache.get_calendars().await;
+                        cache.invalidate_calendars().await;
+                        previous
+                    }
+                    None => None,
+                };
+
+                let calendars = self.list_calendars().await?;
+
+                // Drop cached events of calendars that were removed or unsubscribed
+                if let (Some(cache), Some(previous)) = (&self.cache, previous) {
+                    for old in previous.iter().filter(|old| !calendars.iter().any(|c| c.id == old.id)) {
+                        cache.invalidate_events(&old.id).await;
+                    }
+                }
+
+                Ok(NotificationChange::CalendarList { calendars })
+            }
+            WatchResource::Settings => {
+                // Calendar metadata and event times follow the user's time zone
+                if let Some(cache) = &self.cache {
+                    cache.invalidate_calendars().await;
+                    cache.invalidate_all_events().await;
+                }
+
+                let settings = self.api.list_settings().await?
+                    .into_iter()
+                    .map(|s| (s.id, s.value))
+                    .collect();
+
+                Ok(NotificationChange::Settings { settings })
+            }
+        }
+    }
+
+    fn webhook_manager(&self) -> Result<&GoogleWebhookManager> {
+        self.webhook_manager
+            .as_deref()
+            .ok_or_else(|| CalblendError::Configuration(
+                "Webhook endpoint not configured. Use with_webhook_endpoint()".to_string()
+            ))
     }
 
     /// Check if webhook support is enabled
@@ -216,28 +471,23 @@ impl CalendarProvider for GoogleCalendarProvider {
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
     ) -> Result<Vec<UnifiedCalendarEvent>> {
-        debug!("Listing events for calendar: {}", calendar_id);
-        
-        // Check cache first
-        if let Some(cache) = &self.cache {
-            if let Some(cached) = cache.get_events(calendar_id, start, end).await {
-                debug!("Returning cached events");
-                return Ok(cached);
-            }
-        }
-        
-        // Fetch from API
-        let events = self.api.list_events(calendar_id, start, end).await?;
-        let result: Vec<UnifiedCalendarEvent> = events.into_iter()
-            .map(|e| self.convert_to_unified(e))
-            .collect();
-        
-        // Cache the result
-        if let Some(cache) = &self.cache {
-            cache.set_events(calendar_id, start, end, result.clone()).await;
-        }
-        
-        Ok(result)
+        self.list_events_with_options(calendar_id, start, end, &ListEventsOptions::default())
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn list_events_unexpanded(
+        &self,
+        calendar_id: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<UnifiedCalendarEvent>> {
+        let options = ListEventsOptions::default().with_single_events(false);
+        let listing = self.list_events_detailed(calendar_id, start, end, &options).await?;
+        // Cancelled occurrences are kept so that a restore cancels them too
+        let mut events = listing.events;
+        events.extend(listing.cancelled.into_iter().filter_map(CancelledEvent::into_occurrence));
+        Ok(events)
     }
     
     #[instrument(skip(self, event))]
@@ -246,16 +496,84 @@ impl CalendarProvider for GoogleCalendarProvider {
         calendar_id: &str,
         event: UnifiedCalendarEvent,
     ) -> Result<UnifiedCalendarEvent> {
-        debug!("Creating event in calendar: {}", calendar_id);
-        let google_event = GoogleEvent::from_unified(&event)?;
-        let created = self.api.create_event(calendar_id, google_event).await?;
-        
+        self.insert_event(calendar_id, event, None).await
+    }
+
+    #[instrument(skip(self, event))]
+    async fn create_event_with_notifications(
+        &self,
+        calendar_id: &str,
+        event: UnifiedCalendarEvent,
+        notify: bool,
+    ) -> Result<UnifiedCalendarEvent> {
+        let send_updates = if notify { "all" } else { "none" };
+        self.insert_event(calendar_id, event, Some(send_updates)).await
+    }
+
+    #[instrument(skip(self, event))]
+    async fn update_occurrence(
+        &self,
+        calendar_id: &str,
+        series_id: &str,
+        event: UnifiedCalendarEvent,
+        notify: bool,
+    ) -> Result<UnifiedCalendarEvent> {
+        debug!("Updating occurrence of {} in calendar: {}", series_id, calendar_id);
+        event.validate()?;
+        let original_start = event.original_start.clone().ok_or_else(|| {
+            CalblendError::InvalidData("An occurrence needs its original_start".to_string())
+        })?;
+
+        // Google finds instances by the time they overlap; a day either side
+        // of the original start covers every zone
+        let anchor = match original_start.date_time() {
+            Some(date_time) => date_time.with_timezone(&Utc),
+            None => original_start.date().and_time(NaiveTime::MIN).and_utc(),
+        };
+        let instances = self
+            .api
+            .list_instances(calendar_id, series_id, anchor - Duration::days(1), anchor + Duration::days(2))
+            .await?;
+        let instance = instances
+            .into_iter()
+            .find(|instance| {
+                instance
+                    .original_start_time
+                    .clone()
+                    .and_then(|time| parse_event_time("originalStartTime", time).ok())
+                    .is_some_and(|start| same_moment(&start, &original_start))
+            })
+            .ok_or_else(|| {
+                CalblendError::EventNotFound(format!(
+                    "No occurrence of {} starts at {:?}",
+                    series_id, original_start
+                ))
+            })?;
+        let instance_id = instance.id.clone().unwrap_or_default();
+
+        let google_event = if matches!(event.status, Some(EventStatus::Cancelled)) {
+            // Only the status changes; a cancelled occurrence may carry no times
+            GoogleEvent { status: Some("cancelled".to_string()), ..instance }
+        } else {
+            let mut google_event = GoogleEvent::from_unified(&event)?;
+            google_event.id = instance.id;
+            google_event.recurring_event_id = Some(series_id.to_string());
+            google_event.original_start_time = instance.original_start_time;
+            google_event.etag = instance.etag;
+            google_event
+        };
+        let send_updates = if notify { "all" } else { "none" };
+        let updated = self
+            .api
+            .update_event(calendar_id, &instance_id, google_event, Some(send_updates))
+            .await?;
+
         // Invalidate events cache for this calendar
         if let Some(cache) = &self.cache {
             cache.invalidate_events(calendar_id).await;
         }
-        
-        Ok(self.convert_to_unified(created))
+
+        self.convert_to_unified(updated)
     }
     
     #[instrument(skip(self, event))]
@@ -266,15 +584,16 @@ impl CalendarProvider for GoogleCalendarProvider {
         event: UnifiedCalendarEvent,
     ) -> Result<UnifiedCalendarEvent> {
         debug!("Updating event {} in calendar: {}", event_id, calendar_id);
+        event.validate()?;
         let google_event = GoogleEvent::from_unified(&event)?;
-        let updated = self.api.update_event(calendar_id, event_id, google_event).await?;
+        let updated = self.api.update_event(calendar_id, event_id, google_event, None).await?;
         
         // Invalidate events cache for this calendar
         if let Some(cache) = &self.cache {
             cache.invalidate_events(calendar_id).await;
         }
         
-        Ok(self.convert_to_unified(updated))
+        self.convert_to_unified(updated)
     }
     
     #[instrument(skip(self))]
@@ -1,15 +1,39 @@
 //! Google Calendar API models
 
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, NaiveDate};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::CancelledEvent;
+use crate::recurrence::RecurrencePattern;
 use crate::{
-    Calendar, CalendarSource, ConferenceLink, EventMoment, EventStatus,
-    EventVisibility, Participant, ParticipantStatus, Reminder, ReminderMethod, Result,
+    AccessRole, Calendar, CalendarSource, CalendarUserType, ConferenceLink, ConversionError, EventMoment, EventStatus,
+    EventVisibility, Participant, ParticipantRole, ParticipantStatus, Reminder, ReminderMethod, Result,
     ShowAs, UnifiedCalendarEvent,
 };
 
+/// Parse a Google start, end or original start time
+pub(super) fn parse_event_time(name: &str, time: GoogleEventTime) -> std::result::Result<EventMoment, ConversionError> {
+    if let Some(date_time) = time.date_time {
+        Ok(EventMoment::DateTime {
+            date_time: DateTime::parse_from_rfc3339(&date_time).map_err(|e| {
+                ConversionError::invalid(format!("{}.dateTime", name), date_time.as_str(), e)
+            })?,
+            time_zone: time.time_zone,
+        })
+    } else if let Some(date) = time.date {
+        // All-day event; Google's end date is already exclusive
+        Ok(EventMoment::Date {
+            date: NaiveDate::parse_from_str(&date, GOOGLE_DATE_FORMAT).map_err(|e| {
+                ConversionError::invalid(format!("{}.date", name), date.as_str(), e)
+            })?,
+            time_zone: time.time_zone,
+        })
+    } else {
+        Err(ConversionError::missing(format!("{}.dateTime", name)))
+    }
+}
+
 /// Google Calendar representation
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GoogleCalendar {
@@ -18,21 +42,55 @@ pub struct GoogleCalendar {
     pub description: Option<String>,
     #[serde(rename = "backgroundColor")]
     pub background_color: Option<String>,
+    #[serde(rename = "foregroundColor")]
+    pub foreground_color: Option<String>,
     pub primary: Option<bool>,
     #[serde(rename = "accessRole")]
     pub access_role: String,
+    #[serde(rename = "timeZone")]
+    pub time_zone: Option<String>,
+    #[serde(rename = "defaultReminders")]
+    pub default_reminders: Option<Vec<GoogleReminder>>,
+    #[serde(rename = "conferenceProperties")]
+    pub conference_properties: Option<GoogleConferenceProperties>,
+    pub hidden: Option<bool>,
+    pub selected: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GoogleConferenceProperties {
+    #[serde(rename = "allowedConferenceSolutionTypes")]
+    pub allowed_conference_solution_types: Option<Vec<String>>,
 }
 
 impl From<GoogleCalendar> for Calendar {
     fn from(gc: GoogleCalendar) -> Self {
+        let access_role = match gc.access_role.as_str() {
+            "freeBusyReader" => Some(AccessRole::FreeBusyReader),
+            "reader" => Some(AccessRole::Reader),
+            "writer" => Some(AccessRole::Writer),
+            "owner" => Some(AccessRole::Owner),
+            _ => None,
+        };
         Self {
             id: gc.id,
             name: gc.summary,
             description: gc.description,
             color: gc.background_color,
             is_primary: gc.primary.unwrap_or(false),
-            can_write: matches!(gc.access_role.as_str(), "owner" | "writer"),
+            can_write: access_role.is_some_and(|role| role.can_write()),
             source: CalendarSource::Google,
+            foreground_color: gc.foreground_color,
+            time_zone: gc.time_zone,
+            access_role,
+            default_reminders: gc
+                .default_reminders
+                .map(|reminders| reminders.into_iter().map(Into::into).collect()),
+            conference_types: gc
+                .conference_properties
+                .and_then(|p| p.allowed_conference_solution_types),
+            hidden: gc.hidden,
+            selected: gc.selected,
         }
     }
 }
@@ -41,6 +99,10 @@ impl From<GoogleCalendar> for Calendar {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GoogleEvent {
     pub id: Option<String>,
+    #[serde(rename = "iCalUID")]
+    pub ical_uid: Option<String>,
+    pub sequence: Option<u32>,
+    pub etag: Option<String>,
     pub summary: Option<String>,
     pub description: Option<String>,
     pub location: Option<String>,
@@ -51,6 +113,8 @@ pub struct GoogleEvent {
     pub recurrence: Option<Vec<String>>,
     #[serde(rename = "recurringEventId")]
     pub recurring_event_id: Option<String>,
+    #[serde(rename = "originalStartTime")]
+    pub original_start_time: Option<GoogleEventTime>,
     pub status: Option<String>,
     pub visibility: Option<String>,
     pub transparency: Option<String>,
@@ -64,6 +128,64 @@ pub struct GoogleEvent {
     pub updated: Option<String>,
     #[serde(rename = "htmlLink")]
     pub html_link: Option<String>,
+    #[serde(rename = "extendedProperties")]
+    pub extended_properties: Option<GoogleExtendedProperties>,
+}
+
+/// Key/value pairs applications store on an event
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GoogleExtendedProperties {
+    /// Visible only on this copy of the event
+    pub private: Option<HashMap<String, String>>,
+    /// Visible on every attendee's copy
+    pub shared: Option<HashMap<String, String>>,
+}
+
+/// Private extended property holding the event's categories as a JSON array
+const CATEGORIES_PROPERTY: &str = "categories";
+
+/// Categories stored in the private extended properties
+///
+/// Values that are not a JSON array, e.g. written by another application,
+/// are read as a comma-separated list.
+fn read_categories(properties: Option<&GoogleExtendedProperties>) -> Option<Vec<String>> {
+    let value = properties?.private.as_ref()?.get(CATEGORIES_PROPERTY)?;
+    let categories = serde_json::from_str::<Vec<String>>(value).unwrap_or_else(|_| {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(str::to_string)
+            .collect()
+    });
+    (!categories.is_empty()).then_some(categories)
+}
+
+/// Extended properties to send for an event
+///
+/// Google replaces the whole resource on update, so the properties of a
+/// Google event's `raw` payload are kept and only the categories are set.
+fn write_extended_properties(event: &UnifiedCalendarEvent) -> Result<Option<GoogleExtendedProperties>> {
+    let mut properties = event
+        .raw
+        .as_ref()
+        .filter(|_| event.source == CalendarSource::Google)
+        .and_then(|raw| raw.get("extendedProperties"))
+        .and_then(|value| serde_json::from_value::<GoogleExtendedProperties>(value.clone()).ok())
+        .unwrap_or_default();
+    let private = properties.private.get_or_insert_with(HashMap::new);
+    match event.categories.as_deref() {
+        Some(categories) if !categories.is_empty() => {
+            private.insert(CATEGORIES_PROPERTY.to_string(), serde_json::to_string(categories)?);
+        }
+        _ => {
+            private.remove(CATEGORIES_PROPERTY);
+        }
+    }
+    if private.is_empty() {
+        properties.private = None;
+    }
+    Ok((properties.private.is_some() || properties.shared.is_some()).then_some(properties))
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -75,6 +197,27 @@ pub struct GoogleEventTime {
     pub time_zone: Option<String>,
 }
 
+/// Format of Google's `date` field for all-day events
+const GOOGLE_DATE_FORMAT: &str = "%Y-%m-%d";
+
+impl GoogleEventTime {
+    /// Write dates as `date` and instants as `dateTime`
+    pub fn from_moment(moment: &EventMoment) -> Self {
+        match moment {
+            EventMoment::Date { date, time_zone } => Self {
+                date_time: None,
+                date: Some(date.format(GOOGLE_DATE_FORMAT).to_string()),
+                time_zone: time_zone.clone(),
+            },
+            EventMoment::DateTime { date_time, time_zone } => Self {
+                date_time: Some(date_time.to_rfc3339()),
+                date: None,
+                time_zone: time_zone.clone(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GooglePerson {
     pub email: Option<String>,
@@ -86,6 +229,7 @@ pub struct GooglePerson {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GoogleAttendee {
+    pub id: Option<String>,
     pub email: Option<String>,
     #[serde(rename = "displayName")]
     pub display_name: Option<String>,
@@ -96,8 +240,14 @@ pub struct GoogleAttendee {
     pub is_self: Option<bool>,
     pub resource: Option<bool>,
     pub organizer: Option<bool>,
+    pub comment: Option<String>,
+    #[serde(rename = "additionalGuests")]
+    pub additional_guests: Option<u32>,
 }
 
+/// Domain of Google Workspace room and resource calendars
+const GOOGLE_RESOURCE_DOMAIN: &str = "@resource.calendar.google.com";
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GoogleReminders {
     #[serde(rename = "useDefault")]
@@ -111,6 +261,19 @@ pub struct GoogleReminder {
     pub minutes: i32,
 }
 
+impl From<GoogleReminder> for Reminder {
+    fn from(reminder: GoogleReminder) -> Self {
+        Self {
+            minutes_before: reminder.minutes,
+            method: match reminder.method.as_str() {
+                "email" => Some(ReminderMethod::Email),
+                "sms" => Some(ReminderMethod::Sms),
+                _ => Some(ReminderMethod::Popup),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GoogleConferenceData {
     #[serde(rename = "entryPoints")]
@@ -124,6 +287,13 @@ pub struct GoogleEntryPoint {
     pub uri: String,
 }
 
+/// User setting (`users/me/settings` item)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GoogleSetting {
+    pub id: String,
+    pub value: String,
+}
+
 /// Free/busy request
 #[derive(Debug, Serialize)]
 pub struct GoogleFreeBusyRequest {
@@ -160,23 +330,20 @@ impl GoogleEvent {
     /// Convert from unified format to Google format
     pub fn from_unified(event: &UnifiedCalendarEvent) -> Result<Self> {
         Ok(Self {
-            id: Some(event.id.clone()),
+            // Leave the id to Google for events that do not have one yet
+            id: (!event.id.is_empty()).then(|| event.id.clone()),
+            ical_uid: event.ical_uid.clone(),
+            sequence: event.sequence,
+            etag: event.etag.clone(),
             summary: event.title.clone(),
             description: event.description.clone(),
             location: event.location.clone(),
             color_id: None, // TODO: Map color to colorId
-            start: Some(GoogleEventTime {
-                date_time: Some(event.start.date_time.to_rfc3339()),
-                date: None,
-                time_zone: event.start.time_zone.clone(),
-            }),
-            end: Some(GoogleEventTime {
-                date_time: Some(event.end.date_time.to_rfc3339()),
-                date: None,
-                time_zone: event.end.time_zone.clone(),
-            }),
-            recurrence: event.recurrence_rule.as_ref().map(|r| vec![format!("RRULE:{}", r)]),
-            recurring_event_id: None,
+            start: Some(GoogleEventTime::from_moment(&event.start)),
+            end: Some(GoogleEventTime::from_moment(&event.end)),
+            recurrence: recurrence_lines(event),
+            recurring_event_id: event.series_id.clone(),
+            original_start_time: event.original_start.as_ref().map(GoogleEventTime::from_moment),
             status: event.status.as_ref().map(|s| match s {
                 EventStatus::Confirmed => "confirmed",
                 EventStatus::Tentative => "tentative",
@@ -201,9 +368,14 @@ impl GoogleEvent {
             }),
             attendees: event.attendees.as_ref().map(|attendees| {
                 attendees.iter().map(|a| GoogleAttendee {
+                    id: a.id.clone(),
                     email: a.email.clone(),
                     display_name: a.name.clone(),
-                    optional: a.optional,
+                    optional: match a.role {
+                        Some(ParticipantRole::Optional | ParticipantRole::NonParticipant) => Some(true),
+                        Some(ParticipantRole::Chair | ParticipantRole::Required) => Some(false),
+                        None => a.optional,
+                    },
                     response_status: a.response_status.as_ref().map(|s| match s {
                         ParticipantStatus::Accepted => "accepted",
                         ParticipantStatus::Tentative => "tentative",
@@ -211,8 +383,14 @@ impl GoogleEvent {
                         ParticipantStatus::NeedsAction => "needsAction",
                     }.to_string()),
                     is_self: a.is_self,
-                    resource: a.resource,
+                    resource: match a.user_type {
+                        Some(CalendarUserType::Resource | CalendarUserType::Room) => Some(true),
+                        Some(CalendarUserType::Individual | CalendarUserType::Group) => Some(false),
+                        _ => a.resource,
+                    },
                     organizer: a.organizer,
+                    comment: a.comment.clone(),
+                    additional_guests: a.additional_guests,
                 }).collect()
             }),
             reminders: event.reminders.as_ref().map(|reminders| GoogleReminders {
@@ -236,124 +414,233 @@ impl GoogleEvent {
             })),
             created: None,
             updated: None,
-            html_link: None,
+            html_link: event.html_link.clone(),
+            extended_properties: write_extended_properties(event)?,
+        })
+    }
+
+    /// Whether this is a deletion marker rather than an event
+    ///
+    /// With `showDeleted` Google returns cancelled items that carry no
+    /// start or end, only the ID, the status and for series instances the
+    /// series and original start.
+    pub fn is_tombstone(&self) -> bool {
+        self.status.as_deref() == Some("cancelled") && self.start.is_none() && self.end.is_none()
+    }
+
+    /// Convert a deletion marker, see [`GoogleEvent::is_tombstone`]
+    pub fn into_tombstone(self) -> std::result::Result<CancelledEvent, ConversionError> {
+        let item_id = self.id.clone();
+        Ok(CancelledEvent {
+            id: self.id.ok_or_else(|| ConversionError::missing("id"))?,
+            series_id: self.recurring_event_id,
+            original_start: self
+                .original_start_time
+                .map(|t| parse_event_time("originalStartTime", t))
+                .transpose()
+                .map_err(|e| e.for_item(item_id))?,
         })
     }
 
     /// Convert to unified format
-    pub fn into_unified(self) -> UnifiedCalendarEvent {
-        let parse_time = |time: Option<GoogleEventTime>| -> EventMoment {
-            if let Some(t) = time {
-                if let Some(date_time) = t.date_time {
-                    EventMoment {
-                        date_time: DateTime::parse_from_rfc3339(&date_time)
-                            .unwrap_or_else(|_| DateTime::<FixedOffset>::from(chrono::Utc::now())),
-                        time_zone: t.time_zone,
-                        all_day: Some(false),
-                    }
-                } else if let Some(date) = t.date {
-                    // All-day event
-                    EventMoment {
-                        date_time: DateTime::parse_from_str(&format!("{}T00:00:00Z", date), "%Y-%m-%dT%H:%M:%SZ")
-                            .unwrap_or_else(|_| DateTime::<FixedOffset>::from(chrono::Utc::now())),
-                        time_zone: t.time_zone,
-                        all_day: Some(true),
-                    }
-                } else {
-                    EventMoment {
-                        date_time: DateTime::<FixedOffset>::from(chrono::Utc::now()),
-                        time_zone: None,
-                        all_day: Some(false),
-                    }
-                }
-            } else {
-                EventMoment {
-                    date_time: DateTime::<FixedOffset>::from(chrono::Utc::now()),
-                    time_zone: None,
-                    all_day: Some(false),
-                }
-            }
+    ///
+    /// Fails instead of guessing when the ID or a start/end time is missing
+    /// or malformed.
+    pub fn into_unified(self) -> std::result::Result<UnifiedCalendarEvent, ConversionError> {
+        self.convert_to_unified(true)
+    }
+
+    /// Convert to unified format without copying the payload into `raw`
+    pub fn into_unified_without_raw(self) -> std::result::Result<UnifiedCalendarEvent, ConversionError> {
+        self.convert_to_unified(false)
+    }
+
+    fn convert_to_unified(self, keep_raw: bool) -> std::result::Result<UnifiedCalendarEvent, ConversionError> {
+        let item_id = self.id.clone();
+        self.try_convert_to_unified(keep_raw)
+            .map_err(|e| e.for_item(item_id))
+    }
+
+    fn try_convert_to_unified(self, keep_raw: bool) -> std::result::Result<UnifiedCalendarEvent, ConversionError> {
+        let parse_time = |name: &str, time: Option<GoogleEventTime>| {
+            parse_event_time(name, time.ok_or_else(|| ConversionError::missing(name))?)
+        };
+        let parse_timestamp = |name: &str, value: Option<String>| {
+            value
+                .map(|v| {
+                    DateTime::parse_from_rfc3339(&v)
+                        .map_err(|e| ConversionError::invalid(name, v.as_str(), e))
+                })
+                .transpose()
         };
 
-        UnifiedCalendarEvent {
-            id: self.id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        // Serialize before fields are moved out so the payload is copied at most once
+        let raw = if keep_raw { serde_json::to_value(&self).ok() } else { None };
+
+        let (recurrence_rule, recurrence_exceptions, recurrence) =
+            split_recurrence(self.recurrence.unwrap_or_default());
+
+        Ok(UnifiedCalendarEvent {
+            id: self.id.ok_or_else(|| ConversionError::missing("id"))?,
             source: CalendarSource::Google,
             calendar_id: None,
-            title: self.summary.clone(),
-            description: self.description.clone(),
-            location: self.location.clone(),
-            color: self.color_id.clone(),
-            start: parse_time(self.start.clone()),
-            end: parse_time(self.end.clone()),
-            recurrence_rule: self.recurrence.as_ref().and_then(|rules| {
-                rules.first().map(|r| r.strip_prefix("RRULE:").unwrap_or(r).to_string())
-            }),
-            recurrence_exceptions: None,
-            organizer: self.organizer.as_ref().map(|p| Participant {
+            ical_uid: self.ical_uid,
+            sequence: self.sequence,
+            title: self.summary,
+            description: self.description,
+            location: self.location,
+            color: self.color_id,
+            categories: read_categories(self.extended_properties.as_ref()),
+            start: parse_time("start", self.start)?,
+            end: parse_time("end", self.end)?,
+            recurrence_rule,
+            recurrence_exceptions,
+            recurrence,
+            series_id: self.recurring_event_id,
+            original_start: self
+                .original_start_time
+                .map(|t| parse_time("originalStartTime", Some(t)))
+                .transpose()?,
+            organizer: self.organizer.map(|p| Participant {
                 id: None,
-                email: p.email.clone(),
-                name: p.display_name.clone(),
+                email: p.email,
+                name: p.display_name,
                 optional: Some(false),
                 response_status: None,
                 is_self: p.is_self,
                 resource: Some(false),
                 organizer: Some(true),
+                role: Some(ParticipantRole::Chair),
+                user_type: Some(CalendarUserType::Individual),
+                ..Default::default()
             }),
-            attendees: self.attendees.as_ref().map(|attendees| {
-                attendees.iter().map(|a| Participant {
-                    id: None,
-                    email: a.email.clone(),
-                    name: a.display_name.clone(),
-                    optional: a.optional,
-                    response_status: a.response_status.as_ref().and_then(|s| match s.as_str() {
+            attendees: self.attendees.map(|attendees| {
+                attendees.into_iter().map(|a| Participant {
+                    id: a.id,
+                    role: Some(if a.optional == Some(true) {
+                        ParticipantRole::Optional
+                    } else {
+                        ParticipantRole::Required
+                    }),
+                    user_type: Some(match (a.resource, &a.email) {
+                        (Some(true), Some(email)) if email.ends_with(GOOGLE_RESOURCE_DOMAIN) => CalendarUserType::Room,
+                        (Some(true), _) => CalendarUserType::Resource,
+                        _ => CalendarUserType::Individual,
+                    }),
+                    response_status: a.response_status.as_deref().and_then(|s| match s {
                         "accepted" => Some(ParticipantStatus::Accepted),
                         "tentative" => Some(ParticipantStatus::Tentative),
                         "declined" => Some(ParticipantStatus::Declined),
                         "needsAction" => Some(ParticipantStatus::NeedsAction),
                         _ => None,
                     }),
+                    email: a.email,
+                    name: a.display_name,
+                    optional: a.optional,
                     is_self: a.is_self,
                     resource: a.resource,
                     organizer: a.organizer,
+                    delegated_to: None,
+                    delegated_from: None,
+                    comment: a.comment,
+                    additional_guests: a.additional_guests,
                 }).collect()
             }),
-            status: self.status.as_ref().and_then(|s| match s.as_str() {
+            status: self.status.as_deref().and_then(|s| match s {
                 "confirmed" => Some(EventStatus::Confirmed),
                 "tentative" => Some(EventStatus::Tentative),
                 "cancelled" => Some(EventStatus::Cancelled),
                 _ => None,
             }),
-            visibility: self.visibility.as_ref().and_then(|v| match v.as_str() {
+            visibility: self.visibility.as_deref().and_then(|v| match v {
                 "default" => Some(EventVisibility::Default),
                 "public" => Some(EventVisibility::Public),
                 "private" => Some(EventVisibility::Private),
                 "confidential" => Some(EventVisibility::Confidential),
                 _ => None,
             }),
-            show_as: self.transparency.as_ref().and_then(|t| match t.as_str() {
+            show_as: self.transparency.as_deref().and_then(|t| match t {
                 "transparent" => Some(ShowAs::Free),
                 "opaque" => Some(ShowAs::Busy),
                 _ => None,
             }),
-            reminders: self.reminders.as_ref().and_then(|r| r.overrides.as_ref().map(|overrides| {
-                overrides.iter().map(|o| Reminder {
-                    minutes_before: o.minutes,
-                    method: match o.method.as_str() {
-                        "email" => Some(ReminderMethod::Email),
-                        "sms" => Some(ReminderMethod::Sms),
-                        _ => Some(ReminderMethod::Popup),
-                    },
-                }).collect()
+            reminders: self.reminders.and_then(|r| r.overrides.map(|overrides| {
+                overrides.into_iter().map(Into::into).collect()
             })),
-            conference: self.conference_data.as_ref().and_then(|cd| {
-                cd.entry_points.as_ref().and_then(|eps| eps.first().map(|ep| ConferenceLink {
-                    url: Some(ep.uri.clone()),
+            conference: self.conference_data.and_then(|cd| {
+                cd.entry_points.and_then(|eps| eps.into_iter().next().map(|ep| ConferenceLink {
+                    url: Some(ep.uri),
                     provider: Some("Google Meet".to_string()),
                 }))
             }),
-            raw: serde_json::to_value(&self).ok(),
-            created: self.created.as_ref().and_then(|c| DateTime::parse_from_rfc3339(c).ok()),
-            updated: self.updated.as_ref().and_then(|u| DateTime::parse_from_rfc3339(u).ok()),
+            raw,
+            created: parse_timestamp("created", self.created)?,
+            updated: parse_timestamp("updated", self.updated)?,
+            etag: self.etag,
+            html_link: self.html_link,
+        })
+    }
+}
+
+/// Google `recurrence` lines for a unified event
+///
+/// The raw RRULE and EXDATE strings are sent verbatim while they still
+/// describe the typed pattern, so unedited events round-trip byte for byte;
+/// extra RRULEs and RDATEs come from the pattern, and an edited pattern is
+/// sent in its own formatting.
+fn recurrence_lines(event: &UnifiedCalendarEvent) -> Option<Vec<String>> {
+    let raw = event.recurrence_rule.as_ref().map(|rule| {
+        let mut lines = vec![if rule.starts_with("RRULE:") { rule.clone() } else { format!("RRULE:{}", rule) }];
+        lines.extend(event.recurrence_exceptions.iter().flatten().map(|line| {
+            if line.contains(':') {
+                line.clone()
+            } else {
+                format!("EXDATE:{}", line)
+            }
+        }));
+        lines
+    });
+
+    let raw = match (&event.recurrence, raw) {
+        (Some(pattern), Some(mut raw)) => {
+            raw.extend(pattern.rules.iter().skip(1).map(|rule| format!("RRULE:{}", rule)));
+            raw.extend(pattern.rdates.iter().map(|d| d.to_line("RDATE")));
+            Some(raw)
         }
+        (_, raw) => raw,
+    };
+
+    match (&event.recurrence, raw) {
+        (Some(pattern), Some(raw)) if RecurrencePattern::parse(&raw).ok().as_ref() == Some(pattern) => Some(raw),
+        (Some(pattern), _) => Some(pattern.to_lines()),
+        (None, raw) => raw,
     }
-}
\ No newline at end of file
+}
+
+/// Split Google `recurrence` lines into the RRULE value, the EXDATE lines
+/// and the typed pattern
+///
+/// Extra RRULE and RDATE lines are only kept in the typed pattern. When the
+/// recurrence parser rejects the lines, the RRULE and EXDATE lines are
+/// still kept verbatim and the rest is left to the event's `raw` payload.
+fn split_recurrence(
+    mut lines: Vec<String>,
+) -> (Option<String>, Option<Vec<String>>, Option<RecurrencePattern>) {
+    if lines.is_empty() {
+        return (None, None, None);
+    }
+    let pattern = match RecurrencePattern::parse(&lines) {
+        Ok(pattern) => Some(pattern),
+        Err(e) => {
+            tracing::warn!("Keeping unparsed recurrence {:?}: {}", lines, e);
+            None
+        }
+    };
+    let rule = lines
+        .iter()
+        .position(|line| line.starts_with("RRULE:"))
+        .map(|i| lines.remove(i)["RRULE:".len()..].to_string());
+    lines.retain(|line| line.starts_with("EXDATE"));
+    let exceptions = if lines.is_empty() { None } else { Some(lines) };
+    (rule, exceptions, pattern)
+}
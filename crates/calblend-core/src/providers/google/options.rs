@@ -0,0 +1,247 @@
+//! Request options for Google Calendar event listing
+
+use chrono::{DateTime, Utc};
+
+/// Options controlling how events are listed from Google Calendar
+///
+/// Filters are sent to Google as query parameters rather than applied
+/// client side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListEventsOptions {
+    /// Free-text search (`q`) over summary, description, location,
+    /// attendees and organizer
+    pub query: Option<String>,
+    /// Include cancelled events (`showDeleted`)
+    pub show_deleted: Option<bool>,
+    /// Only events modified after this time (`updatedMin`)
+    pub updated_min: Option<DateTime<Utc>>,
+    /// Only events with this iCalendar UID (`iCalUID`)
+    pub ical_uid: Option<String>,
+    /// Private extended property constraints (`privateExtendedProperty`),
+    /// all of which must match
+    pub private_extended_properties: Vec<(String, String)>,
+    /// Shared extended property constraints (`sharedExtendedProperty`),
+    /// all of which must match
+    pub shared_extended_properties: Vec<(String, String)>,
+    /// Include hidden invitations (`showHiddenInvitations`)
+    pub show_hidden_invitations: Option<bool>,
+    /// Time zone used in the response (`timeZone`), an IANA name
+    pub time_zone: Option<String>,
+    /// Partial response selector sent as Google's `fields` parameter,
+    /// e.g. `items(id,start,end,status)`
+    pub fields: Option<String>,
+    /// Page size sent as `maxResults` (Google caps this at 2500)
+    pub max_results: Option<u32>,
+    /// Drop the provider payload instead of copying it into `raw`
+    pub skip_raw: bool,
+    /// Skip events that fail to convert instead of failing the whole listing
+    pub lenient: bool,
+    /// Return each occurrence of a recurring series (`singleEvents`);
+    /// when false, series come back as their master event plus the
+    /// occurrences that were changed or cancelled
+    pub single_events: bool,
+}
+
+impl Default for ListEventsOptions {
+    fn default() -> Self {
+        Self {
+            query: None,
+            show_deleted: None,
+            updated_min: None,
+            ical_uid: None,
+            private_extended_properties: Vec::new(),
+            shared_extended_properties: Vec::new(),
+            show_hidden_invitations: None,
+            time_zone: None,
+            fields: None,
+            max_results: None,
+            skip_raw: false,
+            lenient: false,
+            single_events: true,
+        }
+    }
+}
+
+impl ListEventsOptions {
+    /// Largest page size Google accepts for `maxResults`
+    pub const MAX_PAGE_SIZE: u32 = 2500;
+
+    /// Options for availability views that only need time blocks
+    ///
+    /// Requests just the fields needed to place an event on a timeline and
+    /// skips the `raw` payload copy.
+    pub fn time_blocks() -> Self {
+        Self::default()
+            .with_event_fields(&["id", "status", "start", "end", "transparency"])
+            .with_max_results(Self::MAX_PAGE_SIZE)
+            .without_raw()
+    }
+
+    /// Set the free-text search query
+    pub fn with_query(mut self, query: impl Into<String>) -> Self {
+        self.query = Some(query.into());
+        self
+    }
+
+    /// Include or exclude cancelled events
+    pub fn with_show_deleted(mut self, show_deleted: bool) -> Self {
+        self.show_deleted = Some(show_deleted);
+        self
+    }
+
+    /// Only return events modified after the given time
+    pub fn with_updated_min(mut self, updated_min: DateTime<Utc>) -> Self {
+        self.updated_min = Some(updated_min);
+        self
+    }
+
+    /// Only return events with the given iCalendar UID
+    pub fn with_ical_uid(mut self, ical_uid: impl Into<String>) -> Self {
+        self.ical_uid = Some(ical_uid.into());
+        self
+    }
+
+    /// Require a private extended property to have the given value
+    pub fn with_private_extended_property(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.private_extended_properties.push((name.into(), value.into()));
+        self
+    }
+
+    /// Require a shared extended property to have the given value
+    pub fn with_shared_extended_property(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.shared_extended_properties.push((name.into(), value.into()));
+        self
+    }
+
+    /// Include or exclude hidden invitations
+    pub fn with_show_hidden_invitations(mut self, show: bool) -> Self {
+        self.show_hidden_invitations = Some(show);
+        self
+    }
+
+    /// Set the time zone used in the response
+    pub fn with_time_zone(mut self, time_zone: impl Into<String>) -> Self {
+        self.time_zone = Some(time_zone.into());
+        self
+    }
+
+    /// Set the raw `fields` selector
+    pub fn with_fields(mut self, fields: impl Into<String>) -> Self {
+        self.fields = Some(fields.into());
+        self
+    }
+
+    /// Restrict each returned event to the given top-level fields
+    pub fn with_event_fields(self, fields: &[&str]) -> Self {
+        self.with_fields(format!("items({})", fields.join(",")))
+    }
+
+    /// Set the page size
+    pub fn with_max_results(mut self, max_results: u32) -> Self {
+        self.max_results = Some(max_results.clamp(1, Self::MAX_PAGE_SIZE));
+        self
+    }
+
+    /// Do not keep the provider payload in `raw`
+    pub fn without_raw(mut self) -> Self {
+        self.skip_raw = true;
+        self
+    }
+
+    /// Skip and report events that fail to convert
+    pub fn lenient(mut self) -> Self {
+        self.lenient = true;
+        self
+    }
+
+    /// Expand recurring series into occurrences, or return them as series
+    pub fn with_single_events(mut self, single_events: bool) -> Self {
+        self.single_events = single_events;
+        self
+    }
+
+    /// The `fields` value to send, always keeping `nextPageToken` so that
+    /// pagination still works with a projection
+    pub(crate) fn fields_param(&self) -> Option<String> {
+        self.fields.as_ref().map(|fields| {
+            if fields.split(',').any(|f| f.trim() == "nextPageToken") {
+                fields.clone()
+            } else {
+                format!("nextPageToken,{}", fields)
+            }
+        })
+    }
+
+    /// Query parameters that change which events or fields are returned
+    fn filter_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+
+        if let Some(query) = &self.query {
+            params.push(("q", query.clone()));
+        }
+        if let Some(show_deleted) = self.show_deleted {
+            params.push(("showDeleted", show_deleted.to_string()));
+        }
+        if let Some(updated_min) = self.updated_min {
+            params.push(("updatedMin", updated_min.to_rfc3339()));
+        }
+        if let Some(ical_uid) = &self.ical_uid {
+            params.push(("iCalUID", ical_uid.clone()));
+        }
+        for (name, value) in &self.private_extended_properties {
+            params.push(("privateExtendedProperty", format!("{}={}", name, value)));
+        }
+        for (name, value) in &self.shared_extended_properties {
+            params.push(("sharedExtendedProperty", format!("{}={}", name, value)));
+        }
+        if let Some(show) = self.show_hidden_invitations {
+            params.push(("showHiddenInvitations", show.to_string()));
+        }
+        if let Some(time_zone) = &self.time_zone {
+            params.push(("timeZone", time_zone.clone()));
+        }
+        if let Some(fields) = self.fields_param() {
+            params.push(("fields", fields));
+        }
+        params.push(("singleEvents", self.single_events.to_string()));
+
+        params
+    }
+
+    /// URL-encoded `name=value` pairs for the events list request
+    pub(crate) fn query_params(&self) -> Vec<String> {
+        let mut params: Vec<String> = self
+            .filter_params()
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", name, urlencoding::encode(&value)))
+            .collect();
+
+        if let Some(max_results) = self.max_results {
+            params.push(format!("maxResults={}", max_results));
+        }
+
+        params
+    }
+
+    /// Stable key identifying these options in the event cache
+    ///
+    /// The page size is left out since it does not change the result.
+    pub(crate) fn cache_key(&self) -> String {
+        let mut key: Vec<String> = self
+            .filter_params()
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        key.push(format!("raw={}", !self.skip_raw));
+        key.push(format!("lenient={}", self.lenient));
+        key.join(";")
+    }
+}
@@ -1,156 +1,1176 @@
 //! Tests for Google Calendar provider
 
-#[cfg(test)]
-mod tests {
-    use super::super::*;
-    use crate::{
-        auth::{test_utils::InMemoryTokenStorage, TokenData},
-        CalendarSource, EventMoment,
+use super::*;
+use crate::{
+    auth::{test_utils::InMemoryTokenStorage, TokenData},
+    CalendarSource, EventMoment,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use wiremock::matchers::{method, path, bearer_token, body_string_contains, header, header_regex, query_param};
+
+async fn setup_mock_provider() -> (GoogleCalendarProvider, MockServer) {
+    setup_mock_provider_with_expiry(Utc::now() + chrono::Duration::hours(1)).await
+}
+
+async fn setup_mock_provider_with_expiry(
+    expires_at: DateTime<Utc>,
+) -> (GoogleCalendarProvider, MockServer) {
+    let mock_server = MockServer::start().await;
+    let token_storage = Arc::new(InMemoryTokenStorage::default());
+    
+    // Store a test token
+    let token = TokenData {
+        access_token: "test_access_token".to_string(),
+        refresh_token: Some("test_refresh_token".to_string()),
+        expires_at: Some(expires_at),
+        token_type: "Bearer".to_string(),
+        scope: Some("https://www.googleapis.com/auth/calendar".to_string()),
     };
-    use chrono::{DateTime, Utc};
-    use std::sync::Arc;
-    use wiremock::{Mock, MockServer, ResponseTemplate};
-    use wiremock::matchers::{method, path, bearer_token};
-
-    async fn setup_mock_provider() -> (GoogleCalendarProvider, MockServer) {
-        let mock_server = MockServer::start().await;
-        let token_storage = Arc::new(InMemoryTokenStorage::default());
-        
-        // Store a test token
-        let token = TokenData {
-            access_token: "test_access_token".to_string(),
-            refresh_token: Some("test_refresh_token".to_string()),
-            expires_at: Some(Utc::now() + chrono::Duration::hours(1)),
-            token_type: "Bearer".to_string(),
-            scope: Some("https://www.googleapis.com/auth/calendar".to_string()),
-        };
-        token_storage.save_token(CalendarSource::Google, token).await.unwrap();
-
-        let config = CalblendConfig::default();
-        let provider = GoogleCalendarProvider::new(
-            "test_client_id".to_string(),
-            "test_client_secret".to_string(),
-            "http://localhost:8080/callback".to_string(),
-            token_storage,
-            config,
-        ).unwrap();
-
-        (provider, mock_server)
+    token_storage.save_token(CalendarSource::Google, token).await.unwrap();
+
+    let config = CalblendConfig::default().with_max_retries(0);
+    let provider = GoogleCalendarProvider::with_endpoints(
+        "test_client_id".to_string(),
+        "test_client_secret".to_string(),
+        "http://localhost:8080/callback".to_string(),
+        token_storage,
+        config,
+        GoogleEndpoints::with_base_url(&mock_server.uri()),
+    ).unwrap();
+
+    (provider, mock_server)
+}
+
+#[tokio::test]
+async fn test_list_calendars() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/users/me/calendarList"))
+        .and(bearer_token("test_access_token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                {
+                    "id": "primary",
+                    "summary": "My Primary Calendar",
+                    "description": "Main calendar",
+                    "backgroundColor": "#4285F4",
+                    "primary": true,
+                    "accessRole": "owner"
+                },
+                {
+                    "id": "work@example.com",
+                    "summary": "Work Calendar",
+                    "backgroundColor": "#DB4437",
+                    "primary": false,
+                    "accessRole": "writer"
+                }
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let calendars = provider.list_calendars().await.unwrap();
+    assert_eq!(calendars.len(), 2);
+    
+    let primary = &calendars[0];
+    assert_eq!(primary.id, "primary");
+    assert_eq!(primary.name, "My Primary Calendar");
+    assert!(primary.is_primary);
+    assert!(primary.can_write);
+    
+    let work = &calendars[1];
+    assert_eq!(work.id, "work@example.com");
+    assert_eq!(work.name, "Work Calendar");
+    assert!(!work.is_primary);
+    assert!(work.can_write);
+}
+
+#[tokio::test]
+async fn test_create_event() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    let new_event = UnifiedCalendarEvent::new(
+        "temp_id".to_string(),
+        CalendarSource::Google,
+        EventMoment::timed(
+            DateTime::parse_from_rfc3339("2024-01-20T10:00:00-08:00").unwrap(),
+            Some("America/Los_Angeles".to_string()),
+        ),
+        EventMoment::timed(
+            DateTime::parse_from_rfc3339("2024-01-20T11:00:00-08:00").unwrap(),
+            Some("America/Los_Angeles".to_string()),
+        ),
+    );
+
+    Mock::given(method("POST"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .and(bearer_token("test_access_token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "generated_event_id",
+            "summary": null,
+            "start": {
+                "dateTime": "2024-01-20T10:00:00-08:00",
+                "timeZone": "America/Los_Angeles"
+            },
+            "end": {
+                "dateTime": "2024-01-20T11:00:00-08:00",
+                "timeZone": "America/Los_Angeles"
+            },
+            "status": "confirmed",
+            "created": "2024-01-15T12:00:00Z",
+            "updated": "2024-01-15T12:00:00Z"
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let created = provider.create_event("primary", new_event).await.unwrap();
+    assert_eq!(created.id, "generated_event_id");
+    assert_eq!(created.source, CalendarSource::Google);
+}
+
+#[tokio::test]
+async fn test_create_event_without_notifications() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    let new_event = UnifiedCalendarEvent::new(
+        String::new(),
+        CalendarSource::Google,
+        EventMoment::all_day(chrono::NaiveDate::from_ymd_opt(2024, 1, 20).unwrap()),
+        EventMoment::all_day(chrono::NaiveDate::from_ymd_opt(2024, 1, 21).unwrap()),
+    );
+
+    Mock::given(method("POST"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .and(query_param("sendUpdates", "none"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "quiet_event_id",
+            "start": { "date": "2024-01-20" },
+            "end": { "date": "2024-01-21" }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let created = provider
+        .create_event_with_notifications("primary", new_event, false)
+        .await
+        .unwrap();
+    assert_eq!(created.id, "quiet_event_id");
+}
+
+#[tokio::test]
+async fn test_auth_url_generation() {
+    let token_storage = Arc::new(InMemoryTokenStorage::default());
+    let config = CalblendConfig::default();
+    let provider = GoogleCalendarProvider::new(
+        "test_client_id".to_string(),
+        "test_client_secret".to_string(),
+        "http://localhost:8080/callback".to_string(),
+        token_storage,
+        config,
+    ).unwrap();
+
+    let auth_url = provider.get_auth_url().await.unwrap();
+    
+    // Verify the URL contains expected components
+    assert!(auth_url.contains("https://accounts.google.com/o/oauth2/v2/auth"));
+    assert!(auth_url.contains("client_id=test_client_id"));
+    assert!(auth_url.contains("redirect_uri=http%3A%2F%2Flocalhost%3A8080%2Fcallback"));
+    assert!(auth_url.contains("response_type=code"));
+    assert!(auth_url.contains("scope="));
+    assert!(auth_url.contains("code_challenge=")); // PKCE
+}
+
+#[tokio::test]
+async fn test_expired_token_is_refreshed_against_configured_token_url() {
+    let (provider, mock_server) =
+        setup_mock_provider_with_expiry(Utc::now() - chrono::Duration::hours(1)).await;
+
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains("grant_type=refresh_token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": "refreshed_access_token",
+            "token_type": "Bearer",
+            "expires_in": 3600
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/users/me/calendarList"))
+        .and(bearer_token("refreshed_access_token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": []
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let calendars = provider.list_calendars().await.unwrap();
+    assert!(calendars.is_empty());
+}
+
+#[tokio::test]
+async fn test_watch_calendar_uses_configured_api_base() {
+    let (provider, mock_server) = setup_mock_provider().await;
+    let provider = provider.with_webhook_endpoint("https://example.com/hook".to_string());
+
+    Mock::given(method("POST"))
+        .and(path("/calendar/v3/calendars/primary/events/watch"))
+        .and(bearer_token("test_access_token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "kind": "api#channel",
+            "id": "channel-1",
+            "resourceId": "resource-1",
+            "resourceUri": format!("{}/calendar/v3/calendars/primary/events", mock_server.uri()),
+            "expiration": "2030-01-01T00:00:00Z"
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/calendar/v3/channels/stop"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let channel = provider.watch_calendar("primary", None, Some(1)).await.unwrap();
+    assert_eq!(channel.id, "channel-1");
+    assert_eq!(channel.resource_id, "resource-1");
+
+    provider.stop_watch(&channel.id, &channel.resource_id).await.unwrap();
+}
+
+#[test]
+fn test_endpoints_with_base_url() {
+    let endpoints = GoogleEndpoints::with_base_url("http://127.0.0.1:9000/");
+    assert_eq!(endpoints.api_base_url, "http://127.0.0.1:9000/calendar/v3");
+    assert_eq!(endpoints.auth_url, "http://127.0.0.1:9000/o/oauth2/v2/auth");
+    assert_eq!(endpoints.token_url, "http://127.0.0.1:9000/token");
+    assert_eq!(endpoints.revoke_url, "http://127.0.0.1:9000/revoke");
+
+    let endpoints = endpoints
+        .with_api_base_url("http://api.test/v3/")
+        .with_auth_url("http://auth.test/auth/")
+        .with_token_url("http://auth.test/token/")
+        .with_revoke_url("http://auth.test/revoke//");
+    assert_eq!(endpoints.api_base_url, "http://api.test/v3");
+    assert_eq!(endpoints.auth_url, "http://auth.test/auth");
+    assert_eq!(endpoints.token_url, "http://auth.test/token");
+    assert_eq!(endpoints.revoke_url, "http://auth.test/revoke");
+}
+
+#[tokio::test]
+async fn test_list_events_with_partial_response() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .and(query_param("fields", "nextPageToken,items(id,status,start,end,transparency)"))
+        .and(query_param("maxResults", "2500"))
+        .and(header_regex("accept-encoding", "gzip"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                {
+                    "id": "evt1",
+                    "status": "confirmed",
+                    "start": { "dateTime": "2024-01-20T10:00:00Z" },
+                    "end": { "dateTime": "2024-01-20T11:00:00Z" }
+                }
+            ]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let events = provider
+        .list_events_with_options("primary", None, None, &ListEventsOptions::time_blocks())
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id, "evt1");
+    assert!(events[0].raw.is_none());
+    assert!(events[0].title.is_none());
+}
+
+#[test]
+fn test_fields_param_keeps_page_token() {
+    let options = ListEventsOptions::default().with_fields("items(id)");
+    assert_eq!(options.fields_param().as_deref(), Some("nextPageToken,items(id)"));
+
+    let options = ListEventsOptions::default().with_fields("nextPageToken,items(id)");
+    assert_eq!(options.fields_param().as_deref(), Some("nextPageToken,items(id)"));
+
+    assert_eq!(ListEventsOptions::default().with_max_results(10_000).max_results, Some(2500));
+}
+
+#[tokio::test]
+async fn test_list_events_passes_filters_to_google() {
+    let (provider, mock_server) = setup_mock_provider().await;
+    let updated_min = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .and(query_param("q", "team sync"))
+        .and(query_param("showDeleted", "true"))
+        .and(query_param("updatedMin", "2024-01-01T00:00:00+00:00"))
+        .and(query_param("iCalUID", "abc@example.com"))
+        .and(query_param("privateExtendedProperty", "crm=42"))
+        .and(query_param("sharedExtendedProperty", "team=core"))
+        .and(query_param("showHiddenInvitations", "false"))
+        .and(query_param("timeZone", "Europe/Berlin"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                {
+                    "id": "evt1",
+                    "start": { "dateTime": "2024-01-20T10:00:00Z" },
+                    "end": { "dateTime": "2024-01-20T11:00:00Z" }
+                }
+            ]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let options = ListEventsOptions::default()
+        .with_query("team sync")
+        .with_show_deleted(true)
+        .with_updated_min(updated_min)
+        .with_ical_uid("abc@example.com")
+        .with_private_extended_property("crm", "42")
+        .with_shared_extended_property("team", "core")
+        .with_show_hidden_invitations(false)
+        .with_time_zone("Europe/Berlin");
+
+    let events = provider
+        .list_events_with_options("primary", None, None, &options)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+
+    // Same options are served from the cache
+    let cached = provider
+        .list_events_with_options("primary", None, None, &options)
+        .await
+        .unwrap();
+    assert_eq!(cached.len(), 1);
+}
+
+#[test]
+fn test_cache_key_distinguishes_filters() {
+    let plain = ListEventsOptions::default();
+    let searched = ListEventsOptions::default().with_query("standup");
+    assert_ne!(plain.cache_key(), searched.cache_key());
+    assert_eq!(
+        plain.cache_key(),
+        ListEventsOptions::default().with_max_results(50).cache_key()
+    );
+}
+
+fn notification_for(resource_uri: String) -> PushNotification {
+    PushNotification {
+        channel_id: "channel-1".to_string(),
+        channel_token: Some("secret".to_string()),
+        channel_expiration: None,
+        resource_id: "resource-1".to_string(),
+        resource_state: "exists".to_string(),
+        resource_uri,
+        message_number: Some("2".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_calendar_list_notification_refreshes_cache() {
+    let (provider, mock_server) = setup_mock_provider().await;
+    let provider = provider.with_webhook_endpoint("https://example.com/hook".to_string());
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/users/me/calendarList"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                { "id": "primary", "summary": "Primary", "accessRole": "owner" },
+                { "id": "team", "summary": "Team", "accessRole": "reader" }
+            ]
+        })))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/users/me/calendarList"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                { "id": "primary", "summary": "Primary", "accessRole": "owner" }
+            ]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    assert_eq!(provider.list_calendars().await.unwrap().len(), 2);
+
+    let notification = notification_for(format!(
+        "{}/calendar/v3/users/me/calendarList?alt=json",
+        mock_server.uri()
+    ));
+    match provider.process_notification(&notification, Some("secret")).await.unwrap() {
+        NotificationChange::CalendarList { calendars } => assert_eq!(calendars.len(), 1),
+        other => panic!("unexpected change: {:?}", other),
     }
 
-    #[tokio::test]
-    async fn test_list_calendars() {
-        let (provider, mock_server) = setup_mock_provider().await;
-
-        Mock::given(method("GET"))
-            .and(path("/calendar/v3/users/me/calendarList"))
-            .and(bearer_token("test_access_token"))
-            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
-                "items": [
-                    {
-                        "id": "primary",
-                        "summary": "My Primary Calendar",
-                        "description": "Main calendar",
-                        "backgroundColor": "#4285F4",
-                        "primary": true,
-                        "accessRole": "owner"
-                    },
-                    {
-                        "id": "work@example.com",
-                        "summary": "Work Calendar",
-                        "backgroundColor": "#DB4437",
-                        "primary": false,
-                        "accessRole": "writer"
-                    }
-                ]
-            })))
-            .mount(&mock_server)
-            .await;
-
-        // Override the base URL for testing
-        // Note: In a real implementation, we'd make the base URL configurable
-
-        let calendars = provider.list_calendars().await.unwrap();
-        assert_eq!(calendars.len(), 2);
-        
-        let primary = &calendars[0];
-        assert_eq!(primary.id, "primary");
-        assert_eq!(primary.name, "My Primary Calendar");
-        assert!(primary.is_primary);
-        assert!(primary.can_write);
-        
-        let work = &calendars[1];
-        assert_eq!(work.id, "work@example.com");
-        assert_eq!(work.name, "Work Calendar");
-        assert!(!work.is_primary);
-        assert!(work.can_write);
+    // Served from the refreshed cache
+    assert_eq!(provider.list_calendars().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_settings_notification_returns_settings() {
+    let (provider, mock_server) = setup_mock_provider().await;
+    let provider = provider.with_webhook_endpoint("https://example.com/hook".to_string());
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/users/me/settings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                { "id": "timezone", "value": "Europe/Berlin" },
+                { "id": "locale", "value": "de" }
+            ]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let notification = notification_for(format!(
+        "{}/calendar/v3/users/me/settings?alt=json",
+        mock_server.uri()
+    ));
+    match provider.process_notification(&notification, Some("secret")).await.unwrap() {
+        NotificationChange::Settings { settings } => {
+            assert_eq!(settings.get("timezone").map(String::as_str), Some("Europe/Berlin"));
+        }
+        other => panic!("unexpected change: {:?}", other),
     }
 
-    #[tokio::test]
-    async fn test_create_event() {
-        let (provider, mock_server) = setup_mock_provider().await;
-
-        let new_event = UnifiedCalendarEvent::new(
-            "temp_id".to_string(),
-            CalendarSource::Google,
-            EventMoment {
-                date_time: DateTime::parse_from_rfc3339("2024-01-20T10:00:00-08:00").unwrap(),
-                time_zone: Some("America/Los_Angeles".to_string()),
-                all_day: Some(false),
-            },
-            EventMoment {
-                date_time: DateTime::parse_from_rfc3339("2024-01-20T11:00:00-08:00").unwrap(),
-                time_zone: Some("America/Los_Angeles".to_string()),
-                all_day: Some(false),
+    let mut sync = notification_for(format!("{}/calendar/v3/users/me/settings", mock_server.uri()));
+    sync.resource_state = "sync".to_string();
+    assert!(matches!(
+        provider.process_notification(&sync, Some("secret")).await.unwrap(),
+        NotificationChange::Sync { resource: WatchResource::Settings }
+    ));
+}
+
+#[test]
+fn test_all_day_event_round_trip() {
+    let google_event: models::GoogleEvent = serde_json::from_value(serde_json::json!({
+        "id": "holiday",
+        "summary": "Public holiday",
+        "start": { "date": "2024-03-10" },
+        "end": { "date": "2024-03-11" }
+    }))
+    .unwrap();
+
+    let unified = google_event.into_unified().unwrap();
+    assert!(unified.is_all_day());
+    assert_eq!(
+        unified.start,
+        EventMoment::all_day(chrono::NaiveDate::from_ymd_opt(2024, 3, 10).unwrap())
+    );
+    assert_eq!(
+        unified.end,
+        EventMoment::all_day(chrono::NaiveDate::from_ymd_opt(2024, 3, 11).unwrap())
+    );
+
+    let back = models::GoogleEvent::from_unified(&unified).unwrap();
+    let start = back.start.unwrap();
+    let end = back.end.unwrap();
+    assert_eq!(start.date.as_deref(), Some("2024-03-10"));
+    assert!(start.date_time.is_none());
+    assert_eq!(end.date.as_deref(), Some("2024-03-11"));
+    assert!(end.date_time.is_none());
+}
+
+#[test]
+fn test_conversion_reports_offending_field() {
+    let google_event: models::GoogleEvent = serde_json::from_value(serde_json::json!({
+        "id": "broken",
+        "start": { "dateTime": "not-a-date" },
+        "end": { "dateTime": "2024-01-20T11:00:00Z" }
+    }))
+    .unwrap();
+
+    let error = google_event.into_unified().unwrap_err();
+    assert_eq!(error.item_id.as_deref(), Some("broken"));
+    assert_eq!(error.field, "start.dateTime");
+    assert_eq!(error.value.as_deref(), Some("not-a-date"));
+
+    let google_event: models::GoogleEvent = serde_json::from_value(serde_json::json!({
+        "start": { "dateTime": "2024-01-20T10:00:00Z" },
+        "end": { "dateTime": "2024-01-20T11:00:00Z" }
+    }))
+    .unwrap();
+    let error = google_event.into_unified().unwrap_err();
+    assert_eq!(error, ConversionError::missing("id"));
+}
+
+#[tokio::test]
+async fn test_list_events_lenient_skips_bad_items() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                {
+                    "id": "good",
+                    "start": { "dateTime": "2024-01-20T10:00:00Z" },
+                    "end": { "dateTime": "2024-01-20T11:00:00Z" }
+                },
+                {
+                    "id": "no-end",
+                    "start": { "dateTime": "2024-01-20T10:00:00Z" }
+                }
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    // Strict mode fails on the first bad item
+    let error = provider.list_events("primary", None, None).await.unwrap_err();
+    assert!(matches!(error, CalblendError::Conversion(ref e) if e.field == "end"));
+
+    let listing = provider
+        .list_events_detailed("primary", None, None, &ListEventsOptions::default().lenient())
+        .await
+        .unwrap();
+    assert_eq!(listing.events.len(), 1);
+    assert_eq!(listing.events[0].id, "good");
+    assert_eq!(listing.skipped.len(), 1);
+    assert_eq!(listing.skipped[0].item_id.as_deref(), Some("no-end"));
+}
+
+#[tokio::test]
+async fn test_list_events_returns_deletion_markers() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .and(query_param("showDeleted", "true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                {
+                    "id": "kept",
+                    "status": "confirmed",
+                    "start": { "dateTime": "2024-01-20T10:00:00Z" },
+                    "end": { "dateTime": "2024-01-20T11:00:00Z" }
+                },
+                { "id": "deleted", "status": "cancelled" },
+                {
+                    "id": "series_20240122T100000Z",
+                    "status": "cancelled",
+                    "recurringEventId": "series",
+                    "originalStartTime": { "dateTime": "2024-01-22T10:00:00Z" }
+                }
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    // Strict mode no longer fails on items without times
+    let listing = provider
+        .list_events_detailed("primary", None, None, &ListEventsOptions::default().with_show_deleted(true))
+        .await
+        .unwrap();
+    assert_eq!(listing.events.len(), 1);
+    assert_eq!(listing.events[0].id, "kept");
+    assert!(listing.skipped.is_empty());
+    assert_eq!(
+        listing.cancelled,
+        vec![
+            CancelledEvent { id: "deleted".to_string(), series_id: None, original_start: None },
+            CancelledEvent {
+                id: "series_20240122T100000Z".to_string(),
+                series_id: Some("series".to_string()),
+                original_start: Some(EventMoment::timed(
+                    DateTime::parse_from_rfc3339("2024-01-22T10:00:00Z").unwrap(),
+                    None,
+                )),
             },
-        );
-
-        Mock::given(method("POST"))
-            .and(path("/calendar/v3/calendars/primary/events"))
-            .and(bearer_token("test_access_token"))
-            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
-                "id": "generated_event_id",
-                "summary": null,
-                "start": {
-                    "dateTime": "2024-01-20T10:00:00-08:00",
-                    "timeZone": "America/Los_Angeles"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_list_events_marks_only_moved_instances_as_exceptions() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                {
+                    "id": "standup_20240108T080000Z",
+                    "recurringEventId": "standup",
+                    "originalStartTime": { "dateTime": "2024-01-08T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+                    "start": { "dateTime": "2024-01-08T08:00:00Z" },
+                    "end": { "dateTime": "2024-01-08T08:15:00Z" }
+                },
+                {
+                    "id": "standup_20240109T080000Z",
+                    "recurringEventId": "standup",
+                    "originalStartTime": { "dateTime": "2024-01-09T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+                    "start": { "dateTime": "2024-01-09T14:00:00+01:00" },
+                    "end": { "dateTime": "2024-01-09T14:15:00+01:00" }
+                }
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let events = provider.list_events("primary", None, None).await.unwrap();
+    assert!(events.iter().all(|e| e.series_id.as_deref() == Some("standup")));
+    assert!(!events[0].is_exception());
+    assert_eq!(events[0].original_start, None);
+    assert!(events[1].is_exception());
+}
+
+#[tokio::test]
+async fn test_create_event_does_not_send_series_fields() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("POST"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "copy",
+            "start": { "dateTime": "2024-01-09T14:00:00+01:00" },
+            "end": { "dateTime": "2024-01-09T14:15:00+01:00" }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut event = UnifiedCalendarEvent::new(
+        String::new(),
+        CalendarSource::Google,
+        EventMoment::timed(DateTime::parse_from_rfc3339("2024-01-09T14:00:00+01:00").unwrap(), None),
+        EventMoment::timed(DateTime::parse_from_rfc3339("2024-01-09T14:15:00+01:00").unwrap(), None),
+    );
+    event.series_id = Some("standup".to_string());
+    event.original_start = Some(EventMoment::timed(
+        DateTime::parse_from_rfc3339("2024-01-09T09:00:00+01:00").unwrap(),
+        None,
+    ));
+    provider.create_event("primary", event).await.unwrap();
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert!(body["recurringEventId"].is_null(), "{}", body);
+    assert!(body["originalStartTime"].is_null(), "{}", body);
+}
+
+#[tokio::test]
+async fn test_update_event_sends_etag() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("PUT"))
+        .and(path("/calendar/v3/calendars/primary/events/review"))
+        .and(header("If-Match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "review",
+            "etag": "\"v2\"",
+            "start": { "dateTime": "2024-01-20T10:00:00Z" },
+            "end": { "dateTime": "2024-01-20T11:00:00Z" }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/calendar/v3/calendars/primary/events/review"))
+        .and(header("If-Match", "\"stale\""))
+        .respond_with(ResponseTemplate::new(412))
+        .mount(&mock_server)
+        .await;
+
+    let mut event = UnifiedCalendarEvent::new(
+        "review".to_string(),
+        CalendarSource::Google,
+        EventMoment::timed(DateTime::parse_from_rfc3339("2024-01-20T10:00:00Z").unwrap(), None),
+        EventMoment::timed(DateTime::parse_from_rfc3339("2024-01-20T11:00:00Z").unwrap(), None),
+    );
+    event.etag = Some("\"v1\"".to_string());
+    let updated = provider.update_event("primary", "review", event.clone()).await.unwrap();
+    assert_eq!(updated.etag.as_deref(), Some("\"v2\""));
+
+    event.etag = Some("\"stale\"".to_string());
+    let error = provider.update_event("primary", "review", event).await.unwrap_err();
+    assert!(matches!(error, CalblendError::Conflict(_)), "{:?}", error);
+}
+
+#[test]
+fn test_categories_round_trip_through_extended_properties() {
+    let google_event: models::GoogleEvent = serde_json::from_value(serde_json::json!({
+        "id": "review",
+        "start": { "dateTime": "2024-01-20T10:00:00Z" },
+        "end": { "dateTime": "2024-01-20T11:00:00Z" },
+        "extendedProperties": { "private": { "categories": "[\"Work\",\"Q1, planning\"]", "crm": "42" } }
+    }))
+    .unwrap();
+    let mut unified = google_event.into_unified().unwrap();
+    assert_eq!(
+        unified.categories,
+        Some(vec!["Work".to_string(), "Q1, planning".to_string()])
+    );
+
+    // Other properties are kept, the categories replaced
+    unified.categories = Some(vec!["Home".to_string()]);
+    let back = models::GoogleEvent::from_unified(&unified).unwrap();
+    let private = back.extended_properties.unwrap().private.unwrap();
+    assert_eq!(private.get("categories").map(String::as_str), Some("[\"Home\"]"));
+    assert_eq!(private.get("crm").map(String::as_str), Some("42"));
+
+    unified.categories = None;
+    let back = models::GoogleEvent::from_unified(&unified).unwrap();
+    assert!(!back.extended_properties.unwrap().private.unwrap().contains_key("categories"));
+
+    // Plain lists written by other applications
+    let google_event: models::GoogleEvent = serde_json::from_value(serde_json::json!({
+        "id": "other",
+        "start": { "date": "2024-01-20" },
+        "end": { "date": "2024-01-21" },
+        "extendedProperties": { "private": { "categories": "Work, Travel" } }
+    }))
+    .unwrap();
+    assert_eq!(
+        google_event.into_unified().unwrap().categories,
+        Some(vec!["Work".to_string(), "Travel".to_string()])
+    );
+}
+
+#[test]
+fn test_multi_line_recurrence_round_trip() {
+    let lines = vec![
+        "RRULE:FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20240331T000000Z".to_string(),
+        "EXDATE;TZID=Europe/Berlin:20240110T090000".to_string(),
+        "RDATE;VALUE=DATE:20240113".to_string(),
+    ];
+    let google_event: models::GoogleEvent = serde_json::from_value(serde_json::json!({
+        "id": "standup",
+        "start": { "dateTime": "2024-01-08T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+        "end": { "dateTime": "2024-01-08T09:15:00+01:00", "timeZone": "Europe/Berlin" },
+        "recurrence": lines
+    }))
+    .unwrap();
+
+    let mut unified = google_event.into_unified().unwrap();
+    assert_eq!(
+        unified.recurrence_rule.as_deref(),
+        Some("FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20240331T000000Z")
+    );
+    // Only the EXDATE is a raw exception; the RDATE lives in the pattern
+    assert_eq!(
+        unified.recurrence_exceptions,
+        Some(vec!["EXDATE;TZID=Europe/Berlin:20240110T090000".to_string()])
+    );
+    let pattern = unified.recurrence.clone().unwrap();
+    assert_eq!(pattern.rule().unwrap().frequency, crate::recurrence::Frequency::Weekly);
+    assert_eq!(pattern.exdates.len(), 1);
+    assert_eq!(pattern.rdates.len(), 1);
+
+    let back = models::GoogleEvent::from_unified(&unified).unwrap();
+    assert_eq!(back.recurrence, Some(lines));
+
+    // Editing the typed pattern is reflected in the provider payload
+    let mut pattern = pattern;
+    pattern.rule_mut().unwrap().interval = 2;
+    unified.set_recurrence(Some(pattern));
+    assert_eq!(
+        unified.recurrence_rule.as_deref(),
+        Some("FREQ=WEEKLY;INTERVAL=2;UNTIL=20240331T000000Z;BYDAY=MO,WE")
+    );
+    let back = models::GoogleEvent::from_unified(&unified).unwrap();
+    assert_eq!(
+        back.recurrence.unwrap()[0],
+        "RRULE:FREQ=WEEKLY;INTERVAL=2;UNTIL=20240331T000000Z;BYDAY=MO,WE"
+    );
+}
+
+#[test]
+fn test_recurring_instance_maps_series_fields() {
+    let google_event: models::GoogleEvent = serde_json::from_value(serde_json::json!({
+        "id": "standup_20240109T080000Z",
+        "recurringEventId": "standup",
+        "originalStartTime": { "dateTime": "2024-01-09T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+        "start": { "dateTime": "2024-01-09T14:00:00+01:00" },
+        "end": { "dateTime": "2024-01-09T14:15:00+01:00" }
+    }))
+    .unwrap();
+
+    let unified = google_event.into_unified().unwrap();
+    assert!(unified.is_exception());
+    assert_eq!(unified.series_id.as_deref(), Some("standup"));
+    assert_eq!(
+        unified.original_start.as_ref().and_then(|m| m.date_time()).map(|dt| dt.to_rfc3339()),
+        Some("2024-01-09T09:00:00+01:00".to_string())
+    );
+
+    let back = models::GoogleEvent::from_unified(&unified).unwrap();
+    assert_eq!(back.recurring_event_id.as_deref(), Some("standup"));
+    assert_eq!(
+        back.original_start_time.and_then(|t| t.time_zone).as_deref(),
+        Some("Europe/Berlin")
+    );
+}
+
+#[test]
+fn test_attendee_roles_and_guest_details() {
+    let google_event: models::GoogleEvent = serde_json::from_value(serde_json::json!({
+        "id": "review",
+        "start": { "dateTime": "2024-01-20T10:00:00Z" },
+        "end": { "dateTime": "2024-01-20T11:00:00Z" },
+        "attendees": [
+            { "email": "ana@example.com", "optional": true, "comment": "Might be late", "additionalGuests": 2 },
+            { "email": "c_1889@resource.calendar.google.com", "resource": true }
+        ]
+    }))
+    .unwrap();
+
+    let unified = google_event.into_unified().unwrap();
+    let attendees = unified.attendees.clone().unwrap();
+    assert_eq!(attendees[0].role, Some(crate::ParticipantRole::Optional));
+    assert_eq!(attendees[0].user_type, Some(crate::CalendarUserType::Individual));
+    assert_eq!(attendees[0].comment.as_deref(), Some("Might be late"));
+    assert_eq!(attendees[0].additional_guests, Some(2));
+    assert_eq!(attendees[1].user_type, Some(crate::CalendarUserType::Room));
+
+    // Roles drive Google's booleans on the way back
+    let mut edited = unified;
+    let attendees = edited.attendees.as_mut().unwrap();
+    attendees[0].role = Some(crate::ParticipantRole::Required);
+    let back = models::GoogleEvent::from_unified(&edited).unwrap();
+    let back_attendees = back.attendees.unwrap();
+    assert_eq!(back_attendees[0].optional, Some(false));
+    assert_eq!(back_attendees[0].additional_guests, Some(2));
+    assert_eq!(back_attendees[1].resource, Some(true));
+}
+
+#[test]
+fn test_identity_and_link_fields_are_typed() {
+    let google_event: models::GoogleEvent = serde_json::from_value(serde_json::json!({
+        "id": "abc123",
+        "iCalUID": "abc123@google.com",
+        "sequence": 3,
+        "etag": "\"3181161784712000\"",
+        "htmlLink": "https://www.google.com/calendar/event?eid=abc123",
+        "start": { "dateTime": "2024-01-20T10:00:00Z" },
+        "end": { "dateTime": "2024-01-20T11:00:00Z" }
+    }))
+    .unwrap();
+
+    let unified = google_event.into_unified().unwrap();
+    assert_eq!(unified.ical_uid.as_deref(), Some("abc123@google.com"));
+    assert_eq!(unified.sequence, Some(3));
+    assert_eq!(unified.etag.as_deref(), Some("\"3181161784712000\""));
+    assert_eq!(
+        unified.html_link.as_deref(),
+        Some("https://www.google.com/calendar/event?eid=abc123")
+    );
+
+    let back = models::GoogleEvent::from_unified(&unified).unwrap();
+    assert_eq!(back.ical_uid.as_deref(), Some("abc123@google.com"));
+    assert_eq!(back.sequence, Some(3));
+}
+
+#[test]
+fn test_calendar_metadata_mapping() {
+    let google_calendar: models::GoogleCalendar = serde_json::from_value(serde_json::json!({
+        "id": "team@example.com",
+        "summary": "Team",
+        "backgroundColor": "#9fe1e7",
+        "foregroundColor": "#000000",
+        "accessRole": "freeBusyReader",
+        "timeZone": "Europe/Berlin",
+        "defaultReminders": [{ "method": "email", "minutes": 30 }],
+        "conferenceProperties": { "allowedConferenceSolutionTypes": ["hangoutsMeet"] },
+        "hidden": false,
+        "selected": true
+    }))
+    .unwrap();
+
+    let calendar = crate::Calendar::from(google_calendar);
+    assert_eq!(calendar.access_role, Some(crate::AccessRole::FreeBusyReader));
+    assert!(!calendar.can_write);
+    assert_eq!(calendar.time_zone.as_deref(), Some("Europe/Berlin"));
+    assert_eq!(calendar.foreground_color.as_deref(), Some("#000000"));
+    let reminders = calendar.default_reminders.unwrap();
+    assert_eq!(reminders[0].minutes_before, 30);
+    assert!(matches!(reminders[0].method, Some(crate::ReminderMethod::Email)));
+    assert_eq!(calendar.conference_types, Some(vec!["hangoutsMeet".to_string()]));
+    assert_eq!(calendar.selected, Some(true));
+}
+
+#[tokio::test]
+async fn test_create_event_validates_before_request() {
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("POST"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let mut event = UnifiedCalendarEvent::new(
+        String::new(),
+        CalendarSource::Google,
+        EventMoment::timed(DateTime::parse_from_rfc3339("2024-01-20T11:00:00Z").unwrap(), None),
+        EventMoment::timed(DateTime::parse_from_rfc3339("2024-01-20T10:00:00Z").unwrap(), None),
+    );
+    event.attendees = Some(vec![crate::Participant {
+        email: Some("nobody".to_string()),
+        ..Default::default()
+    }]);
+
+    let err = provider.create_event("primary", event).await.unwrap_err();
+    let CalblendError::Validation(errors) = err else {
+        panic!("expected validation error, got {:?}", err);
+    };
+    assert!(errors.has(crate::ValidationErrorKind::EndBeforeStart));
+    assert!(errors.has(crate::ValidationErrorKind::InvalidEmail));
+}
+
+#[test]
+fn test_new_event_leaves_id_to_google() {
+    let event = UnifiedCalendarEvent::builder()
+        .with_source(CalendarSource::Google)
+        .with_times(
+            DateTime::parse_from_rfc3339("2024-01-20T10:00:00Z").unwrap(),
+            DateTime::parse_from_rfc3339("2024-01-20T11:00:00Z").unwrap(),
+        )
+        .build()
+        .unwrap();
+    assert!(models::GoogleEvent::from_unified(&event).unwrap().id.is_none());
+}
+
+#[tokio::test]
+async fn test_backup_and_restore_recurring_event_with_override() {
+    use crate::backup::{backup_account, restore_account, BackupOptions, RestoreOptions};
+
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/users/me/calendarList"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [{ "id": "primary", "summary": "Primary", "accessRole": "owner" }]
+        })))
+        .mount(&mock_server)
+        .await;
+    // Unexpanded, so the series comes back once with its override; the
+    // override only changes the title, so it stays at its original start
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .and(query_param("singleEvents", "false"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                {
+                    "id": "standup",
+                    "iCalUID": "standup@google.com",
+                    "summary": "Stand-up",
+                    "start": { "dateTime": "2024-03-11T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+                    "end": { "dateTime": "2024-03-11T09:30:00+01:00", "timeZone": "Europe/Berlin" },
+                    "recurrence": ["RRULE:FREQ=DAILY;COUNT=5"]
                 },
-                "end": {
-                    "dateTime": "2024-01-20T11:00:00-08:00",
-                    "timeZone": "America/Los_Angeles"
+                {
+                    "id": "standup_20240312T080000Z",
+                    "iCalUID": "standup@google.com",
+                    "summary": "Stand-up (demo)",
+                    "recurringEventId": "standup",
+                    "originalStartTime": { "dateTime": "2024-03-12T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+                    "start": { "dateTime": "2024-03-12T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+                    "end": { "dateTime": "2024-03-12T09:30:00+01:00", "timeZone": "Europe/Berlin" }
+                }
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let backup = backup_account(&provider, &BackupOptions::new()).await.unwrap();
+    let events = &backup.calendars[0].events;
+    assert_eq!(events.len(), 2);
+    assert!(events[1].is_exception());
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/restored/events"))
+        .and(query_param("singleEvents", "false"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "items": [] })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/calendar/v3/calendars/restored/events"))
+        .and(query_param("sendUpdates", "none"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "new_standup",
+            "iCalUID": "standup@google.com",
+            "summary": "Stand-up",
+            "start": { "dateTime": "2024-03-11T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+            "end": { "dateTime": "2024-03-11T09:30:00+01:00", "timeZone": "Europe/Berlin" },
+            "recurrence": ["RRULE:FREQ=DAILY;COUNT=5"]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    let instance = serde_json::json!({
+        "id": "new_standup_20240312T080000Z",
+        "etag": "\"7\"",
+        "iCalUID": "standup@google.com",
+        "summary": "Stand-up",
+        "recurringEventId": "new_standup",
+        "originalStartTime": { "dateTime": "2024-03-12T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+        "start": { "dateTime": "2024-03-12T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+        "end": { "dateTime": "2024-03-12T09:30:00+01:00", "timeZone": "Europe/Berlin" }
+    });
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/restored/events/new_standup/instances"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [instance]
+        })))
+        .mount(&mock_server)
+        .await;
+    let mut updated = instance.clone();
+    updated["summary"] = "Stand-up (demo)".into();
+    Mock::given(method("PUT"))
+        .and(path("/calendar/v3/calendars/restored/events/new_standup_20240312T080000Z"))
+        .and(query_param("sendUpdates", "none"))
+        .and(header("If-Match", "\"7\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(updated))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let options = RestoreOptions::new().with_calendar_mapping("primary", "restored");
+    let report = restore_account(&provider, &backup, &options).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!(report.created, 2);
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let put = requests
+        .iter()
+        .find(|r| r.method.as_str() == "PUT")
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&put.body).unwrap();
+    assert_eq!(body["summary"], "Stand-up (demo)");
+    assert_eq!(body["recurringEventId"], "new_standup");
+    assert_eq!(body["originalStartTime"]["dateTime"], "2024-03-12T09:00:00+01:00");
+}
+
+#[tokio::test]
+async fn test_backup_and_restore_cancelled_occurrence() {
+    use crate::backup::{backup_account, restore_account, BackupOptions, RestoreOptions};
+
+    let (provider, mock_server) = setup_mock_provider().await;
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/users/me/calendarList"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [{ "id": "primary", "summary": "Primary", "accessRole": "owner" }]
+        })))
+        .mount(&mock_server)
+        .await;
+    // Unexpanded listings report a removed occurrence by ID only
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/primary/events"))
+        .and(query_param("singleEvents", "false"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [
+                {
+                    "id": "standup",
+                    "iCalUID": "standup@google.com",
+                    "summary": "Stand-up",
+                    "start": { "dateTime": "2024-03-11T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+                    "end": { "dateTime": "2024-03-11T09:30:00+01:00", "timeZone": "Europe/Berlin" },
+                    "recurrence": ["RRULE:FREQ=DAILY;COUNT=5"]
                 },
-                "status": "confirmed",
-                "created": "2024-01-15T12:00:00Z",
-                "updated": "2024-01-15T12:00:00Z"
-            })))
-            .mount(&mock_server)
-            .await;
-
-        let created = provider.create_event("primary", new_event).await.unwrap();
-        assert_eq!(created.id, "generated_event_id");
-        assert_eq!(created.source, CalendarSource::Google);
-    }
+                {
+                    "id": "standup_20240313T080000Z",
+                    "status": "cancelled",
+                    "recurringEventId": "standup",
+                    "originalStartTime": { "dateTime": "2024-03-13T09:00:00+01:00", "timeZone": "Europe/Berlin" }
+                }
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
 
-    #[tokio::test]
-    async fn test_auth_url_generation() {
-        let token_storage = Arc::new(InMemoryTokenStorage::default());
-        let config = CalblendConfig::default();
-        let provider = GoogleCalendarProvider::new(
-            "test_client_id".to_string(),
-            "test_client_secret".to_string(),
-            "http://localhost:8080/callback".to_string(),
-            token_storage,
-            config,
-        ).unwrap();
-
-        let auth_url = provider.get_auth_url().await.unwrap();
-        
-        // Verify the URL contains expected components
-        assert!(auth_url.contains("https://accounts.google.com/o/oauth2/v2/auth"));
-        assert!(auth_url.contains("client_id=test_client_id"));
-        assert!(auth_url.contains("redirect_uri=http%3A%2F%2Flocalhost%3A8080%2Fcallback"));
-        assert!(auth_url.contains("response_type=code"));
-        assert!(auth_url.contains("scope="));
-        assert!(auth_url.contains("code_challenge=")); // PKCE
-    }
-}
\ No newline at end of file
+    let backup = backup_account(&provider, &BackupOptions::new()).await.unwrap();
+    let events = &backup.calendars[0].events;
+    assert_eq!(events.len(), 2);
+    assert!(events[1].is_exception());
+    assert!(matches!(events[1].status, Some(EventStatus::Cancelled)));
+    assert_eq!(events[1].series_id.as_deref(), Some("standup"));
+
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/restored/events"))
+        .and(query_param("singleEvents", "false"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "items": [] })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/calendar/v3/calendars/restored/events"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "new_standup",
+            "iCalUID": "standup@google.com",
+            "summary": "Stand-up",
+            "start": { "dateTime": "2024-03-11T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+            "end": { "dateTime": "2024-03-11T09:30:00+01:00", "timeZone": "Europe/Berlin" },
+            "recurrence": ["RRULE:FREQ=DAILY;COUNT=5"]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    let instance = serde_json::json!({
+        "id": "new_standup_20240313T080000Z",
+        "etag": "\"3\"",
+        "iCalUID": "standup@google.com",
+        "summary": "Stand-up",
+        "recurringEventId": "new_standup",
+        "originalStartTime": { "dateTime": "2024-03-13T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+        "start": { "dateTime": "2024-03-13T09:00:00+01:00", "timeZone": "Europe/Berlin" },
+        "end": { "dateTime": "2024-03-13T09:30:00+01:00", "timeZone": "Europe/Berlin" }
+    });
+    Mock::given(method("GET"))
+        .and(path("/calendar/v3/calendars/restored/events/new_standup/instances"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "items": [instance]
+        })))
+        .mount(&mock_server)
+        .await;
+    let mut cancelled = instance.clone();
+    cancelled["status"] = "cancelled".into();
+    Mock::given(method("PUT"))
+        .and(path("/calendar/v3/calendars/restored/events/new_standup_20240313T080000Z"))
+        .and(header("If-Match", "\"3\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(cancelled))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let options = RestoreOptions::new().with_calendar_mapping("primary", "restored");
+    let report = restore_account(&provider, &backup, &options).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!(report.created, 2);
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let put = requests
+        .iter()
+        .find(|r| r.method.as_str() == "PUT")
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&put.body).unwrap();
+    assert_eq!(body["status"], "cancelled");
+    assert_eq!(body["recurringEventId"], "new_standup");
+    assert_eq!(body["end"]["dateTime"], "2024-03-13T09:30:00+01:00");
+}
@@ -2,12 +2,13 @@
 
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, info, warn, instrument};
 use uuid::Uuid;
 use http::HeaderMap;
 
-use crate::{CalblendError, Result, http::HttpClient};
+use crate::{Calendar, CalblendError, Result, UnifiedCalendarEvent, http::HttpClient};
 use super::auth::GoogleAuth;
 
 /// Google Calendar push notification channel
@@ -57,10 +58,105 @@ pub struct PushNotification {
     pub message_number: Option<String>,
 }
 
+impl PushNotification {
+    /// Determine which watched resource this notification refers to
+    pub fn resource(&self) -> Result<WatchResource> {
+        WatchResource::from_resource_uri(&self.resource_uri)
+    }
+
+    /// Whether this is the initial `sync` message sent when a channel opens
+    pub fn is_sync(&self) -> bool {
+        self.resource_state == "sync"
+    }
+}
+
+/// A Google Calendar resource that can be watched for changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WatchResource {
+    /// Events of a single calendar
+    Events { calendar_id: String },
+    /// The user's calendar list (subscribe, hide, delete, recolor)
+    CalendarList,
+    /// The user's settings (time zone, locale, ...)
+    Settings,
+}
+
+impl WatchResource {
+    /// Collection URL of this resource below the API base
+    fn url(&self, api_base_url: &str) -> String {
+        match self {
+            WatchResource::Events { calendar_id } => format!(
+                "{}/calendars/{}/events",
+                api_base_url,
+                urlencoding::encode(calendar_id)
+            ),
+            WatchResource::CalendarList => format!("{}/users/me/calendarList", api_base_url),
+            WatchResource::Settings => format!("{}/users/me/settings", api_base_url),
+        }
+    }
+
+    /// Parse the `X-Goog-Resource-URI` of a notification
+    ///
+    /// Formats:
+    /// - `.../calendars/{calendarId}/events?alt=json`
+    /// - `.../users/me/calendarList?alt=json`
+    /// - `.../users/me/settings?alt=json`
+    pub fn from_resource_uri(resource_uri: &str) -> Result<Self> {
+        let path = resource_uri.split('?').next().unwrap_or(resource_uri);
+
+        if let Some(rest) = path.split("/calendars/").nth(1) {
+            if let Some(calendar_id) = rest.strip_suffix("/events") {
+                let calendar_id = urlencoding::decode(calendar_id)
+                    .map_err(|e| CalblendError::Provider(format!("Invalid calendar ID: {}", e)))?;
+                return Ok(WatchResource::Events { calendar_id: calendar_id.into_owned() });
+            }
+        }
+        if path.ends_with("/users/me/calendarList") {
+            return Ok(WatchResource::CalendarList);
+        }
+        if path.ends_with("/users/me/settings") {
+            return Ok(WatchResource::Settings);
+        }
+
+        Err(CalblendError::Provider(format!(
+            "Invalid resource URI format: {}",
+            resource_uri
+        )))
+    }
+}
+
+/// Outcome of processing a push notification
+#[derive(Debug, Clone)]
+pub enum NotificationChange {
+    /// Initial message confirming a new channel; nothing changed yet
+    Sync { resource: WatchResource },
+    /// Events of a calendar changed; carries the refreshed recent events
+    Events {
+        calendar_id: String,
+        events: Vec<UnifiedCalendarEvent>,
+    },
+    /// The calendar list changed; carries the refreshed calendars
+    CalendarList { calendars: Vec<Calendar> },
+    /// User settings changed; carries the refreshed settings by ID
+    Settings { settings: HashMap<String, String> },
+}
+
+/// Parse a channel expiration, which Google sends as Unix milliseconds
+fn parse_expiration(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(millis) = value.parse::<i64>() {
+        return DateTime::from_timestamp_millis(millis)
+            .ok_or_else(|| CalblendError::Deserialization(format!("Invalid expiration: {}", value)));
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| CalblendError::Deserialization(e.to_string()))
+}
+
 /// Webhook manager for Google Calendar
 pub struct GoogleWebhookManager {
     auth: Arc<GoogleAuth>,
     http: HttpClient,
+    api_base_url: String,
     webhook_endpoint: String,
 }
 
@@ -69,11 +165,13 @@ impl GoogleWebhookManager {
     pub fn new(
         auth: Arc<GoogleAuth>,
         http: HttpClient,
+        api_base_url: String,
         webhook_endpoint: String,
     ) -> Self {
         Self {
             auth,
             http,
+            api_base_url,
             webhook_endpoint,
         }
     }
@@ -86,7 +184,22 @@ impl GoogleWebhookManager {
         token: Option<String>,
         ttl_hours: Option<i64>,
     ) -> Result<WatchChannel> {
-        debug!("Setting up webhook for calendar: {}", calendar_id);
+        self.watch(
+            &WatchResource::Events { calendar_id: calendar_id.to_string() },
+            token,
+            ttl_hours,
+        ).await
+    }
+
+    /// Start watching a resource (events, calendar list or settings) for changes
+    #[instrument(skip(self, token))]
+    pub async fn watch(
+        &self,
+        resource: &WatchResource,
+        token: Option<String>,
+        ttl_hours: Option<i64>,
+    ) -> Result<WatchChannel> {
+        debug!("Setting up webhook for {:?}", resource);
 
         let access_token = self.auth.get_valid_token().await?;
         
@@ -105,10 +218,7 @@ impl GoogleWebhookManager {
             expiration: Some(expiration.timestamp_millis()),
         };
 
-        let url = format!(
-            "https://www.googleapis.com/calendar/v3/calendars/{}/events/watch",
-            urlencoding::encode(calendar_id)
-        );
+        let url = format!("{}/watch", resource.url(&self.api_base_url));
 
         let response = self.http
             .client()
@@ -133,11 +243,9 @@ impl GoogleWebhookManager {
             .await
             .map_err(|e| CalblendError::Deserialization(e.to_string()))?;
 
-        let expiration = DateTime::parse_from_rfc3339(&watch_response.expiration)
-            .map_err(|e| CalblendError::Deserialization(e.to_string()))?
-            .with_timezone(&Utc);
+        let expiration = parse_expiration(&watch_response.expiration)?;
 
-        info!("Created webhook channel {} for calendar {}", channel_id, calendar_id);
+        info!("Created webhook channel {} for {:?}", channel_id, resource);
 
         Ok(WatchChannel {
             id: watch_response.id,
@@ -166,7 +274,7 @@ impl GoogleWebhookManager {
 
         let response = self.http
             .client()
-            .post("https://www.googleapis.com/calendar/v3/channels/stop")
+            .post(format!("{}/channels/stop", self.api_base_url))
             .bearer_auth(access_token)
             .json(&stop_request)
             .send()
@@ -257,6 +365,7 @@ impl GoogleWebhookManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::google::GoogleEndpoints;
 
     #[test]
     fn test_needs_renewal() {
@@ -287,11 +396,13 @@ mod tests {
             Arc::new(GoogleAuth::new(
                 "".to_string(),
                 "".to_string(),
-                "".to_string(),
+                "http://localhost/callback".to_string(),
                 Arc::new(crate::auth::test_utils::InMemoryTokenStorage::new()),
                 HttpClient::new(&crate::CalblendConfig::default()).unwrap(),
-            )),
+                &GoogleEndpoints::default(),
+            ).unwrap()),
             HttpClient::new(&crate::CalblendConfig::default()).unwrap(),
+            GoogleEndpoints::default().api_base_url,
             "http://localhost/webhook".to_string(),
         );
 
@@ -309,4 +420,36 @@ mod tests {
         assert!(!manager.verify_notification(&notification, Some("wrong")));
         assert!(!manager.verify_notification(&notification, None));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_watch_resource_from_uri() {
+        assert_eq!(
+            WatchResource::from_resource_uri(
+                "https://www.googleapis.com/calendar/v3/calendars/work%40example.com/events?alt=json"
+            ).unwrap(),
+            WatchResource::Events { calendar_id: "work@example.com".to_string() }
+        );
+        assert_eq!(
+            WatchResource::from_resource_uri(
+                "https://www.googleapis.com/calendar/v3/users/me/calendarList?alt=json"
+            ).unwrap(),
+            WatchResource::CalendarList
+        );
+        assert_eq!(
+            WatchResource::from_resource_uri(
+                "https://www.googleapis.com/calendar/v3/users/me/settings?alt=json"
+            ).unwrap(),
+            WatchResource::Settings
+        );
+        assert!(WatchResource::from_resource_uri("https://example.com/other").is_err());
+    }
+
+    #[test]
+    fn test_parse_expiration() {
+        let millis = parse_expiration("1426325213000").unwrap();
+        assert_eq!(millis.timestamp(), 1_426_325_213);
+
+        let rfc3339 = parse_expiration("2030-01-01T00:00:00Z").unwrap();
+        assert_eq!(rfc3339.to_rfc3339(), "2030-01-01T00:00:00+00:00");
+    }
+}
@@ -39,6 +39,7 @@ impl CalendarProvider for OutlookCalendarProvider {
         calendar_id: &str,
         event: UnifiedCalendarEvent,
     ) -> Result<UnifiedCalendarEvent> {
+        event.validate()?;
         // TODO: Implement Microsoft Graph API call
         Ok(event)
     }
@@ -49,6 +50,7 @@ impl CalendarProvider for OutlookCalendarProvider {
         event_id: &str,
         event: UnifiedCalendarEvent,
     ) -> Result<UnifiedCalendarEvent> {
+        event.validate()?;
         // TODO: Implement Microsoft Graph API call
         Ok(event)
     }
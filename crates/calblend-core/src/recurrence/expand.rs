@@ -0,0 +1,620 @@
+//! RRULE expansion in local (wall-clock) time
+
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+
+use super::rule::{Frequency, RecurrenceRule, WeekdayNum};
+
+/// Default for [`ExpandBounds::max_steps`]
+pub(crate) const MAX_EXPANSION_STEPS: u32 = 1_000_000;
+
+/// An expansion gave up after [`ExpandBounds::max_steps`] steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct StepLimitExceeded(pub u32);
+
+/// Bounds for a single rule expansion, all in the event's local time
+pub(crate) struct ExpandBounds<'a> {
+    /// First instance of the series (`DTSTART`)
+    pub dtstart: NaiveDateTime,
+    /// Stop once instances pass this point
+    pub limit: NaiveDateTime,
+    /// Periods ending before this point may be skipped when the rule has no
+    /// `COUNT`, so that old series do not have to be walked from the start
+    pub skip_before: Option<NaiveDateTime>,
+    /// Whether an instance lies beyond the rule's `UNTIL`
+    pub past_until: &'a dyn Fn(NaiveDateTime) -> bool,
+    /// Give up after walking this many periods or producing this many
+    /// instances, whichever comes first
+    pub max_steps: u32,
+}
+
+/// Expand a rule into the local instances between `DTSTART` and the limit
+///
+/// Follows the expand/limit semantics of RFC 5545 section 3.3.10: each
+/// period of the rule's frequency is expanded into candidate instances by the
+/// `BY*` parts, `BYSETPOS` selects among them, then `COUNT` and `UNTIL` end
+/// the series. `DTSTART` counts towards `COUNT` even when the rule does not
+/// generate it, as callers always include it. Rules that need more than
+/// [`ExpandBounds::max_steps`] steps to reach the limit fail rather than run
+/// unbounded.
+pub(crate) fn expand_rule(
+    rule: &RecurrenceRule,
+    bounds: &ExpandBounds<'_>,
+) -> Result<Vec<NaiveDateTime>, StepLimitExceeded> {
+    let expander = Expander::new(rule, bounds.dtstart);
+    let mut out = Vec::new();
+    let mut emitted = 0u32;
+    let mut steps = 0u32;
+    let mut seen_dtstart = false;
+
+    let mut period = match (rule.count, bounds.skip_before) {
+        (None, Some(skip_before)) => expander.periods_before(skip_before),
+        _ => 0,
+    };
+
+    while let Some(period_start) = expander.period_start(period) {
+        if period_start > bounds.limit {
+            break;
+        }
+        steps += 1;
+        if steps > bounds.max_steps {
+            return Err(StepLimitExceeded(bounds.max_steps));
+        }
+
+        for instance in expander.candidates(period_start) {
+            if instance < bounds.dtstart {
+                continue;
+            }
+            if !seen_dtstart {
+                seen_dtstart = true;
+                // Candidates come in order, so a first one past DTSTART means
+                // the rule skips it
+                if instance != bounds.dtstart {
+                    emitted += 1;
+                    if rule.count.is_some_and(|count| emitted >= count) {
+                        return Ok(out);
+                    }
+                }
+            }
+            if (bounds.past_until)(instance) || instance > bounds.limit {
+                return Ok(out);
+            }
+            out.push(instance);
+            emitted += 1;
+            if rule.count.is_some_and(|count| emitted >= count) {
+                return Ok(out);
+            }
+            if emitted > bounds.max_steps {
+                return Err(StepLimitExceeded(bounds.max_steps));
+            }
+        }
+
+        period += 1;
+    }
+
+    Ok(out)
+}
+
+struct Expander<'a> {
+    rule: &'a RecurrenceRule,
+    dtstart: NaiveDateTime,
+    by_second: Vec<u32>,
+    by_minute: Vec<u32>,
+    by_hour: Vec<u32>,
+    by_day: Vec<WeekdayNum>,
+    by_month_day: Vec<i8>,
+    by_month: Vec<u8>,
+}
+
+impl<'a> Expander<'a> {
+    fn new(rule: &'a RecurrenceRule, dtstart: NaiveDateTime) -> Self {
+        let freq = rule.frequency;
+        let from_rule = |values: &[u8], default: u32, applies: bool| -> Vec<u32> {
+            let mut values: Vec<u32> = values.iter().map(|v| *v as u32).collect();
+            if values.is_empty() && applies {
+                values.push(default);
+            }
+            values.sort_unstable();
+            values.dedup();
+            values
+        };
+
+        let mut by_day = rule.by_day.clone();
+        let mut by_month_day = rule.by_month_day.clone();
+        let mut by_month = rule.by_month.clone();
+
+        // Missing day parts are taken from DTSTART (RFC 5545 section 3.3.10)
+        let no_day_parts =
+            rule.by_year_day.is_empty() && by_month_day.is_empty() && by_day.is_empty();
+        match freq {
+            Frequency::Yearly if no_day_parts && rule.by_week_no.is_empty() => {
+                if by_month.is_empty() {
+                    by_month.push(dtstart.month() as u8);
+                }
+                by_month_day.push(dtstart.day() as i8);
+            }
+            Frequency::Yearly if no_day_parts => by_day.push(WeekdayNum::every(dtstart.weekday())),
+            Frequency::Monthly if no_day_parts => by_month_day.push(dtstart.day() as i8),
+            Frequency::Weekly if by_day.is_empty() => {
+                by_day.push(WeekdayNum::every(dtstart.weekday()))
+            }
+            _ => {}
+        }
+
+        Self {
+            rule,
+            dtstart,
+            by_second: from_rule(
+                &rule.by_second,
+                dtstart.second(),
+                freq > Frequency::Secondly,
+            ),
+            by_minute: from_rule(
+                &rule.by_minute,
+                dtstart.minute(),
+                freq > Frequency::Minutely,
+            ),
+            by_hour: from_rule(&rule.by_hour, dtstart.hour(), freq > Frequency::Hourly),
+            by_day,
+            by_month_day,
+            by_month,
+        }
+    }
+
+    /// Start of the n-th period counted from the one containing `DTSTART`
+    fn period_start(&self, n: u64) -> Option<NaiveDateTime> {
+        let step = n.checked_mul(self.rule.interval as u64)?;
+        let date = self.dtstart.date();
+        match self.rule.frequency {
+            Frequency::Yearly => {
+                let year = i32::try_from(date.year() as i64 + step as i64).ok()?;
+                NaiveDate::from_ymd_opt(year, 1, 1).map(midnight)
+            }
+            Frequency::Monthly => date
+                .with_day(1)?
+                .checked_add_months(Months::new(u32::try_from(step).ok()?))
+                .map(midnight),
+            Frequency::Weekly => {
+                let week_start =
+                    date - Duration::days(days_since(date.weekday(), self.rule.week_start));
+                week_start
+                    .checked_add_signed(Duration::try_weeks(step as i64)?)
+                    .map(midnight)
+            }
+            Frequency::Daily => date
+                .checked_add_signed(Duration::try_days(step as i64)?)
+                .map(midnight),
+            Frequency::Hourly => {
+                let base = self.dtstart.with_minute(0)?.with_second(0)?;
+                base.checked_add_signed(Duration::try_hours(step as i64)?)
+            }
+            Frequency::Minutely => {
+                let base = self.dtstart.with_second(0)?;
+                base.checked_add_signed(Duration::try_minutes(step as i64)?)
+            }
+            Frequency::Secondly => self
+                .dtstart
+                .with_nanosecond(0)?
+                .checked_add_signed(Duration::try_seconds(step as i64)?),
+        }
+    }
+
+    /// Number of whole periods that end before `point`, leaving one spare
+    fn periods_before(&self, point: NaiveDateTime) -> u64 {
+        if point <= self.dtstart {
+            return 0;
+        }
+        let date = self.dtstart.date();
+        let units = match self.rule.frequency {
+            Frequency::Yearly => (point.year() - date.year()) as i64,
+            Frequency::Monthly => {
+                (point.year() - date.year()) as i64 * 12 + point.month() as i64
+                    - date.month() as i64
+            }
+            Frequency::Weekly => (point - self.dtstart).num_weeks(),
+            Frequency::Daily => (point - self.dtstart).num_days(),
+            Frequency::Hourly => (point - self.dtstart).num_hours(),
+            Frequency::Minutely => (point - self.dtstart).num_minutes(),
+            Frequency::Secondly => (point - self.dtstart).num_seconds(),
+        };
+        (units / self.rule.interval as i64 - 1).max(0) as u64
+    }
+
+    /// Instances produced by the period starting at `period_start`, sorted
+    fn candidates(&self, period_start: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let days = self.period_days(period_start.date());
+        let times = self.period_times(period_start);
+
+        let mut instances: Vec<NaiveDateTime> = days
+            .into_iter()
+            .filter(|day| self.day_matches(*day))
+            .flat_map(|day| times.iter().map(move |time| day.and_time(*time)))
+            .collect();
+
+        if !self.rule.by_set_pos.is_empty() {
+            let len = instances.len() as i64;
+            let mut selected: Vec<NaiveDateTime> = self
+                .rule
+                .by_set_pos
+                .iter()
+                .filter_map(|pos| {
+                    let index = if *pos > 0 {
+                        *pos as i64 - 1
+                    } else {
+                        len + *pos as i64
+                    };
+                    usize::try_from(index)
+                        .ok()
+                        .and_then(|i| instances.get(i).copied())
+                })
+                .collect();
+            selected.sort_unstable();
+            selected.dedup();
+            instances = selected;
+        }
+
+        instances
+    }
+
+    fn period_days(&self, start: NaiveDate) -> Vec<NaiveDate> {
+        let len = match self.rule.frequency {
+            Frequency::Yearly => days_in_year(start.year()),
+            Frequency::Monthly => days_in_month(start.year(), start.month()),
+            Frequency::Weekly => 7,
+            _ => 1,
+        };
+        start.iter_days().take(len as usize).collect()
+    }
+
+    fn period_times(&self, start: NaiveDateTime) -> Vec<NaiveTime> {
+        let fixed = |value: u32, allowed: &[u32]| -> Vec<u32> {
+            if allowed.is_empty() || allowed.contains(&value) {
+                vec![value]
+            } else {
+                Vec::new()
+            }
+        };
+        let (hours, minutes, seconds) = match self.rule.frequency {
+            Frequency::Hourly => (
+                fixed(start.hour(), &self.by_hour),
+                self.by_minute.clone(),
+                self.by_second.clone(),
+            ),
+            Frequency::Minutely => (
+                fixed(start.hour(), &self.by_hour),
+                fixed(start.minute(), &self.by_minute),
+                self.by_second.clone(),
+            ),
+            Frequency::Secondly => (
+                fixed(start.hour(), &self.by_hour),
+                fixed(start.minute(), &self.by_minute),
+                fixed(start.second(), &self.by_second),
+            ),
+            _ => (
+                self.by_hour.clone(),
+                self.by_minute.clone(),
+                self.by_second.clone(),
+            ),
+        };
+
+        let mut times = Vec::new();
+        for h in &hours {
+            for m in &minutes {
+                for s in &seconds {
+                    // Leap seconds (BYSECOND=60) have no wall-clock instant
+                    if let Some(time) = NaiveTime::from_hms_opt(*h, *m, *s) {
+                        times.push(time);
+                    }
+                }
+            }
+        }
+        times
+    }
+
+    fn day_matches(&self, day: NaiveDate) -> bool {
+        let rule = self.rule;
+
+        if !self.by_month.is_empty() && !self.by_month.contains(&(day.month() as u8)) {
+            return false;
+        }
+
+        if !rule.by_week_no.is_empty() {
+            let (week_year, week) = week_number(day, rule.week_start);
+            let weeks = weeks_in_year(week_year, rule.week_start) as i8;
+            let matches = rule
+                .by_week_no
+                .iter()
+                .any(|n| *n == week || (*n < 0 && weeks + *n + 1 == week));
+            if !matches {
+                return false;
+            }
+        }
+
+        if !rule.by_year_day.is_empty() {
+            let ordinal = day.ordinal() as i16;
+            let from_end = ordinal - days_in_year(day.year()) as i16 - 1;
+            if !rule
+                .by_year_day
+                .iter()
+                .any(|n| *n == ordinal || *n == from_end)
+            {
+                return false;
+            }
+        }
+
+        if !self.by_month_day.is_empty() {
+            let dom = day.day() as i8;
+            let from_end = dom - days_in_month(day.year(), day.month()) as i8 - 1;
+            if !self
+                .by_month_day
+                .iter()
+                .any(|n| *n == dom || *n == from_end)
+            {
+                return false;
+            }
+        }
+
+        if !self.by_day.is_empty() && !self.by_day.iter().any(|wd| self.weekday_matches(day, wd)) {
+            return false;
+        }
+
+        true
+    }
+
+    /// Ordinals count within the month for MONTHLY rules (and YEARLY rules
+    /// with BYMONTH), within the year for other YEARLY rules, and are
+    /// ignored for every other frequency
+    fn weekday_matches(&self, day: NaiveDate, wd: &WeekdayNum) -> bool {
+        if day.weekday() != wd.weekday {
+            return false;
+        }
+        let Some(ordinal) = wd.ordinal else {
+            return true;
+        };
+        let (index, len) = match self.rule.frequency {
+            Frequency::Monthly => (day.day(), days_in_month(day.year(), day.month())),
+            Frequency::Yearly if !self.by_month.is_empty() => {
+                (day.day(), days_in_month(day.year(), day.month()))
+            }
+            Frequency::Yearly if self.rule.by_week_no.is_empty() => {
+                (day.ordinal(), days_in_year(day.year()))
+            }
+            _ => return true,
+        };
+        if ordinal > 0 {
+            ((index - 1) / 7 + 1) as i8 == ordinal
+        } else {
+            ((len - index) / 7 + 1) as i8 == -ordinal
+        }
+    }
+}
+
+fn midnight(date: NaiveDate) -> NaiveDateTime {
+    date.and_time(NaiveTime::MIN)
+}
+
+fn days_since(day: Weekday, week_start: Weekday) -> i64 {
+    (day.num_days_from_monday() as i64 - week_start.num_days_from_monday() as i64).rem_euclid(7)
+}
+
+fn days_in_year(year: i32) -> u32 {
+    if NaiveDate::from_ymd_opt(year, 2, 29).is_some() {
+        366
+    } else {
+        365
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(31)
+}
+
+/// First day of week 1: the first week with at least four days in the year
+fn first_week_start(year: i32, week_start: Weekday) -> NaiveDate {
+    let jan1 = NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or(NaiveDate::MIN);
+    let offset = days_since(jan1.weekday(), week_start);
+    if offset <= 3 {
+        jan1 - Duration::days(offset)
+    } else {
+        jan1 + Duration::days(7 - offset)
+    }
+}
+
+fn weeks_in_year(year: i32, week_start: Weekday) -> u32 {
+    ((first_week_start(year + 1, week_start) - first_week_start(year, week_start)).num_days() / 7)
+        as u32
+}
+
+/// Week-numbering year and week number of a day, with weeks starting on
+/// `week_start`
+fn week_number(day: NaiveDate, week_start: Weekday) -> (i32, i8) {
+    let mut year = day.year();
+    if day < first_week_start(year, week_start) {
+        year -= 1;
+    } else if day >= first_week_start(year + 1, week_start) {
+        year += 1;
+    }
+    let week = (day - first_week_start(year, week_start)).num_days() / 7 + 1;
+    (year, week as i8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    fn expand(rule: &str, dtstart: NaiveDateTime, limit: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let rule: RecurrenceRule = rule.parse().unwrap();
+        let never = |_: NaiveDateTime| false;
+        expand_rule(
+            &rule,
+            &ExpandBounds {
+                dtstart,
+                limit,
+                skip_before: None,
+                past_until: &never,
+                max_steps: MAX_EXPANSION_STEPS,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_monthly_last_weekday_with_setpos() {
+        // Last working day of the month
+        let out = expand(
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1;COUNT=3",
+            at(2024, 1, 31, 17, 0),
+            at(2030, 1, 1, 0, 0),
+        );
+        assert_eq!(
+            out,
+            vec![
+                at(2024, 1, 31, 17, 0),
+                at(2024, 2, 29, 17, 0),
+                at(2024, 3, 29, 17, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yearly_nth_weekday_of_month() {
+        // US Thanksgiving
+        let out = expand(
+            "FREQ=YEARLY;BYMONTH=11;BYDAY=4TH;COUNT=2",
+            at(2024, 11, 28, 12, 0),
+            at(2030, 1, 1, 0, 0),
+        );
+        assert_eq!(out, vec![at(2024, 11, 28, 12, 0), at(2025, 11, 27, 12, 0)]);
+    }
+
+    #[test]
+    fn test_weekly_interval_with_week_start() {
+        // RFC 5545 example: WKST changes which days pair up
+        let out = expand(
+            "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=SU",
+            at(1997, 8, 5, 9, 0),
+            at(1998, 1, 1, 0, 0),
+        );
+        assert_eq!(
+            out,
+            vec![
+                at(1997, 8, 5, 9, 0),
+                at(1997, 8, 17, 9, 0),
+                at(1997, 8, 19, 9, 0),
+                at(1997, 8, 31, 9, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_monthly_skips_missing_days() {
+        let out = expand(
+            "FREQ=MONTHLY;BYMONTHDAY=31;COUNT=3",
+            at(2024, 1, 31, 8, 0),
+            at(2030, 1, 1, 0, 0),
+        );
+        assert_eq!(
+            out,
+            vec![
+                at(2024, 1, 31, 8, 0),
+                at(2024, 3, 31, 8, 0),
+                at(2024, 5, 31, 8, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yearly_by_week_no() {
+        // RFC 5545 example: Monday of week 20
+        let out = expand(
+            "FREQ=YEARLY;BYWEEKNO=20;BYDAY=MO;COUNT=2",
+            at(1997, 5, 12, 9, 0),
+            at(2000, 1, 1, 0, 0),
+        );
+        assert_eq!(out, vec![at(1997, 5, 12, 9, 0), at(1998, 5, 11, 9, 0)]);
+    }
+
+    #[test]
+    fn test_hourly_with_by_hour_limit() {
+        let out = expand(
+            "FREQ=HOURLY;INTERVAL=3;BYHOUR=9,12,15",
+            at(2024, 1, 1, 9, 30),
+            at(2024, 1, 2, 10, 0),
+        );
+        assert_eq!(
+            out,
+            vec![
+                at(2024, 1, 1, 9, 30),
+                at(2024, 1, 1, 12, 30),
+                at(2024, 1, 1, 15, 30),
+                at(2024, 1, 2, 9, 30)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_before_keeps_interval_alignment() {
+        let rule: RecurrenceRule = "FREQ=DAILY;INTERVAL=3".parse().unwrap();
+        let never = |_: NaiveDateTime| false;
+        let out = expand_rule(
+            &rule,
+            &ExpandBounds {
+                dtstart: at(2020, 1, 1, 8, 0),
+                limit: at(2024, 1, 10, 0, 0),
+                skip_before: Some(at(2024, 1, 1, 0, 0)),
+                past_until: &never,
+                max_steps: MAX_EXPANSION_STEPS,
+            },
+        )
+        .unwrap();
+        assert!(out.len() < 10);
+        assert!(out.contains(&at(2024, 1, 7, 8, 0)));
+        assert!(out
+            .iter()
+            .all(|d| (d.date() - at(2020, 1, 1, 8, 0).date()).num_days() % 3 == 0));
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let never = |_: NaiveDateTime| false;
+        let bounds = |rule: &RecurrenceRule, max_steps| {
+            expand_rule(
+                rule,
+                &ExpandBounds {
+                    dtstart: at(2024, 1, 1, 0, 0),
+                    limit: at(2024, 1, 2, 0, 0),
+                    skip_before: None,
+                    past_until: &never,
+                    max_steps,
+                },
+            )
+        };
+
+        // Too many periods
+        let secondly: RecurrenceRule = "FREQ=SECONDLY".parse().unwrap();
+        assert_eq!(bounds(&secondly, 1000), Err(StepLimitExceeded(1000)));
+        assert_eq!(bounds(&secondly, 100_000).unwrap().len(), 86_401);
+
+        // Periods that never produce an instance still count
+        let never_matches: RecurrenceRule = "FREQ=HOURLY;BYMONTH=2;BYMONTHDAY=30".parse().unwrap();
+        assert_eq!(bounds(&never_matches, 10), Err(StepLimitExceeded(10)));
+
+        // Too many instances within few periods
+        let every_minute: RecurrenceRule = "FREQ=DAILY;BYHOUR=0,1,2,3,4,5;BYMINUTE=0,10,20,30,40,50".parse().unwrap();
+        assert_eq!(bounds(&every_minute, 20), Err(StepLimitExceeded(20)));
+    }
+}
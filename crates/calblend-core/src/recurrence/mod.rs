@@ -0,0 +1,747 @@
+//! RFC 5545 recurrence: RRULE/RDATE/EXDATE parsing and expansion
+//!
+//! Occurrences are computed on the wall clock of the event's IANA time zone
+//! and only then mapped to instants, so a 09:00 meeting stays at 09:00 across
+//! DST changes. Local times skipped by a DST gap are shifted forward by the
+//! length of the gap, and repeated local times resolve to the first instance,
+//! as RFC 5545 section 3.3.5 prescribes.
+
+mod expand;
+mod rule;
+
+pub use rule::{weekday_code, Frequency, RecurrenceRule, RecurrenceUntil, WeekdayNum};
+
+use chrono::{
+    DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset, Utc,
+};
+use chrono_tz::Tz;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{EventMoment, EventStatus, UnifiedCalendarEvent};
+use crate::timezone::{parse_time_zone, resolve_local};
+use crate::Result;
+pub(crate) use expand::{expand_rule, ExpandBounds, MAX_EXPANSION_STEPS};
+use rule::{invalid, DATE_FORMAT, DATE_TIME_FORMAT};
+
+/// A single `RDATE` or `EXDATE` value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum RecurrenceDate {
+    /// `VALUE=DATE`
+    Date(NaiveDate),
+    /// UTC date-time (`...Z`)
+    Utc(DateTime<Utc>),
+    /// Local date-time, in `tzid` or the event's zone when `None`
+    Local {
+        date_time: NaiveDateTime,
+        tzid: Option<String>,
+    },
+}
+
+impl RecurrenceDate {
+    /// Parse a single DATE or DATE-TIME value with its optional `TZID`
+    pub fn parse(value: &str, tzid: Option<&str>) -> Result<Self> {
+        // PERIOD values (`start/end`) only contribute their start
+        let value = value.split('/').next().unwrap_or(value).trim();
+        if let Some(utc) = value.strip_suffix('Z') {
+            NaiveDateTime::parse_from_str(utc, DATE_TIME_FORMAT)
+                .map(|dt| RecurrenceDate::Utc(dt.and_utc()))
+                .map_err(|e| invalid(format!("invalid date-time {:?}: {}", value, e)))
+        } else if value.contains('T') {
+            NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+                .map(|date_time| RecurrenceDate::Local {
+                    date_time,
+                    tzid: tzid.map(str::to_string),
+                })
+                .map_err(|e| invalid(format!("invalid date-time {:?}: {}", value, e)))
+        } else {
+            NaiveDate::parse_from_str(value, DATE_FORMAT)
+                .map(RecurrenceDate::Date)
+                .map_err(|e| invalid(format!("invalid date {:?}: {}", value, e)))
+        }
+    }
+
+    /// The value in RFC 5545 basic format, without its `TZID`
+    pub fn value(&self) -> String {
+        match self {
+            RecurrenceDate::Date(date) => date.format(DATE_FORMAT).to_string(),
+            RecurrenceDate::Utc(dt) => format!("{}Z", dt.format(DATE_TIME_FORMAT)),
+            RecurrenceDate::Local { date_time, .. } => date_time.format(DATE_TIME_FORMAT).to_string(),
+        }
+    }
+
+    /// The `TZID` of a local date-time
+    pub fn tzid(&self) -> Option<&str> {
+        match self {
+            RecurrenceDate::Local { tzid, .. } => tzid.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Content line for this value, e.g. `EXDATE;TZID=Europe/Paris:20240102T090000`
+    pub(crate) fn to_line(&self, name: &str) -> String {
+        match self {
+            RecurrenceDate::Date(date) => {
+                format!("{};VALUE=DATE:{}", name, date.format(DATE_FORMAT))
+            }
+            RecurrenceDate::Utc(dt) => format!("{}:{}Z", name, dt.format(DATE_TIME_FORMAT)),
+            RecurrenceDate::Local {
+                date_time,
+                tzid: Some(tzid),
+            } => {
+                format!(
+                    "{};TZID={}:{}",
+                    name,
+                    tzid,
+                    date_time.format(DATE_TIME_FORMAT)
+                )
+            }
+            RecurrenceDate::Local {
+                date_time,
+                tzid: None,
+            } => {
+                format!("{}:{}", name, date_time.format(DATE_TIME_FORMAT))
+            }
+        }
+    }
+
+    /// Wall-clock value in `zone`; dates take the series' time of day
+    fn local_in(&self, zone: &Zone, time_of_day: NaiveTime) -> NaiveDateTime {
+        match self {
+            RecurrenceDate::Date(date) => date.and_time(time_of_day),
+            RecurrenceDate::Utc(dt) => zone.wall_clock(*dt),
+            RecurrenceDate::Local { date_time, tzid } => {
+                match tzid.as_deref().and_then(|name| parse_time_zone(name).ok()) {
+                    Some(tz) => {
+                        zone.wall_clock(Zone::Tz(tz).resolve(*date_time).with_timezone(&Utc))
+                    }
+                    None => *date_time,
+                }
+            }
+        }
+    }
+}
+
+/// Typed recurrence of an event: rules plus explicit extra and excluded dates
+///
+/// Converts losslessly to and from RFC 5545 `RRULE`/`RDATE`/`EXDATE`
+/// content lines, which is also the shape of Google's `recurrence` array.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RecurrencePattern {
+    pub rules: Vec<RecurrenceRule>,
+    pub rdates: Vec<RecurrenceDate>,
+    pub exdates: Vec<RecurrenceDate>,
+}
+
+impl RecurrencePattern {
+    /// A pattern with a single rule
+    pub fn new(rule: RecurrenceRule) -> Self {
+        Self {
+            rules: vec![rule],
+            ..Default::default()
+        }
+    }
+
+    /// The main rule, if any
+    pub fn rule(&self) -> Option<&RecurrenceRule> {
+        self.rules.first()
+    }
+
+    /// Mutable access to the main rule, if any
+    pub fn rule_mut(&mut self) -> Option<&mut RecurrenceRule> {
+        self.rules.first_mut()
+    }
+
+    /// Add an extra occurrence (`RDATE`)
+    pub fn with_rdate(mut self, date: RecurrenceDate) -> Self {
+        self.rdates.push(date);
+        self
+    }
+
+    /// Exclude an occurrence (`EXDATE`)
+    pub fn with_exdate(mut self, date: RecurrenceDate) -> Self {
+        self.exdates.push(date);
+        self
+    }
+
+    /// Parse `RRULE`, `RDATE` and `EXDATE` content lines
+    ///
+    /// Lines without a property name are read as RRULE values. `EXRULE`
+    /// (deprecated by RFC 5545) and unknown properties are rejected.
+    pub fn parse<I, S>(lines: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut set = RecurrencePattern::default();
+        for line in lines {
+            let line = line.as_ref().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((head, value)) = line.split_once(':') else {
+                set.rules.push(line.parse()?);
+                continue;
+            };
+            let mut params = head.split(';');
+            let name = params.next().unwrap_or_default().to_ascii_uppercase();
+            let tzid = params.find_map(|p| {
+                p.split_once('=')
+                    .filter(|(k, _)| k.eq_ignore_ascii_case("TZID"))
+                    .map(|(_, v)| v.trim_matches('"'))
+            });
+
+            match name.as_str() {
+                "RRULE" => set.rules.push(value.parse()?),
+                "RDATE" | "EXDATE" => {
+                    let dates = value
+                        .split(',')
+                        .map(|v| RecurrenceDate::parse(v, tzid))
+                        .collect::<Result<Vec<_>>>()?;
+                    if name == "RDATE" {
+                        set.rdates.extend(dates);
+                    } else {
+                        set.exdates.extend(dates);
+                    }
+                }
+                _ if head.contains('=') => set.rules.push(line.parse()?),
+                other => {
+                    return Err(invalid(format!(
+                        "unsupported recurrence property {:?}",
+                        other
+                    )))
+                }
+            }
+        }
+        Ok(set)
+    }
+
+    /// The recurrence of a unified event
+    ///
+    /// Uses the typed `recurrence` when present. Otherwise parses the raw
+    /// strings: `recurrence_rule` holds the RRULE and each
+    /// `recurrence_exceptions` entry is either an EXDATE content line or a
+    /// bare EXDATE value. Other content lines there, as older versions
+    /// stored them, are still understood.
+    pub fn from_event(event: &UnifiedCalendarEvent) -> Result<Option<Self>> {
+        if let Some(pattern) = &event.recurrence {
+            return Ok(Some(pattern.clone()));
+        }
+        let Some(rule) = &event.recurrence_rule else {
+            return Ok(None);
+        };
+        let mut lines = vec![rule.clone()];
+        for exception in event.recurrence_exceptions.iter().flatten() {
+            if exception.contains(':') {
+                lines.push(exception.clone());
+            } else {
+                lines.push(format!("EXDATE:{}", exception));
+            }
+        }
+        Self::parse(lines).map(Some)
+    }
+
+    /// Content lines for this pattern, one `RDATE`/`EXDATE` value per line
+    pub fn to_lines(&self) -> Vec<String> {
+        self.rules
+            .iter()
+            .map(|rule| format!("RRULE:{}", rule))
+            .chain(self.rdates.iter().map(|d| d.to_line("RDATE")))
+            .chain(self.exdates.iter().map(|d| d.to_line("EXDATE")))
+            .collect()
+    }
+
+    /// Occurrence starts of a series beginning at `dtstart` that fall in
+    /// `[window_start, window_end)`
+    ///
+    /// `dtstart` is always the first occurrence. Timed occurrences carry the
+    /// offset in effect at that local time; date occurrences are placed on
+    /// the window by the event's zone, or UTC without one. Rules too dense
+    /// to expand over the window (about a million steps) fail.
+    pub fn occurrences_between(
+        &self,
+        dtstart: &EventMoment,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> Result<Vec<EventMoment>> {
+        let zone = Zone::for_moment(dtstart);
+        let all_day = dtstart.is_all_day();
+        let local_start = match dtstart {
+            EventMoment::Date { date, .. } => date.and_time(NaiveTime::MIN),
+            EventMoment::DateTime { date_time, .. } => zone.wall_clock(date_time.with_timezone(&Utc)),
+        };
+        let time_of_day = local_start.time();
+        let normalize = |local: NaiveDateTime| {
+            if all_day {
+                local.date().and_time(NaiveTime::MIN)
+            } else {
+                local
+            }
+        };
+
+        // A day of slack each side absorbs offset differences
+        let limit = zone.wall_clock(window_end) + Duration::days(1);
+        let skip_before = zone.wall_clock(window_start) - Duration::days(1);
+
+        let mut locals = vec![local_start];
+        for rule in &self.rules {
+            let past_until = |local: NaiveDateTime| match &rule.until {
+                None => false,
+                Some(RecurrenceUntil::Date(date)) => local.date() > *date,
+                Some(RecurrenceUntil::Floating(until)) => local > *until,
+                Some(RecurrenceUntil::DateTime(until)) => zone.resolve(local) > *until,
+            };
+            let expanded = expand_rule(
+                rule,
+                &ExpandBounds {
+                    dtstart: local_start,
+                    limit,
+                    skip_before: Some(skip_before),
+                    past_until: &past_until,
+                    max_steps: MAX_EXPANSION_STEPS,
+                },
+            )
+            .map_err(|e| invalid(format!("RRULE:{} needs more than {} steps to expand", rule, e.0)))?;
+            locals.extend(expanded);
+        }
+        locals.extend(
+            self.rdates
+                .iter()
+                .map(|d| normalize(d.local_in(&zone, time_of_day))),
+        );
+
+        let excluded: Vec<NaiveDateTime> = self
+            .exdates
+            .iter()
+            .filter(|d| !matches!(d, RecurrenceDate::Date(_)))
+            .map(|d| normalize(d.local_in(&zone, time_of_day)))
+            .collect();
+        let excluded_days: Vec<NaiveDate> = self
+            .exdates
+            .iter()
+            .filter_map(|d| match d {
+                RecurrenceDate::Date(date) => Some(*date),
+                _ => None,
+            })
+            .collect();
+
+        locals.sort_unstable();
+        locals.dedup();
+
+        Ok(locals
+            .into_iter()
+            .filter(|local| !excluded.contains(local) && !excluded_days.contains(&local.date()))
+            .map(|local| (local, zone.resolve(local)))
+            .filter(|(_, at)| *at >= window_start && *at < window_end)
+            .map(|(local, at)| {
+                let time_zone = dtstart.time_zone().map(str::to_string);
+                if all_day {
+                    EventMoment::Date {
+                        date: local.date(),
+                        time_zone,
+                    }
+                } else {
+                    EventMoment::DateTime {
+                        date_time: at,
+                        time_zone,
+                    }
+                }
+            })
+            .collect())
+    }
+}
+
+/// Expand a recurring event into the instances overlapping a window
+///
+/// Non-recurring events are returned as-is when they overlap. Instances keep
+/// the event's duration, point back at the event through `series_id`, and
+/// get Google-style ids (`{id}_{start}`, with the start in UTC basic format
+/// or as a date for all-day events). They are not exceptions, so they carry
+/// no `original_start`.
+pub fn expand_event(
+    event: &UnifiedCalendarEvent,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+) -> Result<Vec<UnifiedCalendarEvent>> {
+    expand_event_with_overrides(event, &[], window_start, window_end)
+}
+
+/// Expand a recurring event, applying overridden occurrences
+///
+/// Each override with an `original_start` replaces the generated occurrence
+/// that started then. Cancelled overrides remove it. Overrides are placed at
+/// their own (possibly moved) times, so an occurrence moved into the window
+/// appears even when its original slot lies outside it. The result is
+/// sorted by start.
+pub fn expand_event_with_overrides(
+    event: &UnifiedCalendarEvent,
+    overrides: &[UnifiedCalendarEvent],
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+) -> Result<Vec<UnifiedCalendarEvent>> {
+    let Some(pattern) = RecurrencePattern::from_event(event)? else {
+        return Ok(if overlaps(event, window_start, window_end) {
+            vec![event.clone()]
+        } else {
+            Vec::new()
+        });
+    };
+
+    let (start_at, end_at) = bounds(event);
+    let duration = end_at - start_at;
+    let end_zone = Zone::for_moment(&event.end);
+    let overridden: Vec<MomentKey> = overrides
+        .iter()
+        .filter_map(|o| o.original_start.as_ref().map(MomentKey::of))
+        .collect();
+
+    let starts = pattern.occurrences_between(&event.start, window_start - duration, window_end)?;
+    let mut instances: Vec<UnifiedCalendarEvent> = starts
+        .into_iter()
+        .filter(|start| !overridden.contains(&MomentKey::of(start)))
+        .filter_map(|start| {
+            let time_zone = event.end.time_zone().map(str::to_string);
+            let (end, stamp) = match &start {
+                EventMoment::Date { date, .. } => (
+                    EventMoment::Date {
+                        date: *date + Duration::days(duration.num_days()),
+                        time_zone,
+                    },
+                    date.format(DATE_FORMAT).to_string(),
+                ),
+                EventMoment::DateTime { date_time, .. } => {
+                    let start_utc = date_time.with_timezone(&Utc);
+                    (
+                        EventMoment::DateTime {
+                            date_time: end_zone.at(start_utc + duration),
+                            time_zone,
+                        },
+                        format!("{}Z", start_utc.format(DATE_TIME_FORMAT)),
+                    )
+                }
+            };
+            if end_zone.resolve(end.naive_local()) <= window_start && duration > Duration::zero()
+            {
+                return None;
+            }
+
+            let mut instance = event.clone();
+            instance.id = format!("{}_{}", event.id, stamp);
+            instance.original_start = None;
+            instance.series_id = Some(event.id.clone());
+            instance.start = start;
+            instance.end = end;
+            instance.recurrence_rule = None;
+            instance.recurrence_exceptions = None;
+            instance.recurrence = None;
+            Some(instance)
+        })
+        .collect();
+
+    instances.extend(
+        overrides
+            .iter()
+            .filter(|o| o.original_start.is_some())
+            .filter(|o| !matches!(o.status, Some(EventStatus::Cancelled)))
+            .filter(|o| overlaps(o, window_start, window_end))
+            .cloned(),
+    );
+    instances.sort_by_key(|e| bounds(e).0);
+    Ok(instances)
+}
+
+/// Expand a list mixing series, their overrides and single events
+///
+/// Overrides are applied to the series named by their `series_id`; those
+/// whose series is not in the list are treated as single events.
+pub fn expand_events(
+    events: &[UnifiedCalendarEvent],
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+) -> Result<Vec<UnifiedCalendarEvent>> {
+    let is_master = |id: &str| events.iter().any(|e| e.id == id && e.is_recurring());
+
+    let mut out = Vec::new();
+    for event in events {
+        if event.is_exception() && event.series_id.as_deref().is_some_and(is_master) {
+            continue;
+        }
+        let overrides: Vec<UnifiedCalendarEvent> = events
+            .iter()
+            .filter(|e| e.is_exception() && e.series_id.as_deref() == Some(event.id.as_str()))
+            .cloned()
+            .collect();
+        out.extend(expand_event_with_overrides(event, &overrides, window_start, window_end)?);
+    }
+    out.sort_by_key(|e| bounds(e).0);
+    Ok(out)
+}
+
+/// Start and end instants of an event
+fn bounds(event: &UnifiedCalendarEvent) -> (DateTime<FixedOffset>, DateTime<FixedOffset>) {
+    (
+        Zone::for_moment(&event.start).resolve(event.start.naive_local()),
+        Zone::for_moment(&event.end).resolve(event.end.naive_local()),
+    )
+}
+
+fn overlaps(event: &UnifiedCalendarEvent, window_start: DateTime<Utc>, window_end: DateTime<Utc>) -> bool {
+    let (start, end) = bounds(event);
+    start < window_end && (end > window_start || start == window_start)
+}
+
+/// Identity of an occurrence start, as matched by `RECURRENCE-ID`
+#[derive(Debug, PartialEq, Eq)]
+enum MomentKey {
+    Day(NaiveDate),
+    Instant(DateTime<Utc>),
+}
+
+impl MomentKey {
+    fn of(moment: &EventMoment) -> Self {
+        match moment {
+            EventMoment::Date { date, .. } => MomentKey::Day(*date),
+            EventMoment::DateTime { date_time, .. } => MomentKey::Instant(date_time.with_timezone(&Utc)),
+        }
+    }
+}
+
+/// Zone in which a series is expanded
+#[derive(Debug, Clone, Copy)]
+enum Zone {
+    Tz(Tz),
+    Fixed(FixedOffset),
+}
+
+impl Zone {
+    /// The moment's IANA zone, falling back to its fixed offset (or UTC for
+    /// dates) when the zone is missing or unknown
+    fn for_moment(moment: &EventMoment) -> Self {
+        if let Some(tz) = moment.time_zone().and_then(|name| parse_time_zone(name).ok()) {
+            return Zone::Tz(tz);
+        }
+        match moment.date_time() {
+            Some(dt) => Zone::Fixed(*dt.offset()),
+            None => Zone::Fixed(Utc.fix()),
+        }
+    }
+
+    fn wall_clock(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Tz(tz) => at.with_timezone(tz).naive_local(),
+            Zone::Fixed(offset) => at.with_timezone(offset).naive_local(),
+        }
+    }
+
+    fn at(&self, at: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Tz(tz) => at.with_timezone(tz).fixed_offset(),
+            Zone::Fixed(offset) => at.with_timezone(offset),
+        }
+    }
+
+    /// Map a wall-clock time to an instant, per RFC 5545 for DST gaps and
+    /// overlaps
+    fn resolve(&self, local: NaiveDateTime) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Fixed(offset) => (local - *offset).and_utc().with_timezone(offset),
+            Zone::Tz(tz) => resolve_local(*tz, local),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CalendarSource;
+    use chrono::{Datelike, TimeZone, Timelike};
+
+    fn utc(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap()
+    }
+
+    fn new_york(y: i32, m: u32, d: u32, h: u32, min: u32) -> EventMoment {
+        let dt = chrono_tz::America::New_York
+            .with_ymd_and_hms(y, m, d, h, min, 0)
+            .unwrap();
+        EventMoment::timed(dt.fixed_offset(), Some("America/New_York".to_string()))
+    }
+
+    #[test]
+    fn test_daily_keeps_local_time_across_dst() {
+        let set = RecurrencePattern::parse(["RRULE:FREQ=DAILY"]).unwrap();
+        let out = set
+            .occurrences_between(
+                &new_york(2024, 3, 8, 9, 0),
+                utc(2024, 3, 8),
+                utc(2024, 3, 12),
+            )
+            .unwrap();
+        let hours: Vec<(u32, i32)> = out
+            .iter()
+            .map(|m| {
+                let dt = m.date_time().unwrap();
+                (dt.hour(), dt.offset().local_minus_utc() / 3600)
+            })
+            .collect();
+        assert_eq!(hours, vec![(9, -5), (9, -5), (9, -4), (9, -4)]);
+    }
+
+    #[test]
+    fn test_nonexistent_local_time_shifts_forward() {
+        let set = RecurrencePattern::parse(["RRULE:FREQ=DAILY;COUNT=3"]).unwrap();
+        let out = set
+            .occurrences_between(
+                &new_york(2024, 3, 9, 2, 30),
+                utc(2024, 3, 1),
+                utc(2024, 3, 20),
+            )
+            .unwrap();
+        let second = out[1].date_time().unwrap();
+        assert_eq!((second.day(), second.hour(), second.minute()), (10, 3, 30));
+    }
+
+    #[test]
+    fn test_count_includes_unmatched_dtstart() {
+        // 2024-03-11 is a Monday; DTSTART is the first of the two instances
+        let set = RecurrencePattern::parse(["RRULE:FREQ=WEEKLY;BYDAY=TU;COUNT=2"]).unwrap();
+        let out = set
+            .occurrences_between(
+                &new_york(2024, 3, 11, 9, 0),
+                utc(2024, 3, 1),
+                utc(2024, 4, 1),
+            )
+            .unwrap();
+        let days: Vec<u32> = out.iter().map(|m| m.date().day()).collect();
+        assert_eq!(days, vec![11, 12]);
+
+        // A matching DTSTART is not counted twice
+        let set = RecurrencePattern::parse(["RRULE:FREQ=WEEKLY;BYDAY=MO,TU;COUNT=3"]).unwrap();
+        let out = set
+            .occurrences_between(
+                &new_york(2024, 3, 11, 9, 0),
+                utc(2024, 3, 1),
+                utc(2024, 4, 1),
+            )
+            .unwrap();
+        let days: Vec<u32> = out.iter().map(|m| m.date().day()).collect();
+        assert_eq!(days, vec![11, 12, 18]);
+    }
+
+    #[test]
+    fn test_rdate_and_exdate_with_tzid() {
+        let set = RecurrencePattern::parse([
+            "RRULE:FREQ=WEEKLY;BYDAY=MO;UNTIL=20240130T000000Z",
+            "EXDATE;TZID=America/New_York:20240115T090000",
+            "RDATE:20240117T140000Z",
+        ])
+        .unwrap();
+        let out = set
+            .occurrences_between(
+                &new_york(2024, 1, 8, 9, 0),
+                utc(2024, 1, 1),
+                utc(2024, 3, 1),
+            )
+            .unwrap();
+        let days: Vec<u32> = out.iter().map(|m| m.date().day()).collect();
+        assert_eq!(days, vec![8, 17, 22, 29]);
+        assert_eq!(out[1].date_time().unwrap().hour(), 9);
+    }
+
+    #[test]
+    fn test_to_lines_round_trip() {
+        let lines = [
+            "RRULE:FREQ=MONTHLY;BYDAY=-1FR",
+            "RDATE;VALUE=DATE:20240105",
+            "EXDATE;TZID=Europe/Berlin:20240126T100000",
+            "EXDATE:20240223T090000Z",
+        ];
+        let set = RecurrencePattern::parse(lines).unwrap();
+        assert_eq!(set.to_lines(), lines);
+        assert!(RecurrencePattern::parse(["EXRULE:FREQ=DAILY"]).is_err());
+    }
+
+    #[test]
+    fn test_expand_all_day_event() {
+        let mut event = UnifiedCalendarEvent::new(
+            "evt".to_string(),
+            CalendarSource::Google,
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 2, 28).unwrap()),
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()),
+        );
+        event.recurrence_rule = Some("RRULE:FREQ=YEARLY".to_string());
+
+        let out = expand_event(&event, utc(2025, 1, 1), utc(2027, 1, 1)).unwrap();
+        let ids: Vec<&str> = out.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["evt_20250228", "evt_20260228"]);
+        assert_eq!(
+            out[0].end,
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap())
+        );
+        assert!(out[0].recurrence_rule.is_none());
+    }
+
+    #[test]
+    fn test_expand_includes_instance_overlapping_window_start() {
+        let mut event = UnifiedCalendarEvent::new(
+            "standup".to_string(),
+            CalendarSource::Google,
+            new_york(2024, 1, 1, 23, 0),
+            new_york(2024, 1, 2, 1, 0),
+        );
+        event.recurrence_rule = Some("FREQ=DAILY;COUNT=5".to_string());
+        event.recurrence_exceptions = Some(vec!["20240104T040000Z".to_string()]);
+
+        // 2024-01-03 05:00Z is midnight in New York, inside the 23:00 instance
+        let window_start = Utc.with_ymd_and_hms(2024, 1, 3, 5, 0, 0).unwrap();
+        let out = expand_event(&event, window_start, utc(2024, 2, 1)).unwrap();
+        let ids: Vec<&str> = out.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "standup_20240103T040000Z",
+                "standup_20240105T040000Z",
+                "standup_20240106T040000Z"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_applies_overrides() {
+        let mut series = UnifiedCalendarEvent::new(
+            "standup".to_string(),
+            CalendarSource::Google,
+            new_york(2024, 1, 8, 9, 0),
+            new_york(2024, 1, 8, 9, 15),
+        );
+        series.recurrence_rule = Some("FREQ=DAILY;COUNT=5".to_string());
+
+        // Tuesday's standup moved to the afternoon, Wednesday's cancelled
+        let mut moved = UnifiedCalendarEvent::new(
+            "standup_20240109T140000Z".to_string(),
+            CalendarSource::Google,
+            new_york(2024, 1, 9, 15, 0),
+            new_york(2024, 1, 9, 15, 15),
+        );
+        moved.series_id = Some("standup".to_string());
+        moved.original_start = Some(new_york(2024, 1, 9, 9, 0));
+        let mut cancelled = moved.clone();
+        cancelled.id = "standup_20240110T140000Z".to_string();
+        cancelled.original_start = Some(new_york(2024, 1, 10, 9, 0));
+        cancelled.status = Some(EventStatus::Cancelled);
+
+        let out = expand_events(&[series, moved, cancelled], utc(2024, 1, 1), utc(2024, 2, 1)).unwrap();
+        let hours: Vec<(u32, u32)> = out
+            .iter()
+            .map(|e| {
+                let dt = e.start.date_time().unwrap();
+                (dt.day(), dt.hour())
+            })
+            .collect();
+        assert_eq!(hours, vec![(8, 9), (9, 15), (11, 9), (12, 9)]);
+        assert!(out.iter().all(|e| e.series_id.as_deref() == Some("standup")));
+        // Only the moved occurrence is an exception
+        let exceptions: Vec<&str> = out.iter().filter(|e| e.is_exception()).map(|e| e.id.as_str()).collect();
+        assert_eq!(exceptions, vec!["standup_20240109T140000Z"]);
+    }
+}
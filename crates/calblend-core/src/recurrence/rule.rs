@@ -0,0 +1,455 @@
+//! RRULE value type: parsing and formatting (RFC 5545 section 3.3.10)
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc, Weekday};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{CalblendError, Result};
+
+/// Recurrence frequency (`FREQ`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Secondly => "SECONDLY",
+            Frequency::Minutely => "MINUTELY",
+            Frequency::Hourly => "HOURLY",
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = CalblendError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "SECONDLY" => Ok(Frequency::Secondly),
+            "MINUTELY" => Ok(Frequency::Minutely),
+            "HOURLY" => Ok(Frequency::Hourly),
+            "DAILY" => Ok(Frequency::Daily),
+            "WEEKLY" => Ok(Frequency::Weekly),
+            "MONTHLY" => Ok(Frequency::Monthly),
+            "YEARLY" => Ok(Frequency::Yearly),
+            other => Err(invalid(format!("unknown FREQ {:?}", other))),
+        }
+    }
+}
+
+/// Weekday with an optional ordinal (`BYDAY`), e.g. `2MO` or `-1FR`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct WeekdayNum {
+    /// 1-based position within the month or year, negative from the end
+    pub ordinal: Option<i8>,
+    #[schemars(schema_with = "weekday_schema")]
+    pub weekday: Weekday,
+}
+
+impl WeekdayNum {
+    /// Every occurrence of the weekday
+    pub fn every(weekday: Weekday) -> Self {
+        Self {
+            ordinal: None,
+            weekday,
+        }
+    }
+
+    /// The n-th occurrence of the weekday (negative counts from the end)
+    pub fn nth(ordinal: i8, weekday: Weekday) -> Self {
+        Self {
+            ordinal: Some(ordinal),
+            weekday,
+        }
+    }
+}
+
+impl fmt::Display for WeekdayNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ordinal) = self.ordinal {
+            write!(f, "{}", ordinal)?;
+        }
+        f.write_str(weekday_code(self.weekday))
+    }
+}
+
+impl FromStr for WeekdayNum {
+    type Err = CalblendError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        // Weekday codes and ordinals are ASCII, so a byte split is safe
+        if s.len() < 2 || !s.is_ascii() {
+            return Err(invalid(format!("invalid BYDAY value {:?}", s)));
+        }
+        let (ordinal, day) = s.split_at(s.len() - 2);
+        let weekday = parse_weekday(day)?;
+        let ordinal = if ordinal.is_empty() {
+            None
+        } else {
+            let n: i8 = ordinal
+                .trim_start_matches('+')
+                .parse()
+                .map_err(|_| invalid(format!("invalid BYDAY ordinal {:?}", s)))?;
+            if n == 0 || !(-53..=53).contains(&n) {
+                return Err(invalid(format!("BYDAY ordinal out of range in {:?}", s)));
+            }
+            Some(n)
+        };
+        Ok(Self { ordinal, weekday })
+    }
+}
+
+/// End of a recurrence (`UNTIL`), in the value type it was written with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum RecurrenceUntil {
+    /// `UNTIL=20241231`
+    Date(NaiveDate),
+    /// `UNTIL=20241231T235959Z`
+    DateTime(DateTime<Utc>),
+    /// `UNTIL=20241231T235959`, interpreted in the event's time zone
+    Floating(NaiveDateTime),
+}
+
+impl fmt::Display for RecurrenceUntil {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecurrenceUntil::Date(date) => write!(f, "{}", date.format(DATE_FORMAT)),
+            RecurrenceUntil::DateTime(dt) => write!(f, "{}Z", dt.format(DATE_TIME_FORMAT)),
+            RecurrenceUntil::Floating(dt) => write!(f, "{}", dt.format(DATE_TIME_FORMAT)),
+        }
+    }
+}
+
+impl FromStr for RecurrenceUntil {
+    type Err = CalblendError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(utc) = s.strip_suffix('Z') {
+            let dt = NaiveDateTime::parse_from_str(utc, DATE_TIME_FORMAT)
+                .map_err(|e| invalid(format!("invalid UNTIL {:?}: {}", s, e)))?;
+            Ok(RecurrenceUntil::DateTime(dt.and_utc()))
+        } else if s.contains('T') {
+            NaiveDateTime::parse_from_str(s, DATE_TIME_FORMAT)
+                .map(RecurrenceUntil::Floating)
+                .map_err(|e| invalid(format!("invalid UNTIL {:?}: {}", s, e)))
+        } else {
+            NaiveDate::parse_from_str(s, DATE_FORMAT)
+                .map(RecurrenceUntil::Date)
+                .map_err(|e| invalid(format!("invalid UNTIL {:?}: {}", s, e)))
+        }
+    }
+}
+
+/// A parsed `RRULE` value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<RecurrenceUntil>,
+    pub by_second: Vec<u8>,
+    pub by_minute: Vec<u8>,
+    pub by_hour: Vec<u8>,
+    pub by_day: Vec<WeekdayNum>,
+    pub by_month_day: Vec<i8>,
+    pub by_year_day: Vec<i16>,
+    pub by_week_no: Vec<i8>,
+    pub by_month: Vec<u8>,
+    pub by_set_pos: Vec<i16>,
+    #[schemars(schema_with = "weekday_schema")]
+    pub week_start: Weekday,
+}
+
+impl RecurrenceRule {
+    /// A rule with the given frequency and no other parts
+    pub fn new(frequency: Frequency) -> Self {
+        Self {
+            frequency,
+            interval: 1,
+            count: None,
+            until: None,
+            by_second: Vec::new(),
+            by_minute: Vec::new(),
+            by_hour: Vec::new(),
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_year_day: Vec::new(),
+            by_week_no: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon,
+        }
+    }
+
+    /// Check the rule parts against the ranges allowed by RFC 5545
+    pub fn validate(&self) -> Result<()> {
+        if self.interval == 0 {
+            return Err(invalid("INTERVAL must be positive"));
+        }
+        if self.count.is_some() && self.until.is_some() {
+            return Err(invalid("COUNT and UNTIL must not both be set"));
+        }
+        check_range("BYSECOND", &self.by_second, |v| v <= 60)?;
+        check_range("BYMINUTE", &self.by_minute, |v| v <= 59)?;
+        check_range("BYHOUR", &self.by_hour, |v| v <= 23)?;
+        check_range("BYMONTHDAY", &self.by_month_day, |v| {
+            v != 0 && (-31..=31).contains(&v)
+        })?;
+        check_range("BYYEARDAY", &self.by_year_day, |v| {
+            v != 0 && (-366..=366).contains(&v)
+        })?;
+        check_range("BYWEEKNO", &self.by_week_no, |v| {
+            v != 0 && (-53..=53).contains(&v)
+        })?;
+        check_range("BYMONTH", &self.by_month, |v| (1..=12).contains(&v))?;
+        check_range("BYSETPOS", &self.by_set_pos, |v| {
+            v != 0 && (-366..=366).contains(&v)
+        })?;
+        if !self.by_week_no.is_empty() && self.frequency != Frequency::Yearly {
+            return Err(invalid("BYWEEKNO is only valid with FREQ=YEARLY"));
+        }
+        if !self.by_year_day.is_empty()
+            && matches!(
+                self.frequency,
+                Frequency::Daily | Frequency::Weekly | Frequency::Monthly
+            )
+        {
+            return Err(invalid(
+                "BYYEARDAY is not valid with DAILY, WEEKLY or MONTHLY",
+            ));
+        }
+        if !self.by_month_day.is_empty() && self.frequency == Frequency::Weekly {
+            return Err(invalid("BYMONTHDAY is not valid with FREQ=WEEKLY"));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = CalblendError;
+
+    /// Parse an RRULE value, with or without the `RRULE:` prefix
+    fn from_str(s: &str) -> Result<Self> {
+        let value = s.trim();
+        let value = value
+            .get(..6)
+            .filter(|p| p.eq_ignore_ascii_case("RRULE:"))
+            .map(|_| &value[6..])
+            .unwrap_or(value);
+
+        let mut frequency = None;
+        let mut rule = RecurrenceRule::new(Frequency::Daily);
+
+        for part in value.split(';').filter(|p| !p.is_empty()) {
+            let (name, val) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("malformed rule part {:?}", part)))?;
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => frequency = Some(val.parse()?),
+                "INTERVAL" => rule.interval = parse_number(name, val)?,
+                "COUNT" => rule.count = Some(parse_number(name, val)?),
+                "UNTIL" => rule.until = Some(val.parse()?),
+                "BYSECOND" => rule.by_second = parse_list(name, val)?,
+                "BYMINUTE" => rule.by_minute = parse_list(name, val)?,
+                "BYHOUR" => rule.by_hour = parse_list(name, val)?,
+                "BYDAY" => rule.by_day = val.split(',').map(str::parse).collect::<Result<_>>()?,
+                "BYMONTHDAY" => rule.by_month_day = parse_list(name, val)?,
+                "BYYEARDAY" => rule.by_year_day = parse_list(name, val)?,
+                "BYWEEKNO" => rule.by_week_no = parse_list(name, val)?,
+                "BYMONTH" => rule.by_month = parse_list(name, val)?,
+                "BYSETPOS" => rule.by_set_pos = parse_list(name, val)?,
+                "WKST" => rule.week_start = parse_weekday(val)?,
+                // Unknown (e.g. X-) parts are ignored as RFC 5545 allows
+                _ => {}
+            }
+        }
+
+        rule.frequency = frequency.ok_or_else(|| invalid("missing FREQ"))?;
+        rule.validate()?;
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    /// Format as an RRULE value without the `RRULE:` prefix
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.frequency.as_str())?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = &self.until {
+            write!(f, ";UNTIL={}", until)?;
+        }
+        write_list(f, "BYSECOND", &self.by_second)?;
+        write_list(f, "BYMINUTE", &self.by_minute)?;
+        write_list(f, "BYHOUR", &self.by_hour)?;
+        write_list(f, "BYDAY", &self.by_day)?;
+        write_list(f, "BYMONTHDAY", &self.by_month_day)?;
+        write_list(f, "BYYEARDAY", &self.by_year_day)?;
+        write_list(f, "BYWEEKNO", &self.by_week_no)?;
+        write_list(f, "BYMONTH", &self.by_month)?;
+        write_list(f, "BYSETPOS", &self.by_set_pos)?;
+        if self.week_start != Weekday::Mon {
+            write!(f, ";WKST={}", weekday_code(self.week_start))?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) const DATE_FORMAT: &str = "%Y%m%d";
+pub(crate) const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+pub(crate) fn invalid(message: impl fmt::Display) -> CalblendError {
+    CalblendError::InvalidData(format!("Invalid recurrence: {}", message))
+}
+
+/// Schema for chrono's serialized weekday names
+fn weekday_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    schemars::schema::SchemaObject {
+        instance_type: Some(schemars::schema::InstanceType::String.into()),
+        enum_values: Some(
+            ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        ),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Two-letter RFC 5545 weekday code
+pub fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_weekday(s: &str) -> Result<Weekday> {
+    match s.to_ascii_uppercase().as_str() {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        other => Err(invalid(format!("unknown weekday {:?}", other))),
+    }
+}
+
+fn parse_number<T: FromStr>(name: &str, value: &str) -> Result<T> {
+    value
+        .trim_start_matches('+')
+        .parse()
+        .map_err(|_| invalid(format!("invalid {} value {:?}", name, value)))
+}
+
+fn parse_list<T: FromStr>(name: &str, value: &str) -> Result<Vec<T>> {
+    value.split(',').map(|v| parse_number(name, v)).collect()
+}
+
+fn check_range<T: Copy + fmt::Display>(
+    name: &str,
+    values: &[T],
+    ok: impl Fn(T) -> bool,
+) -> Result<()> {
+    match values.iter().find(|v| !ok(**v)) {
+        Some(v) => Err(invalid(format!("{} value {} out of range", name, v))),
+        None => Ok(()),
+    }
+}
+
+fn write_list<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    values: &[T],
+) -> fmt::Result {
+    if values.is_empty() {
+        return Ok(());
+    }
+    let joined: Vec<String> = values.iter().map(ToString::to_string).collect();
+    write!(f, ";{}={}", name, joined.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_round_trip() {
+        let rule: RecurrenceRule = "RRULE:FREQ=MONTHLY;INTERVAL=2;COUNT=10;BYDAY=1SU,-1SU;WKST=SU"
+            .parse()
+            .unwrap();
+        assert_eq!(rule.frequency, Frequency::Monthly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.count, Some(10));
+        assert_eq!(
+            rule.by_day,
+            vec![
+                WeekdayNum::nth(1, Weekday::Sun),
+                WeekdayNum::nth(-1, Weekday::Sun)
+            ]
+        );
+        assert_eq!(rule.week_start, Weekday::Sun);
+        assert_eq!(
+            rule.to_string(),
+            "FREQ=MONTHLY;INTERVAL=2;COUNT=10;BYDAY=1SU,-1SU;WKST=SU"
+        );
+    }
+
+    #[test]
+    fn test_parse_until_variants() {
+        let rule: RecurrenceRule = "FREQ=DAILY;UNTIL=20240131T235959Z".parse().unwrap();
+        assert!(matches!(rule.until, Some(RecurrenceUntil::DateTime(_))));
+        let rule: RecurrenceRule = "FREQ=DAILY;UNTIL=20240131".parse().unwrap();
+        assert_eq!(
+            rule.until,
+            Some(RecurrenceUntil::Date(
+                NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()
+            ))
+        );
+        assert_eq!(rule.to_string(), "FREQ=DAILY;UNTIL=20240131");
+    }
+
+    #[test]
+    fn test_rejects_invalid_rules() {
+        assert!("BYDAY=MO".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=DAILY;COUNT=3;UNTIL=20240101"
+            .parse::<RecurrenceRule>()
+            .is_err());
+        assert!("FREQ=MONTHLY;BYMONTHDAY=32"
+            .parse::<RecurrenceRule>()
+            .is_err());
+        assert!("FREQ=WEEKLY;BYWEEKNO=3".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=FORTNIGHTLY".parse::<RecurrenceRule>().is_err());
+        // Non-ASCII days are rejected rather than split mid-character
+        assert!("FREQ=WEEKLY;BYDAY=éA".parse::<RecurrenceRule>().is_err());
+        assert!("1é".parse::<WeekdayNum>().is_err());
+    }
+}
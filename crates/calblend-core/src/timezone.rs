@@ -0,0 +1,389 @@
+//! Time zone normalization built on chrono-tz
+//!
+//! Providers name zones differently: Google uses IANA names, Exchange/Graph
+//! uses Windows names, and older clients still send legacy aliases such as
+//! `US/Eastern`. Everything is resolved to a [`chrono_tz::Tz`] here.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+
+use crate::models::{EventMoment, UnifiedCalendarEvent};
+use crate::{CalblendError, Result};
+
+/// Resolve an IANA name, legacy alias or Windows zone name
+pub fn parse_time_zone(name: &str) -> Result<Tz> {
+    let name = name.trim();
+    let iana = windows_to_iana(name)
+        .or_else(|| legacy_to_iana(name))
+        .unwrap_or(name);
+    iana.parse::<Tz>()
+        .map_err(|_| CalblendError::InvalidData(format!("Unknown time zone: {}", name)))
+}
+
+/// Whether `name` is an IANA zone name known to the tz database
+pub fn is_valid_iana(name: &str) -> bool {
+    name.parse::<Tz>().is_ok()
+}
+
+/// Canonical IANA name for any name accepted by [`parse_time_zone`]
+pub fn canonical_name(name: &str) -> Result<&'static str> {
+    let tz = parse_time_zone(name)?;
+    Ok(legacy_to_iana(tz.name()).unwrap_or(tz.name()))
+}
+
+/// IANA name for a Windows zone name, e.g. `W. Europe Standard Time`
+pub fn windows_to_iana(name: &str) -> Option<&'static str> {
+    WINDOWS_ZONES
+        .iter()
+        .find(|(windows, _)| windows.eq_ignore_ascii_case(name))
+        .map(|(_, iana)| *iana)
+}
+
+/// Windows zone name for an IANA zone, for providers that only accept those
+pub fn iana_to_windows(tz: Tz) -> Option<&'static str> {
+    let name = legacy_to_iana(tz.name()).unwrap_or(tz.name());
+    WINDOWS_ZONES
+        .iter()
+        .find(|(_, iana)| *iana == name)
+        .map(|(windows, _)| *windows)
+}
+
+/// Canonical IANA name for a legacy alias, e.g. `Asia/Calcutta`
+fn legacy_to_iana(name: &str) -> Option<&'static str> {
+    LEGACY_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+        .map(|(_, iana)| *iana)
+}
+
+/// Map a wall-clock time in `tz` to an instant
+///
+/// Follows RFC 5545: a time repeated by a DST overlap resolves to its first
+/// instance, and a time skipped by a DST gap is moved forward by the length
+/// of the gap.
+pub fn resolve_local(tz: Tz, local: NaiveDateTime) -> DateTime<FixedOffset> {
+    match tz.from_local_datetime(&local).earliest() {
+        Some(dt) => dt.fixed_offset(),
+        None => {
+            // Inside a gap: use the offset in effect before it
+            let before = tz.offset_from_utc_datetime(&(local - Duration::days(1))).fix();
+            (local - before).and_utc().with_timezone(&tz).fixed_offset()
+        }
+    }
+}
+
+/// The same moment seen from another zone
+///
+/// Timed moments keep their instant and take the offset `tz` has at that
+/// instant. Dates are floating and keep their calendar date.
+pub fn convert(moment: &EventMoment, tz: Tz) -> EventMoment {
+    match moment {
+        EventMoment::DateTime { date_time, .. } => EventMoment::DateTime {
+            date_time: date_time.with_timezone(&tz).fixed_offset(),
+            time_zone: Some(tz.name().to_string()),
+        },
+        EventMoment::Date { date, .. } => EventMoment::Date {
+            date: *date,
+            time_zone: Some(tz.name().to_string()),
+        },
+    }
+}
+
+/// Validate a moment's zone and bring it to canonical form
+///
+/// The zone name is replaced by its canonical IANA name and, for timed
+/// moments, the offset is recomputed from that zone at the same instant, so
+/// a stale offset can no longer disagree with the zone.
+pub fn normalize(moment: &EventMoment) -> Result<EventMoment> {
+    let Some(name) = moment.time_zone() else {
+        return Ok(moment.clone());
+    };
+    let tz = canonical_name(name)?.parse::<Tz>().map_err(|_| {
+        CalblendError::InternalError(format!("Canonical zone {} does not parse", name))
+    })?;
+    Ok(convert(moment, tz))
+}
+
+/// An event with its times shown in a viewer's zone
+pub fn render_event(event: &UnifiedCalendarEvent, viewer: Tz) -> UnifiedCalendarEvent {
+    let mut rendered = event.clone();
+    rendered.start = convert(&event.start, viewer);
+    rendered.end = convert(&event.end, viewer);
+    rendered.original_start = event.original_start.as_ref().map(|m| convert(m, viewer));
+    rendered
+}
+
+/// Common legacy aliases still accepted by chrono-tz, with their canonical names
+const LEGACY_ALIASES: &[(&str, &str)] = &[
+    ("US/Eastern", "America/New_York"),
+    ("US/Central", "America/Chicago"),
+    ("US/Mountain", "America/Denver"),
+    ("US/Pacific", "America/Los_Angeles"),
+    ("US/Alaska", "America/Anchorage"),
+    ("US/Hawaii", "Pacific/Honolulu"),
+    ("US/Arizona", "America/Phoenix"),
+    ("US/East-Indiana", "America/Indiana/Indianapolis"),
+    ("Canada/Eastern", "America/Toronto"),
+    ("Canada/Central", "America/Winnipeg"),
+    ("Canada/Mountain", "America/Edmonton"),
+    ("Canada/Pacific", "America/Vancouver"),
+    ("Canada/Atlantic", "America/Halifax"),
+    ("Canada/Newfoundland", "America/St_Johns"),
+    ("America/Indianapolis", "America/Indiana/Indianapolis"),
+    ("America/Buenos_Aires", "America/Argentina/Buenos_Aires"),
+    ("America/Godthab", "America/Nuuk"),
+    ("Asia/Calcutta", "Asia/Kolkata"),
+    ("Asia/Saigon", "Asia/Ho_Chi_Minh"),
+    ("Asia/Katmandu", "Asia/Kathmandu"),
+    ("Asia/Rangoon", "Asia/Yangon"),
+    ("Asia/Ulan_Bator", "Asia/Ulaanbaatar"),
+    ("Europe/Kiev", "Europe/Kyiv"),
+    ("Australia/ACT", "Australia/Sydney"),
+    ("Australia/NSW", "Australia/Sydney"),
+    ("Australia/Victoria", "Australia/Melbourne"),
+    ("Australia/Queensland", "Australia/Brisbane"),
+    ("Australia/West", "Australia/Perth"),
+    ("Australia/South", "Australia/Adelaide"),
+    ("Australia/North", "Australia/Darwin"),
+    ("Australia/Tasmania", "Australia/Hobart"),
+    ("GB", "Europe/London"),
+    ("Japan", "Asia/Tokyo"),
+    ("PRC", "Asia/Shanghai"),
+    ("ROK", "Asia/Seoul"),
+    ("Singapore", "Asia/Singapore"),
+    ("Turkey", "Europe/Istanbul"),
+    ("Israel", "Asia/Jerusalem"),
+    ("Egypt", "Africa/Cairo"),
+    ("NZ", "Pacific/Auckland"),
+    ("UTC", "Etc/UTC"),
+    ("UCT", "Etc/UTC"),
+    ("Universal", "Etc/UTC"),
+    ("Zulu", "Etc/UTC"),
+    ("GMT", "Etc/UTC"),
+    ("Etc/GMT", "Etc/UTC"),
+    ("Etc/Universal", "Etc/UTC"),
+    ("Etc/Zulu", "Etc/UTC"),
+];
+
+/// Windows zone names and their IANA equivalents (CLDR `windowsZones`, territory 001)
+const WINDOWS_ZONES: &[(&str, &str)] = &[
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("UTC-11", "Etc/GMT+11"),
+    ("Aleutian Standard Time", "America/Adak"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Marquesas Standard Time", "Pacific/Marquesas"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("UTC-09", "Etc/GMT+9"),
+    ("Pacific Standard Time (Mexico)", "America/Tijuana"),
+    ("UTC-08", "Etc/GMT+8"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Mountain Standard Time (Mexico)", "America/Mazatlan"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Yukon Standard Time", "America/Whitehorse"),
+    ("Central America Standard Time", "America/Guatemala"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Easter Island Standard Time", "Pacific/Easter"),
+    ("Central Standard Time (Mexico)", "America/Mexico_City"),
+    ("Canada Central Standard Time", "America/Regina"),
+    ("SA Pacific Standard Time", "America/Bogota"),
+    ("Eastern Standard Time (Mexico)", "America/Cancun"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("Haiti Standard Time", "America/Port-au-Prince"),
+    ("Cuba Standard Time", "America/Havana"),
+    ("US Eastern Standard Time", "America/Indiana/Indianapolis"),
+    ("Turks And Caicos Standard Time", "America/Grand_Turk"),
+    ("Paraguay Standard Time", "America/Asuncion"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("Venezuela Standard Time", "America/Caracas"),
+    ("Central Brazilian Standard Time", "America/Cuiaba"),
+    ("SA Western Standard Time", "America/La_Paz"),
+    ("Pacific SA Standard Time", "America/Santiago"),
+    ("Newfoundland Standard Time", "America/St_Johns"),
+    ("Tocantins Standard Time", "America/Araguaina"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("SA Eastern Standard Time", "America/Cayenne"),
+    ("Argentina Standard Time", "America/Argentina/Buenos_Aires"),
+    ("Greenland Standard Time", "America/Nuuk"),
+    ("Montevideo Standard Time", "America/Montevideo"),
+    ("Magallanes Standard Time", "America/Punta_Arenas"),
+    ("Saint Pierre Standard Time", "America/Miquelon"),
+    ("Bahia Standard Time", "America/Bahia"),
+    ("UTC-02", "Etc/GMT+2"),
+    ("Azores Standard Time", "Atlantic/Azores"),
+    ("Cape Verde Standard Time", "Atlantic/Cape_Verde"),
+    ("UTC", "Etc/UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("Sao Tome Standard Time", "Africa/Sao_Tome"),
+    ("Morocco Standard Time", "Africa/Casablanca"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("W. Central Africa Standard Time", "Africa/Lagos"),
+    ("Jordan Standard Time", "Asia/Amman"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("Middle East Standard Time", "Asia/Beirut"),
+    ("Egypt Standard Time", "Africa/Cairo"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("Syria Standard Time", "Asia/Damascus"),
+    ("West Bank Standard Time", "Asia/Hebron"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("FLE Standard Time", "Europe/Kyiv"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("South Sudan Standard Time", "Africa/Juba"),
+    ("Kaliningrad Standard Time", "Europe/Kaliningrad"),
+    ("Sudan Standard Time", "Africa/Khartoum"),
+    ("Libya Standard Time", "Africa/Tripoli"),
+    ("Namibia Standard Time", "Africa/Windhoek"),
+    ("Arabic Standard Time", "Asia/Baghdad"),
+    ("Turkey Standard Time", "Europe/Istanbul"),
+    ("Arab Standard Time", "Asia/Riyadh"),
+    ("Belarus Standard Time", "Europe/Minsk"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("E. Africa Standard Time", "Africa/Nairobi"),
+    ("Volgograd Standard Time", "Europe/Volgograd"),
+    ("Iran Standard Time", "Asia/Tehran"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Astrakhan Standard Time", "Europe/Astrakhan"),
+    ("Azerbaijan Standard Time", "Asia/Baku"),
+    ("Russia Time Zone 3", "Europe/Samara"),
+    ("Mauritius Standard Time", "Indian/Mauritius"),
+    ("Saratov Standard Time", "Europe/Saratov"),
+    ("Georgian Standard Time", "Asia/Tbilisi"),
+    ("Caucasus Standard Time", "Asia/Yerevan"),
+    ("Afghanistan Standard Time", "Asia/Kabul"),
+    ("West Asia Standard Time", "Asia/Tashkent"),
+    ("Qyzylorda Standard Time", "Asia/Qyzylorda"),
+    ("Ekaterinburg Standard Time", "Asia/Yekaterinburg"),
+    ("Pakistan Standard Time", "Asia/Karachi"),
+    ("India Standard Time", "Asia/Kolkata"),
+    ("Sri Lanka Standard Time", "Asia/Colombo"),
+    ("Nepal Standard Time", "Asia/Kathmandu"),
+    ("Central Asia Standard Time", "Asia/Almaty"),
+    ("Bangladesh Standard Time", "Asia/Dhaka"),
+    ("Omsk Standard Time", "Asia/Omsk"),
+    ("Myanmar Standard Time", "Asia/Yangon"),
+    ("SE Asia Standard Time", "Asia/Bangkok"),
+    ("Altai Standard Time", "Asia/Barnaul"),
+    ("W. Mongolia Standard Time", "Asia/Hovd"),
+    ("North Asia Standard Time", "Asia/Krasnoyarsk"),
+    ("N. Central Asia Standard Time", "Asia/Novosibirsk"),
+    ("Tomsk Standard Time", "Asia/Tomsk"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("North Asia East Standard Time", "Asia/Irkutsk"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("W. Australia Standard Time", "Australia/Perth"),
+    ("Taipei Standard Time", "Asia/Taipei"),
+    ("Ulaanbaatar Standard Time", "Asia/Ulaanbaatar"),
+    ("Aus Central W. Standard Time", "Australia/Eucla"),
+    ("Transbaikal Standard Time", "Asia/Chita"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("North Korea Standard Time", "Asia/Pyongyang"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Yakutsk Standard Time", "Asia/Yakutsk"),
+    ("Cen. Australia Standard Time", "Australia/Adelaide"),
+    ("AUS Central Standard Time", "Australia/Darwin"),
+    ("E. Australia Standard Time", "Australia/Brisbane"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("West Pacific Standard Time", "Pacific/Port_Moresby"),
+    ("Tasmania Standard Time", "Australia/Hobart"),
+    ("Vladivostok Standard Time", "Asia/Vladivostok"),
+    ("Lord Howe Standard Time", "Australia/Lord_Howe"),
+    ("Bougainville Standard Time", "Pacific/Bougainville"),
+    ("Russia Time Zone 10", "Asia/Srednekolymsk"),
+    ("Magadan Standard Time", "Asia/Magadan"),
+    ("Norfolk Standard Time", "Pacific/Norfolk"),
+    ("Sakhalin Standard Time", "Asia/Sakhalin"),
+    ("Central Pacific Standard Time", "Pacific/Guadalcanal"),
+    ("Russia Time Zone 11", "Asia/Kamchatka"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+    ("UTC+12", "Etc/GMT-12"),
+    ("Fiji Standard Time", "Pacific/Fiji"),
+    ("Chatham Islands Standard Time", "Pacific/Chatham"),
+    ("UTC+13", "Etc/GMT-13"),
+    ("Tonga Standard Time", "Pacific/Tongatapu"),
+    ("Samoa Standard Time", "Pacific/Apia"),
+    ("Line Islands Standard Time", "Pacific/Kiritimati"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Timelike};
+
+    #[test]
+    fn test_parse_time_zone_names() {
+        assert_eq!(parse_time_zone("Europe/Berlin").unwrap(), Tz::Europe__Berlin);
+        assert_eq!(parse_time_zone("W. Europe Standard Time").unwrap(), Tz::Europe__Berlin);
+        assert_eq!(canonical_name("US/Eastern").unwrap(), "America/New_York");
+        assert_eq!(canonical_name("Asia/Calcutta").unwrap(), "Asia/Kolkata");
+        assert_eq!(canonical_name("India Standard Time").unwrap(), "Asia/Kolkata");
+        assert!(parse_time_zone("Mars/Olympus_Mons").is_err());
+        assert!(!is_valid_iana("Pacific Standard Time"));
+        assert_eq!(iana_to_windows(Tz::America__New_York), Some("Eastern Standard Time"));
+        assert_eq!(iana_to_windows(Tz::US__Pacific), Some("Pacific Standard Time"));
+    }
+
+    #[test]
+    fn test_every_windows_zone_resolves() {
+        for (windows, iana) in WINDOWS_ZONES {
+            assert!(is_valid_iana(iana), "{} maps to unknown {}", windows, iana);
+        }
+        for (alias, iana) in LEGACY_ALIASES {
+            assert!(is_valid_iana(iana), "{} maps to unknown {}", alias, iana);
+        }
+    }
+
+    #[test]
+    fn test_resolve_local_across_dst() {
+        let tz = Tz::America__New_York;
+        let gap = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap().and_hms_opt(2, 30, 0).unwrap();
+        let resolved = resolve_local(tz, gap);
+        assert_eq!((resolved.hour(), resolved.minute()), (3, 30));
+
+        let overlap = NaiveDate::from_ymd_opt(2024, 11, 3).unwrap().and_hms_opt(1, 30, 0).unwrap();
+        assert_eq!(resolve_local(tz, overlap).offset().local_minus_utc(), -4 * 3600);
+    }
+
+    #[test]
+    fn test_normalize_fixes_offset_and_name() {
+        // Offset written as if it were winter, zone given as a Windows name
+        let stale = EventMoment::timed(
+            DateTime::parse_from_rfc3339("2024-07-01T12:00:00+00:00").unwrap(),
+            Some("GMT Standard Time".to_string()),
+        );
+        let normalized = normalize(&stale).unwrap();
+        assert_eq!(normalized.time_zone(), Some("Europe/London"));
+        assert_eq!(
+            normalized.date_time().unwrap().to_rfc3339(),
+            "2024-07-01T13:00:00+01:00"
+        );
+        assert!(normalize(&EventMoment::timed(
+            stale.date_time().unwrap(),
+            Some("Nowhere/Special".to_string())
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_render_event_in_viewer_zone() {
+        let event = UnifiedCalendarEvent::new(
+            "evt".to_string(),
+            crate::CalendarSource::Google,
+            EventMoment::timed(
+                DateTime::parse_from_rfc3339("2024-01-15T09:00:00-05:00").unwrap(),
+                Some("America/New_York".to_string()),
+            ),
+            EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 1, 16).unwrap()),
+        );
+        let rendered = render_event(&event, Tz::Asia__Tokyo);
+        assert_eq!(
+            rendered.start.date_time().unwrap().to_rfc3339(),
+            "2024-01-15T23:00:00+09:00"
+        );
+        assert_eq!(rendered.start.time_zone(), Some("Asia/Tokyo"));
+        assert_eq!(rendered.end.date(), NaiveDate::from_ymd_opt(2024, 1, 16).unwrap());
+    }
+}
@@ -0,0 +1,263 @@
+//! Structural checks on unified events before they are sent to a provider
+
+use std::fmt::Write;
+
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::error::{CalblendError, ValidationError, ValidationErrorKind, ValidationErrors};
+use crate::models::{EventMoment, Participant, UnifiedCalendarEvent};
+use crate::recurrence::RecurrencePattern;
+use crate::timezone;
+
+impl UnifiedCalendarEvent {
+    /// Check the event for values no provider would accept
+    ///
+    /// Every failed check is reported, not just the first one.
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+
+        check_time_zone("start.time_zone", &self.start, &mut errors);
+        check_time_zone("end.time_zone", &self.end, &mut errors);
+        check_span(&self.start, &self.end, &mut errors);
+        check_recurrence(self, &mut errors);
+
+        if let Some(organizer) = &self.organizer {
+            check_participant("organizer", organizer, &mut errors);
+        }
+        for (i, attendee) in self.attendees.iter().flatten().enumerate() {
+            check_participant(&format!("attendees[{}]", i), attendee, &mut errors);
+        }
+        for (i, reminder) in self.reminders.iter().flatten().enumerate() {
+            if reminder.minutes_before < 0 {
+                errors.push(ValidationError::new(
+                    format!("reminders[{}].minutes_before", i),
+                    ValidationErrorKind::OutOfRange,
+                    "Reminder must not fire after the event starts",
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+}
+
+fn check_time_zone(field: &str, moment: &EventMoment, errors: &mut Vec<ValidationError>) {
+    if let Some(name) = moment.time_zone() {
+        if timezone::parse_time_zone(name).is_err() {
+            errors.push(ValidationError::new(
+                field,
+                ValidationErrorKind::InvalidTimeZone,
+                format!("Unknown time zone: {}", name),
+            ));
+        }
+    }
+}
+
+fn check_span(start: &EventMoment, end: &EventMoment, errors: &mut Vec<ValidationError>) {
+    let ends_before = match (start, end) {
+        (EventMoment::Date { date: start, .. }, EventMoment::Date { date: end, .. }) => end < start,
+        (
+            EventMoment::DateTime {
+                date_time: start, ..
+            },
+            EventMoment::DateTime { date_time: end, .. },
+        ) => end < start,
+        _ => {
+            errors.push(ValidationError::new(
+                "end",
+                ValidationErrorKind::MixedAllDay,
+                "Start and end must both be dates or both be date-times",
+            ));
+            return;
+        }
+    };
+    if ends_before {
+        errors.push(ValidationError::new(
+            "end",
+            ValidationErrorKind::EndBeforeStart,
+            "End must not be before start",
+        ));
+    }
+}
+
+fn check_recurrence(event: &UnifiedCalendarEvent, errors: &mut Vec<ValidationError>) {
+    let (field, result) = match &event.recurrence {
+        Some(pattern) => ("recurrence", check_rules(pattern)),
+        None => (
+            "recurrence_rule",
+            RecurrencePattern::from_event(event).and_then(|pattern| match pattern {
+                Some(pattern) => check_rules(&pattern),
+                None => Ok(()),
+            }),
+        ),
+    };
+    if let Err(err) = result {
+        errors.push(ValidationError::new(
+            field,
+            ValidationErrorKind::InvalidRecurrence,
+            err.to_string(),
+        ));
+    }
+}
+
+fn check_rules(pattern: &RecurrencePattern) -> crate::Result<()> {
+    pattern.rules.iter().try_for_each(|rule| rule.validate())
+}
+
+fn check_participant(field: &str, participant: &Participant, errors: &mut Vec<ValidationError>) {
+    if let Some(email) = &participant.email {
+        if !is_valid_email(email) {
+            errors.push(ValidationError::new(
+                format!("{}.email", field),
+                ValidationErrorKind::InvalidEmail,
+                format!("Invalid email address: {}", email),
+            ));
+        }
+    }
+}
+
+/// Loose address check: one `@`, a non-empty local part and a dotted domain
+pub(crate) fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && !email.chars().any(char::is_whitespace)
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|label| !label.is_empty())
+}
+
+/// Reject date and time formats chrono cannot render, which would panic
+///
+/// Rendering a sample finds both unparsable formats and ones asking for
+/// fields a date or a time does not have, like `%H` in a date or `%z`.
+pub(crate) fn check_date_time_formats(date_format: &str, time_format: &str) -> crate::Result<()> {
+    let invalid = |value: &str| {
+        CalblendError::InvalidData(format!("Invalid date or time format {:?}", value))
+    };
+    let mut sample = String::new();
+    write!(sample, "{}", NaiveDate::MIN.format(date_format)).map_err(|_| invalid(date_format))?;
+    write!(sample, "{}", NaiveTime::MIN.format(time_format)).map_err(|_| invalid(time_format))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CalendarSource, Reminder};
+    use chrono::{DateTime, NaiveDate};
+
+    fn timed(value: &str) -> EventMoment {
+        EventMoment::timed(DateTime::parse_from_rfc3339(value).unwrap(), None)
+    }
+
+    fn event(start: EventMoment, end: EventMoment) -> UnifiedCalendarEvent {
+        UnifiedCalendarEvent::new("1".to_string(), CalendarSource::Google, start, end)
+    }
+
+    #[test]
+    fn test_valid_event_passes() {
+        let mut event = event(
+            timed("2024-03-10T09:00:00-05:00"),
+            timed("2024-03-10T10:00:00-05:00"),
+        );
+        event.recurrence_rule = Some("FREQ=WEEKLY;BYDAY=MO,WE".to_string());
+        event.attendees = Some(vec![Participant {
+            email: Some("ada@example.com".to_string()),
+            ..Default::default()
+        }]);
+        assert!(event.validate().is_ok());
+
+        // Zero-length events are allowed
+        let instant = timed("2024-03-10T09:00:00Z");
+        assert!(self::event(instant.clone(), instant).validate().is_ok());
+    }
+
+    #[test]
+    fn test_end_is_compared_as_instant() {
+        // 09:00 in New York is 14:00 UTC, so this ends an hour after it starts
+        let event = event(
+            timed("2024-03-10T09:00:00-05:00"),
+            timed("2024-03-10T15:00:00+00:00"),
+        );
+        assert!(event.validate().is_ok());
+
+        let event = self::event(
+            timed("2024-03-10T09:00:00-05:00"),
+            timed("2024-03-10T13:00:00+00:00"),
+        );
+        let errors = event.validate().unwrap_err();
+        assert!(errors.has(ValidationErrorKind::EndBeforeStart));
+    }
+
+    #[test]
+    fn test_mixed_all_day_is_rejected() {
+        let date = EventMoment::all_day(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap());
+        let event = event(date, timed("2024-03-11T00:00:00Z"));
+        let errors = event.validate().unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+        assert_eq!(errors.0[0].field, "end");
+        assert_eq!(errors.0[0].kind, ValidationErrorKind::MixedAllDay);
+    }
+
+    #[test]
+    fn test_reports_every_failure() {
+        let mut event = event(timed("2024-03-10T10:00:00Z"), timed("2024-03-10T09:00:00Z"));
+        event.recurrence_rule = Some("FREQ=SOMETIMES".to_string());
+        event.organizer = Some(Participant {
+            email: Some("organizer@example.com".to_string()),
+            ..Default::default()
+        });
+        event.attendees = Some(vec![
+            Participant {
+                email: Some("ok@example.com".to_string()),
+                ..Default::default()
+            },
+            Participant {
+                email: Some("not an email".to_string()),
+                ..Default::default()
+            },
+        ]);
+        event.reminders = Some(vec![Reminder {
+            minutes_before: -5,
+            method: None,
+        }]);
+
+        let errors = event.validate().unwrap_err();
+        let kinds: Vec<_> = errors.0.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ValidationErrorKind::EndBeforeStart,
+                ValidationErrorKind::InvalidRecurrence,
+                ValidationErrorKind::InvalidEmail,
+                ValidationErrorKind::OutOfRange,
+            ]
+        );
+        assert_eq!(errors.for_field("attendees[1].email").count(), 1);
+        assert!(errors
+            .to_string()
+            .contains("attendees[1].email: Invalid email address"));
+    }
+
+    #[test]
+    fn test_email_check() {
+        assert!(is_valid_email("a.b+tag@mail.example.co.uk"));
+        for bad in [
+            "",
+            "plain",
+            "@example.com",
+            "a@b",
+            "a@@b.com",
+            "a b@example.com",
+            "a@example..com",
+        ] {
+            assert!(!is_valid_email(bad), "{}", bad);
+        }
+    }
+}
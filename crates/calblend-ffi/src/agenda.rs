@@ -0,0 +1,106 @@
+//! Agenda rendering
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use calblend_core::agenda::{self, AgendaOptions};
+
+use crate::error::to_napi_error;
+use crate::models::UnifiedCalendarEvent;
+
+/// Output format of an agenda
+#[napi]
+#[derive(Debug)]
+pub enum AgendaFormat {
+    Text,
+    Markdown,
+    /// HTML fragment with every value escaped
+    Html,
+}
+
+impl From<AgendaFormat> for agenda::AgendaFormat {
+    fn from(format: AgendaFormat) -> Self {
+        match format {
+            AgendaFormat::Text => Self::Text,
+            AgendaFormat::Markdown => Self::Markdown,
+            AgendaFormat::Html => Self::Html,
+        }
+    }
+}
+
+/// How an agenda is grouped and labelled
+#[napi(object)]
+#[derive(Debug)]
+pub struct AgendaRenderOptions {
+    pub format: AgendaFormat,
+    /// IANA zone days and times are shown in; UTC when unset
+    pub time_zone: Option<String>,
+    /// Heading above the days
+    pub title: Option<String>,
+    /// chrono format of day headings, e.g. `%A, %B %-d, %Y`
+    pub date_format: Option<String>,
+    /// chrono format of times, e.g. `%H:%M`
+    pub time_format: Option<String>,
+    /// First day to show, `YYYY-MM-DD`; days without events are shown empty
+    pub first_day: Option<String>,
+    /// Last day to show, `YYYY-MM-DD`; the first day when unset
+    pub last_day: Option<String>,
+    /// Address identifying the reader among attendees
+    pub self_email: Option<String>,
+    /// Whether declined events are listed; true when unset
+    pub include_declined: Option<bool>,
+}
+
+impl TryFrom<&AgendaRenderOptions> for AgendaOptions {
+    type Error = String;
+
+    fn try_from(options: &AgendaRenderOptions) -> std::result::Result<Self, String> {
+        let mut agenda = AgendaOptions::new(
+            options
+                .time_zone
+                .clone()
+                .unwrap_or_else(|| "UTC".to_string()),
+        );
+        agenda.title = options.title.clone();
+        if let Some(format) = &options.date_format {
+            agenda.date_format = format.clone();
+        }
+        if let Some(format) = &options.time_format {
+            agenda.time_format = format.clone();
+        }
+        let day = |value: &str| {
+            value
+                .parse::<chrono::NaiveDate>()
+                .map_err(|e| format!("Invalid day {:?}: {}", value, e))
+        };
+        if let Some(first) = &options.first_day {
+            let first = day(first)?;
+            let last = options
+                .last_day
+                .as_deref()
+                .map(day)
+                .transpose()?
+                .unwrap_or(first);
+            agenda.days = Some((first, last));
+        }
+        agenda.self_email = options.self_email.clone();
+        agenda.include_declined = options.include_declined.unwrap_or(true);
+        Ok(agenda)
+    }
+}
+
+/// Render events as a day-by-day agenda
+#[napi]
+pub fn render_agenda(
+    events: Vec<UnifiedCalendarEvent>,
+    options: AgendaRenderOptions,
+) -> Result<String> {
+    let events = events
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<std::result::Result<Vec<calblend_core::UnifiedCalendarEvent>, String>>()
+        .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    let agenda_options =
+        AgendaOptions::try_from(&options).map_err(|e| Error::new(Status::InvalidArg, e))?;
+    agenda::render_agenda(&events, &agenda_options, options.format.into()).map_err(to_napi_error)
+}
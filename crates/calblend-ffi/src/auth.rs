@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 /// Token data for FFI
 #[napi(object)]
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct TokenData {
     pub access_token: String,
     pub refresh_token: Option<String>,
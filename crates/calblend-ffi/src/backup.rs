@@ -0,0 +1,89 @@
+//! Account backup and restore types
+
+use std::collections::HashMap;
+
+use napi_derive::napi;
+
+use calblend_core::backup;
+
+/// What a backup captured
+#[napi(object)]
+#[derive(Debug)]
+pub struct BackupSummary {
+    pub calendars: u32,
+    pub events: u32,
+}
+
+impl From<&backup::AccountBackup> for BackupSummary {
+    fn from(backup: &backup::AccountBackup) -> Self {
+        let events: usize = backup.calendars.iter().map(|c| c.events.len()).sum();
+        Self {
+            calendars: u32::try_from(backup.calendars.len()).unwrap_or(u32::MAX),
+            events: u32::try_from(events).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+/// How a backup is replayed
+#[napi(object)]
+#[derive(Debug, Default)]
+pub struct RestoreOptions {
+    /// Whether attendees are notified; false when unset
+    pub send_invitations: Option<bool>,
+    /// Target calendar ID for each backed-up calendar ID
+    pub calendar_map: Option<HashMap<String, String>>,
+    /// Only calendars with these backed-up IDs
+    pub calendar_ids: Option<Vec<String>>,
+    /// Leave out events the target calendar already has; true when unset
+    pub skip_existing: Option<bool>,
+}
+
+impl From<RestoreOptions> for backup::RestoreOptions {
+    fn from(options: RestoreOptions) -> Self {
+        let defaults = Self::default();
+        Self {
+            send_invitations: options
+                .send_invitations
+                .unwrap_or(defaults.send_invitations),
+            calendar_map: options.calendar_map.unwrap_or_default(),
+            calendar_ids: options.calendar_ids,
+            skip_existing: options.skip_existing.unwrap_or(defaults.skip_existing),
+        }
+    }
+}
+
+/// An event that could not be restored
+#[napi(object)]
+#[derive(Debug)]
+pub struct RestoreError {
+    pub calendar_id: String,
+    pub event_id: String,
+    pub message: String,
+}
+
+/// Outcome of a restore
+#[napi(object)]
+#[derive(Debug)]
+pub struct RestoreReport {
+    pub created: u32,
+    pub skipped: u32,
+    pub errors: Vec<RestoreError>,
+}
+
+impl From<backup::RestoreReport> for RestoreReport {
+    fn from(report: backup::RestoreReport) -> Self {
+        Self {
+            created: u32::try_from(report.created).unwrap_or(u32::MAX),
+            skipped: u32::try_from(report.skipped).unwrap_or(u32::MAX),
+            errors: report
+                .errors
+                .into_iter()
+                .map(|error| RestoreError {
+                    calendar_id: error.calendar_id,
+                    event_id: error.event_id,
+                    message: error.message,
+                })
+                .collect(),
+        }
+    }
+}
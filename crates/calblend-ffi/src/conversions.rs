@@ -1,7 +1,8 @@
 //! Conversion implementations between FFI and core types
 
 use crate::models::*;
-use chrono::DateTime;
+use calblend_core::recurrence;
+use chrono::{DateTime, NaiveDate};
 
 impl From<calblend_core::Calendar> for Calendar {
     fn from(cal: calblend_core::Calendar) -> Self {
@@ -13,6 +14,279 @@ impl From<calblend_core::Calendar> for Calendar {
             is_primary: cal.is_primary,
             can_write: cal.can_write,
             source: cal.source.into(),
+            foreground_color: cal.foreground_color,
+            time_zone: cal.time_zone,
+            access_role: cal.access_role.map(|role| match role {
+                calblend_core::AccessRole::FreeBusyReader => AccessRole::FreeBusyReader,
+                calblend_core::AccessRole::Reader => AccessRole::Reader,
+                calblend_core::AccessRole::Writer => AccessRole::Writer,
+                calblend_core::AccessRole::Owner => AccessRole::Owner,
+            }),
+            default_reminders: cal
+                .default_reminders
+                .map(|reminders| reminders.into_iter().map(Into::into).collect()),
+            conference_types: cal.conference_types,
+            hidden: cal.hidden,
+            selected: cal.selected,
+        }
+    }
+}
+
+impl From<calblend_core::Reminder> for Reminder {
+    fn from(r: calblend_core::Reminder) -> Self {
+        Self {
+            minutes_before: r.minutes_before,
+            method: r.method.map(|m| match m {
+                calblend_core::ReminderMethod::Popup => ReminderMethod::Popup,
+                calblend_core::ReminderMethod::Email => ReminderMethod::Email,
+                calblend_core::ReminderMethod::Sms => ReminderMethod::Sms,
+            }),
+        }
+    }
+}
+
+impl From<calblend_core::EventMoment> for EventMoment {
+    fn from(moment: calblend_core::EventMoment) -> Self {
+        match moment {
+            calblend_core::EventMoment::Date { date, time_zone } => Self {
+                date_time: None,
+                date: Some(date.format("%Y-%m-%d").to_string()),
+                time_zone,
+                all_day: Some(true),
+            },
+            calblend_core::EventMoment::DateTime { date_time, time_zone } => Self {
+                date_time: Some(date_time.to_rfc3339()),
+                date: None,
+                time_zone,
+                all_day: Some(false),
+            },
+        }
+    }
+}
+
+impl TryFrom<EventMoment> for calblend_core::EventMoment {
+    type Error = String;
+
+    fn try_from(moment: EventMoment) -> Result<Self, Self::Error> {
+        let date_time = moment
+            .date_time
+            .map(|dt| DateTime::parse_from_rfc3339(&dt).map_err(|e| format!("Invalid datetime: {}", e)))
+            .transpose()?;
+        let date = match (moment.date, date_time) {
+            (Some(date), _) => Some(
+                NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| format!("Invalid date: {}", e))?,
+            ),
+            // `allDay` on a bare dateTime means the date of that moment
+            (None, Some(dt)) if moment.all_day == Some(true) => Some(dt.date_naive()),
+            (None, _) => None,
+        };
+
+        match (date, date_time, moment.all_day) {
+            (_, Some(date_time), Some(false)) => Ok(calblend_core::EventMoment::DateTime {
+                date_time,
+                time_zone: moment.time_zone,
+            }),
+            (None, Some(date_time), _) => Ok(calblend_core::EventMoment::DateTime {
+                date_time,
+                time_zone: moment.time_zone,
+            }),
+            (Some(_), None, Some(false)) => Err("allDay is false but dateTime is missing".to_string()),
+            (Some(date), _, _) => Ok(calblend_core::EventMoment::Date {
+                date,
+                time_zone: moment.time_zone,
+            }),
+            (None, None, _) => Err("Event time needs either date or dateTime".to_string()),
+        }
+    }
+}
+
+impl From<recurrence::RecurrenceRule> for RecurrenceRule {
+    fn from(rule: recurrence::RecurrenceRule) -> Self {
+        fn list<T: Copy, U: From<T>>(values: &[T]) -> Option<Vec<U>> {
+            (!values.is_empty()).then(|| values.iter().map(|v| U::from(*v)).collect())
+        }
+
+        Self {
+            frequency: match rule.frequency {
+                recurrence::Frequency::Secondly => RecurrenceFrequency::Secondly,
+                recurrence::Frequency::Minutely => RecurrenceFrequency::Minutely,
+                recurrence::Frequency::Hourly => RecurrenceFrequency::Hourly,
+                recurrence::Frequency::Daily => RecurrenceFrequency::Daily,
+                recurrence::Frequency::Weekly => RecurrenceFrequency::Weekly,
+                recurrence::Frequency::Monthly => RecurrenceFrequency::Monthly,
+                recurrence::Frequency::Yearly => RecurrenceFrequency::Yearly,
+            },
+            interval: Some(rule.interval),
+            count: rule.count,
+            until: rule.until.map(|u| u.to_string()),
+            by_second: list(&rule.by_second),
+            by_minute: list(&rule.by_minute),
+            by_hour: list(&rule.by_hour),
+            by_day: (!rule.by_day.is_empty())
+                .then(|| rule.by_day.iter().map(ToString::to_string).collect()),
+            by_month_day: list(&rule.by_month_day),
+            by_year_day: list(&rule.by_year_day),
+            by_week_no: list(&rule.by_week_no),
+            by_month: list(&rule.by_month),
+            by_set_pos: list(&rule.by_set_pos),
+            week_start: Some(recurrence::weekday_code(rule.week_start).to_string()),
+        }
+    }
+}
+
+impl TryFrom<RecurrenceRule> for recurrence::RecurrenceRule {
+    type Error = String;
+
+    /// Goes through the RRULE text form so the core parser validates it
+    fn try_from(rule: RecurrenceRule) -> Result<Self, Self::Error> {
+        fn join<T: ToString>(values: Option<Vec<T>>) -> Option<String> {
+            values
+                .filter(|v| !v.is_empty())
+                .map(|v| v.iter().map(ToString::to_string).collect::<Vec<_>>().join(","))
+        }
+
+        let frequency = match rule.frequency {
+            RecurrenceFrequency::Secondly => "SECONDLY",
+            RecurrenceFrequency::Minutely => "MINUTELY",
+            RecurrenceFrequency::Hourly => "HOURLY",
+            RecurrenceFrequency::Daily => "DAILY",
+            RecurrenceFrequency::Weekly => "WEEKLY",
+            RecurrenceFrequency::Monthly => "MONTHLY",
+            RecurrenceFrequency::Yearly => "YEARLY",
+        };
+        let parts = [
+            ("INTERVAL", rule.interval.map(|v| v.to_string())),
+            ("COUNT", rule.count.map(|v| v.to_string())),
+            ("UNTIL", rule.until),
+            ("BYSECOND", join(rule.by_second)),
+            ("BYMINUTE", join(rule.by_minute)),
+            ("BYHOUR", join(rule.by_hour)),
+            ("BYDAY", join(rule.by_day)),
+            ("BYMONTHDAY", join(rule.by_month_day)),
+            ("BYYEARDAY", join(rule.by_year_day)),
+            ("BYWEEKNO", join(rule.by_week_no)),
+            ("BYMONTH", join(rule.by_month)),
+            ("BYSETPOS", join(rule.by_set_pos)),
+            ("WKST", rule.week_start),
+        ];
+
+        let mut text = format!("FREQ={}", frequency);
+        for (name, value) in parts {
+            if let Some(value) = value {
+                text.push_str(&format!(";{}={}", name, value));
+            }
+        }
+        text.parse().map_err(|e: calblend_core::CalblendError| e.to_string())
+    }
+}
+
+impl From<recurrence::RecurrencePattern> for RecurrencePattern {
+    fn from(pattern: recurrence::RecurrencePattern) -> Self {
+        let date = |d: recurrence::RecurrenceDate| RecurrenceDate {
+            value: d.value(),
+            tzid: d.tzid().map(str::to_string),
+        };
+        Self {
+            rules: pattern.rules.into_iter().map(Into::into).collect(),
+            rdates: pattern.rdates.into_iter().map(date).collect(),
+            exdates: pattern.exdates.into_iter().map(date).collect(),
+        }
+    }
+}
+
+impl TryFrom<RecurrencePattern> for recurrence::RecurrencePattern {
+    type Error = String;
+
+    fn try_from(pattern: RecurrencePattern) -> Result<Self, Self::Error> {
+        let dates = |dates: Vec<RecurrenceDate>| {
+            dates
+                .into_iter()
+                .map(|d| recurrence::RecurrenceDate::parse(&d.value, d.tzid.as_deref()))
+                .collect::<calblend_core::Result<Vec<_>>>()
+                .map_err(|e| e.to_string())
+        };
+        Ok(Self {
+            rules: pattern
+                .rules
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            rdates: dates(pattern.rdates)?,
+            exdates: dates(pattern.exdates)?,
+        })
+    }
+}
+
+impl From<calblend_core::Participant> for Participant {
+    fn from(p: calblend_core::Participant) -> Self {
+        Self {
+            id: p.id,
+            email: p.email,
+            name: p.name,
+            optional: p.optional,
+            response_status: p.response_status.map(|s| match s {
+                calblend_core::ParticipantStatus::Accepted => ParticipantStatus::Accepted,
+                calblend_core::ParticipantStatus::Tentative => ParticipantStatus::Tentative,
+                calblend_core::ParticipantStatus::Declined => ParticipantStatus::Declined,
+                calblend_core::ParticipantStatus::NeedsAction => ParticipantStatus::NeedsAction,
+            }),
+            is_self: p.is_self,
+            resource: p.resource,
+            organizer: p.organizer,
+            role: p.role.map(|r| match r {
+                calblend_core::ParticipantRole::Chair => ParticipantRole::Chair,
+                calblend_core::ParticipantRole::Required => ParticipantRole::Required,
+                calblend_core::ParticipantRole::Optional => ParticipantRole::Optional,
+                calblend_core::ParticipantRole::NonParticipant => ParticipantRole::NonParticipant,
+            }),
+            user_type: p.user_type.map(|t| match t {
+                calblend_core::CalendarUserType::Individual => CalendarUserType::Individual,
+                calblend_core::CalendarUserType::Group => CalendarUserType::Group,
+                calblend_core::CalendarUserType::Resource => CalendarUserType::Resource,
+                calblend_core::CalendarUserType::Room => CalendarUserType::Room,
+                calblend_core::CalendarUserType::Unknown => CalendarUserType::Unknown,
+            }),
+            delegated_to: p.delegated_to,
+            delegated_from: p.delegated_from,
+            comment: p.comment,
+            additional_guests: p.additional_guests,
+        }
+    }
+}
+
+impl From<Participant> for calblend_core::Participant {
+    fn from(p: Participant) -> Self {
+        Self {
+            id: p.id,
+            email: p.email,
+            name: p.name,
+            optional: p.optional,
+            response_status: p.response_status.map(|s| match s {
+                ParticipantStatus::Accepted => calblend_core::ParticipantStatus::Accepted,
+                ParticipantStatus::Tentative => calblend_core::ParticipantStatus::Tentative,
+                ParticipantStatus::Declined => calblend_core::ParticipantStatus::Declined,
+                ParticipantStatus::NeedsAction => calblend_core::ParticipantStatus::NeedsAction,
+            }),
+            is_self: p.is_self,
+            resource: p.resource,
+            organizer: p.organizer,
+            role: p.role.map(|r| match r {
+                ParticipantRole::Chair => calblend_core::ParticipantRole::Chair,
+                ParticipantRole::Required => calblend_core::ParticipantRole::Required,
+                ParticipantRole::Optional => calblend_core::ParticipantRole::Optional,
+                ParticipantRole::NonParticipant => calblend_core::ParticipantRole::NonParticipant,
+            }),
+            user_type: p.user_type.map(|t| match t {
+                CalendarUserType::Individual => calblend_core::CalendarUserType::Individual,
+                CalendarUserType::Group => calblend_core::CalendarUserType::Group,
+                CalendarUserType::Resource => calblend_core::CalendarUserType::Resource,
+                CalendarUserType::Room => calblend_core::CalendarUserType::Room,
+                CalendarUserType::Unknown => calblend_core::CalendarUserType::Unknown,
+            }),
+            delegated_to: p.delegated_to,
+            delegated_from: p.delegated_from,
+            comment: p.comment,
+            additional_guests: p.additional_guests,
         }
     }
 }
@@ -23,54 +297,22 @@ impl From<calblend_core::UnifiedCalendarEvent> for UnifiedCalendarEvent {
             id: event.id,
             source: event.source.into(),
             calendar_id: event.calendar_id,
+            ical_uid: event.ical_uid,
+            sequence: event.sequence,
             title: event.title,
             description: event.description,
             location: event.location,
             color: event.color,
-            start: EventMoment {
-                date_time: event.start.date_time.to_rfc3339(),
-                time_zone: event.start.time_zone,
-                all_day: event.start.all_day,
-            },
-            end: EventMoment {
-                date_time: event.end.date_time.to_rfc3339(),
-                time_zone: event.end.time_zone,
-                all_day: event.end.all_day,
-            },
+            categories: event.categories,
+            start: event.start.into(),
+            end: event.end.into(),
             recurrence_rule: event.recurrence_rule,
             recurrence_exceptions: event.recurrence_exceptions,
-            organizer: event.organizer.map(|p| Participant {
-                id: p.id,
-                email: p.email,
-                name: p.name,
-                optional: p.optional,
-                response_status: p.response_status.map(|s| match s {
-                    calblend_core::ParticipantStatus::Accepted => ParticipantStatus::Accepted,
-                    calblend_core::ParticipantStatus::Tentative => ParticipantStatus::Tentative,
-                    calblend_core::ParticipantStatus::Declined => ParticipantStatus::Declined,
-                    calblend_core::ParticipantStatus::NeedsAction => ParticipantStatus::NeedsAction,
-                }),
-                is_self: p.is_self,
-                resource: p.resource,
-                organizer: p.organizer,
-            }),
-            attendees: event.attendees.map(|attendees| {
-                attendees.into_iter().map(|p| Participant {
-                    id: p.id,
-                    email: p.email,
-                    name: p.name,
-                    optional: p.optional,
-                    response_status: p.response_status.map(|s| match s {
-                        calblend_core::ParticipantStatus::Accepted => ParticipantStatus::Accepted,
-                        calblend_core::ParticipantStatus::Tentative => ParticipantStatus::Tentative,
-                        calblend_core::ParticipantStatus::Declined => ParticipantStatus::Declined,
-                        calblend_core::ParticipantStatus::NeedsAction => ParticipantStatus::NeedsAction,
-                    }),
-                    is_self: p.is_self,
-                    resource: p.resource,
-                    organizer: p.organizer,
-                }).collect()
-            }),
+            recurrence: event.recurrence.map(Into::into),
+            series_id: event.series_id,
+            original_start: event.original_start.map(Into::into),
+            organizer: event.organizer.map(Into::into),
+            attendees: event.attendees.map(|attendees| attendees.into_iter().map(Into::into).collect()),
             status: event.status.map(|s| match s {
                 calblend_core::EventStatus::Confirmed => EventStatus::Confirmed,
                 calblend_core::EventStatus::Tentative => EventStatus::Tentative,
@@ -89,16 +331,7 @@ impl From<calblend_core::UnifiedCalendarEvent> for UnifiedCalendarEvent {
                 calblend_core::ShowAs::WorkingElsewhere => ShowAs::WorkingElsewhere,
                 calblend_core::ShowAs::Unknown => ShowAs::Unknown,
             }),
-            reminders: event.reminders.map(|reminders| {
-                reminders.into_iter().map(|r| Reminder {
-                    minutes_before: r.minutes_before,
-                    method: r.method.map(|m| match m {
-                        calblend_core::ReminderMethod::Popup => ReminderMethod::Popup,
-                        calblend_core::ReminderMethod::Email => ReminderMethod::Email,
-                        calblend_core::ReminderMethod::Sms => ReminderMethod::Sms,
-                    }),
-                }).collect()
-            }),
+            reminders: event.reminders.map(|reminders| reminders.into_iter().map(Into::into).collect()),
             conference: event.conference.map(|c| ConferenceLink {
                 url: c.url,
                 provider: c.provider,
@@ -106,6 +339,8 @@ impl From<calblend_core::UnifiedCalendarEvent> for UnifiedCalendarEvent {
             raw: event.raw.map(|v| v.to_string()),
             created: event.created.map(|dt| dt.to_rfc3339()),
             updated: event.updated.map(|dt| dt.to_rfc3339()),
+            etag: event.etag,
+            html_link: event.html_link,
         }
     }
 }
@@ -114,63 +349,26 @@ impl TryFrom<UnifiedCalendarEvent> for calblend_core::UnifiedCalendarEvent {
     type Error = String;
 
     fn try_from(event: UnifiedCalendarEvent) -> Result<Self, Self::Error> {
-        let parse_datetime = |s: &str| {
-            DateTime::parse_from_rfc3339(s)
-                .map_err(|e| format!("Invalid datetime: {}", e))
-        };
-
         Ok(calblend_core::UnifiedCalendarEvent {
             id: event.id,
             source: event.source.into(),
             calendar_id: event.calendar_id,
+            ical_uid: event.ical_uid,
+            sequence: event.sequence,
             title: event.title,
             description: event.description,
             location: event.location,
             color: event.color,
-            start: calblend_core::EventMoment {
-                date_time: parse_datetime(&event.start.date_time)?,
-                time_zone: event.start.time_zone,
-                all_day: event.start.all_day,
-            },
-            end: calblend_core::EventMoment {
-                date_time: parse_datetime(&event.end.date_time)?,
-                time_zone: event.end.time_zone,
-                all_day: event.end.all_day,
-            },
+            categories: event.categories,
+            start: event.start.try_into()?,
+            end: event.end.try_into()?,
             recurrence_rule: event.recurrence_rule,
             recurrence_exceptions: event.recurrence_exceptions,
-            organizer: event.organizer.map(|p| calblend_core::Participant {
-                id: p.id,
-                email: p.email,
-                name: p.name,
-                optional: p.optional,
-                response_status: p.response_status.map(|s| match s {
-                    ParticipantStatus::Accepted => calblend_core::ParticipantStatus::Accepted,
-                    ParticipantStatus::Tentative => calblend_core::ParticipantStatus::Tentative,
-                    ParticipantStatus::Declined => calblend_core::ParticipantStatus::Declined,
-                    ParticipantStatus::NeedsAction => calblend_core::ParticipantStatus::NeedsAction,
-                }),
-                is_self: p.is_self,
-                resource: p.resource,
-                organizer: p.organizer,
-            }),
-            attendees: event.attendees.map(|attendees| {
-                attendees.into_iter().map(|p| calblend_core::Participant {
-                    id: p.id,
-                    email: p.email,
-                    name: p.name,
-                    optional: p.optional,
-                    response_status: p.response_status.map(|s| match s {
-                        ParticipantStatus::Accepted => calblend_core::ParticipantStatus::Accepted,
-                        ParticipantStatus::Tentative => calblend_core::ParticipantStatus::Tentative,
-                        ParticipantStatus::Declined => calblend_core::ParticipantStatus::Declined,
-                        ParticipantStatus::NeedsAction => calblend_core::ParticipantStatus::NeedsAction,
-                    }),
-                    is_self: p.is_self,
-                    resource: p.resource,
-                    organizer: p.organizer,
-                }).collect()
-            }),
+            recurrence: event.recurrence.map(TryInto::try_into).transpose()?,
+            series_id: event.series_id,
+            original_start: event.original_start.map(TryInto::try_into).transpose()?,
+            organizer: event.organizer.map(Into::into),
+            attendees: event.attendees.map(|attendees| attendees.into_iter().map(Into::into).collect()),
             status: event.status.map(|s| match s {
                 EventStatus::Confirmed => calblend_core::EventStatus::Confirmed,
                 EventStatus::Tentative => calblend_core::EventStatus::Tentative,
@@ -206,6 +404,48 @@ impl TryFrom<UnifiedCalendarEvent> for calblend_core::UnifiedCalendarEvent {
             raw: event.raw.and_then(|s| serde_json::from_str(&s).ok()),
             created: event.created.and_then(|s| DateTime::parse_from_rfc3339(&s).ok()),
             updated: event.updated.and_then(|s| DateTime::parse_from_rfc3339(&s).ok()),
+            etag: event.etag,
+            html_link: event.html_link,
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moment(date: Option<&str>, date_time: Option<&str>, all_day: Option<bool>) -> EventMoment {
+        EventMoment {
+            date_time: date_time.map(str::to_string),
+            date: date.map(str::to_string),
+            time_zone: None,
+            all_day,
+        }
+    }
+
+    #[test]
+    fn test_event_moment_honours_all_day() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+
+        let core = calblend_core::EventMoment::try_from(moment(None, Some("2024-03-10T00:00:00-05:00"), Some(true)))
+            .unwrap();
+        assert_eq!(core, calblend_core::EventMoment::all_day(day));
+
+        let core = calblend_core::EventMoment::try_from(moment(None, Some("2024-03-10T09:00:00Z"), None)).unwrap();
+        assert!(!core.is_all_day());
+
+        let core = calblend_core::EventMoment::try_from(moment(
+            Some("2024-03-10"),
+            Some("2024-03-10T09:00:00Z"),
+            Some(false),
+        ))
+        .unwrap();
+        assert!(!core.is_all_day());
+
+        let core = calblend_core::EventMoment::try_from(moment(Some("2024-03-10"), None, None)).unwrap();
+        assert_eq!(core, calblend_core::EventMoment::all_day(day));
+
+        assert!(calblend_core::EventMoment::try_from(moment(Some("2024-03-10"), None, Some(false))).is_err());
+        assert!(calblend_core::EventMoment::try_from(moment(None, None, Some(true))).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! CSV import and export
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use calblend_core::csv::{self, CsvFormat};
+
+use crate::error::to_napi_error;
+use crate::models::{CalendarSource, UnifiedCalendarEvent};
+
+/// Built-in column layout
+#[napi]
+#[derive(Debug)]
+pub enum CsvPreset {
+    /// Most event fields, with ISO dates and 24-hour times
+    Default,
+    Google,
+    Outlook,
+}
+
+/// Event field held by a column
+#[napi]
+#[derive(Debug)]
+pub enum CsvField {
+    Uid,
+    Title,
+    Description,
+    Location,
+    Categories,
+    StartDate,
+    StartTime,
+    EndDate,
+    EndTime,
+    AllDay,
+    TimeZone,
+    Private,
+    Sensitivity,
+    Status,
+    ShowAs,
+    ShowAsCode,
+    Organizer,
+    RequiredAttendees,
+    OptionalAttendees,
+    Resources,
+    ReminderOn,
+    ReminderDate,
+    ReminderTime,
+    ReminderMinutes,
+    RecurrenceRule,
+    ConferenceUrl,
+    Ignore,
+}
+
+impl From<CsvField> for csv::CsvField {
+    fn from(field: CsvField) -> Self {
+        match field {
+            CsvField::Uid => Self::Uid,
+            CsvField::Title => Self::Title,
+            CsvField::Description => Self::Description,
+            CsvField::Location => Self::Location,
+            CsvField::Categories => Self::Categories,
+            CsvField::StartDate => Self::StartDate,
+            CsvField::StartTime => Self::StartTime,
+            CsvField::EndDate => Self::EndDate,
+            CsvField::EndTime => Self::EndTime,
+            CsvField::AllDay => Self::AllDay,
+            CsvField::TimeZone => Self::TimeZone,
+            CsvField::Private => Self::Private,
+            CsvField::Sensitivity => Self::Sensitivity,
+            CsvField::Status => Self::Status,
+            CsvField::ShowAs => Self::ShowAs,
+            CsvField::ShowAsCode => Self::ShowAsCode,
+            CsvField::Organizer => Self::Organizer,
+            CsvField::RequiredAttendees => Self::RequiredAttendees,
+            CsvField::OptionalAttendees => Self::OptionalAttendees,
+            CsvField::Resources => Self::Resources,
+            CsvField::ReminderOn => Self::ReminderOn,
+            CsvField::ReminderDate => Self::ReminderDate,
+            CsvField::ReminderTime => Self::ReminderTime,
+            CsvField::ReminderMinutes => Self::ReminderMinutes,
+            CsvField::RecurrenceRule => Self::RecurrenceRule,
+            CsvField::ConferenceUrl => Self::ConferenceUrl,
+            CsvField::Ignore => Self::Ignore,
+        }
+    }
+}
+
+/// A column: its header and the field it holds
+#[napi(object)]
+#[derive(Debug)]
+pub struct CsvColumn {
+    pub header: String,
+    pub field: CsvField,
+}
+
+/// CSV layout; unset values come from the preset
+#[napi(object)]
+#[derive(Debug, Default)]
+pub struct CsvOptions {
+    pub preset: Option<CsvPreset>,
+    /// Columns replacing the preset's
+    pub columns: Option<Vec<CsvColumn>>,
+    /// Single ASCII character, `,` by default
+    pub delimiter: Option<String>,
+    /// chrono format of dates, e.g. `%d.%m.%Y`
+    pub date_format: Option<String>,
+    /// chrono format of times, e.g. `%H:%M`
+    pub time_format: Option<String>,
+    /// Zone times are written in and read as, unless a row names its own
+    pub time_zone: Option<String>,
+}
+
+impl TryFrom<CsvOptions> for CsvFormat {
+    type Error = String;
+
+    fn try_from(options: CsvOptions) -> std::result::Result<Self, String> {
+        let mut format = match options.preset {
+            None | Some(CsvPreset::Default) => CsvFormat::default(),
+            Some(CsvPreset::Google) => CsvFormat::google(),
+            Some(CsvPreset::Outlook) => CsvFormat::outlook(),
+        };
+        if let Some(columns) = options.columns {
+            format.columns = columns
+                .into_iter()
+                .map(|c| csv::CsvColumn::new(c.header, c.field.into()))
+                .collect();
+        }
+        if let Some(delimiter) = options.delimiter {
+            match delimiter.as_bytes() {
+                [byte] => format.delimiter = *byte,
+                _ => return Err(format!("Invalid delimiter {:?}", delimiter)),
+            }
+        }
+        if let Some(date_format) = options.date_format {
+            format.date_format = date_format;
+        }
+        if let Some(time_format) = options.time_format {
+            format.time_format = time_format;
+        }
+        format.time_zone = options.time_zone;
+        Ok(format)
+    }
+}
+
+/// A row that could not be imported
+#[napi(object)]
+#[derive(Debug)]
+pub struct CsvRowError {
+    pub row: u32,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+/// Events read from a CSV file
+#[napi(object)]
+#[derive(Debug)]
+pub struct CsvImportResult {
+    pub events: Vec<UnifiedCalendarEvent>,
+    pub errors: Vec<CsvRowError>,
+}
+
+/// Render events as CSV with a header row
+#[napi]
+pub fn export_csv(
+    events: Vec<UnifiedCalendarEvent>,
+    options: Option<CsvOptions>,
+) -> Result<String> {
+    let events = events
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<std::result::Result<Vec<calblend_core::UnifiedCalendarEvent>, String>>()
+        .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    let format = CsvFormat::try_from(options.unwrap_or_default())
+        .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    csv::write_csv(&events, &format).map_err(to_napi_error)
+}
+
+/// Read events from CSV with a header row
+///
+/// Rows that cannot be read are listed in `errors` instead of failing the
+/// call.
+#[napi]
+pub fn import_csv(
+    text: String,
+    source: CalendarSource,
+    options: Option<CsvOptions>,
+) -> Result<CsvImportResult> {
+    let format = CsvFormat::try_from(options.unwrap_or_default())
+        .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    let result = csv::read_csv(&text, &format, source.into()).map_err(to_napi_error)?;
+    Ok(CsvImportResult {
+        events: result.events.into_iter().map(Into::into).collect(),
+        errors: result
+            .errors
+            .into_iter()
+            .map(|error| CsvRowError {
+                row: u32::try_from(error.row).unwrap_or(u32::MAX),
+                column: error.column,
+                message: error.message,
+            })
+            .collect(),
+    })
+}
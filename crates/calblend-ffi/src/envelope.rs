@@ -0,0 +1,30 @@
+//! Versioned storage format for events
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::error::to_napi_error;
+use crate::models::UnifiedCalendarEvent;
+
+/// Serialize an event into the versioned storage envelope
+#[napi]
+pub fn serialize_event(event: UnifiedCalendarEvent) -> Result<String> {
+    let core_event: calblend_core::UnifiedCalendarEvent = event.try_into()
+        .map_err(|e: String| Error::new(Status::InvalidArg, e))?;
+    calblend_core::envelope::to_string(&core_event).map_err(to_napi_error)
+}
+
+/// Read an event stored by `serializeEvent`, migrating older versions
+#[napi]
+pub fn deserialize_event(json: String) -> Result<UnifiedCalendarEvent> {
+    calblend_core::envelope::from_str::<calblend_core::UnifiedCalendarEvent>(&json)
+        .map(Into::into)
+        .map_err(to_napi_error)
+}
+
+/// JSON Schema of the storage envelope and the model types
+#[napi]
+pub fn event_json_schema() -> Result<String> {
+    serde_json::to_string_pretty(&calblend_core::envelope::json_schema())
+        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+}
@@ -10,6 +10,7 @@ pub fn to_napi_error(err: calblend_core::CalblendError) -> Error {
         calblend_core::CalblendError::Authentication(_) => Status::GenericFailure,
         calblend_core::CalblendError::PermissionDenied(_) => Status::GenericFailure,
         calblend_core::CalblendError::InvalidData(_) => Status::InvalidArg,
+        calblend_core::CalblendError::Validation(_) => Status::InvalidArg,
         calblend_core::CalblendError::CalendarNotFound(_) => Status::ObjectExpected,
         calblend_core::CalblendError::EventNotFound(_) => Status::ObjectExpected,
         _ => Status::GenericFailure,
@@ -0,0 +1,142 @@
+//! iCalendar and jCal import and export
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::error::to_napi_error;
+use crate::models::{CalendarSource, UnifiedCalendarEvent};
+
+/// Calendar-level properties of an iCalendar export
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct IcalExportOptions {
+    /// Calendar name (`X-WR-CALNAME`)
+    pub name: Option<String>,
+    /// Calendar description (`X-WR-CALDESC`)
+    pub description: Option<String>,
+    /// Default time zone (`X-WR-TIMEZONE`)
+    pub time_zone: Option<String>,
+}
+
+impl From<IcalExportOptions> for calblend_core::ical::ExportOptions {
+    fn from(options: IcalExportOptions) -> Self {
+        Self {
+            name: options.name,
+            description: options.description,
+            time_zone: options.time_zone,
+            method: None,
+            calblend_properties: false,
+        }
+    }
+}
+
+/// Render events as an iCalendar (`.ics`) document
+#[napi]
+pub fn export_ical(
+    events: Vec<UnifiedCalendarEvent>,
+    options: Option<IcalExportOptions>,
+) -> Result<String> {
+    let events = events
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<std::result::Result<Vec<calblend_core::UnifiedCalendarEvent>, String>>()
+        .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    Ok(calblend_core::ical::write_calendar(&events, &options.unwrap_or_default().into()))
+}
+
+/// Render events as a jCal (RFC 7265) document, returned as JSON text
+#[napi]
+pub fn export_jcal(
+    events: Vec<UnifiedCalendarEvent>,
+    options: Option<IcalExportOptions>,
+) -> Result<String> {
+    let events = events
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<std::result::Result<Vec<calblend_core::UnifiedCalendarEvent>, String>>()
+        .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    let jcal = calblend_core::ical::write_jcal(&events, &options.unwrap_or_default().into())
+        .map_err(to_napi_error)?;
+    Ok(jcal.to_string())
+}
+
+/// Options for reading an iCalendar document
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct IcalImportOptions {
+    /// Source the imported events are tagged with
+    pub source: CalendarSource,
+    /// Zone for floating times, in place of the file's `X-WR-TIMEZONE`
+    pub default_time_zone: Option<String>,
+}
+
+impl From<IcalImportOptions> for calblend_core::ical::ImportOptions {
+    fn from(options: IcalImportOptions) -> Self {
+        let mut core_options = Self::new(options.source.into());
+        core_options.default_time_zone = options.default_time_zone;
+        core_options
+    }
+}
+
+/// A component of an imported document that could not be fully read
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct IcalImportError {
+    pub component: String,
+    pub uid: Option<String>,
+    pub line: u32,
+    pub message: String,
+}
+
+/// Events read from an iCalendar document
+#[napi(object)]
+#[derive(Debug)]
+pub struct IcalImportResult {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub time_zone: Option<String>,
+    pub events: Vec<UnifiedCalendarEvent>,
+    pub errors: Vec<IcalImportError>,
+}
+
+impl From<calblend_core::ical::ImportResult> for IcalImportResult {
+    fn from(result: calblend_core::ical::ImportResult) -> Self {
+        Self {
+            name: result.name,
+            description: result.description,
+            time_zone: result.time_zone,
+            events: result.events.into_iter().map(Into::into).collect(),
+            errors: result
+                .errors
+                .into_iter()
+                .map(|error| IcalImportError {
+                    component: error.component,
+                    uid: error.uid,
+                    line: u32::try_from(error.line).unwrap_or(u32::MAX),
+                    message: error.message,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Read the events of an iCalendar (`.ics`) document
+///
+/// Broken components are listed in `errors` instead of failing the call.
+#[napi]
+pub fn import_ical(ics: String, options: IcalImportOptions) -> IcalImportResult {
+    calblend_core::ical::read_calendar(&ics, &options.into()).into()
+}
+
+/// Read the events of a jCal (RFC 7265) document given as JSON text
+///
+/// Text that is not a jCal document fails the call; broken components are
+/// listed in `errors` as for iCalendar.
+#[napi]
+pub fn import_jcal(jcal: String, options: IcalImportOptions) -> Result<IcalImportResult> {
+    let jcal: serde_json::Value =
+        serde_json::from_str(&jcal).map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?;
+    let result =
+        calblend_core::ical::read_jcal(&jcal, &options.into()).map_err(to_napi_error)?;
+    Ok(result.into())
+}
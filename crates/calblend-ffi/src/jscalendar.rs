@@ -0,0 +1,53 @@
+//! JSCalendar (RFC 8984) import and export
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::error::to_napi_error;
+use crate::models::{CalendarSource, UnifiedCalendarEvent};
+
+/// Options for reading JSCalendar objects
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct JscalendarReadOptions {
+    /// Source the read events are tagged with
+    pub source: CalendarSource,
+    /// Zone for floating times; UTC when unset
+    pub default_time_zone: Option<String>,
+    /// Language tag whose `localizations` entry is applied, e.g. `de-AT`
+    pub locale: Option<String>,
+}
+
+impl From<JscalendarReadOptions> for calblend_core::jscalendar::ReadOptions {
+    fn from(options: JscalendarReadOptions) -> Self {
+        Self {
+            source: options.source.into(),
+            default_time_zone: options.default_time_zone,
+            locale: options.locale,
+        }
+    }
+}
+
+/// Render events as a JSON array of JSCalendar `Event` objects
+#[napi]
+pub fn export_jscalendar(events: Vec<UnifiedCalendarEvent>) -> Result<String> {
+    let events = events
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<std::result::Result<Vec<calblend_core::UnifiedCalendarEvent>, String>>()
+        .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    Ok(serde_json::Value::Array(calblend_core::jscalendar::write_jscalendar(&events)).to_string())
+}
+
+/// Read JSCalendar JSON: an `Event`, a `Group` or an array of them
+#[napi]
+pub fn import_jscalendar(
+    json: String,
+    options: JscalendarReadOptions,
+) -> Result<Vec<UnifiedCalendarEvent>> {
+    let value: serde_json::Value =
+        serde_json::from_str(&json).map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?;
+    let events = calblend_core::jscalendar::read_jscalendar(&value, &options.into())
+        .map_err(to_napi_error)?;
+    Ok(events.into_iter().map(Into::into).collect())
+}
@@ -10,15 +10,28 @@ mod providers;
 mod token_storage;
 mod auth;
 mod conversions;
+mod envelope;
+mod ical;
+mod jscalendar;
+mod csv;
+mod agenda;
+mod backup;
 
 pub use models::{
-    CalendarSource, ParticipantStatus, ReminderMethod, EventStatus, 
+    CalendarSource, ParticipantStatus, ParticipantRole, CalendarUserType, ReminderMethod, EventStatus, 
     EventVisibility, ShowAs, Participant, Reminder, ConferenceLink,
-    EventMoment, UnifiedCalendarEvent, Calendar
+    EventMoment, UnifiedCalendarEvent, Calendar, AccessRole, RecurrenceFrequency, RecurrenceRule,
+    RecurrenceDate, RecurrencePattern
 };
 pub use error::*;
 pub use client::*;
 pub use providers::google::*;
+pub use envelope::*;
+pub use ical::*;
+pub use jscalendar::*;
+pub use csv::*;
+pub use agenda::*;
+pub use backup::*;
 
 /// Initialize the Calblend library (called automatically by N-API)
 #[napi]
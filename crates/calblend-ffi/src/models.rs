@@ -25,6 +25,25 @@ pub enum ParticipantStatus {
     NeedsAction,
 }
 
+#[napi]
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ParticipantRole {
+    Chair,
+    Required,
+    Optional,
+    NonParticipant,
+}
+
+#[napi]
+#[derive(Debug, Serialize, Deserialize)]
+pub enum CalendarUserType {
+    Individual,
+    Group,
+    Resource,
+    Room,
+    Unknown,
+}
+
 #[napi]
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ReminderMethod {
@@ -72,6 +91,12 @@ pub struct Participant {
     pub is_self: Option<bool>,
     pub resource: Option<bool>,
     pub organizer: Option<bool>,
+    pub role: Option<ParticipantRole>,
+    pub user_type: Option<CalendarUserType>,
+    pub delegated_to: Option<Vec<String>>,
+    pub delegated_from: Option<Vec<String>>,
+    pub comment: Option<String>,
+    pub additional_guests: Option<u32>,
 }
 
 #[napi(object)]
@@ -91,11 +116,58 @@ pub struct ConferenceLink {
 #[napi(object)]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EventMoment {
-    pub date_time: String, // RFC3339 string for JS compatibility
+    pub date_time: Option<String>, // RFC3339 string for JS compatibility
+    pub date: Option<String>, // YYYY-MM-DD for all-day events
     pub time_zone: Option<String>,
     pub all_day: Option<bool>,
 }
 
+#[napi]
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RecurrenceFrequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[napi(object)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecurrenceRule {
+    pub frequency: RecurrenceFrequency,
+    pub interval: Option<u32>,
+    pub count: Option<u32>,
+    pub until: Option<String>, // RFC 5545 DATE or DATE-TIME, e.g. 20241231T235959Z
+    pub by_second: Option<Vec<u32>>,
+    pub by_minute: Option<Vec<u32>>,
+    pub by_hour: Option<Vec<u32>>,
+    pub by_day: Option<Vec<String>>, // e.g. MO, 2TU, -1FR
+    pub by_month_day: Option<Vec<i32>>,
+    pub by_year_day: Option<Vec<i32>>,
+    pub by_week_no: Option<Vec<i32>>,
+    pub by_month: Option<Vec<u32>>,
+    pub by_set_pos: Option<Vec<i32>>,
+    pub week_start: Option<String>, // MO..SU
+}
+
+#[napi(object)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecurrenceDate {
+    pub value: String, // RFC 5545 DATE or DATE-TIME
+    pub tzid: Option<String>,
+}
+
+#[napi(object)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecurrencePattern {
+    pub rules: Vec<RecurrenceRule>,
+    pub rdates: Vec<RecurrenceDate>,
+    pub exdates: Vec<RecurrenceDate>,
+}
+
 #[napi(object)]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UnifiedCalendarEvent {
@@ -103,18 +175,24 @@ pub struct UnifiedCalendarEvent {
     pub id: String,
     pub source: CalendarSource,
     pub calendar_id: Option<String>,
+    pub ical_uid: Option<String>,
+    pub sequence: Option<u32>,
 
     // Content
     pub title: Option<String>,
     pub description: Option<String>,
     pub location: Option<String>,
     pub color: Option<String>,
+    pub categories: Option<Vec<String>>,
 
     // Timing
     pub start: EventMoment,
     pub end: EventMoment,
     pub recurrence_rule: Option<String>,
     pub recurrence_exceptions: Option<Vec<String>>,
+    pub recurrence: Option<RecurrencePattern>,
+    pub series_id: Option<String>,
+    pub original_start: Option<EventMoment>,
 
     // Participation
     pub organizer: Option<Participant>,
@@ -133,6 +211,8 @@ pub struct UnifiedCalendarEvent {
     pub raw: Option<String>, // JSON string for JS compatibility
     pub created: Option<String>, // RFC3339 string
     pub updated: Option<String>, // RFC3339 string
+    pub etag: Option<String>,
+    pub html_link: Option<String>,
 }
 
 #[napi(object)]
@@ -145,6 +225,22 @@ pub struct Calendar {
     pub is_primary: bool,
     pub can_write: bool,
     pub source: CalendarSource,
+    pub foreground_color: Option<String>,
+    pub time_zone: Option<String>,
+    pub access_role: Option<AccessRole>,
+    pub default_reminders: Option<Vec<Reminder>>,
+    pub conference_types: Option<Vec<String>>,
+    pub hidden: Option<bool>,
+    pub selected: Option<bool>,
+}
+
+#[napi]
+#[derive(Debug, Serialize, Deserialize)]
+pub enum AccessRole {
+    FreeBusyReader,
+    Reader,
+    Writer,
+    Owner,
 }
 
 // Conversion helpers between core types and FFI types
@@ -171,10 +267,12 @@ impl From<CalendarSource> for calblend_core::CalendarSource {
 }
 
 // Helper to convert DateTime to/from strings for FFI
+#[allow(dead_code)]
 pub fn datetime_to_string(dt: &DateTime<FixedOffset>) -> String {
     dt.to_rfc3339()
 }
 
+#[allow(dead_code)]
 pub fn string_to_datetime(s: &str) -> Result<DateTime<FixedOffset>> {
     DateTime::parse_from_rfc3339(s)
         .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid datetime: {}", e)))
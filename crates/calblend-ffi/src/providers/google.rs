@@ -6,10 +6,16 @@ use std::sync::Arc;
 use std::collections::HashMap;
 
 use calblend_core::{
-    providers::google::{GoogleCalendarProvider as CoreGoogleProvider, WatchChannel, PushNotification},
+    backup::{backup_account, restore_account, AccountBackup, BackupOptions},
+    providers::google::{
+        GoogleCalendarProvider as CoreGoogleProvider, GoogleEndpoints, ListEventsOptions, NotificationChange,
+        PushNotification, WatchChannel, WatchResource,
+    },
     CalblendConfig, TokenStorage, CalendarProvider,
 };
 
+use crate::backup::{BackupSummary, RestoreOptions, RestoreReport};
+use crate::error::to_napi_error;
 use crate::models::{Calendar, UnifiedCalendarEvent};
 use crate::token_storage::JsTokenStorage;
 
@@ -29,6 +35,7 @@ impl GoogleCalendarProvider {
         client_secret: String,
         redirect_uri: String,
         webhook_endpoint: Option<String>,
+        endpoints: Option<GoogleEndpointsOptions>,
     ) -> Result<Self> {
         let config = CalblendConfig::default()
             .with_timeout_seconds(30)
@@ -37,12 +44,13 @@ impl GoogleCalendarProvider {
         // Use in-memory storage for now
         let token_storage: Arc<dyn TokenStorage> = Arc::new(JsTokenStorage::new());
 
-        let mut provider = CoreGoogleProvider::new(
+        let mut provider = CoreGoogleProvider::with_endpoints(
             client_id,
             client_secret,
             redirect_uri,
             token_storage,
             config,
+            endpoints.map(Into::into).unwrap_or_default(),
         )
         .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
 
@@ -94,6 +102,7 @@ impl GoogleCalendarProvider {
         calendar_id: String,
         start_date: Option<String>,
         end_date: Option<String>,
+        options: Option<GoogleListEventsOptions>,
     ) -> Result<Vec<UnifiedCalendarEvent>> {
         let start = start_date
             .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
@@ -103,8 +112,14 @@ impl GoogleCalendarProvider {
             .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
             .map(|dt| dt.with_timezone(&chrono::Utc));
 
+        let options = options
+            .map(ListEventsOptions::try_from)
+            .transpose()
+            .map_err(|e| Error::new(Status::InvalidArg, e))?
+            .unwrap_or_default();
+
         let events = self.inner
-            .list_events(&calendar_id, start, end)
+            .list_events_with_options(&calendar_id, start, end, &options)
             .await
             .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
 
@@ -161,6 +176,50 @@ impl GoogleCalendarProvider {
             .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
     }
 
+    /// Snapshot every calendar and event into an archive directory
+    #[napi]
+    pub async fn backup_to_dir(
+        &self,
+        dir: String,
+        calendar_ids: Option<Vec<String>>,
+    ) -> Result<BackupSummary> {
+        let options = BackupOptions {
+            calendar_ids,
+            ..Default::default()
+        };
+        let backup = backup_account(self.inner.as_ref(), &options)
+            .await
+            .map_err(to_napi_error)?;
+        let summary = (&backup).into();
+        // File I/O stays off the async runtime's worker threads
+        tokio::task::spawn_blocking(move || backup.write_dir(&dir))
+            .await
+            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
+            .map_err(to_napi_error)?;
+        Ok(summary)
+    }
+
+    /// Replay an archive directory into this account
+    #[napi]
+    pub async fn restore_from_dir(
+        &self,
+        dir: String,
+        options: Option<RestoreOptions>,
+    ) -> Result<RestoreReport> {
+        let backup = tokio::task::spawn_blocking(move || AccountBackup::read_dir(&dir))
+            .await
+            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
+            .map_err(to_napi_error)?;
+        let report = restore_account(
+            self.inner.as_ref(),
+            &backup,
+            &options.unwrap_or_default().into(),
+        )
+        .await
+        .map_err(to_napi_error)?;
+        Ok(report.into())
+    }
+
     /// Check if webhook support is enabled
     #[napi]
     pub fn has_webhook_support(&self) -> bool {
@@ -190,6 +249,50 @@ impl GoogleCalendarProvider {
         Ok(GoogleWatchChannel::from(channel))
     }
 
+    /// Watch the calendar list for changes (webhooks)
+    #[napi]
+    pub async fn watch_calendar_list(
+        &self,
+        token: Option<String>,
+        ttl_hours: Option<i32>,
+    ) -> Result<GoogleWatchChannel> {
+        if !self.has_webhooks {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "Webhook endpoint not configured"
+            ));
+        }
+
+        let channel = self.inner
+            .watch_calendar_list(token, ttl_hours.map(|h| h as i64))
+            .await
+            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+
+        Ok(GoogleWatchChannel::from(channel))
+    }
+
+    /// Watch user settings for changes (webhooks)
+    #[napi]
+    pub async fn watch_settings(
+        &self,
+        token: Option<String>,
+        ttl_hours: Option<i32>,
+    ) -> Result<GoogleWatchChannel> {
+        if !self.has_webhooks {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "Webhook endpoint not configured"
+            ));
+        }
+
+        let channel = self.inner
+            .watch_settings(token, ttl_hours.map(|h| h as i64))
+            .await
+            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+
+        Ok(GoogleWatchChannel::from(channel))
+    }
+
     /// Stop watching a calendar
     #[napi]
     pub async fn stop_watch(
@@ -212,6 +315,7 @@ impl GoogleCalendarProvider {
 
     /// Process a webhook notification
     #[napi]
+    #[allow(clippy::too_many_arguments)]
     pub async fn process_notification(
         &self,
         channel_id: String,
@@ -222,7 +326,7 @@ impl GoogleCalendarProvider {
         resource_uri: String,
         message_number: Option<String>,
         expected_token: Option<String>,
-    ) -> Result<Vec<UnifiedCalendarEvent>> {
+    ) -> Result<GoogleNotificationChange> {
         if !self.has_webhooks {
             return Err(Error::new(
                 Status::GenericFailure,
@@ -240,12 +344,12 @@ impl GoogleCalendarProvider {
             message_number,
         };
 
-        let events = self.inner
+        let change = self.inner
             .process_notification(&notification, expected_token.as_deref())
             .await
             .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
 
-        Ok(events.into_iter().map(Into::into).collect())
+        Ok(change.into())
     }
 
     /// Parse webhook headers from Google Calendar push notification
@@ -302,6 +406,180 @@ impl From<WatchChannel> for GoogleWatchChannel {
     }
 }
 
+/// Endpoint overrides, e.g. for a staging proxy or an emulator
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct GoogleEndpointsOptions {
+    /// Serve every endpoint from this host with Google's path layout
+    pub base_url: Option<String>,
+    /// Calendar API base, e.g. `https://www.googleapis.com/calendar/v3`
+    pub api_base_url: Option<String>,
+    /// OAuth2 authorization endpoint
+    pub auth_url: Option<String>,
+    /// OAuth2 token endpoint
+    pub token_url: Option<String>,
+    /// OAuth2 token revocation endpoint
+    pub revoke_url: Option<String>,
+}
+
+impl From<GoogleEndpointsOptions> for GoogleEndpoints {
+    fn from(options: GoogleEndpointsOptions) -> Self {
+        let mut endpoints = match &options.base_url {
+            Some(base_url) => GoogleEndpoints::with_base_url(base_url),
+            None => GoogleEndpoints::default(),
+        };
+        if let Some(url) = options.api_base_url {
+            endpoints = endpoints.with_api_base_url(url);
+        }
+        if let Some(url) = options.auth_url {
+            endpoints = endpoints.with_auth_url(url);
+        }
+        if let Some(url) = options.token_url {
+            endpoints = endpoints.with_token_url(url);
+        }
+        if let Some(url) = options.revoke_url {
+            endpoints = endpoints.with_revoke_url(url);
+        }
+        endpoints
+    }
+}
+
+/// Options for listing Google Calendar events
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct GoogleListEventsOptions {
+    /// Free-text search (Google `q` parameter)
+    pub query: Option<String>,
+    /// Include cancelled events
+    pub show_deleted: Option<bool>,
+    /// Only events modified after this RFC3339 time
+    pub updated_min: Option<String>,
+    /// Only events with this iCalendar UID
+    pub ical_uid: Option<String>,
+    /// Private extended property constraints
+    pub private_extended_properties: Option<HashMap<String, String>>,
+    /// Shared extended property constraints
+    pub shared_extended_properties: Option<HashMap<String, String>>,
+    /// Include hidden invitations
+    pub show_hidden_invitations: Option<bool>,
+    /// Time zone used in the response
+    pub time_zone: Option<String>,
+    /// Partial response selector (Google `fields` parameter)
+    pub fields: Option<String>,
+    /// Page size (Google `maxResults` parameter)
+    pub max_results: Option<u32>,
+    /// Skip copying the provider payload into `raw`
+    pub skip_raw: Option<bool>,
+    /// Skip events that fail to convert instead of failing the call
+    pub lenient: Option<bool>,
+    /// Return each occurrence of a recurring series; true when unset
+    pub single_events: Option<bool>,
+}
+
+impl TryFrom<GoogleListEventsOptions> for ListEventsOptions {
+    type Error = String;
+
+    fn try_from(options: GoogleListEventsOptions) -> std::result::Result<Self, Self::Error> {
+        let mut result = ListEventsOptions {
+            query: options.query,
+            show_deleted: options.show_deleted,
+            ical_uid: options.ical_uid,
+            show_hidden_invitations: options.show_hidden_invitations,
+            time_zone: options.time_zone,
+            ..Default::default()
+        };
+        if let Some(updated_min) = options.updated_min {
+            let updated_min = chrono::DateTime::parse_from_rfc3339(&updated_min)
+                .map_err(|e| format!("Invalid updatedMin: {}", e))?;
+            result = result.with_updated_min(updated_min.with_timezone(&chrono::Utc));
+        }
+        // Sort so that equal option sets share a cache entry
+        let mut private: Vec<_> = options.private_extended_properties.unwrap_or_default().into_iter().collect();
+        private.sort();
+        result.private_extended_properties = private;
+        let mut shared: Vec<_> = options.shared_extended_properties.unwrap_or_default().into_iter().collect();
+        shared.sort();
+        result.shared_extended_properties = shared;
+        if let Some(fields) = options.fields {
+            result = result.with_fields(fields);
+        }
+        if let Some(max_results) = options.max_results {
+            result = result.with_max_results(max_results);
+        }
+        if options.skip_raw.unwrap_or(false) {
+            result = result.without_raw();
+        }
+        if options.lenient.unwrap_or(false) {
+            result = result.lenient();
+        }
+        if let Some(single_events) = options.single_events {
+            result = result.with_single_events(single_events);
+        }
+        Ok(result)
+    }
+}
+
+/// Result of processing a Google push notification
+#[napi(object)]
+#[derive(Debug)]
+pub struct GoogleNotificationChange {
+    /// `sync`, `events`, `calendarList` or `settings`
+    pub kind: String,
+    /// Resource the new channel watches, `events`, `calendarList` or
+    /// `settings` (`sync` only)
+    pub resource: Option<String>,
+    /// Calendar whose events changed, or that a new `events` channel
+    /// watches (`events` and `sync`)
+    pub calendar_id: Option<String>,
+    /// Refreshed recent events (`events` only)
+    pub events: Option<Vec<UnifiedCalendarEvent>>,
+    /// Refreshed calendar list (`calendarList` only)
+    pub calendars: Option<Vec<Calendar>>,
+    /// Refreshed settings by ID (`settings` only)
+    pub settings: Option<HashMap<String, String>>,
+}
+
+impl From<NotificationChange> for GoogleNotificationChange {
+    fn from(change: NotificationChange) -> Self {
+        let empty = |kind: &str| Self {
+            kind: kind.to_string(),
+            resource: None,
+            calendar_id: None,
+            events: None,
+            calendars: None,
+            settings: None,
+        };
+
+        match change {
+            NotificationChange::Sync { resource } => {
+                let (resource, calendar_id) = match resource {
+                    WatchResource::Events { calendar_id } => ("events", Some(calendar_id)),
+                    WatchResource::CalendarList => ("calendarList", None),
+                    WatchResource::Settings => ("settings", None),
+                };
+                Self {
+                    resource: Some(resource.to_string()),
+                    calendar_id,
+                    ..empty("sync")
+                }
+            }
+            NotificationChange::Events { calendar_id, events } => Self {
+                calendar_id: Some(calendar_id),
+                events: Some(events.into_iter().map(Into::into).collect()),
+                ..empty("events")
+            },
+            NotificationChange::CalendarList { calendars } => Self {
+                calendars: Some(calendars.into_iter().map(Into::into).collect()),
+                ..empty("calendarList")
+            },
+            NotificationChange::Settings { settings } => Self {
+                settings: Some(settings),
+                ..empty("settings")
+            },
+        }
+    }
+}
+
 /// Google webhook notification structure
 #[napi(object)]
 #[derive(Debug, Clone)]
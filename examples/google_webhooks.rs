@@ -3,7 +3,7 @@
 use calblend_core::{
     CalblendConfig, Calendar, CalendarProvider, CalendarSource,
     auth::InMemoryTokenStorage,
-    providers::google::{GoogleCalendarProvider, PushNotification},
+    providers::google::{GoogleCalendarProvider, NotificationChange, PushNotification},
 };
 use std::sync::Arc;
 use tracing::info;
@@ -104,10 +104,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         
         // Process the notification
         match provider.process_notification(&simulated_notification, Some(&webhook_token)).await {
-            Ok(events) => {
+            Ok(NotificationChange::Events { events, .. }) => {
                 println!("✅ Notification processed successfully!");
                 println!("   Found {} recent events", events.len());
             }
+            Ok(change) => {
+                println!("✅ Notification processed: {:?}", change);
+            }
             Err(e) => {
                 println!("❌ Error processing notification: {}", e);
             }
@@ -133,12 +136,12 @@ async fn handle_webhook(
     headers: http::HeaderMap,
     provider: &GoogleCalendarProvider,
     expected_token: &str,
-) -> Result<Vec<calblend_core::UnifiedCalendarEvent>, Box<dyn std::error::Error>> {
+) -> Result<NotificationChange, Box<dyn std::error::Error>> {
     // Parse notification from headers
     let notification = calblend_core::providers::google::GoogleWebhookManager::parse_notification_headers(&headers)?;
     
-    // Process the notification
-    let events = provider.process_notification(&notification, Some(expected_token)).await?;
+    // Process the notification (events, calendar list or settings)
+    let change = provider.process_notification(&notification, Some(expected_token)).await?;
     
-    Ok(events)
+    Ok(change)
 }
\ No newline at end of file
@@ -9,6 +9,18 @@ const binding = require('../index.js');
 export const {
   CalendarClient,
   GoogleCalendarProvider: NativeGoogleCalendarProvider,
+  serializeEvent,
+  deserializeEvent,
+  eventJsonSchema,
+  exportIcal,
+  importIcal,
+  exportJcal,
+  importJcal,
+  exportJscalendar,
+  importJscalendar,
+  exportCsv,
+  importCsv,
+  renderAgenda,
 } = binding;
 
 // Import types from the generated type definitions
@@ -19,8 +31,11 @@ import type {
   Reminder,
   ConferenceLink,
   Calendar,
+  AccessRole as AccessRoleType,
   CalendarSource as CalendarSourceType,
   ParticipantStatus as ParticipantStatusType,
+  ParticipantRole as ParticipantRoleType,
+  CalendarUserType as CalendarUserTypeType,
   ReminderMethod as ReminderMethodType,
   EventStatus as EventStatusType,
   EventVisibility as EventVisibilityType,
@@ -29,6 +44,28 @@ import type {
   BusyStatus as BusyStatusType,
   WatchChannel,
   WebhookNotification,
+  RecurrenceFrequency as RecurrenceFrequencyType,
+  RecurrenceRule,
+  RecurrenceDate,
+  RecurrencePattern,
+  IcalExportOptions,
+  IcalImportOptions,
+  IcalImportError,
+  IcalImportResult,
+  JscalendarReadOptions,
+  GoogleEndpointsOptions,
+  CsvPreset as CsvPresetType,
+  CsvField as CsvFieldType,
+  CsvColumn,
+  CsvOptions,
+  CsvRowError,
+  CsvImportResult,
+  AgendaFormat as AgendaFormatType,
+  AgendaRenderOptions,
+  BackupSummary,
+  RestoreOptions,
+  RestoreError,
+  RestoreReport,
 } from '../index.d.ts';
 
 // Re-export types
@@ -39,8 +76,11 @@ export type {
   Reminder,
   ConferenceLink,
   Calendar,
+  AccessRoleType as AccessRole,
   CalendarSourceType as CalendarSource,
   ParticipantStatusType as ParticipantStatus,
+  ParticipantRoleType as ParticipantRole,
+  CalendarUserTypeType as CalendarUserType,
   ReminderMethodType as ReminderMethod,
   EventStatusType as EventStatus,
   EventVisibilityType as EventVisibility,
@@ -49,6 +89,28 @@ export type {
   BusyStatusType as BusyStatus,
   WatchChannel,
   WebhookNotification,
+  RecurrenceFrequencyType as RecurrenceFrequency,
+  RecurrenceRule,
+  RecurrenceDate,
+  RecurrencePattern,
+  IcalExportOptions,
+  IcalImportOptions,
+  IcalImportError,
+  IcalImportResult,
+  JscalendarReadOptions,
+  GoogleEndpointsOptions,
+  CsvPresetType as CsvPreset,
+  CsvFieldType as CsvField,
+  CsvColumn,
+  CsvOptions,
+  CsvRowError,
+  CsvImportResult,
+  AgendaFormatType as AgendaFormat,
+  AgendaRenderOptions,
+  BackupSummary,
+  RestoreOptions,
+  RestoreError,
+  RestoreReport,
 };
 
 // Export TypeScript-friendly interfaces
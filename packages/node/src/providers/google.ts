@@ -20,6 +20,38 @@ export interface GoogleProviderConfig {
   webhookEndpoint?: string;
 }
 
+export interface ListEventsOptions {
+  /** Free-text search over summary, description, location and people */
+  query?: string;
+  /** Include cancelled events */
+  showDeleted?: boolean;
+  /** Only events modified after this ISO 8601 time */
+  updatedMin?: string;
+  /** Only events with this iCalendar UID */
+  icalUid?: string;
+  /** Private extended property constraints, all of which must match */
+  privateExtendedProperties?: Record<string, string>;
+  /** Shared extended property constraints, all of which must match */
+  sharedExtendedProperties?: Record<string, string>;
+  /** Include hidden invitations */
+  showHiddenInvitations?: boolean;
+  /** IANA time zone used in the response */
+  timeZone?: string;
+  /** Partial response selector, e.g. `items(id,start,end,status)` */
+  fields?: string;
+  /** Page size (Google caps this at 2500) */
+  maxResults?: number;
+  /** Skip copying the provider payload into `raw` */
+  skipRaw?: boolean;
+  /** Skip events that fail to convert instead of rejecting */
+  lenient?: boolean;
+  /**
+   * Return each occurrence of a recurring series (default); when false,
+   * series come back as their master event plus changed occurrences
+   */
+  singleEvents?: boolean;
+}
+
 export interface WatchChannel {
   id: string;
   resourceId: string;
@@ -28,6 +60,14 @@ export interface WatchChannel {
   expiration: string;
 }
 
+export interface NotificationChange {
+  kind: 'sync' | 'events' | 'calendarList' | 'settings';
+  calendarId?: string;
+  events?: UnifiedCalendarEvent[];
+  calendars?: Calendar[];
+  settings?: Record<string, string>;
+}
+
 export interface WebhookNotification {
   channelId: string;
   channelToken?: string;
@@ -95,11 +135,12 @@ export class GoogleCalendarProvider {
   async listEvents(
     calendarId: string,
     startDate?: Date,
-    endDate?: Date
+    endDate?: Date,
+    options?: ListEventsOptions
   ): Promise<UnifiedCalendarEvent[]> {
     const start = startDate?.toISOString();
     const end = endDate?.toISOString();
-    return this.native.listEvents(calendarId, start, end);
+    return this.native.listEvents(calendarId, start, end, options);
   }
 
   /**
@@ -148,6 +189,20 @@ export class GoogleCalendarProvider {
     return this.native.watchCalendar(calendarId, token, ttlHours);
   }
 
+  /**
+   * Watch the calendar list for subscribed, hidden or deleted calendars
+   */
+  async watchCalendarList(token?: string, ttlHours?: number): Promise<WatchChannel> {
+    return this.native.watchCalendarList(token, ttlHours);
+  }
+
+  /**
+   * Watch user settings such as the time zone
+   */
+  async watchSettings(token?: string, ttlHours?: number): Promise<WatchChannel> {
+    return this.native.watchSettings(token, ttlHours);
+  }
+
   /**
    * Stop watching a calendar
    */
@@ -161,7 +216,7 @@ export class GoogleCalendarProvider {
   async processNotification(
     notification: WebhookNotification,
     expectedToken?: string
-  ): Promise<UnifiedCalendarEvent[]> {
+  ): Promise<NotificationChange> {
     return this.native.processNotification(
       notification.channelId,
       notification.channelToken,